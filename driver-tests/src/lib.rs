@@ -0,0 +1,2 @@
+//! Tests of [`embassy_mock::time::driver::VirtualDriver`] registered as the global time driver,
+//! see the `tests` directory.
@@ -0,0 +1,49 @@
+//! The [`VirtualDriver`] registered as the global time driver and driving the real `embassy-time`
+//! types.
+//!
+//! The tests take turns through a lock as they all share the one global driver.
+
+use embassy_mock::time::driver::VirtualDriver;
+use embassy_time::{Duration, Instant, Ticker, Timer};
+use std::sync::Mutex;
+
+embassy_time_driver::time_driver_impl!(static DRIVER: VirtualDriver = VirtualDriver::new());
+
+/// Held by each test so they don't advance the global driver under each other.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[test]
+fn instant_follows_advance() {
+    let _serial = SERIAL.lock().unwrap();
+    let start = Instant::now();
+    DRIVER.advance(Duration::from_secs(1));
+
+    assert_eq!(start.elapsed(), Duration::from_secs(1));
+}
+
+#[test]
+fn timer_completes_without_waiting() {
+    let _serial = SERIAL.lock().unwrap();
+    let start = Instant::now();
+    DRIVER.block_on(Timer::after(Duration::from_secs(3600)));
+
+    assert_eq!(start.elapsed(), Duration::from_secs(3600));
+}
+
+#[test]
+fn ticker_ticks_at_every_interval() {
+    let _serial = SERIAL.lock().unwrap();
+    let start = Instant::now();
+    let ticks = DRIVER.block_on(async {
+        let mut ticker = Ticker::every(Duration::from_secs(60));
+        let mut ticks = Vec::new();
+        for _ in 0..3 {
+            ticker.next().await;
+            ticks.push(start.elapsed());
+        }
+        ticks
+    });
+
+    let expected = [60, 120, 180].map(Duration::from_secs);
+    assert_eq!(ticks, expected);
+}
@@ -0,0 +1,246 @@
+//! Macros for the `embassy-mock` crate, use them through the re-exports in `embassy-mock`.
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parse::Parser, parse_macro_input, punctuated::Punctuated, spanned::Spanned, FnArg, Ident,
+    ItemFn, Meta, Pat, Token,
+};
+
+/// Declare an Embassy task that can also be awaited directly in unit tests.
+///
+/// This wraps [`embassy_executor::task`], passing any arguments on to it, and generates a plain
+/// async function called `<name>_body` that contains the body of the task. The task itself only
+/// awaits the body, so a test can await `<name>_body()` to run the task without an executor while
+/// the production code spawns `<name>()` as normal.
+///
+/// With the `record_args` argument, each call to `<name>()` also records a clone of its arguments
+/// so that a test can check them with `MockSpawner::spawned_args()` once the task is spawned on a
+/// `MockSpawner`. The arguments must then all be [`Clone`] and [`Send`].
+///
+/// [`embassy_executor::task`]: https://docs.rs/embassy-executor/latest/embassy_executor/attr.task.html
+///
+/// # Examples
+///
+/// ```ignore
+/// use embassy_mock::time::{MockTimer, Timer};
+/// use embassy_time::Duration;
+///
+/// #[embassy_mock::task(pool_size = 2, record_args)]
+/// async fn blink(times: usize, period: Duration) {
+///     for _ in 0..times {
+///         MockTimer::after(period).await;
+///     }
+/// }
+///
+/// // The production code spawns the task.
+/// spawner.spawn(blink(3, Duration::from_secs(1))).unwrap();
+///
+/// // The tests check the arguments it was spawned with.
+/// assert_eq!(spawner.spawned_args::<(usize, Duration)>("blink"), [(3, Duration::from_secs(1))]);
+///
+/// // Or await the body directly.
+/// embassy_futures::block_on(blink_body(3, Duration::from_secs(1)));
+/// ```
+#[proc_macro_attribute]
+pub fn task(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = match Punctuated::<Meta, Token![,]>::parse_terminated.parse(args) {
+        Ok(args) => args,
+        Err(error) => return error.into_compile_error().into(),
+    };
+    let task = parse_macro_input!(item as ItemFn);
+
+    match expand_task(&args, task) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.into_compile_error().into(),
+    }
+}
+
+/// Generate the Embassy task and the plain async function with its body.
+fn expand_task(
+    args: &Punctuated<Meta, Token![,]>,
+    task: ItemFn,
+) -> syn::Result<proc_macro2::TokenStream> {
+    // Every other argument is for `embassy_executor::task`.
+    let (record_args, args): (Vec<_>, Vec<_>) = args
+        .iter()
+        .partition(|arg| matches!(arg, Meta::Path(path) if path.is_ident("record_args")));
+
+    if task.sig.asyncness.is_none() {
+        return Err(syn::Error::new(
+            task.sig.fn_token.span(),
+            "task functions must be async",
+        ));
+    }
+
+    let names = task
+        .sig
+        .inputs
+        .iter()
+        .map(argument_name)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = task;
+    let name = sig.ident.clone();
+    let body_name = format_ident!("{}_body", name);
+    let body_doc = format!("The body of the [`{name}()`] task, to await it directly in tests.");
+
+    let mut body_sig = sig.clone();
+    body_sig.ident = body_name.clone();
+
+    // The task only passes its arguments on, so none of them need to be mutable.
+    let mut task_sig = sig;
+    for input in &mut task_sig.inputs {
+        if let FnArg::Typed(arg) = input {
+            if let Pat::Ident(ident) = arg.pat.as_mut() {
+                ident.mutability = None;
+            }
+        }
+    }
+
+    let body = quote! {
+        #[doc = #body_doc]
+        #vis #body_sig #block
+    };
+
+    if record_args.is_empty() {
+        return Ok(quote! {
+            #(#attrs)*
+            #[::embassy_executor::task(#(#args),*)]
+            #vis #task_sig {
+                #body_name(#(#names),*).await
+            }
+
+            #body
+        });
+    }
+
+    // The Embassy task is nested in the function recording the arguments so that both have the
+    // same name, which is also the name the `MockSpawner` gives to the task.
+    let inputs = &task_sig.inputs;
+    let output = &task_sig.output;
+    let task_name = name.to_string();
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis fn #name(#inputs) -> ::embassy_executor::SpawnToken<impl Sized> {
+            #[::embassy_executor::task(#(#args),*)]
+            async fn #name(#inputs) #output {
+                #body_name(#(#names),*).await
+            }
+
+            ::embassy_mock::executor::__record_task_args(#task_name, || {
+                (#(::core::clone::Clone::clone(&#names),)*)
+            });
+            #name(#(#names),*)
+        }
+
+        #body
+    })
+}
+
+/// The name of a task argument, which must be a plain identifier as with Embassy tasks.
+fn argument_name(input: &FnArg) -> syn::Result<Ident> {
+    match input {
+        FnArg::Typed(arg) => match arg.pat.as_ref() {
+            Pat::Ident(ident) if ident.by_ref.is_none() && ident.subpat.is_none() => {
+                Ok(ident.ident.clone())
+            }
+            pat => Err(syn::Error::new(
+                pat.span(),
+                "task arguments must be plain identifiers",
+            )),
+        },
+        FnArg::Receiver(receiver) => {
+            Err(syn::Error::new(receiver.span(), "tasks can't take `self`"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_calls_body_with_its_arguments() {
+        let task = syn::parse_quote! {
+            async fn blink(mut times: usize, period: Duration) {
+                times -= 1;
+            }
+        };
+
+        let tokens = expand_task(&syn::parse_quote!(pool_size = 2), task).unwrap();
+
+        let expected = quote! {
+            #[::embassy_executor::task(pool_size = 2)]
+            async fn blink(times: usize, period: Duration) {
+                blink_body(times, period).await
+            }
+
+            #[doc = "The body of the [`blink()`] task, to await it directly in tests."]
+            async fn blink_body(mut times: usize, period: Duration) {
+                times -= 1;
+            }
+        };
+        assert_eq!(tokens.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn record_args_records_clone_of_arguments() {
+        let task = syn::parse_quote! {
+            /// Blink the LED.
+            pub async fn blink(times: usize, mut period: Duration) {}
+        };
+
+        let tokens = expand_task(&syn::parse_quote!(record_args, pool_size = 2), task).unwrap();
+
+        let expected = quote! {
+            /// Blink the LED.
+            pub fn blink(times: usize, period: Duration) -> ::embassy_executor::SpawnToken<impl Sized> {
+                #[::embassy_executor::task(pool_size = 2)]
+                async fn blink(times: usize, period: Duration) {
+                    blink_body(times, period).await
+                }
+
+                ::embassy_mock::executor::__record_task_args("blink", || {
+                    (::core::clone::Clone::clone(&times), ::core::clone::Clone::clone(&period),)
+                });
+                blink(times, period)
+            }
+
+            #[doc = "The body of the [`blink()`] task, to await it directly in tests."]
+            pub async fn blink_body(times: usize, mut period: Duration) {}
+        };
+        assert_eq!(tokens.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn non_async_function_is_an_error() {
+        let task = syn::parse_quote! {
+            fn blink() {}
+        };
+
+        let error = expand_task(&Punctuated::new(), task).unwrap_err();
+
+        assert_eq!(error.to_string(), "task functions must be async");
+    }
+
+    #[test]
+    fn pattern_argument_is_an_error() {
+        let task = syn::parse_quote! {
+            async fn blink((a, b): (u8, u8)) {}
+        };
+
+        let error = expand_task(&Punctuated::new(), task).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "task arguments must be plain identifiers"
+        );
+    }
+}
@@ -40,24 +40,146 @@
 //! }
 //! ```
 
-use core::sync::atomic::{AtomicUsize, Ordering};
+pub mod dynamic;
+// Both define the `__pender` of `embassy-executor`, so the real executor wins.
+#[cfg(all(feature = "runtime", not(feature = "arch-std")))]
+pub mod runtime;
+
+pub use dynamic::{DynSpawnToken, DynSpawner};
+#[cfg(all(feature = "runtime", not(feature = "arch-std")))]
+pub use runtime::MockExecutor;
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use core::fmt;
+#[cfg(feature = "std")]
+use core::panic::Location;
+use core::{
+    any::Any,
+    future::{ready, Future},
+    ops::RangeInclusive,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
 use embassy_executor::{SpawnError, SpawnToken, Spawner as EmbassySpawner};
 use snafu::prelude::*;
+#[cfg(feature = "std")]
+use std::{cell::RefCell, collections::VecDeque, sync::Mutex, vec::Vec};
+
+#[cfg(all(feature = "runtime", not(feature = "arch-std")))]
+std::thread_local! {
+    /// The executor that runs the tasks spawned on the current thread by the [`MockSpawner`]s that
+    /// are set to run them.
+    static EXECUTOR: MockExecutor = MockExecutor::new();
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    /// The number of tasks expected of each of the next [`MockSpawner`]s created with
+    /// [`Spawner::for_current_executor()`] on the current thread, in creation order.
+    static EXPECTED_FOR_CURRENT_EXECUTOR: RefCell<VecDeque<usize>> =
+        const { RefCell::new(VecDeque::new()) };
+
+    /// The name and arguments of the last task created on the current thread that records its
+    /// arguments, until it is passed to [`MockSpawner::spawn()`].
+    static TASK_ARGS: RefCell<Option<(&'static str, Box<dyn Any + Send>)>> =
+        const { RefCell::new(None) };
+}
+
+/// Record the arguments of a task created by a function generated by `#[embassy_mock::task]` with
+/// `record_args`, for the next call to [`MockSpawner::spawn()`] on the current thread.
+///
+/// The `args` are only cloned when the `std` feature is enabled, so this does nothing in
+/// production code.
+#[doc(hidden)]
+pub fn __record_task_args<A: Any + Send>(name: &'static str, args: impl FnOnce() -> A) {
+    #[cfg(feature = "std")]
+    TASK_ARGS.with(|recorded| *recorded.borrow_mut() = Some((name, Box::new(args()))));
+    #[cfg(not(feature = "std"))]
+    let _ = (name, args);
+}
 
 /// The trait to replace the [`embassy_executor::Spawner`] in code to allow the [`MockSpawner`] to
 /// be used in its place for tests.
 pub trait Spawner {
+    /// Wrapper for [`embassy_executor::Spawner::for_current_executor()`].
+    fn for_current_executor() -> impl Future<Output = Self>
+    where
+        Self: Sized;
+
     /// Wrapper for [`embassy_executor::Spawner::spawn()`].
     fn spawn<S>(&self, token: SpawnToken<S>) -> Result<(), SpawnError>;
+
+    /// Wrapper for [`embassy_executor::Spawner::must_spawn()`].
+    fn must_spawn<S>(&self, token: SpawnToken<S>);
 }
 
 impl Spawner for EmbassySpawner {
+    /// Get a Spawner for the current executor.
+    ///
+    /// This function is `async` just to get access to the current async context. It returns
+    /// instantly, it does not block/yield.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the current executor is not an Embassy executor.
+    fn for_current_executor() -> impl Future<Output = Self> {
+        EmbassySpawner::for_current_executor()
+    }
+
     /// Spawn a task into an executor.
     ///
     /// You obtain the `token` by calling a task function (i.e. one marked with `#[embassy_executor::task]`).
     fn spawn<S>(&self, token: SpawnToken<S>) -> Result<(), SpawnError> {
         self.spawn(token)
     }
+
+    /// Spawn a task into an executor, panicking on failure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the spawning fails.
+    fn must_spawn<S>(&self, token: SpawnToken<S>) {
+        self.must_spawn(token);
+    }
+}
+
+/// The trait to replace the `embassy_executor::Executor` in the code that creates and starts the
+/// executor, to allow the [`MockExecutor`] to be used in its place for tests.
+///
+/// The real executor is only available with one of the `arch-*` features and the
+/// `executor-thread` feature of `embassy-executor`. The `arch-std` feature of this crate enables
+/// both for the host, and implements this trait for the real executor. The [`MockExecutor`] needs
+/// the `runtime` feature instead, and is left out when `arch-std` is enabled too.
+pub trait Executor {
+    /// What [`Self::run()`] returns, [`Infallible`](core::convert::Infallible) for the real
+    /// executor as it never returns.
+    type Output;
+
+    /// Wrapper for `embassy_executor::Executor::new()`.
+    fn new() -> Self
+    where
+        Self: Sized;
+
+    /// Wrapper for `embassy_executor::Executor::run()`.
+    fn run(&'static mut self, init: impl FnOnce(EmbassySpawner)) -> Self::Output;
+}
+
+#[cfg(feature = "arch-std")]
+impl Executor for embassy_executor::Executor {
+    type Output = core::convert::Infallible;
+
+    /// Create a new executor.
+    fn new() -> Self {
+        embassy_executor::Executor::new()
+    }
+
+    /// Run the executor, calling `init` with a spawner for it first.
+    ///
+    /// This never returns.
+    fn run(&'static mut self, init: impl FnOnce(EmbassySpawner)) -> Self::Output {
+        self.run(init)
+    }
 }
 
 /// The errors that are reported by [`MockSpawner`].
@@ -72,6 +194,68 @@ pub enum MockSpawnerError {
         /// The actual number of times [`MockSpawner::spawn()`] was called.
         actual: usize,
     },
+
+    /// The [`MockSpawner::spawn()`] method was called fewer times than the minimum.
+    #[snafu(display("expected to spawn at least {min} task(s), actually spawned {actual}"))]
+    TooFewTasks {
+        /// The minimum number of calls to [`MockSpawner::spawn()`].
+        min: usize,
+
+        /// The actual number of times [`MockSpawner::spawn()`] was called.
+        actual: usize,
+    },
+
+    /// The [`MockSpawner::spawn()`] method was called more times than the maximum.
+    #[snafu(display("expected to spawn at most {max} task(s), actually spawned {actual}"))]
+    TooManyTasks {
+        /// The maximum number of calls to [`MockSpawner::spawn()`].
+        max: usize,
+
+        /// The actual number of times [`MockSpawner::spawn()`] was called.
+        actual: usize,
+    },
+
+    /// A task was spawned out of the order set with [`MockSpawner::expect_order()`].
+    #[snafu(display("expected spawn {index} to be {expected}, actually spawned {actual}"))]
+    WrongTask {
+        /// The index of the call to [`MockSpawner::spawn()`].
+        index: usize,
+
+        /// The name of the expected task.
+        expected: &'static str,
+
+        /// The name of the task that was actually spawned.
+        actual: &'static str,
+    },
+
+    /// A task set with [`MockSpawner::expect_task()`] was spawned the wrong number of times.
+    #[snafu(display("expected to spawn {name} {expected} time(s), actually spawned {actual}"))]
+    WrongTaskCount {
+        /// The name of the task.
+        name: &'static str,
+
+        /// The expected number of times the task is passed to [`MockSpawner::spawn()`].
+        expected: usize,
+
+        /// The actual number of times the task was passed to [`MockSpawner::spawn()`].
+        actual: usize,
+    },
+
+    /// A task was spawned after [`MockSpawner::expect_no_more_spawns()`].
+    #[snafu(display("expected no more tasks to be spawned, actually spawned {actual}"))]
+    UnexpectedSpawns {
+        /// The number of times [`MockSpawner::spawn()`] was called after no more calls were
+        /// expected.
+        actual: usize,
+    },
+
+    /// A task was spawned again before it finished, found with
+    /// [`MockSpawner::detect_duplicate_spawns()`].
+    #[snafu(display("task {name} was spawned again before it finished"))]
+    DuplicateTask {
+        /// The name of the task.
+        name: &'static str,
+    },
 }
 
 /// A mocked version of [`embassy_executor::Spawner`] that can be used in its place for unit tests.
@@ -81,6 +265,9 @@ pub enum MockSpawnerError {
 /// [`Self::done()`] is not called then it asserts that [`Self::spawn()`] was called the correct
 /// number of times when dropped which causes a panic if incorrect.
 ///
+/// The calls are counted atomically, so a [`MockSpawner`] can be shared by code under test that
+/// spawns from several threads.
+///
 /// # Panics
 ///
 /// Panics if [`Self::spawn()`] called the wrong number of times and [`Self`] is dropped before
@@ -137,15 +324,126 @@ pub enum MockSpawnerError {
 /// ```
 #[derive(Debug)]
 pub struct MockSpawner {
-    /// The number of expected calls to [`Self::spawn()`].
-    expected: usize,
+    /// The minimum number of expected calls to [`Self::spawn()`].
+    min: usize,
+
+    /// The maximum number of expected calls to [`Self::spawn()`].
+    max: usize,
 
     /// The number of times [`Self::spawn()`] has been called.
     times_called: AtomicUsize,
 
+    /// The number of calls to [`Self::spawn()`] after which no more are expected, set with
+    /// [`Self::expect_no_more_spawns()`], or [`usize::MAX`] if more are expected.
+    no_more_spawns_after: AtomicUsize,
+
+    /// Has [`Self::reset()`] been called, so the calls before [`Self::expect_no_more_spawns()`]
+    /// may have already been checked by [`Self::checkpoint()`].
+    is_reset: AtomicBool,
+
     /// Has this mock been checked with a call to [`Self::done()`].
     /// If true it is not checked when dropped.
     is_done: bool,
+
+    /// Which calls to [`Self::spawn()`] fail.
+    failure: SpawnFailure,
+
+    /// Called on each call to [`Self::spawn()`], set with [`Self::on_spawn()`].
+    #[cfg(feature = "alloc")]
+    hook: Option<SpawnHook>,
+
+    /// When the spawned tasks are run, or [`None`] if they are never run.
+    #[cfg(all(feature = "runtime", not(feature = "arch-std")))]
+    execution: Option<TaskExecution>,
+
+    /// The names of the tasks passed to [`Self::spawn()`] and where it was called, in call
+    /// order.
+    #[cfg(feature = "std")]
+    spawned: Mutex<Vec<(&'static str, &'static Location<'static>)>>,
+
+    /// The names and arguments of the tasks passed to [`Self::spawn()`] that recorded their
+    /// arguments, in call order.
+    #[cfg(feature = "std")]
+    spawned_args: Mutex<Vec<(&'static str, Box<dyn Any + Send>)>>,
+
+    /// The task pools set with [`Self::pool_size()`], and the singleton pools of the other tasks
+    /// if duplicates are detected.
+    #[cfg(feature = "std")]
+    pools: Mutex<Vec<TaskPool>>,
+
+    /// Is each task without a pool limited to one at a time, set with
+    /// [`Self::detect_duplicate_spawns()`].
+    #[cfg(feature = "std")]
+    detect_duplicates: bool,
+
+    /// The first task that was spawned again before it finished.
+    #[cfg(feature = "std")]
+    duplicate: Mutex<Option<&'static str>>,
+
+    /// The names of the tasks expected to be passed to [`Self::spawn()`], in call order, if set
+    /// with [`Self::expect_order()`].
+    #[cfg(feature = "std")]
+    expected_order: Option<Vec<&'static str>>,
+
+    /// The number of times each task is expected to be passed to [`Self::spawn()`], set with
+    /// [`Self::expect_task()`].
+    #[cfg(feature = "std")]
+    expected_tasks: Vec<(&'static str, usize)>,
+}
+
+/// When the tasks spawned by a [`MockSpawner`] are run, set with
+/// [`MockSpawner::run_tasks_immediately()`] or [`MockSpawner::run_tasks_deferred()`].
+#[cfg(all(feature = "runtime", not(feature = "arch-std")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TaskExecution {
+    /// Run until idle as soon as each task is spawned.
+    Immediate,
+
+    /// Run when [`MockSpawner::run_spawned()`] is called.
+    Deferred,
+}
+
+/// The function called on each call to [`MockSpawner::spawn()`], set with
+/// [`MockSpawner::on_spawn()`].
+#[cfg(feature = "alloc")]
+struct SpawnHook(Box<dyn Fn(usize) -> Result<(), SpawnError> + Send + Sync>);
+
+#[cfg(feature = "alloc")]
+impl fmt::Debug for SpawnHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpawnHook").finish_non_exhaustive()
+    }
+}
+
+/// The pool of a task set with [`MockSpawner::pool_size()`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct TaskPool {
+    /// The name of the task, see [`MockSpawner::spawned_tasks()`].
+    name: &'static str,
+
+    /// The number of the tasks that can be spawned at the same time.
+    size: usize,
+
+    /// The number of the tasks that have been spawned and not finished.
+    used: usize,
+
+    /// Is this the pool of one of a task without a pool size, used to detect duplicate spawns.
+    is_singleton: bool,
+}
+
+/// Which calls to [`MockSpawner::spawn()`] fail, set with [`MockSpawner::fail_spawn()`] or
+/// [`MockSpawner::fail_all_spawns()`].
+#[derive(Debug, Clone, Copy)]
+enum SpawnFailure {
+    /// Every call succeeds.
+    None,
+
+    /// Only the call with this zero-based index fails with the error.
+    At(usize, SpawnError),
+
+    /// Every call fails with the error.
+    All(SpawnError),
 }
 
 impl MockSpawner {
@@ -160,113 +458,1137 @@ impl MockSpawner {
     /// let spawner = MockSpawner::expect(X); // Where `X` is the number of times `spawn()` should be called
     /// ```
     pub const fn expect(expected: usize) -> Self {
+        Self::expect_range(expected..=expected)
+    }
+
+    /// Create a [`MockSpawner`] that expects [`Self::spawn()`] to be called at least `min` times.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, MockSpawnerError, Spawner};
+    ///
+    /// #[embassy_executor::task]
+    /// async fn example_task() {}
+    ///
+    /// let spawner = MockSpawner::expect_at_least(2);
+    /// spawner.spawn(example_task()).unwrap();
+    ///
+    /// let expected = Err(MockSpawnerError::TooFewTasks { min: 2, actual: 1 });
+    /// assert_eq!(spawner.done(), expected);
+    /// ```
+    pub const fn expect_at_least(min: usize) -> Self {
+        Self::expect_range(min..=usize::MAX)
+    }
+
+    /// Create a [`MockSpawner`] that expects [`Self::spawn()`] to be called at most `max` times.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, MockSpawnerError, Spawner};
+    ///
+    /// #[embassy_executor::task(pool_size = 2)]
+    /// async fn example_task() {}
+    ///
+    /// let spawner = MockSpawner::expect_at_most(1);
+    /// spawner.spawn(example_task()).unwrap();
+    /// spawner.spawn(example_task()).unwrap();
+    ///
+    /// let expected = Err(MockSpawnerError::TooManyTasks { max: 1, actual: 2 });
+    /// assert_eq!(spawner.done(), expected);
+    /// ```
+    pub const fn expect_at_most(max: usize) -> Self {
+        Self::expect_range(0..=max)
+    }
+
+    /// Create a [`MockSpawner`] that expects the number of calls to [`Self::spawn()`] to be within
+    /// the `range`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, Spawner};
+    ///
+    /// #[embassy_executor::task(pool_size = 4)]
+    /// async fn worker() {}
+    ///
+    /// fn production_code<S: Spawner>(spawner: &S, workers: usize) {
+    ///     for _ in 0..workers {
+    ///         spawner.must_spawn(worker());
+    ///     }
+    /// }
+    ///
+    /// let spawner = MockSpawner::expect_range(1..=4);
+    /// production_code(&spawner, 3);
+    ///
+    /// assert_eq!(spawner.done(), Ok(()));
+    /// ```
+    pub const fn expect_range(range: RangeInclusive<usize>) -> Self {
         Self {
-            expected,
+            min: *range.start(),
+            max: *range.end(),
             times_called: AtomicUsize::new(0),
+            no_more_spawns_after: AtomicUsize::new(usize::MAX),
+            is_reset: AtomicBool::new(false),
             is_done: false,
+            failure: SpawnFailure::None,
+            #[cfg(feature = "alloc")]
+            hook: None,
+            #[cfg(all(feature = "runtime", not(feature = "arch-std")))]
+            execution: None,
+            #[cfg(feature = "std")]
+            spawned: Mutex::new(Vec::new()),
+            #[cfg(feature = "std")]
+            spawned_args: Mutex::new(Vec::new()),
+            #[cfg(feature = "std")]
+            pools: Mutex::new(Vec::new()),
+            #[cfg(feature = "std")]
+            detect_duplicates: false,
+            #[cfg(feature = "std")]
+            duplicate: Mutex::new(None),
+            #[cfg(feature = "std")]
+            expected_order: None,
+            #[cfg(feature = "std")]
+            expected_tasks: Vec::new(),
         }
     }
 
-    /// Mark the [`MockSpawner`] as done and check if [`Self::spawn()`] was called the correct
-    /// number of times.
+    /// Create a [`MockSpawner`] that expects the tasks called `order` to be passed to
+    /// [`Self::spawn()`] in that order, see [`Self::spawned_tasks()`] for how tasks are named.
     ///
-    /// This is a cleaner way of testing that [`Self::spawn()`] is called the correct number of
-    /// times as [`MockSpawner`] doesn't cause a panic when dropped if this method is called,
-    /// it also returns a [`Result<(), MockSpawnerError>`] which allows checking the outcome of the
-    /// mock.
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, MockSpawnerError, Spawner};
+    ///
+    /// #[embassy_executor::task]
+    /// async fn watchdog() {}
+    ///
+    /// #[embassy_executor::task]
+    /// async fn app() {}
+    ///
+    /// fn init<S: Spawner>(spawner: &S) {
+    ///     spawner.must_spawn(app());
+    ///     spawner.must_spawn(watchdog());
+    /// }
+    ///
+    /// let spawner = MockSpawner::expect_order(["watchdog", "app"]);
+    /// init(&spawner);
+    ///
+    /// let expected = Err(MockSpawnerError::WrongTask {
+    ///     index: 0,
+    ///     expected: "watchdog",
+    ///     actual: "app",
+    /// });
+    /// assert_eq!(spawner.done(), expected);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn expect_order(order: impl IntoIterator<Item = &'static str>) -> Self {
+        let order: Vec<_> = order.into_iter().collect();
+        let mut spawner = Self::expect(order.len());
+        spawner.expected_order = Some(order);
+        spawner
+    }
+
+    /// Expect the task called `name` to be passed to [`Self::spawn()`] `expected` times,
+    /// including the calls that fail, in addition to the expected number of calls in total.
+    ///
+    /// Each task is checked on its own, so the error names the task that was spawned the wrong
+    /// number of times. This replaces any expectation set before for the same task, see
+    /// [`Self::spawned_tasks()`] for how tasks are named.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, MockSpawnerError, Spawner};
+    ///
+    /// #[embassy_executor::task]
+    /// async fn heartbeat() {}
+    ///
+    /// #[embassy_executor::task(pool_size = 2)]
+    /// async fn sensor() {}
+    ///
+    /// fn init<S: Spawner>(spawner: &S) {
+    ///     spawner.must_spawn(sensor());
+    ///     spawner.must_spawn(sensor()); // Should have been `heartbeat()`.
+    /// }
+    ///
+    /// let spawner = MockSpawner::expect(2)
+    ///     .expect_task("heartbeat", 1)
+    ///     .expect_task("sensor", 1);
+    /// init(&spawner);
+    ///
+    /// let expected = Err(MockSpawnerError::WrongTaskCount {
+    ///     name: "heartbeat",
+    ///     expected: 1,
+    ///     actual: 0,
+    /// });
+    /// assert_eq!(spawner.done(), expected);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn expect_task(mut self, name: &'static str, expected: usize) -> Self {
+        self.expected_tasks.retain(|(task, _)| *task != name);
+        self.expected_tasks.push((name, expected));
+        self
+    }
+
+    /// Make the call to [`Self::spawn()`] with the zero-based `index` fail with the `error`, all
+    /// other calls succeed.
+    ///
+    /// The failed call still counts towards the expected number of tasks. This replaces any
+    /// failure set before.
     ///
     /// # Examples
     ///
     /// ```
     /// # #![feature(type_alias_impl_trait)]
     /// #
+    /// use embassy_executor::SpawnError;
     /// use embassy_mock::executor::{MockSpawner, Spawner};
     ///
     /// #[embassy_executor::task]
     /// async fn example_task() {}
     ///
-    /// let spawner = MockSpawner::expect(1);
-    /// spawner.spawn(example_task()).unwrap();
+    /// fn production_code<S: Spawner>(spawner: &S) -> usize {
+    ///     let mut retries = 0;
+    ///     while spawner.spawn(example_task()).is_err() {
+    ///         retries += 1;
+    ///     }
+    ///     retries
+    /// }
     ///
-    /// let res = spawner.done();
+    /// let spawner = MockSpawner::expect(2).fail_spawn(0, SpawnError::Busy);
+    /// assert_eq!(production_code(&spawner), 1);
     ///
-    /// assert_eq!(res, Ok(()));
+    /// assert_eq!(spawner.done(), Ok(()));
     /// ```
+    pub const fn fail_spawn(mut self, index: usize, error: SpawnError) -> Self {
+        self.failure = SpawnFailure::At(index, error);
+        self
+    }
+
+    /// Make every call to [`Self::spawn()`] fail with the `error`.
+    ///
+    /// The failed calls still count towards the expected number of tasks. This replaces any
+    /// failure set before.
+    ///
+    /// # Examples
     ///
     /// ```
     /// # #![feature(type_alias_impl_trait)]
     /// #
-    /// use embassy_mock::executor::{MockSpawner, MockSpawnerError, Spawner};
+    /// use embassy_executor::SpawnError;
+    /// use embassy_mock::executor::{MockSpawner, Spawner};
     ///
     /// #[embassy_executor::task]
     /// async fn example_task() {}
     ///
-    /// let spawner = MockSpawner::expect(4);
-    /// spawner.spawn(example_task()).unwrap();
+    /// let spawner = MockSpawner::expect(2).fail_all_spawns(SpawnError::Busy);
     ///
-    /// let res = spawner.done();
+    /// assert!(spawner.spawn(example_task()).is_err());
+    /// assert!(spawner.spawn(example_task()).is_err());
+    /// assert_eq!(spawner.done(), Ok(()));
+    /// ```
+    pub const fn fail_all_spawns(mut self, error: SpawnError) -> Self {
+        self.failure = SpawnFailure::All(error);
+        self
+    }
+
+    /// Call the `hook` with the zero-based index of each call to [`Self::spawn()`], before the
+    /// task is spawned.
     ///
-    /// let expected = Err(MockSpawnerError::WrongNumberOfTasks {
-    ///     expected: 4,
-    ///     actual: 1,
+    /// The call fails with the error that the `hook` returns, unless it is already set to fail
+    /// with [`Self::fail_spawn()`] or [`Self::fail_all_spawns()`]. This replaces any hook set
+    /// before.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use core::sync::atomic::{AtomicBool, Ordering};
+    /// use embassy_executor::SpawnError;
+    /// use embassy_mock::executor::{MockSpawner, Spawner};
+    ///
+    /// static IS_OUT_OF_MEMORY: AtomicBool = AtomicBool::new(false);
+    ///
+    /// #[embassy_executor::task(pool_size = 3)]
+    /// async fn example_task() {}
+    ///
+    /// let spawner = MockSpawner::expect(3).on_spawn(|index| {
+    ///     // Every spawn after the first fails.
+    ///     if IS_OUT_OF_MEMORY.swap(true, Ordering::Relaxed) {
+    ///         return Err(SpawnError::Busy);
+    ///     }
+    ///     assert_eq!(index, 0);
+    ///     Ok(())
     /// });
-    /// assert_eq!(res, expected);
     ///
-    /// // This doesn't panic when `spawner` is dropped as `spawner.done()` was called.
+    /// assert!(spawner.spawn(example_task()).is_ok());
+    /// assert!(spawner.spawn(example_task()).is_err());
+    /// assert!(spawner.spawn(example_task()).is_err());
+    /// assert_eq!(spawner.done(), Ok(()));
     /// ```
-    pub fn done(mut self) -> Result<(), MockSpawnerError> {
-        let times_called = self.times_called.load(Ordering::Relaxed);
-        let res = if times_called != self.expected {
-            Err(MockSpawnerError::WrongNumberOfTasks {
-                expected: self.expected,
-                actual: times_called,
-            })
-        } else {
-            Ok(())
-        };
-
-        self.is_done = true;
-        res
+    #[cfg(feature = "alloc")]
+    pub fn on_spawn(
+        mut self,
+        hook: impl Fn(usize) -> Result<(), SpawnError> + Send + Sync + 'static,
+    ) -> Self {
+        self.hook = Some(SpawnHook(Box::new(hook)));
+        self
     }
-}
 
-impl Drop for MockSpawner {
-    /// If [`Self::done()`] has not been called before being dropped then check that the number of
-    /// times [`Self::spawn()`] was called is as expected.
-    fn drop(&mut self) {
-        if !self.is_done {
-            let times_called = self.times_called.load(Ordering::Relaxed);
-            assert_eq!(
-                self.expected, times_called,
-                "expected to spawn {} task(s), actually spawned {}",
-                self.expected, times_called
-            );
-        }
+    /// Limit the task called `name` to `size` spawned tasks at the same time, like the
+    /// `pool_size` of `#[embassy_executor::task]`.
+    ///
+    /// Once the pool is full, [`Self::spawn()`] returns [`SpawnError::Busy`] for the task until
+    /// one of them is marked as finished with [`Self::finish_task()`], as the tasks forgotten by
+    /// the [`MockSpawner`] never finish. The failed calls still count towards the expected number
+    /// of tasks. See [`Self::spawned_tasks()`] for how tasks are named.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_executor::SpawnError;
+    /// use embassy_mock::executor::{MockSpawner, Spawner};
+    ///
+    /// #[embassy_executor::task(pool_size = 4)]
+    /// async fn worker() {}
+    ///
+    /// let spawner = MockSpawner::expect(4).pool_size("worker", 2);
+    /// assert!(spawner.spawn(worker()).is_ok());
+    /// assert!(spawner.spawn(worker()).is_ok());
+    /// assert!(matches!(spawner.spawn(worker()), Err(SpawnError::Busy)));
+    ///
+    /// spawner.finish_task("worker");
+    /// assert!(spawner.spawn(worker()).is_ok());
+    /// assert_eq!(spawner.done(), Ok(()));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn pool_size(mut self, name: &'static str, size: usize) -> Self {
+        let pools = self.pools.get_mut().unwrap();
+        pools.retain(|pool| pool.name != name);
+        pools.push(TaskPool {
+            name,
+            size,
+            used: 0,
+            is_singleton: false,
+        });
+        self
     }
-}
-
-impl Spawner for MockSpawner {
-    /// Increment an internal counter of how many times this method is called.
-    fn spawn<S>(&self, token: SpawnToken<S>) -> Result<(), SpawnError> {
-        // Need to forget the token so that it is not dropped which causes a panic
-        core::mem::forget(token);
-        let times_called = self
-            .times_called
-            .load(Ordering::Relaxed)
-            .checked_add(1)
-            .unwrap();
-        self.times_called.store(times_called, Ordering::Relaxed);
 
-        Ok(())
+    /// Mark one of the spawned tasks called `name` as finished, freeing its slot in the pool set
+    /// with [`Self::pool_size()`].
+    ///
+    /// This does nothing if the task has no pool or none of its tasks are spawned.
+    #[cfg(feature = "std")]
+    pub fn finish_task(&self, name: &str) {
+        let mut pools = self.pools.lock().unwrap();
+        if let Some(pool) = pools.iter_mut().find(|pool| pool.name == name) {
+            pool.used = pool.used.saturating_sub(1);
+        }
+    }
+
+    /// Report each task that is spawned again before it finished, as the tasks have a
+    /// `pool_size` of 1 by default.
+    ///
+    /// Spawning the task again returns [`SpawnError::Busy`], the same as the real spawner, and
+    /// fails the check with [`MockSpawnerError::DuplicateTask`]. Mark the task as finished with
+    /// [`Self::finish_task()`] to spawn it again. Tasks with a pool size set with
+    /// [`Self::pool_size()`] can fill their pool without being reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, MockSpawnerError, Spawner};
+    ///
+    /// #[embassy_executor::task]
+    /// async fn watchdog() {}
+    ///
+    /// fn init<S: Spawner>(spawner: &S) {
+    ///     let _ = spawner.spawn(watchdog());
+    ///     let _ = spawner.spawn(watchdog()); // Spawned twice by mistake.
+    /// }
+    ///
+    /// let spawner = MockSpawner::expect(2).detect_duplicate_spawns();
+    /// init(&spawner);
+    ///
+    /// let expected = Err(MockSpawnerError::DuplicateTask { name: "watchdog" });
+    /// assert_eq!(spawner.done(), expected);
+    /// ```
+    #[cfg(feature = "std")]
+    pub const fn detect_duplicate_spawns(mut self) -> Self {
+        self.detect_duplicates = true;
+        self
+    }
+
+    /// Use a slot of the pool of the task called `name`, if it has one.
+    #[cfg(feature = "std")]
+    fn use_pool_slot(&self, name: &'static str) -> Result<(), SpawnError> {
+        let mut pools = self.pools.lock().unwrap();
+        let pool = match pools.iter_mut().position(|pool| pool.name == name) {
+            Some(index) => &mut pools[index],
+            None if self.detect_duplicates => {
+                pools.push(TaskPool {
+                    name,
+                    size: 1,
+                    used: 0,
+                    is_singleton: true,
+                });
+                pools.last_mut().unwrap()
+            }
+            None => return Ok(()),
+        };
+
+        if pool.used < pool.size {
+            pool.used += 1;
+            return Ok(());
+        }
+        if pool.is_singleton {
+            self.duplicate.lock().unwrap().get_or_insert(name);
+        }
+        Err(SpawnError::Busy)
+    }
+
+    /// Run each task as soon as it is spawned, until it completes or is waiting for something
+    /// else to happen, instead of forgetting it.
+    ///
+    /// The tasks are run on a [`MockExecutor`] for the current thread, see
+    /// [`Self::run_spawned()`] to run them again once they are waiting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use core::sync::atomic::{AtomicBool, Ordering};
+    /// use embassy_mock::executor::{MockSpawner, Spawner};
+    ///
+    /// static IS_INITIALISED: AtomicBool = AtomicBool::new(false);
+    ///
+    /// #[embassy_executor::task]
+    /// async fn init_task() {
+    ///     IS_INITIALISED.store(true, Ordering::Relaxed);
+    /// }
+    ///
+    /// let spawner = MockSpawner::expect(1).run_tasks_immediately();
+    /// spawner.must_spawn(init_task());
+    ///
+    /// assert!(IS_INITIALISED.load(Ordering::Relaxed));
+    /// ```
+    #[cfg(all(feature = "runtime", not(feature = "arch-std")))]
+    pub const fn run_tasks_immediately(mut self) -> Self {
+        self.execution = Some(TaskExecution::Immediate);
+        self
+    }
+
+    /// Keep the spawned tasks to be run by [`Self::run_spawned()`] instead of forgetting them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use core::sync::atomic::{AtomicUsize, Ordering};
+    /// use embassy_mock::executor::{MockSpawner, Spawner};
+    ///
+    /// static STARTED: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// #[embassy_executor::task(pool_size = 2)]
+    /// async fn worker() {
+    ///     STARTED.fetch_add(1, Ordering::Relaxed);
+    /// }
+    ///
+    /// let spawner = MockSpawner::expect(2).run_tasks_deferred();
+    /// spawner.must_spawn(worker());
+    /// spawner.must_spawn(worker());
+    /// assert_eq!(STARTED.load(Ordering::Relaxed), 0);
+    ///
+    /// spawner.run_spawned();
+    /// assert_eq!(STARTED.load(Ordering::Relaxed), 2);
+    /// ```
+    #[cfg(all(feature = "runtime", not(feature = "arch-std")))]
+    pub const fn run_tasks_deferred(mut self) -> Self {
+        self.execution = Some(TaskExecution::Deferred);
+        self
+    }
+
+    /// Run the tasks spawned on the current thread by the [`MockSpawner`]s that are set to run
+    /// them, until none of them are ready to make progress.
+    ///
+    /// This runs the [`MockExecutor`] for the current thread, which is shared by all of the
+    /// [`MockSpawner`]s that run their tasks. See [`MockExecutor::run_until_idle()`].
+    #[cfg(all(feature = "runtime", not(feature = "arch-std")))]
+    pub fn run_spawned(&self) {
+        EXECUTOR.with(MockExecutor::run_until_idle);
+    }
+
+    /// Expect the next [`MockSpawner`] created with [`Spawner::for_current_executor()`] on the
+    /// current thread to spawn `expected` tasks.
+    ///
+    /// Unlike a [`MockSpawner`] created with [`Spawner::for_current_executor()`] normally, the
+    /// created spawner is checked when it is dropped and panics if the expectation is not met.
+    /// Calling this multiple times sets the expectations for the following spawners in the order
+    /// they are created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::executor::{MockSpawner, Spawner};
+    ///
+    /// #[embassy_executor::task]
+    /// async fn example_task() {}
+    ///
+    /// async fn production_code<S: Spawner>() {
+    ///     let spawner = S::for_current_executor().await;
+    ///     spawner.must_spawn(example_task());
+    /// }
+    ///
+    /// MockSpawner::expect_for_current_executor(1);
+    /// block_on(production_code::<MockSpawner>());
+    /// ```
+    ///
+    /// ```should_panic
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::executor::{MockSpawner, Spawner};
+    ///
+    /// async fn production_code<S: Spawner>() {
+    ///     let _spawner = S::for_current_executor().await; // Doesn't spawn anything.
+    /// }
+    ///
+    /// MockSpawner::expect_for_current_executor(1);
+    /// block_on(production_code::<MockSpawner>()); // Panics when the spawner is dropped.
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn expect_for_current_executor(expected: usize) {
+        EXPECTED_FOR_CURRENT_EXECUTOR.with(|expectations| {
+            expectations.borrow_mut().push_back(expected);
+        });
+    }
+
+    /// Mark the [`MockSpawner`] as done and check if [`Self::spawn()`] was called the correct
+    /// number of times.
+    ///
+    /// This is a cleaner way of testing that [`Self::spawn()`] is called the correct number of
+    /// times as [`MockSpawner`] doesn't cause a panic when dropped if this method is called,
+    /// it also returns a [`Result<(), MockSpawnerError>`] which allows checking the outcome of the
+    /// mock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, Spawner};
+    ///
+    /// #[embassy_executor::task]
+    /// async fn example_task() {}
+    ///
+    /// let spawner = MockSpawner::expect(1);
+    /// spawner.spawn(example_task()).unwrap();
+    ///
+    /// let res = spawner.done();
+    ///
+    /// assert_eq!(res, Ok(()));
+    /// ```
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, MockSpawnerError, Spawner};
+    ///
+    /// #[embassy_executor::task]
+    /// async fn example_task() {}
+    ///
+    /// let spawner = MockSpawner::expect(4);
+    /// spawner.spawn(example_task()).unwrap();
+    ///
+    /// let res = spawner.done();
+    ///
+    /// let expected = Err(MockSpawnerError::WrongNumberOfTasks {
+    ///     expected: 4,
+    ///     actual: 1,
+    /// });
+    /// assert_eq!(res, expected);
+    ///
+    /// // This doesn't panic when `spawner` is dropped as `spawner.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockSpawnerError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check the calls to [`Self::spawn()`] so far, the same as [`Self::done()`], and then
+    /// [`Self::reset()`] so that the same expectations apply to the next phase of the test.
+    ///
+    /// The calls are forgotten whether or not the check passes. The [`MockSpawner`] is still
+    /// checked when dropped, for the calls made since the last checkpoint.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, MockSpawnerError, Spawner};
+    ///
+    /// #[embassy_executor::task(pool_size = 3)]
+    /// async fn example_task() {}
+    ///
+    /// fn setup<S: Spawner>(spawner: &S) {
+    ///     spawner.must_spawn(example_task());
+    /// }
+    ///
+    /// let spawner = MockSpawner::expect(1);
+    /// setup(&spawner);
+    /// assert_eq!(spawner.checkpoint(), Ok(()));
+    ///
+    /// setup(&spawner);
+    /// setup(&spawner);
+    /// let expected = Err(MockSpawnerError::WrongNumberOfTasks {
+    ///     expected: 1,
+    ///     actual: 2,
+    /// });
+    /// assert_eq!(spawner.checkpoint(), expected);
+    ///
+    /// setup(&spawner);
+    /// assert_eq!(spawner.done(), Ok(()));
+    /// ```
+    pub fn checkpoint(&self) -> Result<(), MockSpawnerError> {
+        let result = self.check();
+        self.reset();
+        result
+    }
+
+    /// Forget the calls to [`Self::spawn()`] so far without checking them, keeping the
+    /// expectations and the failures that are set.
+    ///
+    /// The calls are counted from zero again, including for [`Self::fail_spawn()`], the
+    /// recorded names and arguments of the spawned tasks are cleared and the pools set with
+    /// [`Self::pool_size()`] are emptied. Tasks that are already being run are not affected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, Spawner};
+    ///
+    /// #[embassy_executor::task(pool_size = 3)]
+    /// async fn example_task() {}
+    ///
+    /// let spawner = MockSpawner::expect(1);
+    /// spawner.must_spawn(example_task());
+    /// spawner.must_spawn(example_task());
+    ///
+    /// spawner.reset();
+    ///
+    /// spawner.must_spawn(example_task());
+    /// assert_eq!(spawner.done(), Ok(()));
+    /// ```
+    pub fn reset(&self) {
+        self.times_called.store(0, Ordering::Relaxed);
+        self.is_reset.store(true, Ordering::Relaxed);
+        // Still expect no more calls after the reset.
+        let _ =
+            self.no_more_spawns_after
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |after| {
+                    (after != usize::MAX).then_some(0)
+                });
+        #[cfg(feature = "std")]
+        {
+            self.spawned.lock().unwrap().clear();
+            self.spawned_args.lock().unwrap().clear();
+            for pool in self.pools.lock().unwrap().iter_mut() {
+                pool.used = 0;
+            }
+            *self.duplicate.lock().unwrap() = None;
+        }
+    }
+
+    /// Expect no more calls to [`Self::spawn()`], such as once the code under test has entered a
+    /// low-power state.
+    ///
+    /// The check fails with [`MockSpawnerError::UnexpectedSpawns`] if any task is spawned
+    /// afterwards. The calls made before this are still checked against the other expectations,
+    /// unless there were none since the last [`Self::checkpoint()`] or [`Self::reset()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, MockSpawnerError, Spawner};
+    ///
+    /// #[embassy_executor::task(pool_size = 2)]
+    /// async fn sensor() {}
+    ///
+    /// fn wake<S: Spawner>(spawner: &S) {
+    ///     spawner.must_spawn(sensor());
+    /// }
+    ///
+    /// let spawner = MockSpawner::expect(1);
+    /// wake(&spawner);
+    /// assert_eq!(spawner.checkpoint(), Ok(()));
+    ///
+    /// // Enter the low-power state...
+    /// spawner.expect_no_more_spawns();
+    /// wake(&spawner); // Woken by mistake.
+    ///
+    /// let expected = Err(MockSpawnerError::UnexpectedSpawns { actual: 1 });
+    /// assert_eq!(spawner.done(), expected);
+    /// ```
+    pub fn expect_no_more_spawns(&self) {
+        let times_called = self.times_called.load(Ordering::Relaxed);
+        self.no_more_spawns_after
+            .store(times_called, Ordering::Relaxed);
+    }
+
+    /// Check that the tasks were spawned in the expected order and [`Self::spawn()`] was called
+    /// the expected number of times.
+    fn check(&self) -> Result<(), MockSpawnerError> {
+        let times_called = self.times_called.load(Ordering::Relaxed);
+        let no_more_spawns_after = self.no_more_spawns_after.load(Ordering::Relaxed);
+        if no_more_spawns_after == usize::MAX {
+            return self.check_expectations(times_called);
+        }
+
+        let actual = times_called.saturating_sub(no_more_spawns_after);
+        ensure!(actual == 0, UnexpectedSpawnsSnafu { actual });
+        if no_more_spawns_after == 0 && self.is_reset.load(Ordering::Relaxed) {
+            // Nothing was spawned since the last checkpoint.
+            return Ok(());
+        }
+        self.check_expectations(no_more_spawns_after)
+    }
+
+    /// Check the first `times_called` calls to [`Self::spawn()`] against the expected order and
+    /// number of calls.
+    fn check_expectations(&self, times_called: usize) -> Result<(), MockSpawnerError> {
+        #[cfg(feature = "std")]
+        if let Some(name) = *self.duplicate.lock().unwrap() {
+            return DuplicateTaskSnafu { name }.fail();
+        }
+
+        #[cfg(feature = "std")]
+        if let Some(expected_order) = &self.expected_order {
+            let spawned = self.spawned.lock().unwrap();
+            let wrong_task = expected_order
+                .iter()
+                .zip(spawned.iter().map(|(name, _)| name))
+                .enumerate()
+                .find(|(_, (expected, actual))| expected != actual);
+            if let Some((index, (&expected, &actual))) = wrong_task {
+                return WrongTaskSnafu {
+                    index,
+                    expected,
+                    actual,
+                }
+                .fail();
+            }
+        }
+
+        #[cfg(feature = "std")]
+        for &(name, expected) in &self.expected_tasks {
+            let actual = self.times_spawned(name);
+            ensure!(
+                actual == expected,
+                WrongTaskCountSnafu {
+                    name,
+                    expected,
+                    actual,
+                }
+            );
+        }
+
+        if self.min == self.max {
+            ensure!(
+                times_called == self.min,
+                WrongNumberOfTasksSnafu {
+                    expected: self.min,
+                    actual: times_called,
+                }
+            );
+        }
+        ensure!(
+            times_called >= self.min,
+            TooFewTasksSnafu {
+                min: self.min,
+                actual: times_called,
+            }
+        );
+        ensure!(
+            times_called <= self.max,
+            TooManyTasksSnafu {
+                max: self.max,
+                actual: times_called,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// The names of the tasks passed to [`Self::spawn()`] on this [`MockSpawner`], in call
+    /// order, including the calls that were set to fail.
+    ///
+    /// The name is the name of the function marked with `#[embassy_executor::task]`, without its
+    /// module path. It is taken from [`core::any::type_name()`] of the task's future, so it is
+    /// meant for tests and not guaranteed to be stable between compiler versions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, Spawner};
+    ///
+    /// #[embassy_executor::task]
+    /// async fn task_a() {}
+    ///
+    /// #[embassy_executor::task]
+    /// async fn task_b() {}
+    ///
+    /// let spawner = MockSpawner::expect(2);
+    /// spawner.spawn(task_a()).unwrap();
+    /// spawner.spawn(task_b()).unwrap();
+    ///
+    /// assert_eq!(spawner.spawned_tasks(), ["task_a", "task_b"]);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn spawned_tasks(&self) -> Vec<&'static str> {
+        let spawned = self.spawned.lock().unwrap();
+        spawned.iter().map(|(name, _)| *name).collect()
+    }
+
+    /// The number of times a task called `name` was passed to [`Self::spawn()`] on this
+    /// [`MockSpawner`], see [`Self::spawned_tasks()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, Spawner};
+    ///
+    /// #[embassy_executor::task(pool_size = 2)]
+    /// async fn task_a() {}
+    ///
+    /// #[embassy_executor::task]
+    /// async fn task_b() {}
+    ///
+    /// let spawner = MockSpawner::expect(2);
+    /// spawner.spawn(task_a()).unwrap();
+    /// spawner.spawn(task_a()).unwrap();
+    ///
+    /// assert_eq!(spawner.times_spawned("task_a"), 2);
+    /// assert_eq!(spawner.times_spawned("task_b"), 0); // `task_b` was forgotten.
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn times_spawned(&self, name: &str) -> usize {
+        let spawned = self.spawned.lock().unwrap();
+        spawned
+            .iter()
+            .filter(|(spawned, _)| *spawned == name)
+            .count()
+    }
+
+    /// The names of the tasks passed to [`Self::spawn()`] on this [`MockSpawner`] and where it
+    /// was called from, in call order, see [`Self::spawned_tasks()`].
+    ///
+    /// These are also listed when the [`MockSpawner`] panics as it is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, Spawner};
+    ///
+    /// #[embassy_executor::task]
+    /// async fn example_task() {}
+    ///
+    /// let spawner = MockSpawner::expect(1);
+    /// spawner.must_spawn(example_task());
+    ///
+    /// let sites = spawner.spawn_sites();
+    /// assert_eq!(sites[0].0, "example_task");
+    /// assert_eq!(sites[0].1.line(), line!() - 4);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn spawn_sites(&self) -> Vec<(&'static str, &'static Location<'static>)> {
+        self.spawned.lock().unwrap().clone()
+    }
+
+    /// The arguments that the task called `name` was created with each time it was passed to
+    /// [`Self::spawn()`] on this [`MockSpawner`], in call order, as a tuple of type `A`.
+    ///
+    /// Only the tasks declared with `#[embassy_mock::task(record_args)]` record their arguments,
+    /// see [`Self::spawned_tasks()`] for how tasks are named.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arguments of the task are not the tuple `A`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, Spawner};
+    /// use embassy_time::Duration;
+    ///
+    /// #[embassy_mock::task(record_args)]
+    /// async fn blink(led: u8, period: Duration) {}
+    ///
+    /// fn spawn_tasks<S: Spawner>(spawner: &S) {
+    ///     spawner.must_spawn(blink(2, Duration::from_secs(5)));
+    /// }
+    ///
+    /// let spawner = MockSpawner::expect(1);
+    /// spawn_tasks(&spawner);
+    ///
+    /// let args = spawner.spawned_args::<(u8, Duration)>("blink");
+    /// assert_eq!(args, [(2, Duration::from_secs(5))]);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn spawned_args<A: Any + Clone>(&self, name: &str) -> Vec<A> {
+        let spawned_args = self.spawned_args.lock().unwrap();
+        spawned_args
+            .iter()
+            .filter(|(spawned, _)| *spawned == name)
+            .map(|(_, args)| match args.downcast_ref::<A>() {
+                Some(args) => args.clone(),
+                None => panic!(
+                    "the arguments of `{name}` are not `{}`",
+                    core::any::type_name::<A>()
+                ),
+            })
+            .collect()
+    }
+}
+
+/// The name of the task that creates a [`SpawnToken<S>`], the last segment of the path of its
+/// future.
+#[cfg(feature = "std")]
+fn task_name<S>() -> &'static str {
+    let path = core::any::type_name::<S>();
+    let path = path.strip_suffix("::{{closure}}").unwrap_or(path);
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+impl Drop for MockSpawner {
+    /// If [`Self::done()`] has not been called before being dropped then check that the order and
+    /// number of times [`Self::spawn()`] was called are as expected.
+    ///
+    /// The panic message lists where each task was spawned, see [`Self::spawn_sites()`].
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        #[cfg(feature = "std")]
+        let is_panicking = std::thread::panicking();
+        #[cfg(not(feature = "std"))]
+        let is_panicking = false;
+
+        if !self.is_done && !is_panicking {
+            if let Err(error) = self.check() {
+                #[cfg(feature = "std")]
+                panic!("{error}{}", SpawnSites(&self.spawned.lock().unwrap()));
+                #[cfg(not(feature = "std"))]
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+/// Lists where each task was spawned, one per line after the message of a panic.
+#[cfg(feature = "std")]
+struct SpawnSites<'a>(&'a [(&'static str, &'static Location<'static>)]);
+
+#[cfg(feature = "std")]
+impl fmt::Display for SpawnSites<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "\nno tasks were spawned");
+        }
+
+        write!(f, "\ntasks were spawned at:")?;
+        for (index, (name, location)) in self.0.iter().enumerate() {
+            write!(f, "\n  {index}: {name} at {location}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Spawner for MockSpawner {
+    /// Create a [`MockSpawner`] that doesn't require [`Self::done()`] to be called.
+    /// This allows a [`MockSpawner`] to be created in production code instead of in the test.
+    ///
+    /// The spawner is only checked when dropped if its expectation was set beforehand with
+    /// [`Self::expect_for_current_executor()`]. The returned future is always ready.
+    fn for_current_executor() -> impl Future<Output = Self> {
+        #[cfg(feature = "std")]
+        let expected = EXPECTED_FOR_CURRENT_EXECUTOR
+            .with(|expectations| expectations.borrow_mut().pop_front());
+        #[cfg(not(feature = "std"))]
+        let expected: Option<usize> = None;
+
+        // Only checked if the expectation was set with `expect_for_current_executor()`.
+        let (expected, is_done) = match expected {
+            Some(expected) => (expected, false),
+            None => (0, true),
+        };
+
+        let mut spawner = Self::expect(expected);
+        spawner.is_done = is_done;
+        ready(spawner)
+    }
+
+    /// Increment an internal counter of how many times this method is called.
+    ///
+    /// Returns the error set with [`Self::fail_spawn()`] or [`Self::fail_all_spawns()`] if this
+    /// call is set to fail.
+    ///
+    /// The task is forgotten unless this spawner is set to run it with
+    /// [`Self::run_tasks_immediately()`] or [`Self::run_tasks_deferred()`].
+    #[track_caller]
+    fn spawn<S>(&self, token: SpawnToken<S>) -> Result<(), SpawnError> {
+        let index = self
+            .times_called
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                count.checked_add(1)
+            })
+            .unwrap();
+        #[cfg(feature = "std")]
+        let name = task_name::<S>();
+        #[cfg(feature = "std")]
+        {
+            self.spawned
+                .lock()
+                .unwrap()
+                .push((name, Location::caller()));
+
+            // Arguments recorded by a task that was never spawned here are discarded.
+            let args = TASK_ARGS.with(|recorded| recorded.borrow_mut().take());
+            if let Some((recorded, args)) = args.filter(|(recorded, _)| *recorded == name) {
+                self.spawned_args.lock().unwrap().push((recorded, args));
+            }
+        }
+
+        let failure = match self.failure {
+            SpawnFailure::At(failed, error) if failed == index => Some(error),
+            SpawnFailure::All(error) => Some(error),
+            SpawnFailure::At(..) | SpawnFailure::None => None,
+        };
+        #[cfg(feature = "alloc")]
+        let failure = match &self.hook {
+            Some(SpawnHook(hook)) => {
+                // The hook is called even if this call is already set to fail.
+                let hooked = hook(index);
+                failure.or(hooked.err())
+            }
+            None => failure,
+        };
+        // A task that fails to spawn for another reason doesn't use a slot of its pool.
+        #[cfg(feature = "std")]
+        let failure = failure.or_else(|| self.use_pool_slot(name).err());
+
+        #[cfg(all(feature = "runtime", not(feature = "arch-std")))]
+        if let (None, Some(execution)) = (failure, self.execution) {
+            return EXECUTOR.with(|executor| {
+                executor.spawner().spawn(token)?;
+                // A task spawning another is already being run by the executor.
+                if execution == TaskExecution::Immediate && !executor.is_running() {
+                    executor.run_until_idle();
+                }
+                Ok(())
+            });
+        }
+
+        // Need to forget the token so that it is not dropped which causes a panic
+        core::mem::forget(token);
+        failure.map_or(Ok(()), Err)
+    }
+
+    /// Increment the same internal counter as [`Self::spawn()`], so a task spawned with either
+    /// method counts towards the expected number of tasks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Self::spawn()`] returns an error, the same as the real spawner.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use embassy_mock::executor::{MockSpawner, Spawner};
+    ///
+    /// #[embassy_executor::task]
+    /// async fn example_task() {}
+    ///
+    /// fn production_code<S: Spawner>(spawner: &S) {
+    ///     spawner.must_spawn(example_task());
+    /// }
+    ///
+    /// let spawner = MockSpawner::expect(1);
+    /// production_code(&spawner);
+    ///
+    /// assert_eq!(spawner.done(), Ok(()));
+    /// ```
+    #[track_caller]
+    fn must_spawn<S>(&self, token: SpawnToken<S>) {
+        if let Err(error) = self.spawn(token) {
+            panic!("failed to spawn task: {error:?}");
+        }
+    }
+}
+
+// A test can share one `MockSpawner` between the threads that run the code under test, which is
+// why its counters are atomic, so a new field mustn't make it `!Sync`.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<MockSpawner>();
+    assert_send_sync::<MockSpawnerError>();
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embassy_futures::block_on;
+
+    #[embassy_executor::task]
+    async fn example_task() {}
+
+    #[embassy_executor::task]
+    async fn task_with_args(_value: u32) {}
+
+    #[cfg(feature = "arch-std")]
+    #[test]
+    fn real_executor_is_an_executor() {
+        fn assert_executor<E: Executor<Output = core::convert::Infallible>>() {}
+
+        assert_executor::<embassy_executor::Executor>();
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[embassy_executor::task]
-    async fn example_task() {}
 
     #[test]
     fn can_spawn_single_task_just_drop() {
@@ -298,6 +1620,590 @@ mod tests {
         spawner.spawn(example_task()).unwrap();
     }
 
+    #[test]
+    fn must_spawn_counts_with_spawn() {
+        let spawner = MockSpawner::expect(2);
+        spawner.spawn(example_task()).unwrap();
+        spawner.must_spawn(example_task());
+
+        assert_eq!(spawner.done(), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to spawn 1 task(s), actually spawned 2")]
+    fn must_spawn_too_many_tasks_just_drop() {
+        let spawner = MockSpawner::expect(1);
+        spawner.must_spawn(example_task());
+        spawner.must_spawn(example_task());
+    }
+
+    #[test]
+    fn for_current_executor_is_not_checked_without_expectation() {
+        let spawner = block_on(MockSpawner::for_current_executor());
+        spawner.spawn(example_task()).unwrap();
+    }
+
+    #[test]
+    fn for_current_executor_uses_expectations_in_order() {
+        MockSpawner::expect_for_current_executor(1);
+        MockSpawner::expect_for_current_executor(2);
+
+        let first = block_on(MockSpawner::for_current_executor());
+        let second = block_on(MockSpawner::for_current_executor());
+        first.spawn(example_task()).unwrap();
+        second.spawn(example_task()).unwrap();
+        second.spawn(example_task()).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to spawn 2 task(s), actually spawned 1")]
+    fn for_current_executor_with_expectation_checked_on_drop() {
+        MockSpawner::expect_for_current_executor(2);
+
+        let spawner = block_on(MockSpawner::for_current_executor());
+        spawner.spawn(example_task()).unwrap();
+    }
+
+    #[test]
+    fn fail_spawn_fails_only_that_call() {
+        let spawner = MockSpawner::expect(3).fail_spawn(1, SpawnError::Busy);
+
+        assert!(spawner.spawn(example_task()).is_ok());
+        assert!(matches!(
+            spawner.spawn(example_task()),
+            Err(SpawnError::Busy)
+        ));
+        assert!(spawner.spawn(example_task()).is_ok());
+        assert_eq!(spawner.done(), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "failed to spawn task: Busy")]
+    fn must_spawn_panics_on_failure() {
+        let spawner = MockSpawner::expect(1).fail_all_spawns(SpawnError::Busy);
+        spawner.must_spawn(example_task());
+    }
+
+    #[test]
+    fn records_names_of_spawned_tasks() {
+        let spawner = MockSpawner::expect(3).fail_spawn(2, SpawnError::Busy);
+        spawner.spawn(example_task()).unwrap();
+        spawner.must_spawn(task_with_args(5));
+        let _ = spawner.spawn(example_task());
+
+        assert_eq!(
+            spawner.spawned_tasks(),
+            ["example_task", "task_with_args", "example_task"]
+        );
+        assert_eq!(spawner.times_spawned("example_task"), 2);
+        assert_eq!(spawner.times_spawned("task_with_args"), 1);
+        assert_eq!(spawner.times_spawned("other_task"), 0);
+    }
+
+    #[cfg(feature = "macros")]
+    mod recording_args {
+        use super::*;
+
+        #[crate::task(record_args, pool_size = 4)]
+        async fn recorded_task(_id: u32, _name: &'static str) {}
+
+        #[test]
+        fn records_args_of_spawned_tasks_in_order() {
+            let spawner = MockSpawner::expect(4).fail_spawn(2, SpawnError::Busy);
+            spawner.must_spawn(recorded_task(1, "first"));
+            spawner.must_spawn(task_with_args(7));
+            let _ = spawner.spawn(recorded_task(2, "second"));
+            spawner.must_spawn(recorded_task(3, "third"));
+
+            assert_eq!(
+                spawner.spawned_args::<(u32, &str)>("recorded_task"),
+                [(1, "first"), (2, "second"), (3, "third")]
+            );
+            assert_eq!(spawner.times_spawned("recorded_task"), 3);
+        }
+
+        #[test]
+        fn tasks_that_do_not_record_have_no_args() {
+            let spawner = MockSpawner::expect(1);
+            spawner.must_spawn(task_with_args(7));
+
+            assert!(spawner.spawned_args::<(u32,)>("task_with_args").is_empty());
+        }
+
+        #[test]
+        fn args_of_task_not_spawned_are_discarded() {
+            core::mem::forget(recorded_task(1, "forgotten"));
+
+            let spawner = MockSpawner::expect(1);
+            spawner.must_spawn(example_task());
+
+            assert!(spawner
+                .spawned_args::<(u32, &str)>("recorded_task")
+                .is_empty());
+        }
+
+        #[test]
+        #[should_panic(expected = "the arguments of `recorded_task` are not `(u32,)`")]
+        fn args_of_wrong_type_panics() {
+            let spawner = MockSpawner::expect(1);
+            spawner.must_spawn(recorded_task(1, "first"));
+
+            let _ = spawner.spawned_args::<(u32,)>("recorded_task");
+        }
+    }
+
+    #[test]
+    fn checkpoint_checks_each_phase_separately() {
+        let spawner = MockSpawner::expect(2);
+        spawner.must_spawn(example_task());
+        spawner.must_spawn(example_task());
+        assert_eq!(spawner.checkpoint(), Ok(()));
+
+        spawner.must_spawn(example_task());
+        let expected = Err(MockSpawnerError::WrongNumberOfTasks {
+            expected: 2,
+            actual: 1,
+        });
+        assert_eq!(spawner.checkpoint(), expected);
+
+        spawner.must_spawn(example_task());
+        spawner.must_spawn(example_task());
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to spawn 1 task(s), actually spawned 0")]
+    fn checkpoint_still_checks_last_phase_on_drop() {
+        let spawner = MockSpawner::expect(1);
+        spawner.must_spawn(example_task());
+        spawner.checkpoint().unwrap();
+    }
+
+    #[test]
+    fn reset_clears_recorded_tasks_and_restarts_failures() {
+        let spawner = MockSpawner::expect_order(["task_with_args"]).fail_spawn(0, SpawnError::Busy);
+        assert!(spawner.spawn(example_task()).is_err());
+
+        spawner.reset();
+
+        assert!(spawner.spawned_tasks().is_empty());
+        assert!(spawner.spawn(task_with_args(1)).is_err());
+        assert_eq!(spawner.done(), Ok(()));
+    }
+
+    #[test]
+    fn full_pool_is_busy_until_task_finishes() {
+        let spawner = MockSpawner::expect(5).pool_size("task_with_args", 1);
+        assert!(spawner.spawn(task_with_args(1)).is_ok());
+        assert!(matches!(
+            spawner.spawn(task_with_args(2)),
+            Err(SpawnError::Busy)
+        ));
+        assert!(spawner.spawn(example_task()).is_ok());
+
+        spawner.finish_task("task_with_args");
+        assert!(spawner.spawn(task_with_args(3)).is_ok());
+        assert!(matches!(
+            spawner.spawn(task_with_args(4)),
+            Err(SpawnError::Busy)
+        ));
+    }
+
+    #[test]
+    fn failed_spawn_does_not_use_pool_slot() {
+        let spawner = MockSpawner::expect(2)
+            .fail_spawn(0, SpawnError::Busy)
+            .pool_size("example_task", 1);
+
+        assert!(matches!(
+            spawner.spawn(example_task()),
+            Err(SpawnError::Busy)
+        ));
+        assert!(spawner.spawn(example_task()).is_ok());
+    }
+
+    #[test]
+    fn later_pool_size_replaces_earlier() {
+        let spawner = MockSpawner::expect(3)
+            .pool_size("example_task", 1)
+            .pool_size("example_task", 2);
+
+        assert!(spawner.spawn(example_task()).is_ok());
+        assert!(spawner.spawn(example_task()).is_ok());
+        assert!(matches!(
+            spawner.spawn(example_task()),
+            Err(SpawnError::Busy)
+        ));
+    }
+
+    #[test]
+    fn reset_empties_pools() {
+        let spawner = MockSpawner::expect(1).pool_size("example_task", 1);
+        spawner.must_spawn(example_task());
+
+        spawner.reset();
+
+        assert!(spawner.spawn(example_task()).is_ok());
+    }
+
+    #[test]
+    fn on_spawn_is_called_with_each_index() {
+        static CALLS: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+        let spawner = MockSpawner::expect(3)
+            .fail_spawn(1, SpawnError::Busy)
+            .on_spawn(|index| {
+                CALLS.lock().unwrap().push(index);
+                Ok(())
+            });
+        spawner.must_spawn(example_task());
+        let _ = spawner.spawn(example_task());
+        spawner.must_spawn(task_with_args(1));
+
+        assert_eq!(*CALLS.lock().unwrap(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn on_spawn_error_fails_spawn() {
+        let spawner = MockSpawner::expect(2)
+            .on_spawn(|index| {
+                if index == 1 {
+                    Err(SpawnError::Busy)
+                } else {
+                    Ok(())
+                }
+            })
+            .pool_size("example_task", 1);
+
+        assert!(spawner.spawn(example_task()).is_ok());
+        assert!(matches!(
+            spawner.spawn(example_task()),
+            Err(SpawnError::Busy)
+        ));
+        assert_eq!(spawner.times_spawned("example_task"), 2);
+    }
+
+    #[test]
+    fn later_on_spawn_replaces_earlier() {
+        let spawner = MockSpawner::expect(1)
+            .on_spawn(|_| Err(SpawnError::Busy))
+            .on_spawn(|_| Ok(()));
+
+        assert!(spawner.spawn(example_task()).is_ok());
+    }
+
+    #[test]
+    fn duplicate_spawn_is_busy_and_reported() {
+        let spawner = MockSpawner::expect(4).detect_duplicate_spawns();
+        assert!(spawner.spawn(example_task()).is_ok());
+        assert!(spawner.spawn(task_with_args(1)).is_ok());
+        assert!(matches!(
+            spawner.spawn(task_with_args(2)),
+            Err(SpawnError::Busy)
+        ));
+        assert!(matches!(
+            spawner.spawn(example_task()),
+            Err(SpawnError::Busy)
+        ));
+
+        let expected = Err(MockSpawnerError::DuplicateTask {
+            name: "task_with_args",
+        });
+        assert_eq!(spawner.done(), expected);
+    }
+
+    #[test]
+    fn finished_task_can_be_spawned_again() {
+        let spawner = MockSpawner::expect(2).detect_duplicate_spawns();
+        spawner.must_spawn(example_task());
+
+        spawner.finish_task("example_task");
+
+        spawner.must_spawn(example_task());
+        assert_eq!(spawner.done(), Ok(()));
+    }
+
+    #[test]
+    fn tasks_with_pool_size_are_not_duplicates() {
+        let spawner = MockSpawner::expect(3)
+            .detect_duplicate_spawns()
+            .pool_size("example_task", 2);
+        spawner.must_spawn(example_task());
+        spawner.must_spawn(example_task());
+        assert!(spawner.spawn(example_task()).is_err());
+
+        assert_eq!(spawner.done(), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "task example_task was spawned again before it finished")]
+    fn duplicate_spawn_just_drop() {
+        let spawner = MockSpawner::expect(2).detect_duplicate_spawns();
+        let _ = spawner.spawn(example_task());
+        let _ = spawner.spawn(example_task());
+    }
+
+    fn spawn_in_generic_code<S: Spawner>(spawner: &S) -> u32 {
+        spawner.must_spawn(example_task());
+        line!() - 1
+    }
+
+    #[test]
+    fn records_where_tasks_are_spawned() {
+        let spawner = MockSpawner::expect(2);
+        let line = spawn_in_generic_code(&spawner);
+        let _ = spawner.spawn(task_with_args(1));
+        let other_line = line!() - 1;
+
+        let sites = spawner.spawn_sites();
+        assert_eq!(sites[0].0, "example_task");
+        assert_eq!(sites[0].1.line(), line);
+        assert_eq!(sites[1].0, "task_with_args");
+        assert_eq!(sites[1].1.line(), other_line);
+        assert_eq!(sites[1].1.file(), file!());
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to spawn 1 task(s), actually spawned 2\n\
+                               tasks were spawned at:\n  \
+                               0: example_task at src/executor.rs:")]
+    fn panic_on_drop_lists_spawn_sites() {
+        let spawner = MockSpawner::expect(1);
+        spawn_in_generic_code(&spawner);
+        spawn_in_generic_code(&spawner);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected to spawn 1 task(s), actually spawned 0\nno tasks were spawned"
+    )]
+    fn panic_on_drop_without_spawns() {
+        let _spawner = MockSpawner::expect(1);
+    }
+
+    #[test]
+    fn expect_task_passes_with_expected_counts() {
+        let spawner = MockSpawner::expect(3)
+            .fail_spawn(1, SpawnError::Busy)
+            .expect_task("example_task", 2)
+            .expect_task("task_with_args", 1);
+        spawner.must_spawn(example_task());
+        let _ = spawner.spawn(example_task());
+        spawner.must_spawn(task_with_args(1));
+
+        assert_eq!(spawner.done(), Ok(()));
+    }
+
+    #[test]
+    fn expect_task_reports_over_spawned_task() {
+        let spawner = MockSpawner::expect(3)
+            .expect_task("example_task", 1)
+            .expect_task("task_with_args", 2);
+        spawner.must_spawn(example_task());
+        spawner.must_spawn(example_task());
+        spawner.must_spawn(task_with_args(1));
+
+        let expected = Err(MockSpawnerError::WrongTaskCount {
+            name: "example_task",
+            expected: 1,
+            actual: 2,
+        });
+        assert_eq!(spawner.done(), expected);
+    }
+
+    #[test]
+    fn later_expect_task_replaces_earlier() {
+        let spawner = MockSpawner::expect(1)
+            .expect_task("example_task", 2)
+            .expect_task("example_task", 1);
+        spawner.must_spawn(example_task());
+
+        assert_eq!(spawner.done(), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to spawn task_with_args 1 time(s), actually spawned 0")]
+    fn expect_task_just_drop() {
+        let spawner = MockSpawner::expect(1).expect_task("task_with_args", 1);
+        spawner.must_spawn(example_task());
+    }
+
+    #[test]
+    fn no_more_spawns_passes_without_spawns() {
+        let spawner = MockSpawner::expect(2);
+        spawner.must_spawn(example_task());
+        spawner.must_spawn(example_task());
+        spawner.checkpoint().unwrap();
+
+        spawner.expect_no_more_spawns();
+
+        assert_eq!(spawner.done(), Ok(()));
+    }
+
+    #[test]
+    fn no_more_spawns_still_checks_spawns_before() {
+        let spawner = MockSpawner::expect(3);
+        spawner.must_spawn(example_task());
+        spawner.expect_no_more_spawns();
+
+        let expected = Err(MockSpawnerError::WrongNumberOfTasks {
+            expected: 3,
+            actual: 1,
+        });
+        assert_eq!(spawner.done(), expected);
+    }
+
+    #[test]
+    fn no_more_spawns_still_expected_after_reset() {
+        let spawner = MockSpawner::expect(1);
+        spawner.must_spawn(example_task());
+        spawner.expect_no_more_spawns();
+        spawner.must_spawn(example_task());
+
+        spawner.reset();
+        spawner.must_spawn(task_with_args(1));
+
+        let expected = Err(MockSpawnerError::UnexpectedSpawns { actual: 1 });
+        assert_eq!(spawner.done(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected no more tasks to be spawned, actually spawned 2")]
+    fn no_more_spawns_just_drop() {
+        let spawner = MockSpawner::expect(0);
+        spawner.expect_no_more_spawns();
+        spawner.must_spawn(example_task());
+        spawner.must_spawn(example_task());
+    }
+
+    #[test]
+    fn expect_order_passes_in_order() {
+        let spawner = MockSpawner::expect_order(["task_with_args", "example_task"]);
+        spawner.spawn(task_with_args(1)).unwrap();
+        spawner.spawn(example_task()).unwrap();
+
+        assert_eq!(spawner.done(), Ok(()));
+    }
+
+    #[test]
+    fn expect_order_missing_task_is_wrong_number() {
+        let spawner = MockSpawner::expect_order(["task_with_args", "example_task"]);
+        spawner.spawn(task_with_args(1)).unwrap();
+
+        let expected = Err(MockSpawnerError::WrongNumberOfTasks {
+            expected: 2,
+            actual: 1,
+        });
+        assert_eq!(spawner.done(), expected);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected spawn 1 to be example_task, actually spawned task_with_args"
+    )]
+    fn expect_order_wrong_task_just_drop() {
+        let spawner = MockSpawner::expect_order(["example_task", "example_task"]);
+        spawner.spawn(example_task()).unwrap();
+        spawner.spawn(task_with_args(1)).unwrap();
+    }
+
+    #[test]
+    fn expect_range_accepts_both_ends() {
+        for count in [1, 3] {
+            let spawner = MockSpawner::expect_range(1..=3);
+            for _ in 0..count {
+                spawner.spawn(example_task()).unwrap();
+            }
+
+            assert_eq!(spawner.done(), Ok(()));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to spawn at most 3 task(s), actually spawned 4")]
+    fn expect_range_too_many_tasks_just_drop() {
+        let spawner = MockSpawner::expect_range(1..=3);
+        for _ in 0..4 {
+            spawner.spawn(example_task()).unwrap();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to spawn at least 1 task(s), actually spawned 0")]
+    fn expect_at_least_no_tasks_just_drop() {
+        let _spawner = MockSpawner::expect_at_least(1);
+    }
+
+    #[cfg(all(feature = "runtime", not(feature = "arch-std")))]
+    mod running_tasks {
+        use super::*;
+        use crate::time::{MockClock, MockTimer, Timer};
+        use core::sync::atomic::AtomicBool;
+        use embassy_time::Duration;
+
+        static STEPS: AtomicUsize = AtomicUsize::new(0);
+
+        #[embassy_executor::task]
+        async fn timed_task() {
+            STEPS.fetch_add(1, Ordering::Relaxed);
+            MockTimer::after(Duration::from_secs(1)).await;
+            STEPS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        static HAS_RUN: AtomicBool = AtomicBool::new(false);
+
+        #[embassy_executor::task]
+        async fn failed_task() {
+            HAS_RUN.store(true, Ordering::Relaxed);
+        }
+
+        #[test]
+        fn immediate_tasks_run_until_waiting() {
+            let clock = MockClock::new();
+            let spawner = MockSpawner::expect(1).run_tasks_immediately();
+            spawner.must_spawn(timed_task());
+            assert_eq!(STEPS.load(Ordering::Relaxed), 1);
+
+            clock.advance(Duration::from_secs(1));
+            spawner.run_spawned();
+            assert_eq!(STEPS.load(Ordering::Relaxed), 2);
+
+            spawner.done().unwrap();
+        }
+
+        #[test]
+        fn failed_spawns_are_not_run() {
+            let spawner = MockSpawner::expect(1)
+                .fail_all_spawns(SpawnError::Busy)
+                .run_tasks_deferred();
+            assert!(spawner.spawn(failed_task()).is_err());
+
+            spawner.run_spawned();
+
+            assert!(!HAS_RUN.load(Ordering::Relaxed));
+            spawner.done().unwrap();
+        }
+    }
+
+    #[test]
+    fn counts_spawns_from_many_threads() {
+        const THREADS: usize = 8;
+        const SPAWNS: usize = 1000;
+
+        let spawner = MockSpawner::expect(THREADS * SPAWNS);
+        std::thread::scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    for _ in 0..SPAWNS {
+                        spawner.spawn(example_task()).unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(spawner.times_spawned("example_task"), THREADS * SPAWNS);
+        assert_eq!(spawner.done(), Ok(()));
+    }
+
     #[test]
     fn done_returns_ok() {
         let spawner = MockSpawner::expect(1);
@@ -0,0 +1,226 @@
+//! An object-safe version of the [`Spawner`] trait, so a struct can hold a spawner without being
+//! generic over it.
+//!
+//! The [`Spawner`] trait can't be used as a trait object as its methods are generic over the task
+//! of the [`SpawnToken`]. The [`DynSpawner`] trait takes a [`DynSpawnToken`] instead, which hides
+//! the type of the task, and the methods of [`Spawner`] are provided on `dyn DynSpawner` itself.
+//! This doesn't need the `alloc` feature.
+//!
+//! # Examples
+//! ```
+//! # #![feature(type_alias_impl_trait)]
+//! #
+//! use embassy_mock::executor::{DynSpawner, MockSpawner};
+//!
+//! #[embassy_executor::task]
+//! async fn blink() {}
+//!
+//! // Not generic over the spawner.
+//! struct App<'a> {
+//!     spawner: &'a dyn DynSpawner,
+//! }
+//!
+//! impl App<'_> {
+//!     fn start(&self) {
+//!         self.spawner.must_spawn(blink());
+//!     }
+//! }
+//!
+//! let spawner = MockSpawner::expect(1);
+//! let app = App { spawner: &spawner };
+//! app.start();
+//!
+//! spawner.done().unwrap();
+//! ```
+
+use super::{MockSpawner, Spawner};
+use core::fmt;
+use embassy_executor::{SpawnError, SpawnToken, Spawner as EmbassySpawner};
+
+/// A [`SpawnToken`] with the type of its task hidden, to pass it to a [`DynSpawner`].
+///
+/// This is created by the `spawn()` and `must_spawn()` methods on `dyn DynSpawner`.
+pub struct DynSpawnToken<'a> {
+    /// The token, which is taken when it is spawned.
+    token: &'a mut dyn ErasedSpawnToken,
+}
+
+impl fmt::Debug for DynSpawnToken<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynSpawnToken").finish_non_exhaustive()
+    }
+}
+
+/// Spawn a [`SpawnToken`] on each of the spawners that implement [`DynSpawner`], as the type of
+/// the spawner and the type of the task can't both be hidden.
+trait ErasedSpawnToken {
+    /// Call [`Spawner::spawn()`] of an [`EmbassySpawner`] with the token.
+    fn spawn_on_embassy(&mut self, spawner: &EmbassySpawner) -> Result<(), SpawnError>;
+
+    /// Call [`Spawner::spawn()`] of a [`MockSpawner`] with the token.
+    #[track_caller]
+    fn spawn_on_mock(&mut self, spawner: &MockSpawner) -> Result<(), SpawnError>;
+}
+
+impl<S> ErasedSpawnToken for Option<SpawnToken<S>> {
+    fn spawn_on_embassy(&mut self, spawner: &EmbassySpawner) -> Result<(), SpawnError> {
+        let token = self.take().expect("the token is only spawned once");
+        Spawner::spawn(spawner, token)
+    }
+
+    #[track_caller]
+    fn spawn_on_mock(&mut self, spawner: &MockSpawner) -> Result<(), SpawnError> {
+        let token = self.take().expect("the token is only spawned once");
+        Spawner::spawn(spawner, token)
+    }
+}
+
+/// An object-safe version of the [`Spawner`] trait that is implemented for the
+/// [`embassy_executor::Spawner`] and the [`MockSpawner`].
+///
+/// Use the `spawn()` and `must_spawn()` methods on `dyn DynSpawner` to spawn tasks, the same as
+/// with a [`Spawner`].
+pub trait DynSpawner {
+    /// Type-erased wrapper for [`Spawner::spawn()`].
+    #[track_caller]
+    fn spawn_dyn(&self, token: DynSpawnToken<'_>) -> Result<(), SpawnError>;
+}
+
+impl dyn DynSpawner + '_ {
+    /// Wrapper for [`Spawner::spawn()`].
+    #[track_caller]
+    pub fn spawn<S>(&self, token: SpawnToken<S>) -> Result<(), SpawnError> {
+        let mut token = Some(token);
+        self.spawn_dyn(DynSpawnToken { token: &mut token })
+    }
+
+    /// Wrapper for [`Spawner::must_spawn()`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `spawn()` returns an error, the same as the real spawner.
+    #[track_caller]
+    pub fn must_spawn<S>(&self, token: SpawnToken<S>) {
+        if let Err(error) = self.spawn(token) {
+            panic!("failed to spawn task: {error:?}");
+        }
+    }
+}
+
+impl DynSpawner for EmbassySpawner {
+    /// Call [`Spawner::spawn()`].
+    fn spawn_dyn(&self, token: DynSpawnToken<'_>) -> Result<(), SpawnError> {
+        token.token.spawn_on_embassy(self)
+    }
+}
+
+impl DynSpawner for MockSpawner {
+    /// Call [`Spawner::spawn()`].
+    #[track_caller]
+    fn spawn_dyn(&self, token: DynSpawnToken<'_>) -> Result<(), SpawnError> {
+        token.token.spawn_on_mock(self)
+    }
+}
+
+impl<T: DynSpawner + ?Sized> DynSpawner for &T {
+    /// Call [`DynSpawner::spawn_dyn()`] of the referenced spawner.
+    #[track_caller]
+    fn spawn_dyn(&self, token: DynSpawnToken<'_>) -> Result<(), SpawnError> {
+        (**self).spawn_dyn(token)
+    }
+}
+
+impl<T: DynSpawner + ?Sized> DynSpawner for &mut T {
+    /// Call [`DynSpawner::spawn_dyn()`] of the referenced spawner.
+    #[track_caller]
+    fn spawn_dyn(&self, token: DynSpawnToken<'_>) -> Result<(), SpawnError> {
+        (**self).spawn_dyn(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[embassy_executor::task]
+    async fn example_task() {}
+
+    #[embassy_executor::task]
+    async fn task_with_args(_value: u32) {}
+
+    #[test]
+    fn dyn_spawner_forwards_to_mock_spawner() {
+        let spawner = MockSpawner::expect(2).fail_spawn(1, SpawnError::Busy);
+        {
+            let dyn_spawner: &dyn DynSpawner = &spawner;
+            dyn_spawner.must_spawn(example_task());
+            assert!(matches!(
+                dyn_spawner.spawn(task_with_args(1)),
+                Err(SpawnError::Busy)
+            ));
+        }
+
+        assert_eq!(spawner.spawned_tasks(), ["example_task", "task_with_args"]);
+        spawner.done().unwrap();
+    }
+
+    #[test]
+    fn references_to_spawners_are_spawners() {
+        let mut spawner = MockSpawner::expect(2);
+        {
+            let by_ref: &dyn DynSpawner = &&spawner;
+            by_ref.must_spawn(example_task());
+        }
+        {
+            let by_mut: &dyn DynSpawner = &&mut spawner;
+            by_mut.must_spawn(example_task());
+        }
+
+        spawner.done().unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "failed to spawn task: Busy")]
+    fn must_spawn_panics_on_error() {
+        let spawner = MockSpawner::expect(1).fail_all_spawns(SpawnError::Busy);
+
+        (&spawner as &dyn DynSpawner).must_spawn(example_task());
+    }
+
+    #[test]
+    fn records_where_tasks_are_spawned_through_dyn_spawner() {
+        let spawner = MockSpawner::expect(2);
+        let dyn_spawner: &dyn DynSpawner = &&spawner;
+        dyn_spawner.must_spawn(example_task());
+        let line = line!() - 1;
+        let _ = dyn_spawner.spawn(task_with_args(1));
+        let other_line = line!() - 1;
+
+        let sites = spawner.spawn_sites();
+        assert_eq!(sites[0].1.file(), file!());
+        assert_eq!(sites[0].1.line(), line);
+        assert_eq!(sites[1].1.file(), file!());
+        assert_eq!(sites[1].1.line(), other_line);
+    }
+
+    #[cfg(all(feature = "runtime", not(feature = "arch-std")))]
+    #[test]
+    fn dyn_spawner_forwards_to_embassy_spawner() {
+        use crate::executor::MockExecutor;
+        use core::sync::atomic::{AtomicBool, Ordering};
+
+        static HAS_RUN: AtomicBool = AtomicBool::new(false);
+
+        #[embassy_executor::task]
+        async fn running_task() {
+            HAS_RUN.store(true, Ordering::Relaxed);
+        }
+
+        let executor = MockExecutor::new();
+        let spawner = executor.spawner();
+        (&spawner as &dyn DynSpawner).must_spawn(running_task());
+
+        executor.run_until_idle();
+        assert!(HAS_RUN.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,447 @@
+//! A host runtime that runs the tasks spawned with an [`embassy_executor::Spawner`] in tests.
+//!
+//! The [`MockSpawner`](super::MockSpawner) only counts the spawned tasks, the [`MockExecutor`]
+//! actually polls them. This allows integration testing several tasks that interact with each
+//! other, using the mocked time types and the [`MockClock`] to move time forward.
+//!
+//! The `runtime` feature defines the `__pender` that `embassy-executor` calls when an executor has
+//! work to do. The real executor of the `arch-std` feature defines its own, so this module is left
+//! out when both features are enabled.
+//!
+//! # Examples
+//! ```
+//! # #![feature(type_alias_impl_trait)]
+//! #
+//! use core::sync::atomic::{AtomicUsize, Ordering};
+//! use embassy_mock::{
+//!     executor::{MockExecutor, Spawner},
+//!     time::{MockClock, MockTimer, Timer},
+//! };
+//! use embassy_time::Duration;
+//!
+//! static BLINKS: AtomicUsize = AtomicUsize::new(0);
+//!
+//! #[embassy_executor::task]
+//! async fn blink() {
+//!     for _ in 0..3 {
+//!         MockTimer::after(Duration::from_secs(1)).await;
+//!         BLINKS.fetch_add(1, Ordering::Relaxed);
+//!     }
+//! }
+//!
+//! fn spawn_tasks<S: Spawner>(spawner: &S) {
+//!     spawner.must_spawn(blink());
+//! }
+//!
+//! let clock = MockClock::new();
+//! let executor = MockExecutor::new();
+//! spawn_tasks(&executor.spawner());
+//!
+//! executor.run_for(&clock, Duration::from_millis(2500));
+//! assert_eq!(BLINKS.load(Ordering::Relaxed), 2);
+//! ```
+
+use super::Executor;
+#[cfg(feature = "time")]
+use crate::time::{clock, MockClock};
+use core::{
+    cell::Cell,
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use embassy_executor::{raw, Spawner as EmbassySpawner};
+#[cfg(feature = "time")]
+use embassy_time::Duration;
+use std::{boxed::Box, sync::Mutex, vec::Vec};
+
+/// The executors of the dropped [`MockExecutor`]s that had no tasks waiting to be polled, to be
+/// reused by the next ones.
+///
+/// With the `integrated-timers` feature of `embassy-executor` every executor takes one of the
+/// alarms of the time driver for good, and the driver of `embassy-time/std` only has four.
+static IDLE_EXECUTORS: Mutex<Vec<Parts>> = Mutex::new(Vec::new());
+
+/// The leaked executor of a [`MockExecutor`] and the context it was created with.
+#[derive(Clone, Copy)]
+struct Parts {
+    /// The executor that the tasks are spawned on.
+    executor: &'static raw::Executor,
+
+    /// Signalled by the pender when the executor has tasks to poll.
+    signaler: &'static Signaler,
+}
+
+// SAFETY: The parts are only moved to another thread once the `MockExecutor` that polled them is
+// dropped, so the executor is never polled from two threads at once.
+unsafe impl Send for Parts {}
+
+impl Parts {
+    /// Reuse the parts of a dropped [`MockExecutor`], or create new ones if there are none.
+    fn take() -> Self {
+        let idle = IDLE_EXECUTORS.lock().unwrap().pop();
+        idle.unwrap_or_else(|| {
+            let signaler = Signaler::leak();
+            let context = (signaler as *const Signaler).cast_mut().cast::<()>();
+
+            Self {
+                executor: Box::leak(Box::new(raw::Executor::new(context))),
+                signaler,
+            }
+        })
+    }
+}
+
+/// Called by `embassy-executor` when the executor created with `context` has tasks to poll.
+#[export_name = "__pender"]
+fn __pender(context: *mut ()) {
+    // SAFETY: Every executor is created by `Parts::take()` with a leaked `Signaler` as its context.
+    let signaler = unsafe { &*context.cast_const().cast::<Signaler>() };
+    signaler.signal();
+}
+
+/// The context of a [`MockExecutor`] that the `__pender` signals when the executor has tasks to
+/// poll.
+struct Signaler {
+    /// Set by the pender when the executor has tasks to poll.
+    is_signaled: AtomicBool,
+}
+
+impl Signaler {
+    /// Create a [`Signaler`] that hasn't been signalled, leaked for the executor to refer to.
+    fn leak() -> &'static Self {
+        Box::leak(Box::new(Self {
+            is_signaled: AtomicBool::new(false),
+        }))
+    }
+
+    /// Record that the executor has tasks to poll, which can be called from any thread.
+    fn signal(&self) {
+        self.is_signaled.store(true, Ordering::Release);
+    }
+
+    /// Has the pender been called since the last [`Self::take()`].
+    fn is_signaled(&self) -> bool {
+        self.is_signaled.load(Ordering::Acquire)
+    }
+
+    /// Has the pender been called since the last call, clearing the signal.
+    fn take(&self) -> bool {
+        self.is_signaled.swap(false, Ordering::AcqRel)
+    }
+}
+
+/// An executor for host tests that polls the spawned tasks on the current thread when asked to.
+///
+/// The tasks are spawned with the real [`embassy_executor::Spawner`] returned by
+/// [`Self::spawner()`], which implements the [`Spawner`](super::Spawner) trait. They are only
+/// polled during a call to [`Self::run_until_idle()`] or [`Self::run_for()`].
+///
+/// The executor is leaked so that its tasks can refer to it for the rest of the test, and it is
+/// reused by a later [`MockExecutor`] once this one is dropped, unless tasks are still waiting to
+/// be polled. A task that never completes keeps its slot in the task pool, so spawning it again in
+/// a later test returns [`SpawnError::Busy`](embassy_executor::SpawnError::Busy).
+///
+/// # Examples
+///
+/// ```
+/// # #![feature(type_alias_impl_trait)]
+/// #
+/// use core::sync::atomic::{AtomicBool, Ordering};
+/// use embassy_mock::executor::MockExecutor;
+///
+/// static HAS_RUN: AtomicBool = AtomicBool::new(false);
+///
+/// #[embassy_executor::task]
+/// async fn example_task() {
+///     HAS_RUN.store(true, Ordering::Relaxed);
+/// }
+///
+/// let executor = MockExecutor::new();
+/// executor.spawner().must_spawn(example_task());
+/// assert!(!HAS_RUN.load(Ordering::Relaxed)); // Not polled yet.
+///
+/// executor.run_until_idle();
+/// assert!(HAS_RUN.load(Ordering::Relaxed));
+/// ```
+pub struct MockExecutor {
+    /// The executor that the tasks are spawned on.
+    executor: &'static raw::Executor,
+
+    /// Signalled by the pender when the executor has tasks to poll.
+    signaler: &'static Signaler,
+
+    /// Is the executor being polled, to catch a task trying to run its own executor.
+    is_running: Cell<bool>,
+}
+
+impl MockExecutor {
+    /// Create a [`MockExecutor`] with no tasks.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        let Parts { executor, signaler } = Parts::take();
+
+        Self {
+            executor,
+            signaler,
+            is_running: Cell::new(false),
+        }
+    }
+
+    /// A [`embassy_executor::Spawner`] that spawns tasks on this executor.
+    pub fn spawner(&self) -> EmbassySpawner {
+        self.executor.spawner()
+    }
+
+    /// Poll the tasks that have been spawned or woken until none of them are ready to make
+    /// progress.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from one of the tasks of this executor.
+    pub fn run_until_idle(&self) {
+        assert!(
+            !self.is_running.replace(true),
+            "MockExecutor can't be run from one of its own tasks"
+        );
+
+        while self.signaler.take() {
+            // SAFETY: `is_running` ensures that this isn't called reentrantly and the executor
+            // isn't `Send` so it is only polled from the thread that created it.
+            unsafe { self.executor.poll() };
+        }
+
+        self.is_running.set(false);
+    }
+
+    /// Is the executor polling its tasks, meaning that this is called from one of them.
+    pub(super) fn is_running(&self) -> bool {
+        self.is_running.get()
+    }
+
+    /// Run the tasks while moving the `clock` forward by `duration`, jumping it to each deadline
+    /// that the tasks are waiting for in turn.
+    ///
+    /// This finishes instantly however long the `duration` is, leaving the clock `duration`
+    /// after where it started.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from one of the tasks of this executor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #![feature(type_alias_impl_trait)]
+    /// #
+    /// use core::sync::atomic::{AtomicUsize, Ordering};
+    /// use embassy_mock::{
+    ///     executor::MockExecutor,
+    ///     time::{Instant, MockClock, MockInstant, MockTicker, Ticker},
+    /// };
+    /// use embassy_time::Duration;
+    ///
+    /// static TICKS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// #[embassy_executor::task]
+    /// async fn count_ticks() {
+    ///     let mut ticker = MockTicker::every(Duration::from_secs(1));
+    ///     loop {
+    ///         ticker.next().await;
+    ///         TICKS.fetch_add(1, Ordering::Relaxed);
+    ///     }
+    /// }
+    ///
+    /// let clock = MockClock::new();
+    /// let executor = MockExecutor::new();
+    /// executor.spawner().must_spawn(count_ticks());
+    ///
+    /// executor.run_for(&clock, Duration::from_secs(10));
+    ///
+    /// assert_eq!(TICKS.load(Ordering::Relaxed), 10);
+    /// assert_eq!(clock.now(), MockInstant::from_secs(10));
+    /// ```
+    #[cfg(feature = "time")]
+    pub fn run_for(&self, clock: &MockClock, duration: Duration) {
+        let end = clock.now() + duration;
+
+        loop {
+            self.run_until_idle();
+            match clock::next_deadline() {
+                Some(deadline) if deadline <= end => clock.set_now(deadline),
+                _ => break,
+            }
+        }
+
+        clock.set_now(end);
+        self.run_until_idle();
+    }
+}
+
+/// Use the [`MockExecutor`] in place of the real executor in the code that starts it.
+/// # Examples
+///
+/// ```
+/// # #![feature(type_alias_impl_trait)]
+/// #
+/// use core::sync::atomic::{AtomicBool, Ordering};
+/// use embassy_mock::executor::{Executor, MockExecutor, Spawner};
+///
+/// static IS_STARTED: AtomicBool = AtomicBool::new(false);
+///
+/// #[embassy_executor::task]
+/// async fn app() {
+///     IS_STARTED.store(true, Ordering::Relaxed);
+/// }
+///
+/// // Called from `main` with the real executor, which never returns.
+/// fn start<E: Executor>(executor: &'static mut E) -> E::Output {
+///     executor.run(|spawner| spawner.must_spawn(app()))
+/// }
+///
+/// let executor = Box::leak(Box::new(MockExecutor::new()));
+/// start(executor);
+///
+/// assert!(IS_STARTED.load(Ordering::Relaxed));
+/// ```
+impl Executor for MockExecutor {
+    type Output = ();
+
+    /// Create a [`MockExecutor`] with no tasks.
+    fn new() -> Self {
+        Self::new()
+    }
+
+    /// Call `init` with a spawner for this executor and then run its tasks until none of them
+    /// are ready to make progress, see [`Self::run_until_idle()`].
+    ///
+    /// Unlike the real executor, this returns so the test can check what the tasks did.
+    fn run(&'static mut self, init: impl FnOnce(EmbassySpawner)) {
+        init(self.spawner());
+        self.run_until_idle();
+    }
+}
+
+impl Drop for MockExecutor {
+    /// Give the executor to the next [`MockExecutor`], unless tasks are waiting to be polled.
+    fn drop(&mut self) {
+        if !self.signaler.is_signaled() {
+            IDLE_EXECUTORS.lock().unwrap().push(Parts {
+                executor: self.executor,
+                signaler: self.signaler,
+            });
+        }
+    }
+}
+
+impl fmt::Debug for MockExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockExecutor")
+            .field("is_pended", &self.signaler.is_signaled())
+            .field("is_running", &self.is_running)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::{Instant as _, MockInstant, MockTimer, Timer};
+    use core::sync::atomic::AtomicUsize;
+    use embassy_futures::yield_now;
+
+    static STEPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[embassy_executor::task]
+    async fn yielding_task() {
+        for _ in 0..3 {
+            STEPS.fetch_add(1, Ordering::Relaxed);
+            yield_now().await;
+        }
+    }
+
+    static PING: AtomicBool = AtomicBool::new(false);
+    static PONGS: AtomicUsize = AtomicUsize::new(0);
+
+    #[embassy_executor::task]
+    async fn pinger() {
+        MockTimer::after(Duration::from_secs(1)).await;
+        PING.store(true, Ordering::Relaxed);
+    }
+
+    #[embassy_executor::task]
+    async fn ponger() {
+        while !PING.load(Ordering::Relaxed) {
+            MockTimer::after(Duration::from_millis(100)).await;
+        }
+        PONGS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    static WAKE_UPS: AtomicUsize = AtomicUsize::new(0);
+
+    #[embassy_executor::task]
+    async fn sleeper() {
+        loop {
+            MockTimer::after(Duration::from_secs(3)).await;
+            WAKE_UPS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    static IS_STARTED: AtomicBool = AtomicBool::new(false);
+
+    #[embassy_executor::task]
+    async fn app() {
+        IS_STARTED.store(true, Ordering::Relaxed);
+    }
+
+    fn start<E: Executor>(executor: &'static mut E) -> E::Output {
+        executor.run(|spawner| spawner.must_spawn(app()))
+    }
+
+    #[test]
+    fn executor_run_spawns_and_runs_tasks() {
+        let executor = Box::leak(Box::new(<MockExecutor as Executor>::new()));
+
+        start(executor);
+
+        assert!(IS_STARTED.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn run_until_idle_polls_until_no_task_is_woken() {
+        let executor = MockExecutor::new();
+        executor.spawner().spawn(yielding_task()).unwrap();
+
+        executor.run_until_idle();
+
+        assert_eq!(STEPS.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn tasks_interact_with_virtual_time() {
+        let clock = MockClock::new();
+        let executor = MockExecutor::new();
+        let spawner = executor.spawner();
+        spawner.must_spawn(ponger());
+        spawner.must_spawn(pinger());
+
+        executor.run_for(&clock, Duration::from_millis(999));
+        assert_eq!(PONGS.load(Ordering::Relaxed), 0);
+
+        // The ponger checks again at its next 100ms timer after the ping.
+        executor.run_for(&clock, Duration::from_millis(200));
+        assert_eq!(PONGS.load(Ordering::Relaxed), 1);
+        assert_eq!(clock.now(), MockInstant::from_millis(1199));
+    }
+
+    #[test]
+    fn run_for_stops_at_end_of_duration() {
+        let clock = MockClock::new();
+        let executor = MockExecutor::new();
+        executor.spawner().must_spawn(sleeper());
+
+        executor.run_for(&clock, Duration::from_secs(10));
+
+        assert_eq!(WAKE_UPS.load(Ordering::Relaxed), 3);
+        assert_eq!(clock.now(), MockInstant::from_secs(10));
+    }
+}
@@ -0,0 +1,45 @@
+//! A mocked version of the `embassy-futures` crate.
+//!
+//! The traits and their implementations for the Embassy functions are always available, the
+//! mocks need the `std` feature. So do [`poll_once()`] and [`run_until_stalled()`], to drive the
+//! code under test by hand with the [`Stepper`], and [`block_on_or_fail()`], to run it without
+//! hanging. The [`ManualFuture`] is the building block for hand-rolled mocks of the traits of a
+//! project, pending until the test completes it, and the [`TestWaker`] checks how often a
+//! hand-written future is woken and by which mock.
+
+#[cfg(feature = "std")]
+pub mod block_on;
+#[cfg(feature = "std")]
+pub mod manual;
+pub mod select;
+#[cfg(feature = "std")]
+pub mod waker;
+pub mod yield_now;
+
+#[cfg(feature = "std")]
+pub use block_on::{block_on_or_fail, poll_once, run_until_stalled, Stepper};
+#[cfg(feature = "std")]
+pub use manual::ManualFuture;
+pub use select::{EmbassySelector, Selector};
+#[cfg(feature = "std")]
+pub use select::{MockSelector, MockSelectorError, SelectBranch};
+#[cfg(feature = "std")]
+pub use waker::TestWaker;
+pub use yield_now::{EmbassyYielder, Yielder};
+#[cfg(feature = "std")]
+pub use yield_now::{MockYielder, MockYielderError};
+
+// A `ManualFuture` is completed by the test while the code under test awaits it, possibly on
+// another thread, and the waker of a `TestWaker` can be woken from any thread like any other waker.
+// The `MockSelector` and `MockYielder` stand in for functions that any task can call.
+#[cfg(feature = "std")]
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<ManualFuture<u8>>();
+    assert_send_sync::<MockSelector>();
+    assert_send_sync::<MockSelectorError>();
+    assert_send_sync::<TestWaker>();
+    assert_send_sync::<MockYielder>();
+    assert_send_sync::<MockYielderError>();
+};
@@ -0,0 +1,567 @@
+//! Utilities to drive a future under test by hand, to check the state of the mocks while it is
+//! part way through.
+//!
+//! `embassy_futures::block_on()` runs a future to completion, so a test can only check where it
+//! ended up. [`poll_once()`] and [`run_until_stalled()`] borrow the future instead, so the test can
+//! check the mocks each time it stops, change them, and carry on. The [`Stepper`] owns the future
+//! and polls it one step at a time, keeping its output once it completes. When the test only needs
+//! the output, [`block_on_or_fail()`] runs the future to completion without hanging if it never
+//! completes.
+//!
+//! # Examples
+//! ```
+//! use embassy_mock::sync::{Receiver, Sender};
+//!
+//! // Acknowledge each request before handling the next.
+//! async fn serve(requests: &impl Receiver<u8>, responses: &impl Sender<u8>) {
+//!     loop {
+//!         let request = requests.receive().await;
+//!         responses.send(request + 1).await;
+//!     }
+//! }
+//!
+//! # test_serve_responds_to_each_request();
+//! // The unit tests that check the intermediate states.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use core::pin::pin;
+//!     use embassy_mock::{
+//!         futures::run_until_stalled,
+//!         sync::{MockReceiver, MockSender},
+//!     };
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_serve_responds_to_each_request() {
+//!         let requests = MockReceiver::with_received([1]);
+//!         let responses = MockSender::new();
+//!         let mut serve = pin!(serve(&requests, &responses));
+//!
+//!         assert!(run_until_stalled(serve.as_mut()).is_pending());
+//!         assert_eq!(responses.sent(), [2]);
+//!
+//!         requests.push_received(5);
+//!         assert!(run_until_stalled(serve.as_mut()).is_pending());
+//!         assert_eq!(responses.sent(), [2, 6]);
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use crate::waiting;
+use core::{
+    fmt,
+    future::Future,
+    pin::{pin, Pin},
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll, Waker},
+};
+use std::{boxed::Box, sync::Arc, task::Wake, vec::Vec};
+
+/// Poll the `future` once, returning whether it completed.
+///
+/// Unlike `embassy_futures::poll_once()` the future is borrowed rather than dropped, so the test
+/// can check the mocks and then poll it again. The waker given to the future does nothing, use
+/// [`run_until_stalled()`] to carry on polling while the future wakes itself.
+///
+/// # Examples
+///
+/// ```
+/// use core::{pin::pin, task::Poll};
+/// use embassy_mock::{
+///     futures::poll_once,
+///     sync::{MockSignal, Signal},
+/// };
+///
+/// // Report that it is waiting before waiting for the value.
+/// async fn handshake(ready: &impl Signal<u8>, reply: &impl Signal<u8>) -> u8 {
+///     ready.signal(1);
+///     reply.wait().await
+/// }
+///
+/// let ready = MockSignal::expect(1);
+/// let reply = MockSignal::expect(0);
+/// let mut handshake = pin!(handshake(&ready, &reply));
+///
+/// assert_eq!(poll_once(handshake.as_mut()), Poll::Pending);
+/// assert_eq!(ready.signals(), [1]);
+///
+/// reply.trigger(9);
+/// assert_eq!(poll_once(handshake), Poll::Ready(9));
+/// ```
+pub fn poll_once<F: Future + ?Sized>(future: Pin<&mut F>) -> Poll<F::Output> {
+    let waker = Waker::from(Arc::new(WakeFlag::default()));
+    future.poll(&mut Context::from_waker(&waker))
+}
+
+/// Poll the `future` until it completes or is pending without having woken itself, so it can't
+/// make any more progress until something outside of it changes.
+///
+/// This returns [`Poll::Pending`] once the future is waiting for the test, such as for a message
+/// to be pushed to a mock, which the test can then check and change before calling it again.
+/// Time doesn't pass, so a future that is waiting for a mocked timer with a `MockClock` attached
+/// is stalled too.
+///
+/// A future that always wakes itself, such as a loop that yields without ever waiting, never
+/// stalls, so this never returns for it.
+///
+/// # Examples
+///
+/// ```
+/// use core::{pin::pin, task::Poll};
+/// use embassy_futures::yield_now;
+/// use embassy_mock::{
+///     futures::run_until_stalled,
+///     sync::{MockReceiver, Receiver},
+/// };
+///
+/// // Yields between each message, waiting for three in total.
+/// async fn sum(input: &impl Receiver<u8>) -> u8 {
+///     let mut total = 0;
+///     for _ in 0..3 {
+///         total += input.receive().await;
+///         yield_now().await;
+///     }
+///     total
+/// }
+///
+/// let input = MockReceiver::with_received([1, 2]);
+/// let mut sum = pin!(sum(&input));
+///
+/// // The yields don't stall it, waiting for the third message does.
+/// assert_eq!(run_until_stalled(sum.as_mut()), Poll::Pending);
+///
+/// input.push_received(4);
+/// assert_eq!(run_until_stalled(sum), Poll::Ready(7));
+/// ```
+pub fn run_until_stalled<F: Future + ?Sized>(mut future: Pin<&mut F>) -> Poll<F::Output> {
+    let woken = Arc::new(WakeFlag::default());
+    let waker = Waker::from(Arc::clone(&woken));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return Poll::Ready(output);
+        }
+        if !woken.take() {
+            return Poll::Pending;
+        }
+    }
+}
+
+/// Run the `future` to completion, polling it in a busy loop as `embassy_futures::block_on()`
+/// does, but failing the test instead of hanging if it hasn't completed after `max_polls` polls.
+///
+/// # Panics
+///
+/// Panics if the future is still pending after `max_polls` polls, listing the mocks it was waiting
+/// for in the last poll, such as `MockReceiver::receive()` for a receive with no message left.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::{
+///     futures::block_on_or_fail,
+///     sync::{MockReceiver, Receiver},
+/// };
+///
+/// async fn sum(input: &impl Receiver<u8>) -> u8 {
+///     input.receive().await + input.receive().await
+/// }
+///
+/// let input = MockReceiver::with_received([1, 2]);
+/// assert_eq!(block_on_or_fail(sum(&input), 10), 3);
+/// ```
+///
+/// ```should_panic
+/// use embassy_mock::{
+///     futures::block_on_or_fail,
+///     sync::{MockReceiver, Receiver},
+/// };
+///
+/// async fn sum(input: &impl Receiver<u8>) -> u8 {
+///     input.receive().await + input.receive().await
+/// }
+///
+/// let input = MockReceiver::with_received([1]);
+/// // Panics: expected the future to complete within 10 poll(s), actually it is still waiting for
+/// // MockReceiver::receive()
+/// block_on_or_fail(sum(&input), 10);
+/// ```
+#[track_caller]
+pub fn block_on_or_fail<F: Future>(future: F, max_polls: usize) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(WakeFlag::default()));
+    let mut cx = Context::from_waker(&waker);
+    let mut waiting_for = Vec::new();
+    for _ in 0..max_polls {
+        let (poll, waited) = waiting::recording(|| future.as_mut().poll(&mut cx));
+        if let Poll::Ready(output) = poll {
+            return output;
+        }
+        waiting_for = waited;
+    }
+
+    if waiting_for.is_empty() {
+        panic!(
+            "expected the future to complete within {max_polls} poll(s), actually it is still \
+            pending without waiting for any of the mocks"
+        );
+    }
+    panic!(
+        "expected the future to complete within {max_polls} poll(s), actually it is still waiting \
+        for {}",
+        waiting_for.join(", ")
+    );
+}
+
+/// A tiny executor for a single future, that only polls it when the test calls [`Self::step()`].
+///
+/// Each step is one poll, so the test can check the mocks between any two polls to verify how the
+/// code under test interleaves with them. It records whether the future has woken itself since the
+/// last step, which is when a real executor would poll it again, and keeps the output once the
+/// future completes, dropping the future as an executor would.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::{
+///     futures::Stepper,
+///     sync::{MockMutex, MockSender, Mutex, Sender},
+/// };
+///
+/// // Reports the count while holding the lock, then counts the report.
+/// async fn report(count: &impl Mutex<u8>, reports: &impl Sender<u8>) -> u8 {
+///     let mut count = count.lock().await;
+///     reports.send(*count).await;
+///     *count += 1;
+///     *count
+/// }
+///
+/// let count = MockMutex::new(0);
+/// let reports = MockSender::new();
+/// reports.set_full(true);
+/// let mut stepper = Stepper::new(report(&count, &reports));
+///
+/// // Waiting for room to send, while still holding the lock.
+/// assert!(!stepper.step());
+/// assert!(count.try_lock().is_err());
+///
+/// reports.set_full(false);
+/// assert!(stepper.is_woken());
+/// assert!(stepper.step());
+/// assert_eq!(stepper.output(), Some(&1));
+/// assert_eq!(stepper.steps(), 2);
+/// assert_eq!(reports.sent(), [0]);
+/// ```
+pub struct Stepper<'a, T> {
+    /// The future under test, until it completes.
+    future: Option<Pin<Box<dyn Future<Output = T> + 'a>>>,
+
+    /// The output of the future, once it has completed.
+    output: Option<T>,
+
+    /// The number of times the future has been polled.
+    steps: usize,
+
+    /// Records the wakes of the `waker`.
+    woken: Arc<WakeFlag>,
+
+    /// The waker given to each poll of the future.
+    waker: Waker,
+}
+
+impl<T: fmt::Debug> fmt::Debug for Stepper<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stepper")
+            .field("output", &self.output)
+            .field("steps", &self.steps)
+            .field("woken", &self.woken)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, T> Stepper<'a, T> {
+    /// Create a [`Stepper`] for the `future`, which isn't polled until the first step.
+    pub fn new(future: impl Future<Output = T> + 'a) -> Self {
+        let woken = Arc::new(WakeFlag::default());
+        Self {
+            future: Some(Box::pin(future)),
+            output: None,
+            steps: 0,
+            waker: Waker::from(Arc::clone(&woken)),
+            woken,
+        }
+    }
+
+    /// Poll the future once, unless it has already completed, returning whether it has.
+    pub fn step(&mut self) -> bool {
+        let Some(future) = &mut self.future else {
+            return true;
+        };
+
+        self.woken.take();
+        self.steps += 1;
+        if let Poll::Ready(output) = future.as_mut().poll(&mut Context::from_waker(&self.waker)) {
+            self.output = Some(output);
+            self.future = None;
+        }
+        self.is_finished()
+    }
+
+    /// Step the future while it wakes itself, until it completes or waits for something outside
+    /// of it, returning whether it has completed, see [`run_until_stalled()`].
+    pub fn step_until_stalled(&mut self) -> bool {
+        while !self.step() {
+            if !self.is_woken() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Has the future completed.
+    pub fn is_finished(&self) -> bool {
+        self.future.is_none()
+    }
+
+    /// Has the future been woken since the last step, so an executor would poll it again.
+    pub fn is_woken(&self) -> bool {
+        self.woken.0.load(Ordering::SeqCst)
+    }
+
+    /// The number of times the future has been polled.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// The output of the future, once it has completed.
+    pub fn output(&self) -> Option<&T> {
+        self.output.as_ref()
+    }
+
+    /// Take the output of the future, once it has completed.
+    pub fn into_output(self) -> Option<T> {
+        self.output
+    }
+}
+
+/// A [`Wake`] implementation that records if it was woken.
+#[derive(Debug, Default)]
+struct WakeFlag(AtomicBool);
+
+impl WakeFlag {
+    /// Has it been woken since the last call, forgetting the wake.
+    fn take(&self) -> bool {
+        self.0.swap(false, Ordering::SeqCst)
+    }
+}
+
+impl Wake for WakeFlag {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{
+        cell::Cell,
+        future::{pending, poll_fn},
+        pin::pin,
+    };
+    use embassy_futures::yield_now;
+
+    async fn count_yields(count: &Cell<u32>, yields: u32) -> u32 {
+        for _ in 0..yields {
+            count.set(count.get() + 1);
+            yield_now().await;
+        }
+        count.get()
+    }
+
+    #[test]
+    fn poll_once_polls_exactly_once() {
+        let count = Cell::new(0);
+        let mut future = pin!(count_yields(&count, 2));
+
+        assert_eq!(poll_once(future.as_mut()), Poll::Pending);
+        assert_eq!(count.get(), 1);
+        assert_eq!(poll_once(future.as_mut()), Poll::Pending);
+        assert_eq!(count.get(), 2);
+        assert_eq!(poll_once(future), Poll::Ready(2));
+    }
+
+    #[test]
+    fn run_until_stalled_runs_through_yields() {
+        let count = Cell::new(0);
+
+        assert_eq!(
+            run_until_stalled(pin!(count_yields(&count, 5))),
+            Poll::Ready(5)
+        );
+    }
+
+    #[test]
+    fn run_until_stalled_stops_without_wake() {
+        let polls = Cell::new(0);
+        let mut future = pin!(poll_fn(|_| {
+            polls.set(polls.get() + 1);
+            Poll::<()>::Pending
+        }));
+
+        assert_eq!(run_until_stalled(future.as_mut()), Poll::Pending);
+        assert_eq!(run_until_stalled(future), Poll::Pending);
+        assert_eq!(polls.get(), 2);
+    }
+
+    #[test]
+    fn run_until_stalled_forgets_wakes_between_calls() {
+        let polls = Cell::new(0);
+        let mut future = pin!(poll_fn(|cx| {
+            polls.set(polls.get() + 1);
+            if polls.get() == 1 {
+                cx.waker().wake_by_ref();
+            }
+            Poll::<()>::Pending
+        }));
+
+        assert_eq!(run_until_stalled(future.as_mut()), Poll::Pending);
+        assert_eq!(polls.get(), 2);
+        assert_eq!(run_until_stalled(future), Poll::Pending);
+        assert_eq!(polls.get(), 3);
+    }
+
+    #[test]
+    fn block_on_or_fail_returns_output() {
+        let count = Cell::new(0);
+
+        assert_eq!(block_on_or_fail(count_yields(&count, 3), 4), 3);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected the future to complete within 3 poll(s), actually it is still \
+        pending without waiting for any of the mocks"
+    )]
+    fn block_on_or_fail_reports_pending_future() {
+        let count = Cell::new(0);
+
+        block_on_or_fail(count_yields(&count, 3), 3);
+    }
+
+    #[test]
+    fn waits_are_only_recorded_for_last_poll() {
+        let receiver = crate::sync::MockReceiver::with_received([1]);
+        let receive = crate::sync::Receiver::receive(&receiver);
+        let mut receive = pin!(receive);
+
+        let (poll, waited) = waiting::recording(|| poll_once(receive.as_mut()));
+        assert_eq!(poll, Poll::Ready(1));
+        assert!(waited.is_empty());
+        let (poll, waited) = waiting::recording(|| poll_once(receive));
+        assert_eq!(poll, Poll::Pending);
+        assert_eq!(waited, ["MockReceiver::receive()"]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected the future to complete within 5 poll(s), actually it is still waiting \
+        for MockSignal::wait(), MockMutex::lock()"
+    )]
+    fn block_on_or_fail_lists_waiting_mocks() {
+        use crate::sync::{MockMutex, MockSignal, Mutex, Signal};
+        use embassy_futures::join::join;
+
+        let signal = MockSignal::<u8>::expect(0);
+        let mutex = MockMutex::new(0);
+        let _guard = mutex.try_lock().unwrap();
+
+        block_on_or_fail(join(signal.wait(), mutex.lock()), 5);
+    }
+
+    #[test]
+    fn nothing_is_recorded_outside_block_on_or_fail() {
+        let signal = crate::sync::MockSignal::<u8>::expect(0);
+
+        assert_eq!(
+            poll_once(pin!(crate::sync::Signal::wait(&signal))),
+            Poll::Pending
+        );
+
+        let ((), waited) = waiting::recording(|| {});
+        assert!(waited.is_empty());
+    }
+
+    #[test]
+    fn stepper_polls_once_per_step() {
+        let count = Cell::new(0);
+        let mut stepper = Stepper::new(count_yields(&count, 2));
+        assert_eq!(count.get(), 0);
+
+        assert!(!stepper.step());
+        assert_eq!(count.get(), 1);
+        assert!(stepper.is_woken());
+        assert!(!stepper.step());
+        assert!(stepper.step());
+
+        assert_eq!(stepper.steps(), 3);
+        assert_eq!(stepper.into_output(), Some(2));
+    }
+
+    #[test]
+    fn stepper_does_not_poll_after_completion() {
+        let mut stepper = Stepper::new(async { 4 });
+
+        assert!(stepper.step());
+        assert!(stepper.step());
+
+        assert!(stepper.is_finished());
+        assert_eq!(stepper.steps(), 1);
+        assert_eq!(stepper.output(), Some(&4));
+    }
+
+    #[test]
+    fn stepper_drops_completed_future() {
+        let held = Arc::new(1);
+        let future_held = Arc::clone(&held);
+        let mut stepper = Stepper::new(async move { *future_held });
+
+        assert!(stepper.step());
+
+        assert_eq!(Arc::strong_count(&held), 1);
+        assert_eq!(stepper.output(), Some(&1));
+    }
+
+    #[test]
+    fn stepper_steps_until_stalled() {
+        let count = Cell::new(0);
+        let signal = crate::sync::MockSignal::expect(0);
+        let mut stepper = Stepper::new(async {
+            count_yields(&count, 3).await;
+            crate::sync::Signal::wait(&signal).await
+        });
+
+        assert!(!stepper.step_until_stalled());
+        assert_eq!(stepper.steps(), 4);
+        assert!(!stepper.is_woken());
+
+        signal.trigger(7);
+        assert!(stepper.step_until_stalled());
+        assert_eq!(stepper.output(), Some(&7));
+    }
+
+    #[test]
+    fn works_with_unsized_futures() {
+        let mut future: Pin<&mut dyn Future<Output = ()>> = pin!(pending());
+
+        assert_eq!(poll_once(future.as_mut()), Poll::Pending);
+        assert_eq!(run_until_stalled(future), Poll::Pending);
+    }
+}
@@ -0,0 +1,255 @@
+//! A [`ManualFuture`] that stays pending until the test completes it, to build hand-rolled mocks
+//! of the traits of a project that return futures.
+//!
+//! # Examples
+//! ```
+//! use core::future::Future;
+//!
+//! // A trait of the project, for a sensor that takes a while to measure.
+//! trait Sensor {
+//!     fn measure(&self) -> impl Future<Output = u16>;
+//! }
+//!
+//! async fn is_too_hot(sensor: &impl Sensor) -> bool {
+//!     sensor.measure().await > 80
+//! }
+//!
+//! # test_is_too_hot();
+//! // The unit tests that mock the `Sensor` with a `ManualFuture`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_mock::futures::{ManualFuture, Stepper};
+//!
+//!     struct MockSensor {
+//!         measurement: ManualFuture<u16>,
+//!     }
+//!
+//!     impl Sensor for MockSensor {
+//!         fn measure(&self) -> impl Future<Output = u16> {
+//!             self.measurement.wait()
+//!         }
+//!     }
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_is_too_hot() {
+//!         let sensor = MockSensor { measurement: ManualFuture::new() };
+//!         let mut stepper = Stepper::new(is_too_hot(&sensor));
+//!
+//!         assert!(!stepper.step());
+//!         assert!(sensor.measurement.is_awaited());
+//!         sensor.measurement.complete(95);
+//!
+//!         assert!(stepper.step());
+//!         assert_eq!(stepper.output(), Some(&true));
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use crate::waiting;
+use core::{
+    future::{Future, IntoFuture},
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use std::{sync::Mutex, vec::Vec};
+
+/// A future that stays pending until the test calls [`Self::complete()`], that plugs in anywhere
+/// the code under test awaits something external.
+///
+/// The futures returned by [`Self::wait()`], or by awaiting a reference to it, wait for the value
+/// given to [`Self::complete()`]. The first of them to be polled after it is completed takes the
+/// value, so the same [`ManualFuture`] can be completed again for the next call of a mocked
+/// method.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::futures::{poll_once, ManualFuture};
+/// use std::pin::pin;
+///
+/// let reply = ManualFuture::new();
+/// let mut first = pin!(reply.wait());
+/// assert!(poll_once(first.as_mut()).is_pending());
+///
+/// reply.complete("pong");
+/// assert!(reply.is_completed());
+/// assert_eq!(poll_once(first), "pong".into());
+///
+/// // The value was taken, so the next wait is pending until it is completed again.
+/// assert!(poll_once(pin!(reply.wait())).is_pending());
+/// ```
+#[derive(Debug)]
+pub struct ManualFuture<T> {
+    /// The state shared with the futures that wait for it.
+    state: Mutex<ManualState<T>>,
+}
+
+/// The state of a [`ManualFuture`].
+#[derive(Debug)]
+struct ManualState<T> {
+    /// The value given to [`ManualFuture::complete()`], until it is taken by a wait.
+    value: Option<T>,
+
+    /// The wakers of the waits that are pending.
+    wakers: Vec<Waker>,
+}
+
+impl<T> ManualFuture<T> {
+    /// Create a [`ManualFuture`] that is pending until it is completed.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(ManualState {
+                value: None,
+                wakers: Vec::new(),
+            }),
+        }
+    }
+
+    /// Wait for the value given to [`Self::complete()`].
+    pub fn wait(&self) -> impl Future<Output = T> + '_ {
+        ManualWait { manual: self }
+    }
+
+    /// Complete the waits with the `value`, replacing a value that hasn't been taken yet, and wake
+    /// the waits that are pending.
+    pub fn complete(&self, value: T) {
+        let mut state = self.lock();
+        state.value = Some(value);
+        for waker in state.wakers.drain(..) {
+            waiting::waking("ManualFuture::complete()", || waker.wake());
+        }
+    }
+
+    /// Has it been completed with a value that a wait hasn't taken yet.
+    pub fn is_completed(&self) -> bool {
+        self.lock().value.is_some()
+    }
+
+    /// Is a wait pending, for the code under test to be woken by [`Self::complete()`].
+    pub fn is_awaited(&self) -> bool {
+        !self.lock().wakers.is_empty()
+    }
+
+    /// Lock the state, which is never poisoned as the mock doesn't panic while holding the lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, ManualState<T>> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl<'a, T> IntoFuture for &'a ManualFuture<T> {
+    type Output = T;
+    type IntoFuture = ManualWait<'a, T>;
+
+    /// Wait for the value given to [`ManualFuture::complete()`].
+    fn into_future(self) -> Self::IntoFuture {
+        ManualWait { manual: self }
+    }
+}
+
+/// The future that waits for a [`ManualFuture`] to be completed.
+#[derive(Debug)]
+pub struct ManualWait<'a, T> {
+    /// The manual future that is waited for.
+    manual: &'a ManualFuture<T>,
+}
+
+impl<T> Future for ManualWait<'_, T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.manual.lock();
+        match state.value.take() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                if !state.wakers.iter().any(|other| other.will_wake(cx.waker())) {
+                    state.wakers.push(cx.waker().clone());
+                }
+                waiting::record("ManualFuture::wait()");
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        futures::{block_on_or_fail, poll_once, Stepper},
+        sync::WakeProbe,
+    };
+    use core::pin::pin;
+    use embassy_futures::join::join;
+
+    #[test]
+    fn completed_before_wait_is_ready() {
+        let manual = ManualFuture::new();
+
+        manual.complete(1);
+
+        assert_eq!(block_on_or_fail(manual.wait(), 1), 1);
+        assert!(!manual.is_completed());
+    }
+
+    #[test]
+    fn wait_is_woken_by_complete() {
+        let manual = ManualFuture::new();
+        let mut probe = WakeProbe::new(manual.wait());
+
+        probe.assert_pending();
+        assert!(manual.is_awaited());
+        manual.complete(2);
+
+        assert_eq!(probe.assert_ready(), 2);
+        assert!(!manual.is_awaited());
+    }
+
+    #[test]
+    fn awaiting_reference_waits() {
+        let manual = ManualFuture::new();
+        let mut stepper = Stepper::new(async { (&manual).await + 1 });
+
+        assert!(!stepper.step());
+        manual.complete(3);
+
+        assert!(stepper.step());
+        assert_eq!(stepper.into_output(), Some(4));
+    }
+
+    #[test]
+    fn complete_replaces_untaken_value() {
+        let manual = ManualFuture::new();
+
+        manual.complete(1);
+        manual.complete(2);
+
+        assert_eq!(poll_once(pin!(manual.wait())), Poll::Ready(2));
+    }
+
+    #[test]
+    fn first_wait_polled_takes_the_value() {
+        let manual = ManualFuture::new();
+        let mut stepper = Stepper::new(join(manual.wait(), manual.wait()));
+
+        assert!(!stepper.step());
+        manual.complete(5);
+        assert!(!stepper.step());
+        manual.complete(6);
+
+        assert!(stepper.step());
+        assert_eq!(stepper.into_output(), Some((5, 6)));
+    }
+
+    #[test]
+    #[should_panic(expected = "still waiting for ManualFuture::wait()")]
+    fn wait_is_reported_when_never_completed() {
+        let manual = ManualFuture::<u8>::new();
+
+        block_on_or_fail(manual.wait(), 2);
+    }
+}
@@ -0,0 +1,394 @@
+//! Traits and mocked types to allow unit testing functions that call
+//! [`embassy_futures::select::select()`] to wait for the first of two events.
+//!
+//! When both futures can complete in the same poll the real `select` always picks the first, so
+//! which branch a test takes depends on the order the mocks become ready. The [`MockSelector`] is
+//! told which branch completes for each call instead.
+//!
+//! # Examples
+//! ```
+//! use core::future::ready;
+//! use embassy_futures::select::Either;
+//! use embassy_mock::futures::Selector;
+//!
+//! // Generic over the `Selector` trait
+//! async fn next_event(selector: &impl Selector) -> &'static str {
+//!     match selector.select(ready("button"), ready("timeout")).await {
+//!         Either::First(event) | Either::Second(event) => event,
+//!     }
+//! }
+//!
+//! // The real code selects with the `EmbassySelector`.
+//! // next_event(&EmbassySelector).await;
+//!
+//! # test_next_event_takes_forced_branch();
+//! // The unit tests that use the `MockSelector`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_futures::block_on;
+//!     use embassy_mock::futures::{MockSelector, SelectBranch};
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_next_event_takes_forced_branch() {
+//!         let selector = MockSelector::script([SelectBranch::Second, SelectBranch::First]);
+//!
+//!         assert_eq!(block_on(next_event(&selector)), "timeout");
+//!         assert_eq!(block_on(next_event(&selector)), "button");
+//!
+//!         selector.done().unwrap();
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use core::future::Future;
+use embassy_futures::select::{select, Either};
+#[cfg(feature = "std")]
+use {
+    core::{
+        future::poll_fn,
+        pin::pin,
+        task::{Context, Poll},
+    },
+    snafu::prelude::*,
+    std::{collections::VecDeque, sync::Mutex, vec::Vec},
+};
+
+/// The trait to replace [`embassy_futures::select::select()`] in code to allow the
+/// [`MockSelector`] to be used in its place for tests.
+pub trait Selector {
+    /// Wrapper for [`embassy_futures::select::select()`].
+    fn select<A: Future, B: Future>(
+        &self,
+        first: A,
+        second: B,
+    ) -> impl Future<Output = Either<A::Output, B::Output>>;
+}
+
+/// The [`Selector`] for the real code, that calls [`embassy_futures::select::select()`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EmbassySelector;
+
+impl Selector for EmbassySelector {
+    /// Wait for one of two futures to complete, dropping the other.
+    fn select<A: Future, B: Future>(
+        &self,
+        first: A,
+        second: B,
+    ) -> impl Future<Output = Either<A::Output, B::Output>> {
+        select(first, second)
+    }
+}
+
+impl<S: Selector + ?Sized> Selector for &S {
+    /// Call [`Selector::select()`] of the referenced selector.
+    fn select<A: Future, B: Future>(
+        &self,
+        first: A,
+        second: B,
+    ) -> impl Future<Output = Either<A::Output, B::Output>> {
+        (**self).select(first, second)
+    }
+}
+
+/// A branch of [`Selector::select()`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectBranch {
+    /// The first future, returned as [`Either::First`].
+    First,
+
+    /// The second future, returned as [`Either::Second`].
+    Second,
+}
+
+/// The errors that are reported by [`MockSelector`].
+#[cfg(feature = "std")]
+#[derive(Debug, Snafu, PartialEq)]
+pub enum MockSelectorError {
+    /// Some of the scripted branches were never selected.
+    #[snafu(display("expected {remaining} more select(s) to take their scripted branch"))]
+    UnselectedBranches {
+        /// The number of scripted branches that were never used.
+        remaining: usize,
+    },
+}
+
+/// A mocked version of [`embassy_futures::select::select()`] that can be used in its place for
+/// unit tests.
+///
+/// Each call to [`Selector::select()`] takes the next branch of the script, and only that future
+/// is polled, so it completes first however soon the other one could. The other future is
+/// dropped without being polled at all, so it doesn't take anything such as a message from a
+/// channel. The forced future must complete for the select to complete, so the test has to make
+/// sure it does.
+///
+/// Once the script has run out it selects as the real `select`, polling the first future and
+/// then the second. It records which branch completed each select.
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if any of the scripted branches
+/// were never selected.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::{block_on, select::Either};
+/// use embassy_mock::{
+///     futures::{MockSelector, SelectBranch, Selector},
+///     sync::{MockReceiver, MockSignal, Receiver, Signal},
+/// };
+///
+/// // Handle the commands until asked to stop.
+/// async fn run(
+///     commands: &impl Receiver<u8>,
+///     stop: &impl Signal<()>,
+///     selector: &impl Selector,
+/// ) -> u8 {
+///     let mut total = 0;
+///     loop {
+///         match selector.select(commands.receive(), stop.wait()).await {
+///             Either::First(command) => total += command,
+///             Either::Second(()) => break total,
+///         }
+///     }
+/// }
+///
+/// // Stop while there is still a command waiting.
+/// let commands = MockReceiver::with_received([1, 2, 4]);
+/// let stop = MockSignal::expect(0);
+/// stop.trigger(());
+/// let selector = MockSelector::script([
+///     SelectBranch::First,
+///     SelectBranch::First,
+///     SelectBranch::Second,
+/// ]);
+///
+/// assert_eq!(block_on(run(&commands, &stop, &selector)), 3);
+/// assert_eq!(selector.selected().last(), Some(&SelectBranch::Second));
+///
+/// // The last command wasn't taken by the dropped branch.
+/// assert_eq!(commands.try_receive(), Ok(4));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockSelector {
+    /// The scripted branches and the selected ones, shared by every call.
+    state: Mutex<SelectorState>,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+/// The state of a [`MockSelector`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct SelectorState {
+    /// The branches still to be forced, in call order.
+    script: VecDeque<SelectBranch>,
+
+    /// The branches that completed, in the order the selects completed.
+    selected: Vec<SelectBranch>,
+}
+
+#[cfg(feature = "std")]
+impl MockSelector {
+    /// Create a [`MockSelector`] that selects as the real `select`, recording the branches that
+    /// completed.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(SelectorState {
+                script: VecDeque::new(),
+                selected: Vec::new(),
+            }),
+            is_done: false,
+        }
+    }
+
+    /// Create a [`MockSelector`] where each call to [`Selector::select()`] completes with the
+    /// next branch of the `script`.
+    pub fn script(script: impl IntoIterator<Item = SelectBranch>) -> Self {
+        let selector = Self::new();
+        selector.lock().script.extend(script);
+        selector
+    }
+
+    /// Add the `branch` to be forced after the others in the script.
+    pub fn push_branch(&self, branch: SelectBranch) {
+        self.lock().script.push_back(branch);
+    }
+
+    /// The branches that completed each select, in the order they completed.
+    pub fn selected(&self) -> Vec<SelectBranch> {
+        self.lock().selected.clone()
+    }
+
+    /// Check that all of the scripted branches were selected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::future::ready;
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::futures::{MockSelector, MockSelectorError, SelectBranch, Selector};
+    ///
+    /// let selector = MockSelector::script([SelectBranch::Second, SelectBranch::Second]);
+    /// block_on(selector.select(ready(1), ready(2)));
+    ///
+    /// let expected = Err(MockSelectorError::UnselectedBranches { remaining: 1 });
+    /// assert_eq!(selector.done(), expected);
+    ///
+    /// // This doesn't panic when `selector` is dropped as `selector.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockSelectorError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that all of the scripted branches were selected.
+    fn check(&self) -> Result<(), MockSelectorError> {
+        let remaining = self.lock().script.len();
+        ensure!(remaining == 0, UnselectedBranchesSnafu { remaining });
+
+        Ok(())
+    }
+
+    /// Lock the state, which is never poisoned as the mock doesn't panic while holding the lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, SelectorState> {
+        self.state.lock().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for MockSelector {
+    /// If [`Self::done()`] has not been called before being dropped then check that all of the
+    /// scripted branches were selected.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Selector for MockSelector {
+    /// Poll only the future of the next scripted branch until it completes, or both futures as
+    /// the real `select` once the script has run out.
+    fn select<A: Future, B: Future>(
+        &self,
+        first: A,
+        second: B,
+    ) -> impl Future<Output = Either<A::Output, B::Output>> {
+        let forced = self.lock().script.pop_front();
+        async move {
+            let mut first = pin!(first);
+            let mut second = pin!(second);
+            let output = poll_fn(|cx: &mut Context<'_>| match forced {
+                Some(SelectBranch::First) => first.as_mut().poll(cx).map(Either::First),
+                Some(SelectBranch::Second) => second.as_mut().poll(cx).map(Either::Second),
+                None => match first.as_mut().poll(cx) {
+                    Poll::Ready(output) => Poll::Ready(Either::First(output)),
+                    Poll::Pending => second.as_mut().poll(cx).map(Either::Second),
+                },
+            })
+            .await;
+
+            let branch = match output {
+                Either::First(_) => SelectBranch::First,
+                Either::Second(_) => SelectBranch::Second,
+            };
+            self.lock().selected.push(branch);
+            output
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::future::{pending, ready};
+    use embassy_futures::block_on;
+    use futures_util::task::noop_waker_ref;
+
+    async fn first_ready(selector: &impl Selector) -> Either<u8, u8> {
+        selector.select(ready(1), ready(2)).await
+    }
+
+    #[test]
+    fn embassy_selector_prefers_first() {
+        let output = block_on(first_ready(&EmbassySelector));
+
+        assert!(matches!(output, Either::First(1)));
+    }
+
+    #[test]
+    fn mock_forces_scripted_branches() {
+        let selector = MockSelector::script([SelectBranch::Second, SelectBranch::First]);
+
+        assert!(matches!(
+            block_on(first_ready(&selector)),
+            Either::Second(2)
+        ));
+        assert!(matches!(block_on(first_ready(&selector)), Either::First(1)));
+
+        assert_eq!(
+            selector.selected(),
+            [SelectBranch::Second, SelectBranch::First]
+        );
+        selector.done().unwrap();
+    }
+
+    #[test]
+    fn mock_selects_as_real_once_script_runs_out() {
+        let selector = MockSelector::new();
+
+        assert!(matches!(block_on(first_ready(&selector)), Either::First(1)));
+        let output = block_on(selector.select(pending::<u8>(), ready(2)));
+
+        assert!(matches!(output, Either::Second(2)));
+        assert_eq!(
+            selector.selected(),
+            [SelectBranch::First, SelectBranch::Second]
+        );
+    }
+
+    #[test]
+    fn forced_branch_waits_even_if_other_is_ready() {
+        let selector = MockSelector::script([SelectBranch::First]);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut select = pin!(selector.select(pending::<u8>(), ready(2)));
+
+        assert!(select.as_mut().poll(&mut cx).is_pending());
+        assert!(select.as_mut().poll(&mut cx).is_pending());
+        assert!(selector.selected().is_empty());
+    }
+
+    #[test]
+    fn pushed_branch_is_forced_after_script() {
+        let selector = MockSelector::script([SelectBranch::First]);
+        selector.push_branch(SelectBranch::Second);
+
+        assert!(matches!(block_on(first_ready(&selector)), Either::First(1)));
+        assert!(matches!(
+            block_on(first_ready(&selector)),
+            Either::Second(2)
+        ));
+        selector.done().unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 1 more select(s) to take their scripted branch")]
+    fn unselected_branches_just_drop() {
+        let _selector = MockSelector::script([SelectBranch::Second]);
+    }
+}
@@ -0,0 +1,338 @@
+//! A [`TestWaker`] that records each time it is woken and by which mock, to check that
+//! hand-written futures wake their task exactly once per event.
+//!
+//! # Examples
+//! ```
+//! use core::{
+//!     future::Future,
+//!     pin::Pin,
+//!     sync::atomic::{AtomicBool, Ordering},
+//!     task::{Context, Poll},
+//! };
+//! use embassy_mock::sync::AtomicWaker;
+//!
+//! // A hand-written future that waits for the interrupt to flag that the data is ready.
+//! struct DataReady<'a, W> {
+//!     is_ready: &'a AtomicBool,
+//!     waker: &'a W,
+//! }
+//!
+//! impl<W: AtomicWaker> Future for DataReady<'_, W> {
+//!     type Output = ();
+//!
+//!     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+//!         self.waker.register(cx.waker());
+//!         if self.is_ready.swap(false, Ordering::AcqRel) {
+//!             Poll::Ready(())
+//!         } else {
+//!             Poll::Pending
+//!         }
+//!     }
+//! }
+//!
+//! # test_data_ready_is_woken_once();
+//! // The unit tests that check the wakes.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_mock::{futures::TestWaker, sync::MockAtomicWaker};
+//!     use std::pin::pin;
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_data_ready_is_woken_once() {
+//!         let is_ready = AtomicBool::new(false);
+//!         let waker = MockAtomicWaker::new();
+//!         let test_waker = TestWaker::new();
+//!         let mut ready = pin!(DataReady { is_ready: &is_ready, waker: &waker });
+//!
+//!         assert!(ready.as_mut().poll(&mut test_waker.context()).is_pending());
+//!         is_ready.store(true, Ordering::Release);
+//!         waker.wake();
+//!
+//!         test_waker.assert_woken_once_by("MockAtomicWaker::wake()");
+//!         assert!(ready.poll(&mut test_waker.context()).is_ready());
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use crate::waiting;
+use core::task::{Context, Waker};
+use std::{
+    string::String,
+    sync::{Arc, Mutex},
+    task::Wake,
+    vec::Vec,
+};
+
+/// A waker for tests that records each time it is woken and which mock woke it, with assertions
+/// on the number of wakes.
+///
+/// A mock that wakes it on the same thread is recorded by the name of the mock method that woke
+/// it, such as `"MockSignal::signal()"`. Anything else that wakes it, including a future waking
+/// itself or a mock on another thread, is recorded as [`None`].
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::futures::{ManualFuture, TestWaker};
+/// use std::{future::Future, pin::pin};
+///
+/// let reply = ManualFuture::new();
+/// let waker = TestWaker::new();
+/// let mut wait = pin!(reply.wait());
+///
+/// assert!(wait.as_mut().poll(&mut waker.context()).is_pending());
+/// waker.assert_wake_count(0);
+///
+/// waker.waker().wake_by_ref();
+/// reply.complete(7);
+///
+/// assert_eq!(waker.wakes(), [None, Some("ManualFuture::complete()")]);
+/// assert_eq!(waker.wake_count_by("ManualFuture::complete()"), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TestWaker {
+    /// Records the wakes of the `waker`.
+    wakes: Arc<WakeLog>,
+
+    /// The waker that records its wakes.
+    waker: Waker,
+}
+
+/// A [`Wake`] implementation that records the mock that woke it each time.
+#[derive(Debug, Default)]
+struct WakeLog(Mutex<Vec<Option<&'static str>>>);
+
+impl Wake for WakeLog {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let mock = waiting::waking_mock();
+        self.0.lock().unwrap().push(mock);
+    }
+}
+
+impl TestWaker {
+    /// Create a [`TestWaker`] that hasn't been woken.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        let wakes = Arc::new(WakeLog::default());
+        Self {
+            waker: Waker::from(Arc::clone(&wakes)),
+            wakes,
+        }
+    }
+
+    /// The [`Waker`] that records its wakes with this [`TestWaker`].
+    pub fn waker(&self) -> &Waker {
+        &self.waker
+    }
+
+    /// A [`Context`] to poll a future with the [`Self::waker()`].
+    pub fn context(&self) -> Context<'_> {
+        Context::from_waker(&self.waker)
+    }
+
+    /// The number of times it has been woken.
+    pub fn wake_count(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// The number of times it has been woken by the `mock`.
+    pub fn wake_count_by(&self, mock: &str) -> usize {
+        self.lock()
+            .iter()
+            .filter(|&&woken_by| woken_by == Some(mock))
+            .count()
+    }
+
+    /// The mock that woke it each time it was woken, in order, or [`None`] for the wakes that
+    /// weren't by a mock.
+    pub fn wakes(&self) -> Vec<Option<&'static str>> {
+        self.lock().clone()
+    }
+
+    /// Forget the wakes so far, to check the wakes of the next event.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Assert that it has been woken `expected` times.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it was woken a different number of times.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use embassy_mock::futures::TestWaker;
+    ///
+    /// let waker = TestWaker::new();
+    /// // Waking too often makes the executor poll the task for nothing.
+    /// waker.waker().wake_by_ref();
+    /// waker.waker().wake_by_ref();
+    ///
+    /// // Panics: expected to be woken 1 time(s), actually woken 2 by another waker, another waker
+    /// waker.assert_wake_count(1);
+    /// ```
+    #[track_caller]
+    pub fn assert_wake_count(&self, expected: usize) {
+        let wakes = self.lock();
+        assert!(
+            wakes.len() == expected,
+            "expected to be woken {expected} time(s), actually woken {}{}",
+            wakes.len(),
+            Self::by(&wakes)
+        );
+    }
+
+    /// Assert that it has been woken exactly once, by the `mock`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it wasn't woken once, or if it was woken by something else.
+    #[track_caller]
+    pub fn assert_woken_once_by(&self, mock: &str) {
+        let wakes = self.lock();
+        assert!(
+            *wakes == [Some(mock)],
+            "expected to be woken once by {mock}, actually woken {}{}",
+            wakes.len(),
+            Self::by(&wakes)
+        );
+    }
+
+    /// Describe which mocks made the `wakes`, if there were any.
+    fn by(wakes: &[Option<&'static str>]) -> String {
+        if wakes.is_empty() {
+            return String::new();
+        }
+
+        let mocks: Vec<_> = wakes
+            .iter()
+            .map(|mock| mock.unwrap_or("another waker"))
+            .collect();
+        std::format!(" by {}", mocks.join(", "))
+    }
+
+    /// Lock the wakes, which are never poisoned as nothing panics while holding the lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Option<&'static str>>> {
+        self.wakes.0.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        futures::{ManualFuture, MockYielder, Yielder},
+        sync::{MockMutex, MockSender, MockSignal, Mutex, Sender, Signal},
+    };
+    use core::{future::Future, pin::pin};
+
+    #[test]
+    fn not_woken_until_woken() {
+        let waker = TestWaker::new();
+
+        waker.assert_wake_count(0);
+        assert!(waker.wakes().is_empty());
+    }
+
+    #[test]
+    fn records_the_mock_that_woke_it() {
+        let signal = MockSignal::expect(1);
+        let waker = TestWaker::new();
+        let mut wait = pin!(signal.wait());
+
+        assert!(wait.as_mut().poll(&mut waker.context()).is_pending());
+        signal.signal(2);
+
+        waker.assert_woken_once_by("MockSignal::signal()");
+    }
+
+    #[test]
+    fn records_each_event_after_clear() {
+        let sender = MockSender::new();
+        sender.set_full(true);
+        let waker = TestWaker::new();
+        let mut send = pin!(sender.send(1));
+
+        assert!(send.as_mut().poll(&mut waker.context()).is_pending());
+        sender.set_full(false);
+        waker.assert_woken_once_by("MockSender::set_full()");
+        waker.clear();
+
+        assert!(send.poll(&mut waker.context()).is_ready());
+        waker.assert_wake_count(0);
+    }
+
+    #[test]
+    fn records_self_wakes_of_mocks() {
+        let yielder = MockYielder::new();
+        let waker = TestWaker::new();
+
+        assert!(pin!(yielder.yield_now())
+            .poll(&mut waker.context())
+            .is_pending());
+
+        assert_eq!(waker.wakes(), [Some("MockYielder::yield_now()")]);
+    }
+
+    #[test]
+    fn counts_wakes_by_each_mock() {
+        let mutex = MockMutex::new(0);
+        let manual = ManualFuture::new();
+        let waker = TestWaker::new();
+        let guard = mutex.try_lock().unwrap();
+        let mut lock = pin!(mutex.lock());
+        let mut wait = pin!(manual.wait());
+
+        assert!(lock.as_mut().poll(&mut waker.context()).is_pending());
+        assert!(wait.as_mut().poll(&mut waker.context()).is_pending());
+        drop(guard);
+        manual.complete(());
+
+        assert_eq!(waker.wake_count(), 2);
+        assert_eq!(waker.wake_count_by("MockMutexGuard::drop()"), 1);
+        assert_eq!(waker.wake_count_by("ManualFuture::complete()"), 1);
+    }
+
+    #[test]
+    fn wakes_from_another_thread_are_not_by_a_mock() {
+        let waker = TestWaker::new();
+        let other = waker.waker().clone();
+
+        std::thread::spawn(move || other.wake()).join().unwrap();
+
+        assert_eq!(waker.wakes(), [None]);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to be woken 1 time(s), actually woken 0")]
+    fn missing_wake_is_reported() {
+        TestWaker::new().assert_wake_count(1);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected to be woken once by ManualFuture::complete(), actually woken 2 by \
+                    ManualFuture::complete(), another waker"
+    )]
+    fn extra_wake_is_reported() {
+        let manual = ManualFuture::new();
+        let waker = TestWaker::new();
+        let mut wait = pin!(manual.wait());
+        assert!(wait.as_mut().poll(&mut waker.context()).is_pending());
+
+        manual.complete(1);
+        waker.waker().wake_by_ref();
+
+        waker.assert_woken_once_by("ManualFuture::complete()");
+    }
+}
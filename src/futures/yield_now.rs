@@ -0,0 +1,341 @@
+//! Traits and mocked types to allow unit testing functions that call
+//! [`embassy_futures::yield_now()`] to let the other tasks run during a long computation.
+//!
+//! # Examples
+//! ```
+//! use embassy_mock::futures::Yielder;
+//!
+//! // Generic over the `Yielder` trait
+//! async fn checksum(data: &[u8], yielder: &impl Yielder) -> u8 {
+//!     let mut sum = 0u8;
+//!     for chunk in data.chunks(64) {
+//!         sum = chunk.iter().fold(sum, |sum, byte| sum.wrapping_add(*byte));
+//!         yielder.yield_now().await;
+//!     }
+//!     sum
+//! }
+//!
+//! // The real code yields with the `EmbassyYielder`.
+//! // checksum(&data, &EmbassyYielder).await;
+//!
+//! # test_checksum_yields_per_chunk();
+//! // The unit tests that use the `MockYielder`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_futures::block_on;
+//!     use embassy_mock::futures::MockYielder;
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_checksum_yields_per_chunk() {
+//!         let yielder = MockYielder::new().expect_at_least(4);
+//!
+//!         assert_eq!(block_on(checksum(&[1; 256], &yielder)), 0);
+//!
+//!         yielder.done().unwrap();
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use core::future::Future;
+#[cfg(feature = "std")]
+use {
+    crate::waiting,
+    core::{
+        pin::Pin,
+        sync::atomic::{AtomicUsize, Ordering},
+        task::{Context, Poll},
+    },
+    snafu::prelude::*,
+};
+
+/// The trait to replace [`embassy_futures::yield_now()`] in code to allow the [`MockYielder`] to
+/// be used in its place for tests.
+pub trait Yielder {
+    /// Wrapper for [`embassy_futures::yield_now()`].
+    fn yield_now(&self) -> impl Future<Output = ()>;
+}
+
+/// The [`Yielder`] for the real code, that calls [`embassy_futures::yield_now()`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EmbassyYielder;
+
+impl Yielder for EmbassyYielder {
+    /// Yield from the current task once, allowing other tasks to run.
+    fn yield_now(&self) -> impl Future<Output = ()> {
+        embassy_futures::yield_now()
+    }
+}
+
+impl<Y: Yielder + ?Sized> Yielder for &Y {
+    /// Call [`Yielder::yield_now()`] of the referenced yielder.
+    fn yield_now(&self) -> impl Future<Output = ()> {
+        (**self).yield_now()
+    }
+}
+
+/// The errors that are reported by [`MockYielder`].
+#[cfg(feature = "std")]
+#[derive(Debug, Snafu, PartialEq)]
+pub enum MockYielderError {
+    /// The code under test yielded fewer times than expected.
+    #[snafu(display("expected to yield at least {minimum} time(s), actually yielded {actual}"))]
+    TooFewYields {
+        /// The minimum number of yields that were expected.
+        minimum: usize,
+
+        /// The number of times the code yielded.
+        actual: usize,
+    },
+}
+
+/// A mocked version of [`embassy_futures::yield_now()`] that can be used in its place for unit
+/// tests.
+///
+/// It yields as the real function does, returning [`Poll::Pending`] once after waking the task,
+/// so the other futures polled by the same executor, `join` or `select` get to run. It counts
+/// the yields, which are the futures returned by [`Yielder::yield_now()`] that were polled.
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if it yielded fewer times than
+/// given to [`Self::expect_at_least()`].
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::{block_on, join::join};
+/// use embassy_mock::futures::{MockYielder, Yielder};
+/// use std::cell::RefCell;
+///
+/// // Handle the jobs one at a time, letting the other tasks run in between.
+/// async fn run_jobs(jobs: &[u8], log: &RefCell<Vec<u8>>, yielder: &impl Yielder) {
+///     for &job in jobs {
+///         log.borrow_mut().push(job);
+///         yielder.yield_now().await;
+///     }
+/// }
+///
+/// let log = RefCell::new(Vec::new());
+/// let yielder = MockYielder::new();
+///
+/// block_on(join(
+///     run_jobs(&[1, 2], &log, &yielder),
+///     run_jobs(&[10, 20], &log, &yielder),
+/// ));
+///
+/// assert_eq!(*log.borrow(), [1, 10, 2, 20]);
+/// assert_eq!(yielder.yield_count(), 4);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockYielder {
+    /// The number of times the code yielded.
+    yield_count: AtomicUsize,
+
+    /// The minimum number of yields that are expected, if checked.
+    minimum: Option<usize>,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+#[cfg(feature = "std")]
+impl MockYielder {
+    /// Create a [`MockYielder`] that hasn't yielded, without checking how many times it yields.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            yield_count: AtomicUsize::new(0),
+            minimum: None,
+            is_done: false,
+        }
+    }
+
+    /// Expect the code under test to yield at least `minimum` times, to check that a long
+    /// computation gives the other tasks a chance to run often enough.
+    pub const fn expect_at_least(mut self, minimum: usize) -> Self {
+        self.minimum = Some(minimum);
+        self
+    }
+
+    /// The number of times the code has yielded so far.
+    pub fn yield_count(&self) -> usize {
+        self.yield_count.load(Ordering::Relaxed)
+    }
+
+    /// Check that the code yielded at least as many times as given to
+    /// [`Self::expect_at_least()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::futures::{MockYielder, MockYielderError, Yielder};
+    ///
+    /// let yielder = MockYielder::new().expect_at_least(2);
+    /// block_on(yielder.yield_now());
+    ///
+    /// let expected = Err(MockYielderError::TooFewYields { minimum: 2, actual: 1 });
+    /// assert_eq!(yielder.done(), expected);
+    ///
+    /// // This doesn't panic when `yielder` is dropped as `yielder.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockYielderError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that the code yielded at least the expected number of times.
+    fn check(&self) -> Result<(), MockYielderError> {
+        if let Some(minimum) = self.minimum {
+            let actual = self.yield_count();
+            ensure!(actual >= minimum, TooFewYieldsSnafu { minimum, actual });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for MockYielder {
+    /// If [`Self::done()`] has not been called before being dropped then check that the code
+    /// yielded at least the expected number of times.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Yielder for MockYielder {
+    /// Yield once when polled, counting the yield.
+    fn yield_now(&self) -> impl Future<Output = ()> {
+        MockYieldFuture {
+            yielder: self,
+            has_yielded: false,
+        }
+    }
+}
+
+/// The future returned by [`Yielder::yield_now()`] for a [`MockYielder`].
+#[cfg(feature = "std")]
+struct MockYieldFuture<'a> {
+    /// The yielder that counts the yield.
+    yielder: &'a MockYielder,
+
+    /// Has the future yielded, so the next poll completes.
+    has_yielded: bool,
+}
+
+#[cfg(feature = "std")]
+impl Future for MockYieldFuture<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.has_yielded {
+            return Poll::Ready(());
+        }
+
+        self.has_yielded = true;
+        self.yielder.yield_count.fetch_add(1, Ordering::Relaxed);
+        waiting::waking("MockYielder::yield_now()", || cx.waker().wake_by_ref());
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{cell::Cell, pin::pin};
+    use embassy_futures::{block_on, join::join};
+    use futures_util::task::noop_waker_ref;
+    use std::vec::Vec;
+
+    async fn count_down(counter: &Cell<u32>, steps: u32, yielder: &impl Yielder) {
+        for _ in 0..steps {
+            counter.set(counter.get() + 1);
+            yielder.yield_now().await;
+        }
+    }
+
+    async fn watch(counter: &Cell<u32>, steps: u32) -> Vec<u32> {
+        let mut seen = Vec::new();
+        for _ in 0..steps {
+            seen.push(counter.get());
+            embassy_futures::yield_now().await;
+        }
+        seen
+    }
+
+    #[test]
+    fn embassy_yielder_lets_other_futures_run() {
+        let counter = Cell::new(0);
+
+        let ((), seen) = block_on(join(
+            count_down(&counter, 3, &EmbassyYielder),
+            watch(&counter, 3),
+        ));
+
+        assert_eq!(seen, [1, 2, 3]);
+    }
+
+    #[test]
+    fn mock_lets_other_futures_run() {
+        let counter = Cell::new(0);
+        let yielder = MockYielder::new().expect_at_least(3);
+
+        let ((), seen) = block_on(join(count_down(&counter, 3, &yielder), watch(&counter, 3)));
+
+        assert_eq!(seen, [1, 2, 3]);
+        assert_eq!(yielder.yield_count(), 3);
+        yielder.done().unwrap();
+    }
+
+    #[test]
+    fn yield_is_pending_once_and_wakes() {
+        let yielder = MockYielder::new();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut yield_now = pin!(yielder.yield_now());
+
+        assert_eq!(yield_now.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(yield_now.poll(&mut cx), Poll::Ready(()));
+        assert_eq!(yielder.yield_count(), 1);
+    }
+
+    #[test]
+    fn yield_that_is_never_polled_is_not_counted() {
+        let yielder = MockYielder::new();
+
+        drop(yielder.yield_now());
+
+        assert_eq!(yielder.yield_count(), 0);
+    }
+
+    #[test]
+    fn more_yields_than_minimum_is_ok() {
+        let yielder = MockYielder::new().expect_at_least(1);
+
+        block_on(async {
+            yielder.yield_now().await;
+            yielder.yield_now().await;
+        });
+
+        yielder.done().unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to yield at least 2 time(s), actually yielded 0")]
+    fn too_few_yields_just_drop() {
+        let _yielder = MockYielder::new().expect_at_least(2);
+    }
+}
@@ -0,0 +1,21 @@
+//! Mocked types for the `embedded-io-async` traits, to allow unit testing protocol handlers that
+//! talk over an Embassy UART or USB serial class.
+//!
+//! The code under test stays generic over [`embedded_io_async::Read`] and
+//! [`embedded_io_async::Write`], so there are no traits to replace, and the [`MockSerial`] is given
+//! in place of the real peripheral. It needs the `std` feature, which the `io` feature enables.
+
+pub mod serial;
+
+pub use serial::{MockSerial, MockSerialError, Transaction};
+
+// `Read` and `Write` are implemented for `&MockSerial` so a reader task and a writer task can share
+// one mock like the halves of a split UART. As those tasks may run on different threads, the mock
+// is `Sync` as well as `Send`.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<MockSerial>();
+    assert_send_sync::<MockSerialError>();
+    assert_send_sync::<Transaction>();
+};
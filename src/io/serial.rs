@@ -0,0 +1,644 @@
+//! A mocked serial port to allow unit testing functions that talk a protocol over the
+//! [`embedded_io_async::Read`] and [`embedded_io_async::Write`] traits, such as an Embassy UART
+//! or USB serial class.
+//!
+//! # Examples
+//! ```
+//! use embedded_io_async::{Read, Write};
+//!
+//! // Check that the modem answers the attention command.
+//! async fn is_modem_ready<S: Read + Write>(serial: &mut S) -> Result<bool, S::Error> {
+//!     serial.write_all(b"AT\r\n").await?;
+//!     serial.flush().await?;
+//!
+//!     let mut reply = [0; 4];
+//!     let length = serial.read(&mut reply).await?;
+//!     Ok(&reply[..length] == b"OK\r\n")
+//! }
+//!
+//! # test_is_modem_ready();
+//! // The unit tests that use the `MockSerial`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_futures::block_on;
+//!     use embassy_mock::io::{MockSerial, Transaction};
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_is_modem_ready() {
+//!         let mut serial = MockSerial::new([
+//!             Transaction::write(b"AT\r\n"),
+//!             Transaction::Flush,
+//!             Transaction::read(b"OK\r\n"),
+//!         ]);
+//!
+//!         assert_eq!(block_on(is_modem_ready(&mut serial)), Ok(true));
+//!
+//!         serial.done().unwrap();
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use crate::waiting;
+use core::{
+    future::poll_fn,
+    task::{Poll, Waker},
+};
+use embedded_io_async::{ErrorKind, ErrorType, Read, Write};
+use snafu::prelude::*;
+use std::{collections::VecDeque, sync::Mutex, vec::Vec};
+
+/// A step of the transcript of a [`MockSerial`], in the order the code under test is expected
+/// to make them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transaction {
+    /// Provide the bytes to [`Read::read()`], over as many reads as the code needs to take them.
+    Read(Vec<u8>),
+
+    /// Expect the bytes to be written by [`Write::write()`], over as many writes as the code
+    /// needs to write them.
+    Write(Vec<u8>),
+
+    /// Expect a call to [`Write::flush()`].
+    Flush,
+
+    /// Fail the next [`Read::read()`] with the error.
+    ReadError(ErrorKind),
+
+    /// Fail the next [`Write::write()`] with the error.
+    WriteError(ErrorKind),
+}
+
+impl Transaction {
+    /// Provide the `bytes` to [`Read::read()`].
+    pub fn read(bytes: impl AsRef<[u8]>) -> Self {
+        Self::Read(bytes.as_ref().to_vec())
+    }
+
+    /// Expect the `bytes` to be written by [`Write::write()`].
+    pub fn write(bytes: impl AsRef<[u8]>) -> Self {
+        Self::Write(bytes.as_ref().to_vec())
+    }
+
+    /// Describe the call that is expected for this transaction.
+    fn expected_call(&self) -> &'static str {
+        match self {
+            Self::Read(_) | Self::ReadError(_) => "read()",
+            Self::Write(_) | Self::WriteError(_) => "write()",
+            Self::Flush => "flush()",
+        }
+    }
+}
+
+/// The errors that are reported by [`MockSerial`].
+#[derive(Debug, Snafu, PartialEq)]
+pub enum MockSerialError {
+    /// A method was called that didn't match the next transaction of the transcript.
+    #[snafu(display("expected transaction {index} to be {expected}, actually called {method}"))]
+    UnexpectedCall {
+        /// The index of the transaction in the transcript.
+        index: usize,
+
+        /// The call that the transaction expected.
+        expected: &'static str,
+
+        /// The method that was called.
+        method: &'static str,
+    },
+
+    /// The bytes that were written didn't match the bytes of the transaction.
+    #[snafu(display(
+        "expected transaction {index} to write {expected:?}, actually wrote {actual:?}"
+    ))]
+    WrongWrite {
+        /// The index of the transaction in the transcript.
+        index: usize,
+
+        /// The bytes that were still expected to be written by the transaction.
+        expected: Vec<u8>,
+
+        /// The bytes that were written instead.
+        actual: Vec<u8>,
+    },
+
+    /// A write or flush was made after the end of the transcript.
+    #[snafu(display("expected the transcript to have ended, actually called {method}"))]
+    ExtraCall {
+        /// The method that was called.
+        method: &'static str,
+    },
+
+    /// Some of the transactions of the transcript were never made.
+    #[snafu(display("expected {remaining} more transaction(s) of the transcript"))]
+    UnfinishedTranscript {
+        /// The number of transactions that were never made.
+        remaining: usize,
+    },
+}
+
+/// A mocked serial port, implementing [`Read`] and [`Write`], that follows a transcript of the
+/// expected writes and the reads to provide.
+///
+/// Each call must match the next [`Transaction`] of the transcript, otherwise it fails with
+/// [`ErrorKind::Other`] and the mismatch is reported by [`Self::done()`]. A read at the end of
+/// the transcript waits, as a serial port with nothing received, until the test adds more
+/// transactions with [`Self::push()`]. The read and write halves can be used at the same time
+/// through shared references, as `&MockSerial` also implements both traits.
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if a call didn't match the
+/// transcript, or if any of the transactions were never made.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::io::{MockSerial, Transaction};
+/// use embedded_io_async::{ErrorKind, Read, Write};
+///
+/// // Echo each chunk that is received until the line fails.
+/// async fn echo(mut rx: impl Read, mut tx: impl Write) {
+///     let mut buffer = [0; 8];
+///     while let Ok(length) = rx.read(&mut buffer).await {
+///         if tx.write_all(&buffer[..length]).await.is_err() {
+///             break;
+///         }
+///     }
+/// }
+///
+/// let serial = MockSerial::new([
+///     Transaction::read(b"hello"),
+///     Transaction::write(b"hello"),
+///     Transaction::ReadError(ErrorKind::BrokenPipe),
+/// ]);
+///
+/// block_on(echo(&serial, &serial));
+///
+/// assert_eq!(serial.written(), b"hello");
+/// serial.done().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct MockSerial {
+    /// The state shared with the futures of the reads.
+    state: Mutex<SerialState>,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+/// The state of a [`MockSerial`].
+#[derive(Debug)]
+struct SerialState {
+    /// The transactions still to be made, the first of them possibly partly made.
+    transcript: VecDeque<Transaction>,
+
+    /// The index in the transcript of the first of the `transcript`.
+    index: usize,
+
+    /// All of the bytes that were written, in order.
+    written: Vec<u8>,
+
+    /// The first call that didn't match the transcript, if any.
+    error: Option<MockSerialError>,
+
+    /// The waker of the read that is waiting for the transcript to continue.
+    waker: Option<Waker>,
+}
+
+impl SerialState {
+    /// Finish the first transaction of the transcript.
+    fn advance(&mut self) {
+        self.transcript.pop_front();
+        self.index += 1;
+    }
+
+    /// Record the `error`, unless an earlier one was recorded.
+    fn fail(&mut self, error: MockSerialError) -> ErrorKind {
+        self.error.get_or_insert(error);
+        ErrorKind::Other
+    }
+
+    /// Record that the `method` didn't match the next transaction.
+    fn unexpected(&mut self, method: &'static str) -> ErrorKind {
+        let error = match self.transcript.front() {
+            Some(transaction) => MockSerialError::UnexpectedCall {
+                index: self.index,
+                expected: transaction.expected_call(),
+                method,
+            },
+            None => MockSerialError::ExtraCall { method },
+        };
+        self.fail(error)
+    }
+}
+
+impl MockSerial {
+    /// Create a [`MockSerial`] that expects the `transcript`.
+    pub fn new(transcript: impl IntoIterator<Item = Transaction>) -> Self {
+        Self {
+            state: Mutex::new(SerialState {
+                transcript: transcript.into_iter().collect(),
+                index: 0,
+                written: Vec::new(),
+                error: None,
+                waker: None,
+            }),
+            is_done: false,
+        }
+    }
+
+    /// Add the `transaction` to the end of the transcript, waking a read that is waiting for it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::{
+    ///     futures::Stepper,
+    ///     io::{MockSerial, Transaction},
+    /// };
+    /// use embedded_io_async::Read;
+    ///
+    /// let serial = MockSerial::new([]);
+    /// let mut stepper = Stepper::new(async {
+    ///     let mut byte = [0];
+    ///     (&serial).read(&mut byte).await.map(|_| byte[0])
+    /// });
+    ///
+    /// // Nothing has been received yet.
+    /// assert!(!stepper.step());
+    ///
+    /// serial.push(Transaction::read([0x55]));
+    /// assert!(stepper.step());
+    /// assert_eq!(stepper.into_output(), Some(Ok(0x55)));
+    /// ```
+    pub fn push(&self, transaction: Transaction) {
+        let mut state = self.lock();
+        state.transcript.push_back(transaction);
+        if let Some(waker) = state.waker.take() {
+            waiting::waking("MockSerial::push()", || waker.wake());
+        }
+    }
+
+    /// All of the bytes that were written, in order.
+    pub fn written(&self) -> Vec<u8> {
+        self.lock().written.clone()
+    }
+
+    /// The number of transactions of the transcript that haven't been made.
+    pub fn remaining(&self) -> usize {
+        self.lock().transcript.len()
+    }
+
+    /// Check that every call matched the transcript and that every transaction was made.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::io::{MockSerial, MockSerialError, Transaction};
+    /// use embedded_io_async::Write;
+    ///
+    /// let mut serial = MockSerial::new([Transaction::write(b"PING"), Transaction::Flush]);
+    /// assert!(block_on(serial.write_all(b"PONG")).is_err());
+    ///
+    /// let expected = Err(MockSerialError::WrongWrite {
+    ///     index: 0,
+    ///     expected: b"PING".to_vec(),
+    ///     actual: b"PONG".to_vec(),
+    /// });
+    /// assert_eq!(serial.done(), expected);
+    ///
+    /// // This doesn't panic when `serial` is dropped as `serial.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockSerialError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that every call matched the transcript and that every transaction was made.
+    fn check(&self) -> Result<(), MockSerialError> {
+        let mut state = self.lock();
+        if let Some(error) = state.error.take() {
+            return Err(error);
+        }
+
+        let remaining = state.transcript.len();
+        ensure!(remaining == 0, UnfinishedTranscriptSnafu { remaining });
+
+        Ok(())
+    }
+
+    /// Read from the next transaction into the `buf`, waiting for the transcript to continue if
+    /// it has ended.
+    async fn read_transcript(&self, buf: &mut [u8]) -> Result<usize, ErrorKind> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        poll_fn(|cx| {
+            let mut state = self.lock();
+            match state.transcript.front_mut() {
+                Some(Transaction::Read(bytes)) => {
+                    let length = buf.len().min(bytes.len());
+                    buf[..length].copy_from_slice(&bytes[..length]);
+                    bytes.drain(..length);
+                    if bytes.is_empty() {
+                        state.advance();
+                    }
+                    Poll::Ready(Ok(length))
+                }
+                Some(Transaction::ReadError(kind)) => {
+                    let kind = *kind;
+                    state.advance();
+                    Poll::Ready(Err(kind))
+                }
+                Some(_) => Poll::Ready(Err(state.unexpected("read()"))),
+                None => {
+                    state.waker = Some(cx.waker().clone());
+                    waiting::record("MockSerial::read()");
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    /// Write the `buf` as part of the next transaction.
+    fn write_transcript(&self, buf: &[u8]) -> Result<usize, ErrorKind> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut state = self.lock();
+        let index = state.index;
+        match state.transcript.front_mut() {
+            Some(Transaction::Write(expected)) => {
+                let length = buf.len().min(expected.len());
+                if buf[..length] != expected[..length] {
+                    let error = MockSerialError::WrongWrite {
+                        index,
+                        expected: expected.clone(),
+                        actual: buf.to_vec(),
+                    };
+                    state.advance();
+                    return Err(state.fail(error));
+                }
+
+                expected.drain(..length);
+                if expected.is_empty() {
+                    state.advance();
+                }
+                state.written.extend_from_slice(&buf[..length]);
+                Ok(length)
+            }
+            Some(Transaction::WriteError(kind)) => {
+                let kind = *kind;
+                state.advance();
+                Err(kind)
+            }
+            _ => Err(state.unexpected("write()")),
+        }
+    }
+
+    /// Flush as the next transaction.
+    fn flush_transcript(&self) -> Result<(), ErrorKind> {
+        let mut state = self.lock();
+        if state.transcript.front() == Some(&Transaction::Flush) {
+            state.advance();
+            Ok(())
+        } else {
+            Err(state.unexpected("flush()"))
+        }
+    }
+
+    /// Lock the state, which is never poisoned as the mock doesn't panic while holding the lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, SerialState> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl Drop for MockSerial {
+    /// If [`Self::done()`] has not been called before being dropped then check that every call
+    /// matched the transcript and that every transaction was made.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+impl ErrorType for MockSerial {
+    type Error = ErrorKind;
+}
+
+impl Read for MockSerial {
+    /// Read the bytes of the next transaction, waiting while the transcript has ended.
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.read_transcript(buf).await
+    }
+}
+
+impl Write for MockSerial {
+    /// Check the bytes against the next transaction.
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.write_transcript(buf)
+    }
+
+    /// Check that a flush is the next transaction.
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.flush_transcript()
+    }
+}
+
+impl ErrorType for &MockSerial {
+    type Error = ErrorKind;
+}
+
+impl Read for &MockSerial {
+    /// Read the bytes of the next transaction of the referenced serial port.
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.read_transcript(buf).await
+    }
+}
+
+impl Write for &MockSerial {
+    /// Check the bytes against the next transaction of the referenced serial port.
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.write_transcript(buf)
+    }
+
+    /// Check that a flush is the next transaction of the referenced serial port.
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.flush_transcript()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::futures::{block_on_or_fail, Stepper};
+    use embassy_futures::block_on;
+
+    #[test]
+    fn reads_are_split_over_small_buffers() {
+        let mut serial = MockSerial::new([Transaction::read(b"abc")]);
+        let mut buffer = [0; 2];
+
+        assert_eq!(block_on(serial.read(&mut buffer)), Ok(2));
+        assert_eq!(buffer, *b"ab");
+        assert_eq!(block_on(serial.read(&mut buffer)), Ok(1));
+        assert_eq!(buffer[0], b'c');
+
+        serial.done().unwrap();
+    }
+
+    #[test]
+    fn read_stops_at_end_of_transaction() {
+        let mut serial = MockSerial::new([Transaction::read(b"ab"), Transaction::read(b"cd")]);
+        let mut buffer = [0; 4];
+
+        assert_eq!(block_on(serial.read(&mut buffer)), Ok(2));
+        assert_eq!(serial.remaining(), 1);
+        assert_eq!(block_on(serial.read(&mut buffer)), Ok(2));
+
+        assert_eq!(buffer[..2], *b"cd");
+        serial.done().unwrap();
+    }
+
+    #[test]
+    fn read_exact_spans_transactions() {
+        let mut serial = MockSerial::new([Transaction::read(b"ab"), Transaction::read(b"cd")]);
+        let mut buffer = [0; 4];
+
+        block_on(serial.read_exact(&mut buffer)).unwrap();
+
+        assert_eq!(buffer, *b"abcd");
+        serial.done().unwrap();
+    }
+
+    #[test]
+    fn writes_are_split_over_calls() {
+        let mut serial = MockSerial::new([Transaction::write(b"hello")]);
+
+        assert_eq!(block_on(serial.write(b"he")), Ok(2));
+        assert_eq!(block_on(serial.write(b"llo, world")), Ok(3));
+
+        assert_eq!(serial.written(), b"hello");
+        serial.done().unwrap();
+    }
+
+    #[test]
+    fn empty_buffers_are_not_transactions() {
+        let mut serial = MockSerial::new([Transaction::Flush]);
+
+        assert_eq!(block_on(serial.read(&mut [])), Ok(0));
+        assert_eq!(block_on(serial.write(&[])), Ok(0));
+
+        assert_eq!(serial.remaining(), 1);
+        serial.done().unwrap_err();
+    }
+
+    #[test]
+    fn scripted_errors_are_returned() {
+        let mut serial = MockSerial::new([
+            Transaction::WriteError(ErrorKind::TimedOut),
+            Transaction::ReadError(ErrorKind::Interrupted),
+        ]);
+
+        assert_eq!(block_on(serial.write(b"x")), Err(ErrorKind::TimedOut));
+        assert_eq!(block_on(serial.read(&mut [0])), Err(ErrorKind::Interrupted));
+
+        assert_eq!(serial.written(), []);
+        serial.done().unwrap();
+    }
+
+    #[test]
+    fn read_waits_for_pushed_transaction() {
+        let serial = MockSerial::new([Transaction::write(b"?")]);
+        let mut stepper = Stepper::new(async {
+            let (mut rx, mut tx) = (&serial, &serial);
+            tx.write_all(b"?").await.unwrap();
+            let mut reply = [0; 2];
+            rx.read_exact(&mut reply).await.map(|()| reply)
+        });
+
+        assert!(!stepper.step());
+        serial.push(Transaction::read(b"!"));
+        assert!(!stepper.step());
+        serial.push(Transaction::read(b"!"));
+
+        assert!(stepper.step());
+        assert_eq!(stepper.into_output(), Some(Ok(*b"!!")));
+        serial.done().unwrap();
+    }
+
+    #[test]
+    fn unexpected_call_fails_and_is_reported() {
+        let mut serial = MockSerial::new([Transaction::read(b"ok")]);
+
+        assert_eq!(block_on(serial.write(b"x")), Err(ErrorKind::Other));
+
+        let expected = Err(MockSerialError::UnexpectedCall {
+            index: 0,
+            expected: "read()",
+            method: "write()",
+        });
+        assert_eq!(serial.done(), expected);
+    }
+
+    #[test]
+    fn first_mismatch_is_reported() {
+        let mut serial = MockSerial::new([Transaction::write(b"a"), Transaction::Flush]);
+
+        assert_eq!(block_on(serial.write(b"b")), Err(ErrorKind::Other));
+        assert_eq!(block_on(serial.write(b"c")), Err(ErrorKind::Other));
+
+        let expected = Err(MockSerialError::WrongWrite {
+            index: 0,
+            expected: b"a".to_vec(),
+            actual: b"b".to_vec(),
+        });
+        assert_eq!(serial.done(), expected);
+    }
+
+    #[test]
+    fn calls_after_transcript_are_reported() {
+        let mut serial = MockSerial::new([]);
+
+        assert_eq!(block_on(serial.flush()), Err(ErrorKind::Other));
+
+        let expected = Err(MockSerialError::ExtraCall { method: "flush()" });
+        assert_eq!(serial.done(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "still waiting for MockSerial::read()")]
+    fn read_at_end_of_transcript_waits() {
+        let mut serial = MockSerial::new([]);
+
+        block_on_or_fail(serial.read(&mut [0]), 2).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 1 more transaction(s) of the transcript")]
+    fn unfinished_transcript_just_drop() {
+        let _serial = MockSerial::new([Transaction::Flush]);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected transaction 1 to be flush(), actually called write()")]
+    fn unexpected_call_just_drop() {
+        let mut serial = MockSerial::new([Transaction::write(b"a"), Transaction::Flush]);
+
+        block_on(serial.write_all(b"ab")).unwrap_err();
+    }
+}
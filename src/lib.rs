@@ -3,13 +3,149 @@
 //! simple wrapper for the public API. This crate also provides mocked versions of these types
 //! which also implement the traits provided so they can be used to replace the real types in unit
 //! tests.
+//!
+//! # Checks on drop
+//!
+//! The mocks that check their expectations in `done()` also check them when they are dropped
+//! without it, and panic if they aren't met. With the `std` feature the check is skipped while the
+//! thread is already panicking, so that the panic of a failed test isn't turned into an abort.
 
 #![no_std]
 #![cfg_attr(test, feature(type_alias_impl_trait))]
 #![warn(missing_docs)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+// The code generated by the macros refers to this crate by name, including in its own tests.
+#[cfg(all(test, feature = "macros"))]
+extern crate self as embassy_mock;
+
 #[cfg(feature = "executor")]
 pub mod executor;
 
+#[cfg(feature = "futures")]
+pub mod futures;
+
+#[cfg(feature = "io")]
+pub mod io;
+
+#[cfg(feature = "sync")]
+pub mod sync;
+
 #[cfg(feature = "time")]
 pub mod time;
+
+#[cfg(feature = "std")]
+mod waiting;
+
+/// Declare an Embassy task that can also be awaited directly in unit tests.
+///
+/// # Examples
+///
+/// ```
+/// # #![feature(type_alias_impl_trait)]
+/// #
+/// use core::sync::atomic::{AtomicU32, Ordering};
+/// use embassy_futures::block_on;
+/// use embassy_mock::executor::{MockSpawner, Spawner};
+///
+/// static TOTAL: AtomicU32 = AtomicU32::new(0);
+///
+/// #[embassy_mock::task(pool_size = 2)]
+/// async fn add(amount: u32) {
+///     TOTAL.fetch_add(amount, Ordering::Relaxed);
+/// }
+///
+/// fn spawn_tasks<S: Spawner>(spawner: &S) {
+///     spawner.must_spawn(add(1));
+///     spawner.must_spawn(add(2));
+/// }
+///
+/// // The tasks are spawned as normal.
+/// let spawner = MockSpawner::expect(2);
+/// spawn_tasks(&spawner);
+///
+/// // And the body can be awaited without an executor.
+/// block_on(add_body(3));
+/// assert_eq!(TOTAL.load(Ordering::Relaxed), 3);
+/// ```
+///
+/// With `record_args`, the arguments of each spawned task can be checked on the [`MockSpawner`]:
+///
+/// ```
+/// # #![feature(type_alias_impl_trait)]
+/// #
+/// use embassy_mock::executor::{MockSpawner, Spawner};
+///
+/// #[embassy_mock::task(pool_size = 2, record_args)]
+/// async fn blink(times: u32, colour: &'static str) {
+///     let _ = (times, colour);
+/// }
+///
+/// let spawner = MockSpawner::expect(2);
+/// spawner.must_spawn(blink(3, "red"));
+/// spawner.must_spawn(blink(1, "green"));
+///
+/// assert_eq!(
+///     spawner.spawned_args::<(u32, &'static str)>("blink"),
+///     [(3, "red"), (1, "green")]
+/// );
+/// ```
+///
+/// [`MockSpawner`]: executor::MockSpawner
+#[cfg(feature = "macros")]
+pub use embassy_mock_macros::task;
+
+#[cfg(all(test, feature = "macros"))]
+mod tests {
+    #[cfg(all(feature = "runtime", not(feature = "arch-std")))]
+    use crate::executor::MockExecutor;
+    use crate::executor::{MockSpawner, Spawner};
+    use core::sync::atomic::{AtomicU32, Ordering};
+    use embassy_futures::block_on;
+
+    static TOTAL: AtomicU32 = AtomicU32::new(0);
+
+    #[crate::task]
+    async fn add_to_total(mut amount: u32, times: u32) {
+        for _ in 0..times {
+            TOTAL.fetch_add(amount, Ordering::Relaxed);
+            amount += 1;
+        }
+    }
+
+    #[crate::task]
+    async fn double(value: &'static AtomicU32) {
+        value.fetch_add(value.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+
+    #[test]
+    fn task_body_can_be_awaited_directly() {
+        static VALUE: AtomicU32 = AtomicU32::new(21);
+
+        block_on(double_body(&VALUE));
+
+        assert_eq!(VALUE.load(Ordering::Relaxed), 42);
+    }
+
+    #[test]
+    fn task_can_be_spawned() {
+        let spawner = MockSpawner::expect(1);
+
+        spawner.must_spawn(add_to_total(1, 1));
+    }
+
+    #[cfg(all(feature = "runtime", not(feature = "arch-std")))]
+    #[test]
+    fn spawned_task_runs_body() {
+        let executor = MockExecutor::new();
+        executor.spawner().must_spawn(add_to_total(2, 3));
+
+        executor.run_until_idle();
+
+        assert_eq!(TOTAL.load(Ordering::Relaxed), 2 + 3 + 4);
+    }
+}
@@ -0,0 +1,110 @@
+//! A mocked version of the `embassy-sync` crate.
+//!
+//! The traits and their implementations for the Embassy types are always available, the mocks
+//! need the `std` feature. So does the [`TestRawMutex`], for tests that use the real Embassy types
+//! instead of the mocks, the [`WakeProbe`], for tests that check the code under test is woken, and
+//! the [`TestBus`], for tests that run several tasks wired together by the mocks.
+
+pub mod blocking_mutex;
+#[cfg(feature = "std")]
+pub mod bus;
+pub mod channel;
+pub mod lazy_lock;
+pub mod mutex;
+pub mod once_lock;
+pub mod priority_channel;
+pub mod pubsub;
+#[cfg(feature = "std")]
+pub mod raw_mutex;
+pub mod signal;
+pub mod waitqueue;
+#[cfg(feature = "std")]
+pub mod wake;
+pub mod watch;
+pub mod zerocopy_channel;
+
+pub use blocking_mutex::BlockingMutex;
+#[cfg(feature = "std")]
+pub use blocking_mutex::{MockBlockingMutex, MockBlockingMutexError};
+#[cfg(feature = "std")]
+pub use bus::TestBus;
+pub use channel::{Channel, ChannelLen, Receiver, Sender};
+#[cfg(feature = "std")]
+pub use channel::{
+    ChannelEvent, ChannelTranscript, MockChannel, MockChannelError, MockReceiver, MockSender,
+};
+pub use lazy_lock::LazyLock;
+#[cfg(feature = "std")]
+pub use lazy_lock::{MockLazyLock, MockLazyLockError};
+pub use mutex::Mutex;
+#[cfg(feature = "std")]
+pub use mutex::{MockMutex, MockMutexError, MockMutexGuard};
+pub use once_lock::OnceLock;
+#[cfg(feature = "std")]
+pub use once_lock::{MockOnceLock, MockOnceLockError};
+#[cfg(feature = "std")]
+pub use priority_channel::{MockPriorityChannel, MockPriorityChannelError};
+#[cfg(feature = "std")]
+pub use pubsub::{
+    MockPubSubChannel, MockPublisher, MockSubscriber, MockSubscriberError, PublishMethod,
+};
+pub use pubsub::{PubSubChannel, Publisher, Subscriber};
+#[cfg(feature = "std")]
+pub use raw_mutex::{TestChannel, TestMutex, TestRawMutex, TestSignal, TestWatch};
+pub use signal::Signal;
+#[cfg(feature = "std")]
+pub use signal::{MockSignal, MockSignalError};
+pub use waitqueue::AtomicWaker;
+#[cfg(feature = "std")]
+pub use waitqueue::{MockAtomicWaker, MockAtomicWakerError};
+#[cfg(feature = "std")]
+pub use wake::WakeProbe;
+#[cfg(feature = "std")]
+pub use watch::{
+    MockWatch, MockWatchReceiver, MockWatchReceiverError, MockWatchReceiverHandle, MockWatchSender,
+};
+pub use watch::{WatchReceiver, WatchSender};
+#[cfg(feature = "std")]
+pub use zerocopy_channel::{MockZeroCopyChannelError, MockZeroCopyReceiver, MockZeroCopySender};
+pub use zerocopy_channel::{ZeroCopyReceiver, ZeroCopySender};
+
+// The `embassy-sync` primitives exist to be shared between tasks, usually through a `static`, which
+// needs them to be `Sync`. The mocks are too so they can go in the same `static`s as the types they
+// replace, and `Send` so a `MockSender` or `MockReceiver` can be moved into a task on a thread.
+#[cfg(feature = "std")]
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<MockAtomicWaker>();
+    assert_send_sync::<MockAtomicWakerError>();
+    assert_send_sync::<MockBlockingMutex<u8>>();
+    assert_send_sync::<MockBlockingMutexError>();
+    assert_send_sync::<ChannelTranscript<u8>>();
+    assert_send_sync::<MockChannel<u8>>();
+    assert_send_sync::<MockChannelError>();
+    assert_send_sync::<MockReceiver<u8>>();
+    assert_send_sync::<MockSender<u8>>();
+    assert_send_sync::<MockLazyLock<u8>>();
+    assert_send_sync::<MockLazyLockError>();
+    assert_send_sync::<MockMutex<u8>>();
+    assert_send_sync::<MockMutexError>();
+    assert_send_sync::<MockOnceLock<u8>>();
+    assert_send_sync::<MockOnceLockError>();
+    assert_send_sync::<MockPriorityChannel<u8>>();
+    assert_send_sync::<MockPriorityChannelError>();
+    assert_send_sync::<MockPubSubChannel<u8>>();
+    assert_send_sync::<MockPublisher<u8>>();
+    assert_send_sync::<MockSubscriber<u8>>();
+    assert_send_sync::<MockSubscriberError>();
+    assert_send_sync::<MockSignal<u8>>();
+    assert_send_sync::<TestRawMutex>();
+    assert_send_sync::<MockSignalError>();
+    assert_send_sync::<MockWatch<u8>>();
+    assert_send_sync::<MockWatchReceiver<u8>>();
+    assert_send_sync::<MockWatchReceiverError>();
+    assert_send_sync::<MockWatchReceiverHandle<'static, u8>>();
+    assert_send_sync::<MockWatchSender<u8>>();
+    assert_send_sync::<MockZeroCopyChannelError>();
+    assert_send_sync::<MockZeroCopyReceiver<u8>>();
+    assert_send_sync::<MockZeroCopySender<u8>>();
+};
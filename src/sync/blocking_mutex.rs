@@ -0,0 +1,442 @@
+//! Traits and mocked types to allow unit testing functions that use an
+//! [`embassy_sync::blocking_mutex::Mutex`].
+//!
+//! # Examples
+//! ```
+//! use core::cell::Cell;
+//! use embassy_mock::sync::BlockingMutex;
+//!
+//! // Generic over the `BlockingMutex` trait
+//! fn count_event(events: &impl BlockingMutex<Cell<u32>>) {
+//!     events.lock(|count| count.set(count.get() + 1));
+//! }
+//!
+//! # test_count_event();
+//! // The unit tests that use the `MockBlockingMutex`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_mock::sync::MockBlockingMutex;
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_count_event() {
+//!         let events = MockBlockingMutex::new(Cell::new(0))
+//!             .expect_locks(2)
+//!             .expect_produced(1, |count| count.get() == 2);
+//!
+//!         count_event(&events);
+//!         count_event(&events);
+//!
+//!         assert_eq!(events.done(), Ok(()));
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use embassy_sync::blocking_mutex::{raw::RawMutex, Mutex as EmbassyBlockingMutex};
+#[cfg(feature = "std")]
+use {
+    super::TestRawMutex,
+    core::{
+        fmt,
+        sync::atomic::{AtomicUsize, Ordering},
+    },
+    snafu::prelude::*,
+    std::{
+        boxed::Box,
+        sync::{Mutex, PoisonError},
+        vec::Vec,
+    },
+};
+
+/// The trait to replace the [`embassy_sync::blocking_mutex::Mutex`] in code to allow the
+/// [`MockBlockingMutex`] to be used in its place for tests.
+pub trait BlockingMutex<T> {
+    /// Wrapper for [`embassy_sync::blocking_mutex::Mutex::lock()`].
+    fn lock<U>(&self, f: impl FnOnce(&T) -> U) -> U;
+
+    /// Wrapper for [`embassy_sync::blocking_mutex::Mutex::get_mut()`].
+    fn get_mut(&mut self) -> &mut T;
+}
+
+impl<R: RawMutex, T> BlockingMutex<T> for EmbassyBlockingMutex<R, T> {
+    /// Creates a critical section and grants temporary access to the protected data.
+    fn lock<U>(&self, f: impl FnOnce(&T) -> U) -> U {
+        self.lock(f)
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the `Mutex` mutably, no actual locking needs to take place, the
+    /// mutable borrow statically guarantees no locks exist.
+    fn get_mut(&mut self) -> &mut T {
+        self.get_mut()
+    }
+}
+
+/// The errors that are reported by [`MockBlockingMutex`].
+#[cfg(feature = "std")]
+#[derive(Debug, Snafu, PartialEq)]
+pub enum MockBlockingMutexError {
+    /// The mutex was not locked the expected number of times.
+    #[snafu(display("expected to lock {expected} time(s), actually locked {actual}"))]
+    WrongNumberOfLocks {
+        /// The number of locks that were expected.
+        expected: usize,
+
+        /// The number of times the mutex was locked.
+        actual: usize,
+    },
+
+    /// The value at the start of a lock section didn't match the predicate given to
+    /// [`MockBlockingMutex::expect_observed()`].
+    #[snafu(display("expected the value observed by lock {index} to match, actually it didn't"))]
+    UnmatchedObserved {
+        /// The index of the lock, in call order.
+        index: usize,
+    },
+
+    /// The value at the end of a lock section didn't match the predicate given to
+    /// [`MockBlockingMutex::expect_produced()`].
+    #[snafu(display("expected the value produced by lock {index} to match, actually it didn't"))]
+    UnmatchedProduced {
+        /// The index of the lock, in call order.
+        index: usize,
+    },
+}
+
+/// A predicate that the value in a lock section is expected to match.
+#[cfg(feature = "std")]
+type ValueMatcher<T> = Box<dyn Fn(&T) -> bool + Send + Sync>;
+
+/// A mocked version of [`embassy_sync::blocking_mutex::Mutex`] that can be used in its place for
+/// unit tests.
+///
+/// It locks as a real blocking mutex, using the [`TestRawMutex`], and it counts every lock
+/// section, including the nested ones. The value can be checked at the start of a section, as the
+/// closure observes it, and at the end, as the closure produced it.
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if the mutex was not locked the
+/// number of times given to [`Self::expect_locks()`], or if a value didn't match the predicates
+/// given to [`Self::expect_observed()`] and [`Self::expect_produced()`].
+///
+/// # Examples
+///
+/// ```
+/// use core::cell::RefCell;
+/// use embassy_mock::sync::{BlockingMutex, MockBlockingMutex};
+///
+/// let log = MockBlockingMutex::new(RefCell::new(Vec::new()))
+///     .expect_locks(1)
+///     .expect_observed(0, |log| log.borrow().is_empty());
+///
+/// log.lock(|log| log.borrow_mut().push("boot"));
+///
+/// assert_eq!(log.lock_count(), 1);
+/// ```
+#[cfg(feature = "std")]
+pub struct MockBlockingMutex<T> {
+    /// The real mutex that protects the value.
+    mutex: EmbassyBlockingMutex<TestRawMutex, T>,
+
+    /// The number of times the mutex has been locked.
+    lock_count: AtomicUsize,
+
+    /// The number of times the mutex is expected to be locked, if checked.
+    expected: Option<usize>,
+
+    /// The predicates that the value at the start of each lock section is expected to match, by
+    /// the index of the lock.
+    observed: Vec<(usize, ValueMatcher<T>)>,
+
+    /// The predicates that the value at the end of each lock section is expected to match, by the
+    /// index of the lock.
+    produced: Vec<(usize, ValueMatcher<T>)>,
+
+    /// The first value that didn't match its predicate.
+    mismatch: Mutex<Option<MockBlockingMutexError>>,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<T> fmt::Debug for MockBlockingMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockBlockingMutex")
+            .field("lock_count", &self.lock_count)
+            .field("expected", &self.expected)
+            .field("observed", &self.observed.len())
+            .field("produced", &self.produced.len())
+            .field("mismatch", &self.mismatch)
+            .field("is_done", &self.is_done)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> MockBlockingMutex<T> {
+    /// Create an unlocked [`MockBlockingMutex`] that protects the `value`.
+    pub const fn new(value: T) -> Self {
+        Self {
+            mutex: EmbassyBlockingMutex::new(value),
+            lock_count: AtomicUsize::new(0),
+            expected: None,
+            observed: Vec::new(),
+            produced: Vec::new(),
+            mismatch: Mutex::new(None),
+            is_done: false,
+        }
+    }
+
+    /// Expect the mutex to be locked `expected` times.
+    pub const fn expect_locks(mut self, expected: usize) -> Self {
+        self.expected = Some(expected);
+        self
+    }
+
+    /// Expect the value at the start of the lock section at `index`, in call order, to match the
+    /// `predicate`.
+    pub fn expect_observed(
+        mut self,
+        index: usize,
+        predicate: impl Fn(&T) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.observed.push((index, Box::new(predicate)));
+        self
+    }
+
+    /// Expect the value at the end of the lock section at `index`, in call order, to match the
+    /// `predicate`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::cell::Cell;
+    /// use embassy_mock::sync::{BlockingMutex, MockBlockingMutex, MockBlockingMutexError};
+    ///
+    /// let level = MockBlockingMutex::new(Cell::new(1)).expect_produced(0, |level| level.get() > 1);
+    ///
+    /// level.lock(|level| level.set(0));
+    ///
+    /// let expected = Err(MockBlockingMutexError::UnmatchedProduced { index: 0 });
+    /// assert_eq!(level.done(), expected);
+    /// ```
+    pub fn expect_produced(
+        mut self,
+        index: usize,
+        predicate: impl Fn(&T) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.produced.push((index, Box::new(predicate)));
+        self
+    }
+
+    /// The number of times the mutex has been locked so far.
+    pub fn lock_count(&self) -> usize {
+        self.lock_count.load(Ordering::Relaxed)
+    }
+
+    /// Check that the mutex was locked the number of times given to [`Self::expect_locks()`], and
+    /// that the values matched the predicates of the lock sections that were reached.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{BlockingMutex, MockBlockingMutex, MockBlockingMutexError};
+    ///
+    /// let counter = MockBlockingMutex::new(0).expect_locks(2);
+    /// counter.lock(|_| ());
+    ///
+    /// let expected = Err(MockBlockingMutexError::WrongNumberOfLocks { expected: 2, actual: 1 });
+    /// assert_eq!(counter.done(), expected);
+    ///
+    /// // This doesn't panic when `counter` is dropped as `counter.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockBlockingMutexError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that the mutex was locked the expected number of times with the expected values.
+    fn check(&self) -> Result<(), MockBlockingMutexError> {
+        if let Some(error) = self.mismatch().take() {
+            return Err(error);
+        }
+
+        if let Some(expected) = self.expected {
+            let actual = self.lock_count();
+            ensure!(
+                actual == expected,
+                WrongNumberOfLocksSnafu { expected, actual }
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Check the `value` against the `matchers` of the lock section at `index`, keeping the
+    /// first mismatch.
+    fn check_matchers(
+        &self,
+        matchers: &[(usize, ValueMatcher<T>)],
+        index: usize,
+        value: &T,
+        error: MockBlockingMutexError,
+    ) {
+        let is_unmatched = matchers
+            .iter()
+            .any(|(matcher_index, predicate)| *matcher_index == index && !predicate(value));
+        if is_unmatched {
+            self.mismatch().get_or_insert(error);
+        }
+    }
+
+    /// Lock the first mismatch, which is never poisoned as the mock doesn't panic while holding
+    /// the lock.
+    fn mismatch(&self) -> std::sync::MutexGuard<'_, Option<MockBlockingMutexError>> {
+        self.mismatch.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for MockBlockingMutex<T> {
+    /// If [`Self::done()`] has not been called before being dropped then check that the mutex was
+    /// locked the expected number of times with the expected values.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> BlockingMutex<T> for MockBlockingMutex<T> {
+    /// Count the lock section and run `f` with the value, checking it before and after.
+    fn lock<U>(&self, f: impl FnOnce(&T) -> U) -> U {
+        self.mutex.lock(|value| {
+            let index = self.lock_count.fetch_add(1, Ordering::Relaxed);
+            self.check_matchers(
+                &self.observed,
+                index,
+                value,
+                MockBlockingMutexError::UnmatchedObserved { index },
+            );
+
+            let result = f(value);
+
+            self.check_matchers(
+                &self.produced,
+                index,
+                value,
+                MockBlockingMutexError::UnmatchedProduced { index },
+            );
+            result
+        })
+    }
+
+    /// Get the value without locking the mutex, so it isn't counted.
+    fn get_mut(&mut self) -> &mut T {
+        self.mutex.get_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::{Cell, RefCell};
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    use std::{thread, vec};
+
+    fn swap_pair(pair: &impl BlockingMutex<RefCell<(u8, u8)>>) {
+        pair.lock(|pair| {
+            let mut pair = pair.borrow_mut();
+            *pair = (pair.1, pair.0);
+        });
+    }
+
+    #[test]
+    fn embassy_blocking_mutex() {
+        let mut mutex = EmbassyBlockingMutex::<NoopRawMutex, _>::new(RefCell::new((1, 2)));
+
+        swap_pair(&mutex);
+
+        assert_eq!(*BlockingMutex::get_mut(&mut mutex).get_mut(), (2, 1));
+    }
+
+    #[test]
+    fn checks_observed_and_produced_values() {
+        let pair = MockBlockingMutex::new(RefCell::new((1, 2)))
+            .expect_locks(2)
+            .expect_observed(0, |pair| *pair.borrow() == (1, 2))
+            .expect_produced(0, |pair| *pair.borrow() == (2, 1))
+            .expect_produced(1, |pair| *pair.borrow() == (1, 2));
+
+        swap_pair(&pair);
+        swap_pair(&pair);
+
+        pair.done().unwrap();
+    }
+
+    #[test]
+    fn reports_first_mismatch() {
+        let value = MockBlockingMutex::new(Cell::new(0))
+            .expect_observed(1, |value| value.get() == 5)
+            .expect_produced(2, |value| value.get() == 5);
+
+        for _ in 0..3 {
+            value.lock(|value| value.set(value.get() + 1));
+        }
+
+        let expected = Err(MockBlockingMutexError::UnmatchedObserved { index: 1 });
+        assert_eq!(value.done(), expected);
+    }
+
+    #[test]
+    fn nested_sections_are_counted() {
+        let mut value = MockBlockingMutex::new(Cell::new(0))
+            .expect_locks(2)
+            .expect_observed(1, |value| value.get() == 1);
+
+        value.lock(|outer| {
+            outer.set(1);
+            value.lock(|inner| inner.set(2));
+        });
+
+        assert_eq!(value.get_mut().get(), 2);
+        assert_eq!(value.lock_count(), 2);
+        value.done().unwrap();
+    }
+
+    #[test]
+    fn locks_across_threads() {
+        let log = MockBlockingMutex::new(RefCell::new(vec![])).expect_locks(5);
+
+        thread::scope(|scope| {
+            for thread in 0..4 {
+                let log = &log;
+                scope.spawn(move || log.lock(|log| log.borrow_mut().push(thread)));
+            }
+        });
+
+        let mut log = log.lock(|log| log.take());
+        log.sort_unstable();
+        assert_eq!(log, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected the value produced by lock 0 to match, actually it didn't")]
+    fn unmatched_produced_just_drop() {
+        let value = MockBlockingMutex::new(0).expect_produced(0, |value| *value == 1);
+
+        value.lock(|_| ());
+    }
+}
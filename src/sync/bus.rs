@@ -0,0 +1,651 @@
+//! A [`TestBus`] to run several task functions that talk to each other through the sync mocks,
+//! to test multi-task logic as a unit.
+//!
+//! The task functions are written against the sync traits as usual, and the test wires them
+//! together by giving them the same mocks, such as a [`MockChannel::loopback()`] between a
+//! producer and a consumer, a [`MockSignal`] and a [`MockWatch`]. The [`TestBus`] then polls the
+//! tasks on the test thread, only polling a task again once it has been woken, as an executor
+//! would. As the mocks are owned by the test rather than leaked into `'static` tasks, they are
+//! still checked when dropped at the end of the test.
+//!
+//! A [`TestBus::with_seed()`] polls the woken tasks in an order picked by the seed instead, and
+//! [`TestBus::for_each_seed()`] runs the test with many seeds to explore the interleavings of the
+//! tasks, reporting the seed that failed so that the failure can be reproduced.
+//!
+//! [`MockChannel::loopback()`]: super::MockChannel::loopback()
+//! [`MockSignal`]: super::MockSignal
+//! [`MockWatch`]: super::MockWatch
+//!
+//! # Examples
+//! ```
+//! use embassy_mock::sync::{Receiver, Sender, Signal, WatchSender};
+//!
+//! async fn sample(readings: &impl Sender<u16>, samples: &[u16]) {
+//!     for &sample in samples {
+//!         readings.send(sample).await;
+//!     }
+//! }
+//!
+//! async fn monitor(
+//!     readings: &impl Receiver<u16>,
+//!     level: &impl WatchSender<u16>,
+//!     alarm: &impl Signal<u16>,
+//! ) {
+//!     loop {
+//!         let reading = readings.receive().await;
+//!         level.send(reading);
+//!         if reading > 100 {
+//!             alarm.signal(reading);
+//!         }
+//!     }
+//! }
+//!
+//! async fn shut_down(alarm: &impl Signal<u16>, shut_down_at: &impl WatchSender<u16>) {
+//!     shut_down_at.send(alarm.wait().await);
+//! }
+//!
+//! # test_high_reading_shuts_down();
+//! // The unit tests that run the tasks together on a `TestBus`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_mock::sync::{MockChannel, MockSignal, MockWatch, TestBus};
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_high_reading_shuts_down() {
+//!         let readings = MockChannel::loopback_with_capacity(1);
+//!         let level = MockWatch::new();
+//!         let alarm = MockSignal::expect(1);
+//!         let shut_down_at = MockWatch::new();
+//!
+//!         let mut bus = TestBus::new();
+//!         bus.spawn("sample", sample(&readings, &[20, 150, 30]));
+//!         bus.spawn("monitor", monitor(&readings, &level, &alarm));
+//!         bus.spawn("shut_down", shut_down(&alarm, &shut_down_at));
+//!         bus.run_until_idle();
+//!
+//!         // The monitor runs forever, waiting for the next reading.
+//!         assert_eq!(bus.pending(), ["monitor"]);
+//!         assert_eq!(level.sent(), [20, 150, 30]);
+//!         assert_eq!(shut_down_at.sent(), [150]);
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use super::wake::WakeCount;
+#[cfg(feature = "time")]
+use crate::time::{clock, MockClock};
+use core::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Waker},
+};
+#[cfg(feature = "time")]
+use embassy_time::Duration;
+use std::{
+    boxed::Box,
+    panic::{self, AssertUnwindSafe},
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
+
+/// Runs several task futures on the test thread, polling each of them whenever it is woken.
+///
+/// Unlike the `MockExecutor` of the `runtime` feature the tasks don't need to be `'static`, so
+/// they can borrow the mocks that wire them together from the test. The tasks are only polled
+/// during a call to [`Self::run_until_idle()`], or `Self::run_for()` with the `time` feature.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::sync::{MockSignal, Signal, TestBus};
+///
+/// let request = MockSignal::expect(1);
+/// let response = MockSignal::expect(1);
+///
+/// let mut bus = TestBus::new();
+/// bus.spawn("server", async {
+///     let value: u8 = request.wait().await;
+///     response.signal(value * 2);
+/// });
+/// bus.spawn("client", async {
+///     request.signal(4);
+///     assert_eq!(response.wait().await, 8);
+/// });
+/// bus.run_until_idle();
+///
+/// bus.assert_all_finished();
+/// ```
+pub struct TestBus<'a> {
+    /// The spawned tasks, in spawn order.
+    tasks: Vec<BusTask<'a>>,
+
+    /// The seed that picks the order the woken tasks are polled in, if it isn't spawn order.
+    seed: Option<u64>,
+
+    /// The state of the random numbers generated from the `seed`.
+    random: u64,
+}
+
+/// A task spawned on a [`TestBus`].
+struct BusTask<'a> {
+    /// The name the task was spawned with.
+    name: &'static str,
+
+    /// The future of the task, until it completes.
+    future: Option<Pin<Box<dyn Future<Output = ()> + 'a>>>,
+
+    /// Records whether the task was woken since it was last polled.
+    wakes: Arc<WakeCount>,
+
+    /// The waker given to each poll of the task.
+    waker: Waker,
+}
+
+impl fmt::Debug for TestBus<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestBus")
+            .field("pending", &self.pending())
+            .field("seed", &self.seed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> TestBus<'a> {
+    /// Create a [`TestBus`] with no tasks.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            tasks: Vec::new(),
+            seed: None,
+            random: 0,
+        }
+    }
+
+    /// Create a [`TestBus`] with no tasks, that polls one woken task at a time in an order picked
+    /// by the `seed`, rather than polling all of the woken tasks in spawn order.
+    ///
+    /// The same seed always gives the same order, so a failing interleaving can be reproduced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockMutex, Mutex, TestBus};
+    ///
+    /// let log = MockMutex::new(Vec::new());
+    ///
+    /// let mut bus = TestBus::with_seed(7);
+    /// for name in ["a", "b", "c"] {
+    ///     let log = &log;
+    ///     bus.spawn(name, async move { log.lock().await.push(name) });
+    /// }
+    /// bus.run_until_idle();
+    ///
+    /// bus.assert_all_finished();
+    /// assert_eq!(log.try_lock().unwrap().len(), 3);
+    /// ```
+    pub const fn with_seed(seed: u64) -> Self {
+        Self {
+            tasks: Vec::new(),
+            seed: Some(seed),
+            random: seed,
+        }
+    }
+
+    /// Run the `test` once with each of the `seeds`, for it to create a
+    /// [`TestBus::with_seed()`] to explore the interleavings of its tasks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `test` panics, adding the seed it failed with to the message.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use embassy_futures::yield_now;
+    /// use embassy_mock::sync::TestBus;
+    /// use std::cell::Cell;
+    ///
+    /// // Panics: the test failed with seed N, rerun it with `TestBus::with_seed(N)`: ...
+    /// TestBus::for_each_seed(0..100, |seed| {
+    ///     let count = Cell::new(0);
+    ///
+    ///     let mut bus = TestBus::with_seed(seed);
+    ///     for name in ["first", "second"] {
+    ///         bus.spawn(name, async {
+    ///             // A lost update when the other task increments between the read and the write.
+    ///             let read = count.get();
+    ///             yield_now().await;
+    ///             count.set(read + 1);
+    ///         });
+    ///     }
+    ///     bus.run_until_idle();
+    ///
+    ///     assert_eq!(count.get(), 2);
+    /// });
+    /// ```
+    #[track_caller]
+    pub fn for_each_seed(seeds: impl IntoIterator<Item = u64>, mut test: impl FnMut(u64)) {
+        for seed in seeds {
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| test(seed))) {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| String::from(*message))
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                panic!(
+                    "the test failed with seed {seed}, rerun it with `TestBus::with_seed({seed})`: \
+                     {message}"
+                );
+            }
+        }
+    }
+
+    /// The seed that picks the order the woken tasks are polled in, or [`None`] if they are
+    /// polled in spawn order.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Spawn the `task` with the `name` used to refer to it in the checks, to be polled by the
+    /// next run.
+    pub fn spawn(&mut self, name: &'static str, task: impl Future<Output = ()> + 'a) {
+        let wakes = Arc::new(WakeCount::default());
+        let waker = Waker::from(Arc::clone(&wakes));
+        // Poll the task in the next run.
+        waker.wake_by_ref();
+
+        self.tasks.push(BusTask {
+            name,
+            future: Some(Box::pin(task)),
+            wakes,
+            waker,
+        });
+    }
+
+    /// Poll the tasks that have been spawned or woken until none of them are ready to make
+    /// progress.
+    pub fn run_until_idle(&mut self) {
+        if self.seed.is_some() {
+            self.run_seeded_until_idle();
+            return;
+        }
+
+        let mut is_idle = false;
+        while !is_idle {
+            is_idle = true;
+            for task in &mut self.tasks {
+                let Some(future) = &mut task.future else {
+                    continue;
+                };
+                if task.wakes.take() {
+                    is_idle = false;
+                    if future
+                        .as_mut()
+                        .poll(&mut Context::from_waker(&task.waker))
+                        .is_ready()
+                    {
+                        task.future = None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Poll one of the woken tasks at a time, picked by the seed, until none of them are woken.
+    fn run_seeded_until_idle(&mut self) {
+        loop {
+            let woken: Vec<_> = self
+                .tasks
+                .iter()
+                .enumerate()
+                .filter(|(_, task)| task.future.is_some() && task.wakes.is_woken())
+                .map(|(index, _)| index)
+                .collect();
+            if woken.is_empty() {
+                break;
+            }
+
+            let pick = (self.next_random() % woken.len() as u64) as usize;
+            let task = &mut self.tasks[woken[pick]];
+            task.wakes.take();
+            if let Some(future) = &mut task.future {
+                if future
+                    .as_mut()
+                    .poll(&mut Context::from_waker(&task.waker))
+                    .is_ready()
+                {
+                    task.future = None;
+                }
+            }
+        }
+    }
+
+    /// The next random number from the seed, using SplitMix64.
+    fn next_random(&mut self) -> u64 {
+        self.random = self.random.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.random;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Run the tasks while moving the `clock` forward by `duration`, jumping it to each deadline
+    /// that the tasks are waiting for in turn.
+    ///
+    /// This finishes instantly however long the `duration` is, leaving the clock `duration`
+    /// after where it started.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::{
+    ///     sync::{MockWatch, TestBus, WatchSender},
+    ///     time::{MockClock, MockTimer, Timer},
+    /// };
+    /// use embassy_time::Duration;
+    ///
+    /// let uptime = MockWatch::new();
+    /// let clock = MockClock::new();
+    ///
+    /// let mut bus = TestBus::new();
+    /// bus.spawn("count_uptime", async {
+    ///     for seconds in 1.. {
+    ///         MockTimer::after(Duration::from_secs(1)).await;
+    ///         uptime.send(seconds);
+    ///     }
+    /// });
+    /// bus.run_for(&clock, Duration::from_millis(3500));
+    ///
+    /// assert_eq!(uptime.sent(), [1, 2, 3]);
+    /// ```
+    #[cfg(feature = "time")]
+    pub fn run_for(&mut self, clock: &MockClock, duration: Duration) {
+        let end = clock.now() + duration;
+
+        loop {
+            self.run_until_idle();
+            match clock::next_deadline() {
+                Some(deadline) if deadline <= end => clock.set_now(deadline),
+                _ => break,
+            }
+        }
+
+        clock.set_now(end);
+        self.run_until_idle();
+    }
+
+    /// Has the task spawned with the `name` completed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no task was spawned with the `name`.
+    #[track_caller]
+    pub fn is_finished(&self, name: &str) -> bool {
+        let Some(task) = self.tasks.iter().find(|task| task.name == name) else {
+            panic!("expected a task named {name:?}, actually none was spawned");
+        };
+        task.future.is_none()
+    }
+
+    /// The names of the tasks that haven't completed, in spawn order.
+    pub fn pending(&self) -> Vec<&'static str> {
+        self.tasks
+            .iter()
+            .filter(|task| task.future.is_some())
+            .map(|task| task.name)
+            .collect()
+    }
+
+    /// Assert that every task has completed, to check that none of them is stuck waiting for
+    /// another.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the tasks haven't completed.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use embassy_mock::sync::{MockChannel, Receiver, TestBus};
+    ///
+    /// let requests = MockChannel::<u8>::loopback();
+    ///
+    /// let mut bus = TestBus::new();
+    /// // The request is never sent.
+    /// bus.spawn("server", async {
+    ///     requests.receive().await;
+    /// });
+    /// bus.run_until_idle();
+    ///
+    /// // Panics: expected every task to finish, actually ["server"] are still pending
+    /// bus.assert_all_finished();
+    /// ```
+    #[track_caller]
+    pub fn assert_all_finished(&self) {
+        let pending = self.pending();
+        let seed = self
+            .seed
+            .map(|seed| std::format!(" with seed {seed}"))
+            .unwrap_or_default();
+        assert!(
+            pending.is_empty(),
+            "expected every task to finish, actually {pending:?} are still pending{seed}"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::{
+        MockChannel, MockMutex, MockSignal, MockWatch, Mutex, Receiver, Sender, Signal,
+        WatchReceiver, WatchSender,
+    };
+    use core::cell::Cell;
+    use embassy_futures::yield_now;
+
+    async fn forward(input: &impl Receiver<u8>, output: &impl Sender<u8>, count: usize) {
+        for _ in 0..count {
+            output.send(input.receive().await).await;
+        }
+    }
+
+    #[test]
+    fn tasks_pass_messages_through_a_pipeline() {
+        let first = MockChannel::loopback_with_capacity(1);
+        let second = MockChannel::loopback_with_capacity(1);
+        let received = Cell::new(Vec::new());
+
+        let mut bus = TestBus::new();
+        bus.spawn("consume", async {
+            for _ in 0..3 {
+                let message = second.receive().await;
+                let mut messages = received.take();
+                messages.push(message);
+                received.set(messages);
+            }
+        });
+        bus.spawn("forward", forward(&first, &second, 3));
+        bus.spawn("produce", async {
+            for message in 1..=3 {
+                first.send(message).await;
+            }
+        });
+        bus.run_until_idle();
+
+        bus.assert_all_finished();
+        assert_eq!(received.take(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn tasks_only_run_when_polled() {
+        let signal = MockSignal::expect(1);
+
+        let mut bus = TestBus::new();
+        bus.spawn("signal", async { signal.signal(1) });
+
+        assert_eq!(signal.times_signalled(), 0);
+        bus.run_until_idle();
+        assert_eq!(signal.times_signalled(), 1);
+    }
+
+    #[test]
+    fn waiting_task_is_pending_until_woken_by_the_test() {
+        let watch = MockWatch::new();
+        let mut receiver = watch.receiver();
+        let doubled = MockWatch::new();
+
+        let mut bus = TestBus::new();
+        bus.spawn("double", async {
+            let value: u8 = receiver.changed().await;
+            doubled.send(value * 2);
+        });
+        bus.run_until_idle();
+        assert_eq!(bus.pending(), ["double"]);
+
+        watch.send(3);
+        bus.run_until_idle();
+
+        assert!(bus.is_finished("double"));
+        assert_eq!(doubled.sent(), [6]);
+    }
+
+    #[test]
+    fn yielding_tasks_take_turns() {
+        let mutex = MockMutex::new(Vec::new());
+
+        let mut bus = TestBus::new();
+        for name in ["a", "b"] {
+            let mutex = &mutex;
+            bus.spawn(name, async move {
+                for _ in 0..2 {
+                    mutex.lock().await.push(name);
+                    yield_now().await;
+                }
+            });
+        }
+        bus.run_until_idle();
+
+        bus.assert_all_finished();
+        assert_eq!(*mutex.try_lock().unwrap(), ["a", "b", "a", "b"]);
+    }
+
+    /// The order that three yielding tasks take turns in with the `seed`.
+    fn seeded_order(seed: u64) -> Vec<&'static str> {
+        let mutex = MockMutex::new(Vec::new());
+
+        let mut bus = TestBus::with_seed(seed);
+        for name in ["a", "b", "c"] {
+            let mutex = &mutex;
+            bus.spawn(name, async move {
+                for _ in 0..3 {
+                    mutex.lock().await.push(name);
+                    yield_now().await;
+                }
+            });
+        }
+        bus.run_until_idle();
+
+        bus.assert_all_finished();
+        let order = mutex.try_lock().unwrap().clone();
+        order
+    }
+
+    #[test]
+    fn same_seed_gives_same_order() {
+        assert_eq!(seeded_order(42), seeded_order(42));
+    }
+
+    #[test]
+    fn seeds_give_different_orders() {
+        let orders: Vec<_> = (0..10).map(seeded_order).collect();
+
+        assert!(orders.iter().any(|order| *order != orders[0]));
+        assert!(orders.iter().all(|order| order.len() == 9));
+    }
+
+    #[test]
+    fn seeded_tasks_pass_messages_through_a_pipeline() {
+        TestBus::for_each_seed(0..20, |seed| {
+            let first = MockChannel::loopback_with_capacity(1);
+            let second = MockChannel::loopback_with_capacity(1);
+            let received = MockWatch::new();
+
+            let mut bus = TestBus::with_seed(seed);
+            bus.spawn("consume", async {
+                for _ in 0..3 {
+                    received.send(second.receive().await);
+                }
+            });
+            bus.spawn("forward", forward(&first, &second, 3));
+            bus.spawn("produce", async {
+                for message in 1..=3 {
+                    first.send(message).await;
+                }
+            });
+            bus.run_until_idle();
+
+            assert_eq!(bus.seed(), Some(seed));
+            bus.assert_all_finished();
+            assert_eq!(received.sent(), [1, 2, 3]);
+        });
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "the test failed with seed 3, rerun it with `TestBus::with_seed(3)`: \
+                    unlucky seed"
+    )]
+    fn failing_seed_is_reported() {
+        TestBus::for_each_seed(0..10, |seed| {
+            assert!(seed != 3, "unlucky seed");
+        });
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected every task to finish, actually [\"wait\"] are still pending \
+                    with seed 5"
+    )]
+    fn stuck_seeded_task_is_reported_with_seed() {
+        let signal = MockSignal::<u8>::expect(0);
+
+        let mut bus = TestBus::with_seed(5);
+        bus.spawn("wait", async {
+            signal.wait().await;
+        });
+        bus.run_until_idle();
+
+        bus.assert_all_finished();
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a task named \"missing\", actually none was spawned")]
+    fn unknown_task_name_is_reported() {
+        let bus = TestBus::new();
+
+        bus.is_finished("missing");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected every task to finish, actually [\"wait\"] are still pending"
+    )]
+    fn stuck_task_is_reported() {
+        let signal = MockSignal::<u8>::expect(0);
+
+        let mut bus = TestBus::new();
+        bus.spawn("wait", async {
+            signal.wait().await;
+        });
+        bus.run_until_idle();
+
+        bus.assert_all_finished();
+    }
+}
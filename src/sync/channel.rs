@@ -0,0 +1,2782 @@
+//! Traits and mocked types to allow unit testing functions that use an
+//! [`embassy_sync::channel::Channel`], or one of its [`embassy_sync::channel::Sender`] and
+//! [`embassy_sync::channel::Receiver`] halves.
+//!
+//! # Examples
+//! ```
+//! use embassy_mock::sync::{Receiver, Sender};
+//!
+//! // Generic over the `Receiver` and `Sender` traits
+//! async fn forward_doubled(input: impl Receiver<u32>, output: impl Sender<u32>) {
+//!     let value = input.receive().await;
+//!     output.send(value * 2).await;
+//! }
+//!
+//! # test_forward_doubled();
+//! // The unit tests that use the `MockReceiver` and `MockSender`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_futures::block_on;
+//!     use embassy_mock::sync::{MockReceiver, MockSender};
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_forward_doubled() {
+//!         let input = MockReceiver::with_received([21]);
+//!         let output = MockSender::new();
+//!
+//!         block_on(forward_doubled(&input, &output));
+//!
+//!         assert_eq!(output.sent(), [42]);
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+#[cfg(all(feature = "std", feature = "time"))]
+use crate::time::{clock, MockInstant};
+use core::future::Future;
+use embassy_sync::{
+    blocking_mutex::raw::RawMutex,
+    channel::{
+        Channel as EmbassyChannel, DynamicReceiver, DynamicSender, Receiver as EmbassyReceiver,
+        Sender as EmbassySender, TryReceiveError, TrySendError,
+    },
+};
+#[cfg(all(feature = "std", feature = "stream"))]
+use futures_core::{stream::FusedStream, Stream};
+#[cfg(all(feature = "std", feature = "sink"))]
+use futures_sink::Sink;
+#[cfg(feature = "std")]
+use {
+    crate::waiting,
+    core::{
+        fmt,
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    },
+    snafu::prelude::*,
+    std::{
+        boxed::Box,
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+        vec::Vec,
+    },
+};
+
+/// The trait to replace the [`embassy_sync::channel::Sender`] and
+/// [`embassy_sync::channel::DynamicSender`] in code to allow the [`MockSender`] to be used in its
+/// place for tests.
+///
+/// # Examples
+///
+/// A struct that would hold a [`DynamicSender`], to not depend on the capacity of the channel,
+/// holds any [`Sender`] instead.
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::sync::{MockSender, Sender};
+/// use embassy_sync::{blocking_mutex::raw::NoopRawMutex, channel::Channel};
+///
+/// struct Logger<S> {
+///     output: S,
+/// }
+///
+/// impl<S: Sender<&'static str>> Logger<S> {
+///     async fn log(&self, line: &'static str) {
+///         self.output.send(line).await;
+///     }
+/// }
+///
+/// // The production code passes a `DynamicSender`.
+/// let channel = Channel::<NoopRawMutex, &str, 4>::new();
+/// let logger = Logger { output: channel.dyn_sender() };
+/// block_on(logger.log("started"));
+/// assert_eq!(channel.try_receive(), Ok("started"));
+///
+/// // The tests pass a `MockSender`.
+/// let sender = MockSender::new();
+/// let logger = Logger { output: &sender };
+/// block_on(logger.log("started"));
+/// assert_eq!(sender.sent(), ["started"]);
+/// ```
+pub trait Sender<T> {
+    /// Wrapper for [`embassy_sync::channel::Sender::send()`].
+    fn send(&self, message: T) -> impl Future<Output = ()>;
+
+    /// Wrapper for [`embassy_sync::channel::Sender::try_send()`].
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>>;
+}
+
+/// The trait to replace the [`embassy_sync::channel::Receiver`] and
+/// [`embassy_sync::channel::DynamicReceiver`] in code to allow the [`MockReceiver`] to be used in
+/// its place for tests.
+pub trait Receiver<T> {
+    /// Wrapper for [`embassy_sync::channel::Receiver::receive()`].
+    fn receive(&self) -> impl Future<Output = T>;
+
+    /// Wrapper for [`embassy_sync::channel::Receiver::try_receive()`].
+    fn try_receive(&self) -> Result<T, TryReceiveError>;
+}
+
+/// The trait to replace the [`embassy_sync::channel::Channel`] in code to allow the
+/// [`MockChannel`] to be used in its place for tests.
+///
+/// This is implemented for everything that is both a [`Sender`] and a [`Receiver`].
+pub trait Channel<T>: Sender<T> + Receiver<T> {}
+
+impl<T, C: Sender<T> + Receiver<T> + ?Sized> Channel<T> for C {}
+
+/// The trait to inspect how many messages are waiting in an [`embassy_sync::channel::Channel`],
+/// or one of its [`embassy_sync::channel::Sender`] and [`embassy_sync::channel::Receiver`]
+/// halves, to allow the [`MockSender`], [`MockReceiver`] and [`MockChannel`] to be used in their
+/// place for tests.
+///
+/// The [`embassy_sync::channel::DynamicSender`] and [`embassy_sync::channel::DynamicReceiver`]
+/// can't be inspected, so this is separate from the [`Sender`] and [`Receiver`] traits.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::sync::{ChannelLen, MockSender, Sender};
+///
+/// // Only queue a low priority message while the queue is less than half full.
+/// fn log(output: &(impl Sender<&'static str> + ChannelLen), message: &'static str) {
+///     if output.len() < 4 {
+///         let _ = output.try_send(message);
+///     }
+/// }
+///
+/// let output = MockSender::new();
+/// log(&output, "booted");
+/// output.set_len(Some(4));
+/// log(&output, "idle");
+///
+/// assert_eq!(output.sent(), ["booted"]);
+/// ```
+pub trait ChannelLen {
+    /// Wrapper for [`embassy_sync::channel::Channel::len()`].
+    fn len(&self) -> usize;
+
+    /// Wrapper for [`embassy_sync::channel::Channel::is_empty()`].
+    fn is_empty(&self) -> bool;
+
+    /// Wrapper for [`embassy_sync::channel::Channel::is_full()`].
+    fn is_full(&self) -> bool;
+}
+
+impl<M: RawMutex, T, const N: usize> Sender<T> for EmbassyChannel<M, T, N> {
+    /// Send a value, waiting until there is capacity.
+    ///
+    /// Sending completes when the value has been pushed to the channel's queue. This doesn't mean
+    /// the value has been received yet.
+    fn send(&self, message: T) -> impl Future<Output = ()> {
+        self.send(message)
+    }
+
+    /// Attempt to immediately send a message.
+    ///
+    /// This method differs from [`Sender::send()`] by returning immediately if the channel's
+    /// buffer is full, instead of waiting.
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        self.try_send(message)
+    }
+}
+
+impl<M: RawMutex, T, const N: usize> Receiver<T> for EmbassyChannel<M, T, N> {
+    /// Receive the next value.
+    ///
+    /// If there are no messages in the channel's buffer, this method will wait until there is
+    /// at least one message.
+    fn receive(&self) -> impl Future<Output = T> {
+        self.receive()
+    }
+
+    /// Attempt to immediately receive a message.
+    ///
+    /// This method will either receive a message from the channel immediately or return an
+    /// error if the channel is empty.
+    fn try_receive(&self) -> Result<T, TryReceiveError> {
+        self.try_receive()
+    }
+}
+
+impl<M: RawMutex, T, const N: usize> ChannelLen for EmbassyChannel<M, T, N> {
+    /// Returns the number of elements currently in the channel.
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    /// Returns whether the channel is empty.
+    fn is_empty(&self) -> bool {
+        self.is_empty()
+    }
+
+    /// Returns whether the channel is full.
+    fn is_full(&self) -> bool {
+        self.is_full()
+    }
+}
+
+impl<M: RawMutex, T, const N: usize> Sender<T> for EmbassySender<'_, M, T, N> {
+    /// Sends a value, see [`embassy_sync::channel::Channel::send()`].
+    fn send(&self, message: T) -> impl Future<Output = ()> {
+        self.send(message)
+    }
+
+    /// Attempt to immediately send a message, see [`embassy_sync::channel::Channel::try_send()`].
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        self.try_send(message)
+    }
+}
+
+impl<M: RawMutex, T, const N: usize> Receiver<T> for EmbassyReceiver<'_, M, T, N> {
+    /// Receive the next value, see [`embassy_sync::channel::Channel::receive()`].
+    fn receive(&self) -> impl Future<Output = T> {
+        self.receive()
+    }
+
+    /// Attempt to immediately receive the next value, see
+    /// [`embassy_sync::channel::Channel::try_receive()`].
+    fn try_receive(&self) -> Result<T, TryReceiveError> {
+        self.try_receive()
+    }
+}
+
+impl<M: RawMutex, T, const N: usize> ChannelLen for EmbassySender<'_, M, T, N> {
+    /// Returns the number of elements currently in the channel, see
+    /// [`embassy_sync::channel::Channel::len()`].
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    /// Returns whether the channel is empty, see
+    /// [`embassy_sync::channel::Channel::is_empty()`].
+    fn is_empty(&self) -> bool {
+        self.is_empty()
+    }
+
+    /// Returns whether the channel is full, see [`embassy_sync::channel::Channel::is_full()`].
+    fn is_full(&self) -> bool {
+        self.is_full()
+    }
+}
+
+impl<M: RawMutex, T, const N: usize> ChannelLen for EmbassyReceiver<'_, M, T, N> {
+    /// Returns the number of elements currently in the channel, see
+    /// [`embassy_sync::channel::Channel::len()`].
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    /// Returns whether the channel is empty, see
+    /// [`embassy_sync::channel::Channel::is_empty()`].
+    fn is_empty(&self) -> bool {
+        self.is_empty()
+    }
+
+    /// Returns whether the channel is full, see [`embassy_sync::channel::Channel::is_full()`].
+    fn is_full(&self) -> bool {
+        self.is_full()
+    }
+}
+
+impl<T> Sender<T> for DynamicSender<'_, T> {
+    /// Sends a value, see [`embassy_sync::channel::Channel::send()`].
+    fn send(&self, message: T) -> impl Future<Output = ()> {
+        self.send(message)
+    }
+
+    /// Attempt to immediately send a message, see [`embassy_sync::channel::Channel::try_send()`].
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        self.try_send(message)
+    }
+}
+
+impl<T> Receiver<T> for DynamicReceiver<'_, T> {
+    /// Receive the next value, see [`embassy_sync::channel::Channel::receive()`].
+    fn receive(&self) -> impl Future<Output = T> {
+        self.receive()
+    }
+
+    /// Attempt to immediately receive the next value, see
+    /// [`embassy_sync::channel::Channel::try_receive()`].
+    fn try_receive(&self) -> Result<T, TryReceiveError> {
+        self.try_receive()
+    }
+}
+
+impl<T, S: Sender<T> + ?Sized> Sender<T> for &S {
+    /// Call [`Sender::send()`] of the referenced sender.
+    fn send(&self, message: T) -> impl Future<Output = ()> {
+        (**self).send(message)
+    }
+
+    /// Call [`Sender::try_send()`] of the referenced sender.
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        (**self).try_send(message)
+    }
+}
+
+impl<T, R: Receiver<T> + ?Sized> Receiver<T> for &R {
+    /// Call [`Receiver::receive()`] of the referenced receiver.
+    fn receive(&self) -> impl Future<Output = T> {
+        (**self).receive()
+    }
+
+    /// Call [`Receiver::try_receive()`] of the referenced receiver.
+    fn try_receive(&self) -> Result<T, TryReceiveError> {
+        (**self).try_receive()
+    }
+}
+
+/// The errors that are reported by [`MockSender`], [`MockReceiver`] and [`MockChannel`].
+#[cfg(feature = "std")]
+#[derive(Debug, Snafu, PartialEq)]
+pub enum MockChannelError {
+    /// A mock that was created to never be used was used.
+    #[snafu(display("expected to never be used, actually called {method}"))]
+    UnexpectedUse {
+        /// The first method that was called.
+        method: &'static str,
+    },
+
+    /// A message was sent while the expected capacity of unreceived messages was queued, which
+    /// the real channel would have rejected or waited for.
+    #[snafu(display(
+        "expected at most {capacity} unreceived message(s), actually sent message {index} when full"
+    ))]
+    CapacityExceeded {
+        /// The capacity given to [`MockSender::expect_capacity()`].
+        capacity: usize,
+
+        /// The index of the first message that was sent when full, in call order.
+        index: usize,
+    },
+
+    /// Some of the messages given to [`MockReceiver::with_received()`] were never received.
+    #[snafu(display("expected {remaining} more message(s) to be received"))]
+    UnreceivedMessages {
+        /// The number of messages that were never received.
+        remaining: usize,
+    },
+}
+
+/// An event recorded in a [`ChannelTranscript`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelEvent<T> {
+    /// The message was sent with a [`MockSender`].
+    Sent(T),
+
+    /// The message was received with a [`MockReceiver`].
+    Received(T),
+}
+
+/// An ordered record of the messages sent and received with the mocks that share it.
+///
+/// When the test owns the [`MockSender`] given to one function and the [`MockReceiver`] given to
+/// another, giving both the same transcript with `with_transcript()` records the messages going
+/// in and out in the order it happened. Cloning a [`ChannelTranscript`] shares the record.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::sync::{
+///     ChannelEvent, ChannelTranscript, MockReceiver, MockSender, Receiver, Sender,
+/// };
+///
+/// async fn respond(requests: &impl Receiver<u8>, responses: &impl Sender<u8>) {
+///     let request = requests.receive().await;
+///     responses.send(request + 1).await;
+/// }
+///
+/// let transcript = ChannelTranscript::new();
+/// let requests = MockReceiver::with_received([1, 5]).with_transcript(&transcript);
+/// let responses = MockSender::new().with_transcript(&transcript);
+///
+/// block_on(respond(&requests, &responses));
+/// block_on(respond(&requests, &responses));
+///
+/// use ChannelEvent::{Received, Sent};
+/// assert_eq!(transcript.events(), [Received(1), Sent(2), Received(5), Sent(6)]);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct ChannelTranscript<T> {
+    /// The events in the order they happened, shared by the clones.
+    events: Arc<Mutex<Vec<ChannelEvent<T>>>>,
+}
+
+#[cfg(feature = "std")]
+impl<T> ChannelTranscript<T> {
+    /// Create an empty [`ChannelTranscript`].
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            events: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// The events recorded so far, in the order they happened.
+    pub fn events(&self) -> Vec<ChannelEvent<T>>
+    where
+        T: Clone,
+    {
+        self.lock().clone()
+    }
+
+    /// Record the `event` after the others.
+    fn push(&self, event: ChannelEvent<T>) {
+        self.lock().push(event);
+    }
+
+    /// Lock the events, which are never poisoned as the mocks don't panic while holding the lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<ChannelEvent<T>>> {
+        self.events.lock().unwrap()
+    }
+}
+
+// Implemented manually as the clones share the events, so `T` doesn't need to be `Clone`.
+#[cfg(feature = "std")]
+impl<T> Clone for ChannelTranscript<T> {
+    fn clone(&self) -> Self {
+        Self {
+            events: Arc::clone(&self.events),
+        }
+    }
+}
+
+/// Records the messages of a mock in a [`ChannelTranscript`].
+///
+/// The message is cloned into the transcript, which the mocks can't require of every `T`, so the
+/// clone is captured when the transcript is given.
+#[cfg(feature = "std")]
+struct TranscriptRecorder<T>(Box<dyn Fn(&T) + Send + Sync>);
+
+#[cfg(feature = "std")]
+impl<T> TranscriptRecorder<T> {
+    /// Record each message in the `transcript` as the event made by `to_event`.
+    fn new(transcript: &ChannelTranscript<T>, to_event: fn(T) -> ChannelEvent<T>) -> Self
+    where
+        T: Clone + Send + 'static,
+    {
+        let transcript = transcript.clone();
+        Self(Box::new(move |message: &T| {
+            transcript.push(to_event(message.clone()));
+        }))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> fmt::Debug for TranscriptRecorder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TranscriptRecorder")
+    }
+}
+
+/// A mocked version of [`embassy_sync::channel::Sender`] that can be used in its place for unit
+/// tests.
+///
+/// Every message passed to [`Sender::send()`] and [`Sender::try_send()`] is recorded. Sending
+/// completes immediately unless the test has made the channel full with [`Self::set_full()`], to
+/// check how the code handles backpressure. It then waits until the test frees capacity with
+/// [`Self::free_capacity()`] or sets the channel to not be full.
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if it was used after being
+/// created by [`Self::expect_never()`], or if it held more messages than given to
+/// [`Self::expect_capacity()`].
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::sync::{MockSender, Sender};
+///
+/// let sender = MockSender::new();
+///
+/// block_on(sender.send("ping"));
+/// sender.try_send("pong").unwrap();
+///
+/// assert_eq!(sender.sent(), ["ping", "pong"]);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockSender<T> {
+    /// The sent messages and the waiting send, shared by every call.
+    state: Mutex<SenderState<T>>,
+
+    /// Records the sent messages in a transcript, if one was given.
+    transcript: Option<TranscriptRecorder<T>>,
+
+    /// The queue the sent messages are also passed to, if created by [`MockChannel::loopback()`].
+    loopback: Option<Arc<Mutex<Loopback<T>>>>,
+
+    /// Is this mock expected to never be used, see [`Self::expect_never()`].
+    never_used: bool,
+
+    /// The capacity of the production channel, if checked, see [`Self::expect_capacity()`].
+    capacity: Option<usize>,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+/// The state of a [`MockSender`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct SenderState<T> {
+    /// The messages that were sent, in call order.
+    sent: Vec<T>,
+
+    /// Is the channel full, so that nothing can be sent beyond the `free` capacity.
+    is_full: bool,
+
+    /// The number of messages that can still be sent while the channel is full, see
+    /// [`MockSender::free_capacity()`].
+    free: usize,
+
+    /// The wakers of the sends that are waiting for the channel to not be full.
+    wakers: Vec<Waker>,
+
+    /// The first method of [`Sender`] that was called, if any.
+    first_use: Option<&'static str>,
+
+    /// The number of sent messages the test has marked as received, see
+    /// [`MockSender::mark_received()`].
+    received: usize,
+
+    /// The index of the first message sent beyond the expected capacity, if any.
+    overflow: Option<usize>,
+
+    /// The length reported instead of the number of waiting messages, see
+    /// [`MockSender::set_len()`].
+    len: Option<usize>,
+}
+
+#[cfg(feature = "std")]
+impl<T> MockSender<T> {
+    /// Create a [`MockSender`] that hasn't sent any messages.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(SenderState {
+                sent: Vec::new(),
+                is_full: false,
+                free: 0,
+                wakers: Vec::new(),
+                first_use: None,
+                received: 0,
+                overflow: None,
+                len: None,
+            }),
+            transcript: None,
+            loopback: None,
+            never_used: false,
+            capacity: None,
+            is_done: false,
+        }
+    }
+
+    /// Create a [`MockSender`] that is expected to never be used, to check that the code under
+    /// test doesn't send anything, not even with a call that fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockChannelError, MockSender, Sender};
+    ///
+    /// let sender = MockSender::expect_never();
+    /// let _ = sender.try_send(1);
+    ///
+    /// let expected = Err(MockChannelError::UnexpectedUse { method: "Sender::try_send()" });
+    /// assert_eq!(sender.done(), expected);
+    /// ```
+    pub const fn expect_never() -> Self {
+        let mut sender = Self::new();
+        sender.never_used = true;
+        sender
+    }
+
+    /// Expect no more than `capacity` sent messages to be waiting to be received at any time, as
+    /// that is all the production channel can hold.
+    ///
+    /// The mock still accepts every message, so the test carries on, but sending one while
+    /// `capacity` are waiting is reported by [`Self::done()`]. The consumer drains the messages
+    /// by receiving them from a [`MockChannel::loopback()`], otherwise the test marks them as
+    /// received with [`Self::mark_received()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockChannelError, MockSender, Sender};
+    ///
+    /// // Queues a report for every reading, without waiting for them to be handled.
+    /// fn report_all(reports: &impl Sender<u16>, readings: &[u16]) {
+    ///     for &reading in readings {
+    ///         let _ = reports.try_send(reading);
+    ///     }
+    /// }
+    ///
+    /// // The production channel holds 2 reports.
+    /// let reports = MockSender::new().expect_capacity(2);
+    /// report_all(&reports, &[1, 2]);
+    /// reports.mark_received(2);
+    /// report_all(&reports, &[3, 4, 5]);
+    ///
+    /// let expected = Err(MockChannelError::CapacityExceeded { capacity: 2, index: 4 });
+    /// assert_eq!(reports.done(), expected);
+    /// ```
+    pub const fn expect_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Mark the oldest `count` sent messages that are still waiting as received, as the consumer
+    /// would have, making room for more within the capacity given to [`Self::expect_capacity()`].
+    pub fn mark_received(&self, count: usize) {
+        let mut state = self.lock();
+        state.received = state.sent.len().min(state.received + count);
+    }
+
+    /// Also record every message sent in the `transcript`, as a [`ChannelEvent::Sent`].
+    pub fn with_transcript(mut self, transcript: &ChannelTranscript<T>) -> Self
+    where
+        T: Clone + Send + 'static,
+    {
+        self.transcript = Some(TranscriptRecorder::new(transcript, ChannelEvent::Sent));
+        self
+    }
+
+    /// Set whether the channel is full.
+    ///
+    /// While it is full, [`Sender::try_send()`] returns [`TrySendError::Full`] and
+    /// [`Sender::send()`] waits, neither recording the message. Setting it to not be full wakes
+    /// the sends that are waiting. Either way it forgets any capacity given to
+    /// [`Self::free_capacity()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockSender, Sender};
+    /// use embassy_sync::channel::TrySendError;
+    ///
+    /// // Drop the reading when the queue is full.
+    /// fn report(sender: &impl Sender<u16>, reading: u16) {
+    ///     let _ = sender.try_send(reading);
+    /// }
+    ///
+    /// let sender = MockSender::new();
+    /// report(&sender, 1);
+    /// sender.set_full(true);
+    /// report(&sender, 2);
+    /// assert_eq!(sender.try_send(3), Err(TrySendError::Full(3)));
+    ///
+    /// assert_eq!(sender.sent(), [1]);
+    /// ```
+    pub fn set_full(&self, is_full: bool) {
+        let mut state = self.lock();
+        state.is_full = is_full;
+        state.free = 0;
+        if !is_full {
+            for waker in state.wakers.drain(..) {
+                waiting::waking("MockSender::set_full()", || waker.wake());
+            }
+        }
+    }
+
+    /// Let `count` more messages be sent while the channel is full, as if the consumer had made
+    /// room for them, waking the sends that are waiting.
+    ///
+    /// The channel stays full, so once they have been sent the next send waits again. This lets
+    /// the test release a producer that is held back one message at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::{future::Future, pin::pin, task::{Context, Poll}};
+    /// use embassy_mock::sync::{MockSender, Sender};
+    /// use futures_util::task::noop_waker_ref;
+    ///
+    /// async fn produce(output: &impl Sender<u8>) {
+    ///     for value in 0..3 {
+    ///         output.send(value).await;
+    ///     }
+    /// }
+    ///
+    /// let sender = MockSender::new();
+    /// sender.set_full(true);
+    /// let mut cx = Context::from_waker(noop_waker_ref());
+    /// let mut produce = pin!(produce(&sender));
+    /// assert_eq!(produce.as_mut().poll(&mut cx), Poll::Pending);
+    ///
+    /// sender.free_capacity(2);
+    /// assert_eq!(produce.as_mut().poll(&mut cx), Poll::Pending);
+    /// assert_eq!(sender.sent(), [0, 1]);
+    ///
+    /// sender.free_capacity(1);
+    /// assert_eq!(produce.poll(&mut cx), Poll::Ready(()));
+    /// assert_eq!(sender.sent(), [0, 1, 2]);
+    /// ```
+    pub fn free_capacity(&self, count: usize) {
+        let mut state = self.lock();
+        state.free += count;
+        for waker in state.wakers.drain(..) {
+            waiting::waking("MockSender::free_capacity()", || waker.wake());
+        }
+    }
+
+    /// Set the length reported by [`ChannelLen::len()`], or [`None`] to report the number of sent
+    /// messages that are waiting to be received again.
+    ///
+    /// Without it the length is the number of messages waiting in a [`MockChannel::loopback()`],
+    /// otherwise the number sent and not yet marked as received with [`Self::mark_received()`].
+    /// Setting it lets the test take the code under test down its flow control branches without
+    /// sending that many messages. [`ChannelLen::is_full()`] still follows [`Self::set_full()`],
+    /// or reports the channel as full once the length reaches [`Self::expect_capacity()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{ChannelLen, MockSender, Sender};
+    ///
+    /// // Sends a batch of readings once there is room for all of them.
+    /// fn flush(output: &(impl Sender<u16> + ChannelLen), readings: &[u16]) -> bool {
+    ///     if output.len() + readings.len() > 4 {
+    ///         return false;
+    ///     }
+    ///     readings.iter().all(|&reading| output.try_send(reading).is_ok())
+    /// }
+    ///
+    /// let output = MockSender::new();
+    /// assert!(flush(&output, &[1, 2]));
+    /// assert_eq!(output.len(), 2);
+    /// assert!(!flush(&output, &[3, 4, 5]));
+    ///
+    /// // The consumer has caught up.
+    /// output.set_len(Some(1));
+    /// assert!(flush(&output, &[3, 4, 5]));
+    ///
+    /// output.set_len(None);
+    /// assert_eq!(output.len(), 5);
+    /// ```
+    pub fn set_len(&self, len: Option<usize>) {
+        self.lock().len = len;
+    }
+
+    /// The messages sent with this [`MockSender`], in call order.
+    pub fn sent(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.lock().sent.clone()
+    }
+
+    /// Check that this [`MockSender`] wasn't used if it was created by [`Self::expect_never()`],
+    /// and that it never held more messages than given to [`Self::expect_capacity()`].
+    pub fn done(mut self) -> Result<(), MockChannelError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that the sender wasn't used if it is expected to never be, and that it never
+    /// exceeded the expected capacity.
+    fn check(&self) -> Result<(), MockChannelError> {
+        let state = self.lock();
+        if let (true, Some(method)) = (self.never_used, state.first_use) {
+            return UnexpectedUseSnafu { method }.fail();
+        }
+        if let (Some(capacity), Some(index)) = (self.capacity, state.overflow) {
+            return CapacityExceededSnafu { capacity, index }.fail();
+        }
+
+        Ok(())
+    }
+
+    /// Record that the `method` was called, if it is the first.
+    fn record_use(&self, method: &'static str) {
+        self.lock().first_use.get_or_insert(method);
+    }
+
+    /// Is the channel full, in which case the `waker` is registered to be woken once it may not be.
+    fn wait_if_full(
+        state: &mut SenderState<T>,
+        loopback: Option<&mut Loopback<T>>,
+        waker: Option<&Waker>,
+    ) -> bool {
+        let is_full = state.is_full && state.free == 0;
+        let is_loopback_full = loopback.as_ref().is_some_and(|loopback| loopback.is_full());
+        if !is_full && !is_loopback_full {
+            return false;
+        }
+
+        if let Some(waker) = waker {
+            if !state.wakers.iter().any(|other| other.will_wake(waker)) {
+                state.wakers.push(waker.clone());
+            }
+            if let Some(loopback) = loopback {
+                loopback.send_waker = Some(waker.clone());
+            }
+        }
+        true
+    }
+
+    /// Record the `message` and pass it on to the loopback, unless the channel is full.
+    ///
+    /// If it is full then the `waker` is woken once it may not be, the locks being held until it
+    /// is registered so that wake can't be missed.
+    fn push(&self, message: T, waker: Option<&Waker>) -> Result<(), TrySendError<T>> {
+        let mut state = self.lock();
+        let mut loopback = self
+            .loopback
+            .as_ref()
+            .map(|loopback| loopback.lock().unwrap());
+        if Self::wait_if_full(&mut state, loopback.as_deref_mut(), waker) {
+            return Err(TrySendError::Full(message));
+        }
+        if state.is_full {
+            state.free -= 1;
+        }
+        if let Some(transcript) = &self.transcript {
+            (transcript.0)(&message);
+        }
+        let waiting = Self::waiting(&state, loopback.as_deref());
+        if self.capacity.is_some_and(|capacity| waiting >= capacity) {
+            let index = state.sent.len();
+            state.overflow.get_or_insert(index);
+        }
+        match &mut loopback {
+            Some(loopback) => {
+                state.sent.push((loopback.clone)(&message));
+                loopback.queue.push_back(message);
+                if let Some(waker) = loopback.receive_waker.take() {
+                    waiting::waking("MockSender::send()", || waker.wake());
+                }
+            }
+            None => state.sent.push(message),
+        }
+
+        Ok(())
+    }
+
+    /// The number of sent messages waiting to be received, from the `loopback` if there is one.
+    fn waiting(state: &SenderState<T>, loopback: Option<&Loopback<T>>) -> usize {
+        loopback.map_or(state.sent.len() - state.received, |loopback| {
+            loopback.queue.len()
+        })
+    }
+
+    /// Lock the state, which is never poisoned as the mock doesn't panic while holding the lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, SenderState<T>> {
+        self.state.lock().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for MockSender<T> {
+    /// If [`Self::done()`] has not been called before being dropped then check that the sender
+    /// wasn't used if it is expected to never be.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Sender<T> for MockSender<T> {
+    /// Record the `message`, waiting while the channel is full.
+    fn send(&self, message: T) -> impl Future<Output = ()> {
+        self.record_use("Sender::send()");
+        MockSendFuture {
+            sender: self,
+            message: Some(message),
+        }
+    }
+
+    /// Record the `message`, unless the channel is full.
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        self.record_use("Sender::try_send()");
+        self.push(message, None)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> ChannelLen for MockSender<T> {
+    /// The length given to [`MockSender::set_len()`], otherwise the number of sent messages that
+    /// are waiting to be received.
+    fn len(&self) -> usize {
+        let state = self.lock();
+        let loopback = self
+            .loopback
+            .as_ref()
+            .map(|loopback| loopback.lock().unwrap());
+        state
+            .len
+            .unwrap_or_else(|| Self::waiting(&state, loopback.as_deref()))
+    }
+
+    /// Is the length zero.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Is the channel set to be full, or the loopback full, or has the length reached the
+    /// expected capacity.
+    fn is_full(&self) -> bool {
+        let len = self.len();
+        let state = self.lock();
+        let is_loopback_full = self
+            .loopback
+            .as_ref()
+            .is_some_and(|loopback| loopback.lock().unwrap().is_full());
+        (state.is_full && state.free == 0)
+            || is_loopback_full
+            || self.capacity.is_some_and(|capacity| len >= capacity)
+    }
+}
+
+/// The future returned by [`Sender::send()`] for a [`MockSender`].
+#[cfg(feature = "std")]
+struct MockSendFuture<'a, T> {
+    /// The sender the message is sent with.
+    sender: &'a MockSender<T>,
+
+    /// The message, until it is sent.
+    message: Option<T>,
+}
+
+// The message is moved out without ever being pinned.
+#[cfg(feature = "std")]
+impl<T> Unpin for MockSendFuture<'_, T> {}
+
+#[cfg(feature = "std")]
+impl<T> Future for MockSendFuture<'_, T> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let Some(message) = this.message.take() else {
+            return Poll::Ready(());
+        };
+
+        match this.sender.push(message, Some(cx.waker())) {
+            Ok(()) => Poll::Ready(()),
+            Err(TrySendError::Full(message)) => {
+                this.message = Some(message);
+                waiting::record("MockSender::send()");
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Send the messages as a sink, which is only ready while the channel isn't full.
+///
+/// Each message is recorded as soon as it is started, so flushing and closing complete straight
+/// away. Starting to send a message while the channel is full gives it back as
+/// [`TrySendError::Full`].
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::sync::MockChannel;
+/// use futures_util::StreamExt;
+///
+/// let input = MockChannel::with_received([1, 2, 3]);
+/// let output = MockChannel::new();
+///
+/// let scaled = input.receiver().take(3).map(|n| Ok(n * 10));
+/// block_on(scaled.forward(output.sender())).unwrap();
+///
+/// assert_eq!(output.sent(), [10, 20, 30]);
+/// ```
+#[cfg(all(feature = "std", feature = "sink"))]
+impl<T> Sink<T> for &MockSender<T> {
+    type Error = TrySendError<T>;
+
+    /// Ready once the channel isn't full.
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.record_use("Sink::poll_ready()");
+        let mut state = self.lock();
+        let mut loopback = self
+            .loopback
+            .as_ref()
+            .map(|loopback| loopback.lock().unwrap());
+        if MockSender::wait_if_full(&mut state, loopback.as_deref_mut(), Some(cx.waker())) {
+            waiting::record("MockSender::poll_ready()");
+            Poll::Pending
+        } else {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Record the `message`, unless the channel is full.
+    fn start_send(self: Pin<&mut Self>, message: T) -> Result<(), Self::Error> {
+        self.record_use("Sink::start_send()");
+        self.push(message, None)
+    }
+
+    /// Complete straight away as the messages are recorded when they are started.
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// Complete straight away as the channel can't be closed.
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(all(feature = "std", feature = "sink"))]
+impl<T> Sink<T> for MockSender<T> {
+    type Error = TrySendError<T>;
+
+    /// Ready once the channel isn't full.
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut &*self).poll_ready(cx)
+    }
+
+    /// Record the `message`, unless the channel is full.
+    fn start_send(self: Pin<&mut Self>, message: T) -> Result<(), Self::Error> {
+        Pin::new(&mut &*self).start_send(message)
+    }
+
+    /// Complete straight away as the messages are recorded when they are started.
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut &*self).poll_flush(cx)
+    }
+
+    /// Complete straight away as the channel can't be closed.
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut &*self).poll_close(cx)
+    }
+}
+
+/// A mocked version of [`embassy_sync::channel::Receiver`] that can be used in its place for
+/// unit tests.
+///
+/// The messages returned by [`Receiver::receive()`] are scripted by the test.
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if any of the scripted messages
+/// were never received, or if it was used after being created by [`Self::expect_never()`].
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::sync::{MockReceiver, Receiver};
+///
+/// let receiver = MockReceiver::with_received(["ping", "pong"]);
+///
+/// assert_eq!(block_on(receiver.receive()), "ping");
+/// assert_eq!(block_on(receiver.receive()), "pong");
+///
+/// assert_eq!(receiver.done(), Ok(()));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockReceiver<T> {
+    /// The messages and the waiting receive, shared by every call.
+    state: Mutex<ReceiverState<T>>,
+
+    /// Records the received messages in a transcript, if one was given.
+    transcript: Option<TranscriptRecorder<T>>,
+
+    /// The queue of sent messages received after the scripted ones, if created by
+    /// [`MockChannel::loopback()`].
+    loopback: Option<Arc<Mutex<Loopback<T>>>>,
+
+    /// Is this mock expected to never be used, see [`Self::expect_never()`].
+    never_used: bool,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+/// The state of a [`MockReceiver`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct ReceiverState<T> {
+    /// The messages still to be returned by [`Receiver::receive()`], in order, with the polls
+    /// that return [`Poll::Pending`] between them.
+    to_receive: VecDeque<Poll<T>>,
+
+    /// The messages that arrive once the mocked clock reaches their time, in time order.
+    #[cfg(feature = "time")]
+    arrivals: VecDeque<(MockInstant, T)>,
+
+    /// The waker of the receive that is waiting for a message.
+    waker: Option<Waker>,
+
+    /// The first method of [`Receiver`] that was called, if any.
+    first_use: Option<&'static str>,
+
+    /// The length reported instead of the number of messages to receive, see
+    /// [`MockReceiver::set_len()`].
+    len: Option<usize>,
+
+    /// Is the channel reported as full, see [`MockReceiver::set_full()`].
+    is_full: bool,
+}
+
+#[cfg(feature = "std")]
+impl<T> MockReceiver<T> {
+    /// Create a [`MockReceiver`] with no messages to receive.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(ReceiverState {
+                to_receive: VecDeque::new(),
+                #[cfg(feature = "time")]
+                arrivals: VecDeque::new(),
+                waker: None,
+                first_use: None,
+                len: None,
+                is_full: false,
+            }),
+            transcript: None,
+            loopback: None,
+            never_used: false,
+            is_done: false,
+        }
+    }
+
+    /// Create a [`MockReceiver`] that is expected to never be used, to check that the code under
+    /// test doesn't receive anything, not even with a call that fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockChannelError, MockReceiver, Receiver};
+    ///
+    /// let receiver = MockReceiver::<u8>::expect_never();
+    /// let _ = receiver.try_receive();
+    ///
+    /// let expected = Err(MockChannelError::UnexpectedUse { method: "Receiver::try_receive()" });
+    /// assert_eq!(receiver.done(), expected);
+    /// ```
+    pub const fn expect_never() -> Self {
+        let mut receiver = Self::new();
+        receiver.never_used = true;
+        receiver
+    }
+
+    /// Also record every message received in the `transcript`, as a
+    /// [`ChannelEvent::Received`].
+    pub fn with_transcript(mut self, transcript: &ChannelTranscript<T>) -> Self
+    where
+        T: Clone + Send + 'static,
+    {
+        self.transcript = Some(TranscriptRecorder::new(transcript, ChannelEvent::Received));
+        self
+    }
+
+    /// Create a [`MockReceiver`] where [`Receiver::receive()`] returns the `messages` in order.
+    ///
+    /// Once they have all been received, [`Receiver::receive()`] waits until another message is
+    /// added with [`Self::push_received()`].
+    pub fn with_received(messages: impl IntoIterator<Item = T>) -> Self {
+        Self::script(messages.into_iter().map(Poll::Ready))
+    }
+
+    /// Create a [`MockReceiver`] where each poll of [`Receiver::receive()`] returns the next
+    /// entry of the `script`.
+    ///
+    /// A [`Poll::Pending`] entry makes a single poll return [`Poll::Pending`], waking the waker
+    /// so the executor polls it again, or a single [`Receiver::try_receive()`] return
+    /// [`TryReceiveError::Empty`]. This lets code that does other work while waiting for a
+    /// message, such as in a `select`, be driven deterministically.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::{future::Future, pin::pin, task::{Context, Poll}};
+    /// use embassy_mock::sync::{MockReceiver, Receiver};
+    /// use futures_util::task::noop_waker_ref;
+    ///
+    /// let receiver = MockReceiver::script([Poll::Ready(1), Poll::Pending, Poll::Ready(2)]);
+    /// let mut cx = Context::from_waker(noop_waker_ref());
+    ///
+    /// assert_eq!(pin!(receiver.receive()).poll(&mut cx), Poll::Ready(1));
+    ///
+    /// {
+    ///     let mut receive = pin!(receiver.receive());
+    ///     assert_eq!(receive.as_mut().poll(&mut cx), Poll::Pending);
+    ///     assert_eq!(receive.as_mut().poll(&mut cx), Poll::Ready(2));
+    /// }
+    ///
+    /// receiver.done().unwrap();
+    /// ```
+    pub fn script(script: impl IntoIterator<Item = Poll<T>>) -> Self {
+        let receiver = Self::new();
+        receiver.lock().to_receive.extend(script);
+        receiver
+    }
+
+    /// Add the `message` to be received after the others, waking the receive that is waiting for
+    /// it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::{future::Future, pin::pin, task::{Context, Poll}};
+    /// use embassy_mock::sync::{MockReceiver, Receiver};
+    /// use futures_util::task::noop_waker_ref;
+    ///
+    /// let receiver = MockReceiver::new();
+    /// let mut cx = Context::from_waker(noop_waker_ref());
+    /// let mut receive = pin!(receiver.receive());
+    /// assert_eq!(receive.as_mut().poll(&mut cx), Poll::Pending);
+    ///
+    /// receiver.push_received(7);
+    /// assert_eq!(receive.poll(&mut cx), Poll::Ready(7));
+    /// ```
+    pub fn push_received(&self, message: T) {
+        let mut state = self.lock();
+        state.to_receive.push_back(Poll::Ready(message));
+        if let Some(waker) = state.waker.take() {
+            waiting::waking("MockReceiver::push_received()", || waker.wake());
+        }
+    }
+
+    /// Add the `message` to arrive once the mocked clock of the current thread reaches `at`, to be
+    /// received after the messages that arrived before it.
+    ///
+    /// A receive that is waiting for it registers `at` with the clock, so running the code with
+    /// the [`MockClock`](crate::time::MockClock) moves time forward to its arrival. If no
+    /// [`MockClock`](crate::time::MockClock) is alive then it arrives straight away, as the
+    /// mocked timers complete straight away.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::pin::pin;
+    /// use embassy_futures::select::{select, Either};
+    /// use embassy_mock::{
+    ///     sync::{MockReceiver, Receiver},
+    ///     time::{Instant, MockClock, MockInstant, MockTimer, Timer},
+    /// };
+    /// use embassy_time::Duration;
+    ///
+    /// // Add up the messages received within 50 ms of the first.
+    /// async fn coalesce<T: Timer>(input: &impl Receiver<u8>) -> u8 {
+    ///     let mut total = input.receive().await;
+    ///     let mut window = pin!(T::after(Duration::from_millis(50)));
+    ///     loop {
+    ///         match select(input.receive(), window.as_mut()).await {
+    ///             Either::First(message) => total += message,
+    ///             Either::Second(()) => break total,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let clock = MockClock::new();
+    /// let input = MockReceiver::new();
+    /// input.push_received_at(MockInstant::from_millis(10), 1);
+    /// input.push_received_at(MockInstant::from_millis(40), 2);
+    /// input.push_received_at(MockInstant::from_millis(70), 4);
+    /// input.push_received_at(MockInstant::from_millis(100), 8);
+    ///
+    /// // The window starts at the first message and closes at 60 ms.
+    /// assert_eq!(clock.block_on(coalesce::<MockTimer>(&input)), 3);
+    /// assert_eq!(clock.block_on(coalesce::<MockTimer>(&input)), 12);
+    /// assert_eq!(clock.now(), MockInstant::from_millis(120));
+    /// ```
+    #[cfg(feature = "time")]
+    pub fn push_received_at(&self, at: MockInstant, message: T) {
+        let mut state = self.lock();
+        let index = state.arrivals.partition_point(|(other, _)| *other <= at);
+        state.arrivals.insert(index, (at, message));
+        // The waiting receive registers the time of the next arrival with the clock when polled.
+        if let Some(waker) = state.waker.take() {
+            waiting::waking("MockReceiver::push_received_at()", || waker.wake());
+        }
+    }
+
+    /// Set the length reported by [`ChannelLen::len()`], or [`None`] to report the number of
+    /// messages still to be received.
+    ///
+    /// Without it the length is the number of scripted messages that have arrived and not been
+    /// received, plus those waiting in a [`MockChannel::loopback()`]. Setting it lets the test
+    /// take the code under test down its flow control branches without scripting that many
+    /// messages.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{ChannelLen, MockReceiver, Receiver};
+    ///
+    /// // Drain the backlog in one go when it has built up, otherwise handle one message.
+    /// fn handle(input: &(impl Receiver<u8> + ChannelLen)) -> usize {
+    ///     let count = if input.len() > 8 { input.len() } else { 1 };
+    ///     (0..count).filter(|_| input.try_receive().is_ok()).count()
+    /// }
+    ///
+    /// let input = MockReceiver::with_received([1, 2, 3]);
+    /// assert_eq!(input.len(), 3);
+    /// assert_eq!(handle(&input), 1);
+    ///
+    /// input.set_len(Some(9));
+    /// assert_eq!(handle(&input), 2);
+    /// assert!(!input.is_empty());
+    ///
+    /// input.set_len(None);
+    /// assert!(input.is_empty());
+    /// ```
+    pub fn set_len(&self, len: Option<usize>) {
+        self.lock().len = len;
+    }
+
+    /// Set whether [`ChannelLen::is_full()`] reports the channel as full, as if the producer had
+    /// filled it.
+    ///
+    /// A [`MockChannel::loopback_with_capacity()`] is also reported as full while it holds as many
+    /// messages as its capacity.
+    pub fn set_full(&self, is_full: bool) {
+        self.lock().is_full = is_full;
+    }
+
+    /// Check that all of the messages given to [`Self::with_received()`], [`Self::script()`] and
+    /// [`Self::push_received()`] were received.
+    ///
+    /// Scripted [`Poll::Pending`] entries that were never reached aren't reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::sync::{MockChannelError, MockReceiver, Receiver};
+    ///
+    /// let receiver = MockReceiver::with_received([1, 2, 3]);
+    /// block_on(receiver.receive());
+    ///
+    /// let expected = Err(MockChannelError::UnreceivedMessages { remaining: 2 });
+    /// assert_eq!(receiver.done(), expected);
+    ///
+    /// // This doesn't panic when `receiver` is dropped as `receiver.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockChannelError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that the receiver wasn't used if it is expected to never be, and that all of the
+    /// scripted messages were received.
+    fn check(&self) -> Result<(), MockChannelError> {
+        if let (true, Some(method)) = (self.never_used, self.lock().first_use) {
+            return UnexpectedUseSnafu { method }.fail();
+        }
+
+        let scripted = self
+            .lock()
+            .to_receive
+            .iter()
+            .filter(|entry| entry.is_ready())
+            .count();
+        let looped = self
+            .loopback
+            .as_ref()
+            .map_or(0, |loopback| loopback.lock().unwrap().queue.len());
+        #[cfg(feature = "time")]
+        let scripted = scripted + self.lock().arrivals.len();
+        let remaining = scripted + looped;
+        ensure!(remaining == 0, UnreceivedMessagesSnafu { remaining });
+
+        Ok(())
+    }
+
+    /// Take the next scripted entry, or else the next message from the loopback, recording it in
+    /// the transcript if it is a message.
+    ///
+    /// If there is neither then the `waker` is woken when a message is sent to the loopback.
+    fn pop(&self, state: &mut ReceiverState<T>, waker: Option<&Waker>) -> Option<Poll<T>> {
+        #[cfg(feature = "time")]
+        Self::deliver_arrivals(state, waker);
+
+        let entry = state.to_receive.pop_front().or_else(|| {
+            let mut loopback = self.loopback.as_ref()?.lock().unwrap();
+            let message = loopback.queue.pop_front();
+            match (&message, waker) {
+                (Some(_), _) => {
+                    if let Some(waker) = loopback.send_waker.take() {
+                        waiting::waking("MockReceiver::receive()", || waker.wake());
+                    }
+                }
+                (None, Some(waker)) => loopback.receive_waker = Some(waker.clone()),
+                (None, None) => {}
+            }
+            message.map(Poll::Ready)
+        });
+        if let (Some(Poll::Ready(message)), Some(transcript)) = (&entry, &self.transcript) {
+            (transcript.0)(message);
+        }
+
+        entry
+    }
+
+    /// Move the messages whose arrival time the mocked clock has reached to the end of the
+    /// messages to receive.
+    ///
+    /// If there is then nothing to receive the `waker` is registered with the clock to be woken
+    /// when the next message arrives.
+    #[cfg(feature = "time")]
+    fn deliver_arrivals(state: &mut ReceiverState<T>, waker: Option<&Waker>) {
+        let is_attached = clock::is_attached();
+        while let Some((at, _)) = state.arrivals.front() {
+            if is_attached && *at > clock::now() {
+                break;
+            }
+            if let Some((_, message)) = state.arrivals.pop_front() {
+                state.to_receive.push_back(Poll::Ready(message));
+            }
+        }
+
+        if let (true, Some((at, _)), Some(waker)) =
+            (state.to_receive.is_empty(), state.arrivals.front(), waker)
+        {
+            clock::poll_deadline(*at, waker);
+        }
+    }
+
+    /// Take the next scripted entry, or else the next message from the loopback, waiting until
+    /// there is a message.
+    fn poll_receive(&self, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.lock();
+        match self.pop(&mut state, Some(cx.waker())) {
+            Some(Poll::Ready(message)) => Poll::Ready(message),
+            Some(Poll::Pending) => {
+                waiting::waking("MockReceiver::receive()", || cx.waker().wake_by_ref());
+                Poll::Pending
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                waiting::record("MockReceiver::receive()");
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Record that the `method` was called, if it is the first.
+    fn record_use(&self, method: &'static str) {
+        self.lock().first_use.get_or_insert(method);
+    }
+
+    /// Run `f` with the loopback, if there is one.
+    fn with_loopback<R>(&self, f: impl FnOnce(&Loopback<T>) -> R) -> Option<R> {
+        Some(f(&self.loopback.as_ref()?.lock().unwrap()))
+    }
+
+    /// Lock the state, which is never poisoned as the mock doesn't panic while holding the lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, ReceiverState<T>> {
+        self.state.lock().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for MockReceiver<T> {
+    /// If [`Self::done()`] has not been called before being dropped then check that all of the
+    /// scripted messages were received.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Receiver<T> for MockReceiver<T> {
+    /// Return the next scripted message, waiting until there is one.
+    fn receive(&self) -> impl Future<Output = T> {
+        self.record_use("Receiver::receive()");
+        MockReceiveFuture { receiver: self }
+    }
+
+    /// Return the next scripted message, or [`TryReceiveError::Empty`] if there isn't one.
+    fn try_receive(&self) -> Result<T, TryReceiveError> {
+        self.record_use("Receiver::try_receive()");
+        match self.pop(&mut self.lock(), None) {
+            Some(Poll::Ready(message)) => Ok(message),
+            Some(Poll::Pending) | None => Err(TryReceiveError::Empty),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> ChannelLen for MockReceiver<T> {
+    /// The length given to [`MockReceiver::set_len()`], otherwise the number of messages that
+    /// can be received without waiting.
+    fn len(&self) -> usize {
+        #[cfg(feature = "time")]
+        Self::deliver_arrivals(&mut self.lock(), None);
+        let state = self.lock();
+        if let Some(len) = state.len {
+            return len;
+        }
+
+        let scripted = state
+            .to_receive
+            .iter()
+            .filter(|entry| entry.is_ready())
+            .count();
+        scripted
+            + self
+                .with_loopback(|loopback| loopback.queue.len())
+                .unwrap_or(0)
+    }
+
+    /// Is the length zero.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Is the channel set to be full, or the loopback full.
+    fn is_full(&self) -> bool {
+        self.lock().is_full || self.with_loopback(Loopback::is_full).unwrap_or(false)
+    }
+}
+
+/// The future returned by [`Receiver::receive()`] for a [`MockReceiver`].
+#[cfg(feature = "std")]
+struct MockReceiveFuture<'a, T> {
+    /// The receiver the message is received from.
+    receiver: &'a MockReceiver<T>,
+}
+
+#[cfg(feature = "std")]
+impl<T> Future for MockReceiveFuture<'_, T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.receiver.poll_receive(cx)
+    }
+}
+
+/// Receive the messages as a stream, which never ends as the channel can't be closed.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::sync::MockReceiver;
+/// use futures_util::StreamExt;
+///
+/// let receiver = MockReceiver::with_received([1, 2, 3, 4]);
+///
+/// let even = (&receiver).take(4).filter(|n| core::future::ready(n % 2 == 0));
+/// assert_eq!(block_on(even.collect::<Vec<_>>()), [2, 4]);
+///
+/// receiver.done().unwrap();
+/// ```
+#[cfg(all(feature = "std", feature = "stream"))]
+impl<T> Stream for &MockReceiver<T> {
+    type Item = T;
+
+    /// Return the next scripted message, waiting until there is one.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.record_use("Stream::poll_next()");
+        self.poll_receive(cx).map(Some)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "stream"))]
+impl<T> FusedStream for &MockReceiver<T> {
+    /// Always `false` as the stream never ends.
+    fn is_terminated(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(all(feature = "std", feature = "stream"))]
+impl<T> Stream for MockReceiver<T> {
+    type Item = T;
+
+    /// Return the next scripted message, waiting until there is one.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut &*self).poll_next(cx)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "stream"))]
+impl<T> FusedStream for MockReceiver<T> {
+    /// Always `false` as the stream never ends.
+    fn is_terminated(&self) -> bool {
+        false
+    }
+}
+
+/// The queue that connects the halves of a [`MockChannel`] created by [`MockChannel::loopback()`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct Loopback<T> {
+    /// The messages that were sent and not yet received, in order.
+    queue: VecDeque<T>,
+
+    /// The number of messages the queue holds before it is full, [`None`] if it is unbounded.
+    capacity: Option<usize>,
+
+    /// Copies each message sent, so the sender records it as well as passing it on.
+    clone: fn(&T) -> T,
+
+    /// The waker of the send that is waiting for the queue to not be full.
+    send_waker: Option<Waker>,
+
+    /// The waker of the receive that is waiting for a message.
+    receive_waker: Option<Waker>,
+}
+
+#[cfg(feature = "std")]
+impl<T> Loopback<T> {
+    /// Is the queue holding as many messages as its capacity.
+    fn is_full(&self) -> bool {
+        self.capacity
+            .is_some_and(|capacity| self.queue.len() >= capacity)
+    }
+}
+
+/// A mocked version of [`embassy_sync::channel::Channel`] that can be used in its place for unit
+/// tests.
+///
+/// This is a [`MockSender`] and a [`MockReceiver`] together, so the messages sent are recorded
+/// and not received, unless it is created by [`Self::loopback()`].
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if any of the messages given to
+/// [`Self::with_received()`], or sent on a [`Self::loopback()`], were never received, if it was
+/// used after being created by [`Self::expect_never()`], or if it held more messages than given
+/// to [`Self::expect_capacity()`].
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::sync::{Receiver, MockChannel, Sender};
+///
+/// let channel = MockChannel::with_received(["ping"]);
+///
+/// assert_eq!(block_on(channel.receive()), "ping");
+/// block_on(channel.send("pong"));
+///
+/// assert_eq!(channel.sent(), ["pong"]);
+/// assert_eq!(channel.done(), Ok(()));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockChannel<T> {
+    /// Records the messages sent on the channel.
+    sender: MockSender<T>,
+
+    /// Returns the scripted messages received from the channel.
+    receiver: MockReceiver<T>,
+}
+
+#[cfg(feature = "std")]
+impl<T> MockChannel<T> {
+    /// Create a [`MockChannel`] with no messages to receive.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            sender: MockSender::new(),
+            receiver: MockReceiver::new(),
+        }
+    }
+
+    /// Create a [`MockChannel`] that is expected to never be used, see
+    /// [`MockSender::expect_never()`] and [`MockReceiver::expect_never()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockChannel, Sender};
+    ///
+    /// // Only reports the reading when the feature is enabled.
+    /// fn measure(reports: &impl Sender<u16>, is_reporting: bool) {
+    ///     if is_reporting {
+    ///         let _ = reports.try_send(42);
+    ///     }
+    /// }
+    ///
+    /// let reports = MockChannel::expect_never();
+    /// measure(&reports, false);
+    /// reports.done().unwrap();
+    /// ```
+    pub const fn expect_never() -> Self {
+        Self {
+            sender: MockSender::expect_never(),
+            receiver: MockReceiver::expect_never(),
+        }
+    }
+
+    /// Create a [`MockChannel`] where [`Receiver::receive()`] returns the `messages` in order.
+    ///
+    /// See [`MockReceiver::with_received()`].
+    pub fn with_received(messages: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            sender: MockSender::new(),
+            receiver: MockReceiver::with_received(messages),
+        }
+    }
+
+    /// Create a [`MockChannel`] where each poll of [`Receiver::receive()`] returns the next entry
+    /// of the `script`.
+    ///
+    /// See [`MockReceiver::script()`].
+    pub fn script(script: impl IntoIterator<Item = Poll<T>>) -> Self {
+        Self {
+            sender: MockSender::new(),
+            receiver: MockReceiver::script(script),
+        }
+    }
+
+    /// Create a [`MockChannel`] where the messages sent are received, in order, as well as being
+    /// recorded.
+    ///
+    /// This connects the [`Self::sender()`] and [`Self::receiver()`] halves, so a producer and a
+    /// consumer can be tested together without the real executor. Any messages given to
+    /// [`Self::push_received()`] are received before the ones sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::{block_on, join::join};
+    /// use embassy_mock::sync::{MockChannel, Receiver, Sender};
+    ///
+    /// async fn produce(output: impl Sender<u8>) {
+    ///     for reading in 1..=3 {
+    ///         output.send(reading).await;
+    ///     }
+    /// }
+    ///
+    /// async fn consume(input: impl Receiver<u8>) -> u8 {
+    ///     let mut total = 0;
+    ///     for _ in 0..3 {
+    ///         total += input.receive().await;
+    ///     }
+    ///     total
+    /// }
+    ///
+    /// let channel = MockChannel::loopback();
+    ///
+    /// let ((), total) = block_on(join(produce(channel.sender()), consume(channel.receiver())));
+    ///
+    /// assert_eq!(total, 6);
+    /// assert_eq!(channel.sent(), [1, 2, 3]);
+    /// channel.done().unwrap();
+    /// ```
+    pub fn loopback() -> Self
+    where
+        T: Clone,
+    {
+        Self::connected(None)
+    }
+
+    /// Create a [`MockChannel`] like [`Self::loopback()`] that holds at most `capacity` messages
+    /// that haven't been received.
+    ///
+    /// While it holds that many, [`Sender::try_send()`] returns [`TrySendError::Full`] and
+    /// [`Sender::send()`] waits until a message is received, as with a real channel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockChannel, Receiver, Sender};
+    /// use embassy_sync::channel::TrySendError;
+    ///
+    /// let channel = MockChannel::loopback_with_capacity(1);
+    ///
+    /// channel.try_send('a').unwrap();
+    /// assert_eq!(channel.try_send('b'), Err(TrySendError::Full('b')));
+    /// assert_eq!(channel.try_receive(), Ok('a'));
+    /// channel.try_send('c').unwrap();
+    /// assert_eq!(channel.try_receive(), Ok('c'));
+    ///
+    /// assert_eq!(channel.sent(), ['a', 'c']);
+    /// ```
+    pub fn loopback_with_capacity(capacity: usize) -> Self
+    where
+        T: Clone,
+    {
+        Self::connected(Some(capacity))
+    }
+
+    /// Create a [`MockChannel`] with its halves connected by a queue of the `capacity`.
+    fn connected(capacity: Option<usize>) -> Self
+    where
+        T: Clone,
+    {
+        let loopback = Arc::new(Mutex::new(Loopback {
+            queue: VecDeque::new(),
+            capacity,
+            clone: T::clone,
+            send_waker: None,
+            receive_waker: None,
+        }));
+        let mut sender = MockSender::new();
+        sender.loopback = Some(Arc::clone(&loopback));
+        let mut receiver = MockReceiver::new();
+        receiver.loopback = Some(loopback);
+
+        Self { sender, receiver }
+    }
+
+    /// Expect no more than `capacity` sent messages to be waiting to be received at any time, see
+    /// [`MockSender::expect_capacity()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::sync::{MockChannel, Receiver, Sender};
+    ///
+    /// // Queues each job before handling the previous one.
+    /// async fn run_jobs(jobs: &(impl Sender<u8> + Receiver<u8>), count: u8) {
+    ///     jobs.send(0).await;
+    ///     for job in 1..count {
+    ///         jobs.send(job).await;
+    ///         jobs.receive().await;
+    ///     }
+    /// }
+    ///
+    /// // The production channel holds 2 jobs.
+    /// let jobs = MockChannel::loopback().expect_capacity(2);
+    /// block_on(run_jobs(&jobs, 5));
+    ///
+    /// assert_eq!(jobs.try_receive(), Ok(4));
+    /// jobs.done().unwrap();
+    /// ```
+    pub fn expect_capacity(self, capacity: usize) -> Self {
+        Self {
+            sender: self.sender.expect_capacity(capacity),
+            receiver: self.receiver,
+        }
+    }
+
+    /// Mark the oldest `count` sent messages that are still waiting as received, see
+    /// [`MockSender::mark_received()`].
+    pub fn mark_received(&self, count: usize) {
+        self.sender.mark_received(count);
+    }
+
+    /// Also record every message sent and received in the `transcript`, see
+    /// [`MockSender::with_transcript()`] and [`MockReceiver::with_transcript()`].
+    pub fn with_transcript(self, transcript: &ChannelTranscript<T>) -> Self
+    where
+        T: Clone + Send + 'static,
+    {
+        Self {
+            sender: self.sender.with_transcript(transcript),
+            receiver: self.receiver.with_transcript(transcript),
+        }
+    }
+
+    /// The sending half of this channel, to pass to code that takes a [`Sender`].
+    pub const fn sender(&self) -> &MockSender<T> {
+        &self.sender
+    }
+
+    /// The receiving half of this channel, to pass to code that takes a [`Receiver`].
+    pub const fn receiver(&self) -> &MockReceiver<T> {
+        &self.receiver
+    }
+
+    /// Add the `message` to be received after the others, see [`MockReceiver::push_received()`].
+    pub fn push_received(&self, message: T) {
+        self.receiver.push_received(message);
+    }
+
+    /// Add the `message` to arrive once the mocked clock reaches `at`, see
+    /// [`MockReceiver::push_received_at()`].
+    #[cfg(feature = "time")]
+    pub fn push_received_at(&self, at: MockInstant, message: T) {
+        self.receiver.push_received_at(at, message);
+    }
+
+    /// Set whether the channel is full, see [`MockSender::set_full()`].
+    pub fn set_full(&self, is_full: bool) {
+        self.sender.set_full(is_full);
+    }
+
+    /// Set the length reported by [`ChannelLen::len()`], or [`None`] to report the number of
+    /// messages still to be received, see [`MockReceiver::set_len()`].
+    pub fn set_len(&self, len: Option<usize>) {
+        self.receiver.set_len(len);
+    }
+
+    /// Let `count` more messages be sent while the channel is full, see
+    /// [`MockSender::free_capacity()`].
+    pub fn free_capacity(&self, count: usize) {
+        self.sender.free_capacity(count);
+    }
+
+    /// The messages sent on this [`MockChannel`], in call order.
+    pub fn sent(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.sender.sent()
+    }
+
+    /// Check that all of the messages given to [`Self::with_received()`] and
+    /// [`Self::push_received()`], and those sent on a [`Self::loopback()`], were received, and
+    /// that the channel wasn't used if it was created by [`Self::expect_never()`], see
+    /// [`MockSender::done()`] and [`MockReceiver::done()`].
+    pub fn done(self) -> Result<(), MockChannelError> {
+        let sender = self.sender.done();
+        let receiver = self.receiver.done();
+        sender.and(receiver)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Sender<T> for MockChannel<T> {
+    /// Record the `message`, see [`MockSender`].
+    fn send(&self, message: T) -> impl Future<Output = ()> {
+        self.sender.send(message)
+    }
+
+    /// Record the `message` unless the channel is full, see [`MockSender`].
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        self.sender.try_send(message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Receiver<T> for MockChannel<T> {
+    /// Return the next scripted message, see [`MockReceiver`].
+    fn receive(&self) -> impl Future<Output = T> {
+        self.receiver.receive()
+    }
+
+    /// Return the next scripted message if there is one, see [`MockReceiver`].
+    fn try_receive(&self) -> Result<T, TryReceiveError> {
+        self.receiver.try_receive()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> ChannelLen for MockChannel<T> {
+    /// The number of messages still to be received, see [`MockReceiver`].
+    ///
+    /// Unless it is a [`MockChannel::loopback()`] the messages sent are only recorded, so they
+    /// don't count.
+    fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    /// Is the length zero.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Is the channel full, see [`MockSender::set_full()`] and [`MockReceiver::set_full()`].
+    fn is_full(&self) -> bool {
+        self.sender.is_full() || self.receiver.is_full()
+    }
+}
+
+impl<C: ChannelLen + ?Sized> ChannelLen for &C {
+    /// Call [`ChannelLen::len()`] of the referenced channel.
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    /// Call [`ChannelLen::is_empty()`] of the referenced channel.
+    fn is_empty(&self) -> bool {
+        (**self).is_empty()
+    }
+
+    /// Call [`ChannelLen::is_full()`] of the referenced channel.
+    fn is_full(&self) -> bool {
+        (**self).is_full()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::WakeProbe;
+    use core::pin::pin;
+    use embassy_futures::block_on;
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    use futures_util::task::noop_waker_ref;
+    #[cfg(feature = "sink")]
+    use futures_util::SinkExt;
+    #[cfg(feature = "stream")]
+    use futures_util::StreamExt;
+    #[cfg(feature = "time")]
+    use {
+        crate::time::{Instant as _, MockClock},
+        embassy_time::Duration,
+    };
+
+    async fn forward<I: Channel<u32>, O: Channel<u32>>(input: &I, output: &O, count: usize) {
+        for _ in 0..count {
+            output.send(input.receive().await).await;
+        }
+    }
+
+    async fn forward_halves(input: impl Receiver<u32>, output: impl Sender<u32>, count: usize) {
+        for _ in 0..count {
+            output.send(input.receive().await).await;
+        }
+    }
+
+    #[test]
+    fn embassy_channel_sends_and_receives() {
+        let input = EmbassyChannel::<NoopRawMutex, u32, 2>::new();
+        let output = EmbassyChannel::<NoopRawMutex, u32, 2>::new();
+        block_on(Sender::send(&input, 1));
+        block_on(Sender::send(&input, 2));
+
+        block_on(forward(&input, &output, 2));
+
+        assert_eq!(output.try_receive(), Ok(1));
+        assert_eq!(output.try_receive(), Ok(2));
+    }
+
+    #[test]
+    fn embassy_halves_send_and_receive() {
+        let input = EmbassyChannel::<NoopRawMutex, u32, 2>::new();
+        let output = EmbassyChannel::<NoopRawMutex, u32, 2>::new();
+        block_on(Sender::send(&input.sender(), 1));
+        block_on(Sender::send(&input.sender(), 2));
+
+        block_on(forward_halves(input.receiver(), output.sender(), 2));
+
+        assert_eq!(output.try_receive(), Ok(1));
+        assert_eq!(output.try_receive(), Ok(2));
+    }
+
+    #[test]
+    fn embassy_dynamic_halves_send_and_receive() {
+        let input = EmbassyChannel::<NoopRawMutex, u32, 2>::new();
+        let output = EmbassyChannel::<NoopRawMutex, u32, 4>::new();
+        block_on(Sender::send(&input.dyn_sender(), 1));
+        block_on(Sender::send(&DynamicSender::from(input.sender()), 2));
+
+        block_on(forward_halves(input.dyn_receiver(), output.dyn_sender(), 1));
+        block_on(forward_halves(
+            DynamicReceiver::from(input.receiver()),
+            output.dyn_sender(),
+            1,
+        ));
+
+        assert_eq!(output.try_receive(), Ok(1));
+        assert_eq!(output.try_receive(), Ok(2));
+    }
+
+    #[test]
+    fn mock_receives_scripted_and_records_sent() {
+        let input = MockChannel::with_received([3, 4]);
+        let output = MockChannel::new();
+
+        block_on(forward(&input, &output, 2));
+
+        assert_eq!(output.sent(), [3, 4]);
+        assert!(input.sent().is_empty());
+        input.done().unwrap();
+        output.done().unwrap();
+    }
+
+    #[test]
+    fn mock_halves_are_used_in_isolation() {
+        let input = MockReceiver::with_received([5, 6]);
+        let output = MockSender::new();
+
+        block_on(forward_halves(&input, &output, 2));
+
+        assert_eq!(output.sent(), [5, 6]);
+        input.done().unwrap();
+    }
+
+    #[test]
+    fn mock_channel_halves_share_its_state() {
+        let channel = MockChannel::with_received([7]);
+
+        block_on(forward_halves(channel.receiver(), channel.sender(), 1));
+        block_on(channel.send(8));
+
+        assert_eq!(channel.sent(), [7, 8]);
+        channel.done().unwrap();
+    }
+
+    #[test]
+    fn receive_waits_for_pushed_message() {
+        let channel = MockChannel::with_received([1]);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        assert_eq!(block_on(channel.receive()), 1);
+
+        let mut receive = pin!(channel.receive());
+        assert_eq!(receive.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(receive.as_mut().poll(&mut cx), Poll::Pending);
+
+        channel.push_received(2);
+        assert_eq!(receive.poll(&mut cx), Poll::Ready(2));
+    }
+
+    #[test]
+    fn unpolled_send_is_not_recorded() {
+        let sender = MockSender::new();
+
+        drop(sender.send(1));
+        block_on(sender.send(2));
+
+        assert_eq!(sender.sent(), [2]);
+    }
+
+    #[test]
+    fn embassy_try_send_and_try_receive() {
+        let channel = EmbassyChannel::<NoopRawMutex, u32, 1>::new();
+
+        assert_eq!(Receiver::try_receive(&channel), Err(TryReceiveError::Empty));
+        assert_eq!(Sender::try_send(&channel.sender(), 1), Ok(()));
+        assert_eq!(
+            Sender::try_send(&channel.dyn_sender(), 2),
+            Err(TrySendError::Full(2))
+        );
+        assert_eq!(Receiver::try_receive(&channel.dyn_receiver()), Ok(1));
+    }
+
+    #[test]
+    fn try_receive_returns_scripted_then_empty() {
+        let receiver = MockReceiver::with_received([1]);
+
+        assert_eq!(receiver.try_receive(), Ok(1));
+        assert_eq!(receiver.try_receive(), Err(TryReceiveError::Empty));
+        receiver.done().unwrap();
+    }
+
+    #[test]
+    fn full_sender_rejects_try_send() {
+        let channel = MockChannel::new();
+        channel.try_send(1).unwrap();
+
+        channel.set_full(true);
+        assert_eq!(channel.try_send(2), Err(TrySendError::Full(2)));
+        channel.set_full(false);
+        channel.try_send(3).unwrap();
+
+        assert_eq!(channel.sent(), [1, 3]);
+    }
+
+    #[test]
+    fn send_waits_while_full() {
+        let sender = MockSender::new();
+        sender.set_full(true);
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        let mut send = pin!(sender.send(1));
+        assert_eq!(send.as_mut().poll(&mut cx), Poll::Pending);
+        assert!(sender.sent().is_empty());
+
+        sender.set_full(false);
+        assert_eq!(send.poll(&mut cx), Poll::Ready(()));
+        assert_eq!(sender.sent(), [1]);
+    }
+
+    #[test]
+    fn scripted_pending_polls_between_messages() {
+        let receiver =
+            MockReceiver::script([Poll::Ready(1), Poll::Pending, Poll::Pending, Poll::Ready(2)]);
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        assert_eq!(pin!(receiver.receive()).poll(&mut cx), Poll::Ready(1));
+        {
+            let mut receive = pin!(receiver.receive());
+            assert_eq!(receive.as_mut().poll(&mut cx), Poll::Pending);
+            assert_eq!(receive.as_mut().poll(&mut cx), Poll::Pending);
+            assert_eq!(receive.as_mut().poll(&mut cx), Poll::Ready(2));
+        }
+        receiver.done().unwrap();
+    }
+
+    #[test]
+    fn scripted_pending_wakes_for_block_on() {
+        let channel = MockChannel::script([Poll::Pending, Poll::Ready(1)]);
+
+        assert_eq!(block_on(channel.receive()), 1);
+        channel.done().unwrap();
+    }
+
+    #[test]
+    fn scripted_pending_is_empty_for_try_receive() {
+        let receiver = MockReceiver::script([Poll::Pending, Poll::Ready(1)]);
+
+        assert_eq!(receiver.try_receive(), Err(TryReceiveError::Empty));
+        assert_eq!(receiver.try_receive(), Ok(1));
+    }
+
+    #[test]
+    fn unreached_pending_is_not_reported() {
+        let receiver = MockReceiver::script([Poll::Ready(1), Poll::Pending]);
+        block_on(receiver.receive());
+
+        assert_eq!(receiver.done(), Ok(()));
+    }
+
+    #[test]
+    fn transcript_records_both_ends_in_order() {
+        let transcript = ChannelTranscript::new();
+        let input = MockReceiver::with_received([1, 2]).with_transcript(&transcript);
+        let output = MockSender::new().with_transcript(&transcript);
+
+        block_on(forward_halves(&input, &output, 2));
+
+        use ChannelEvent::{Received, Sent};
+        assert_eq!(
+            transcript.events(),
+            [Received(1), Sent(1), Received(2), Sent(2)]
+        );
+        input.done().unwrap();
+    }
+
+    #[test]
+    fn transcript_skips_rejected_and_pending() {
+        let transcript = ChannelTranscript::new();
+        let channel = MockChannel::script([Poll::Pending, Poll::Ready(3)])
+            .with_transcript(&transcript.clone());
+
+        channel.set_full(true);
+        assert_eq!(channel.try_send(1), Err(TrySendError::Full(1)));
+        assert_eq!(channel.try_receive(), Err(TryReceiveError::Empty));
+        channel.set_full(false);
+        channel.try_send(2).unwrap();
+        assert_eq!(channel.try_receive(), Ok(3));
+
+        use ChannelEvent::{Received, Sent};
+        assert_eq!(transcript.events(), [Sent(2), Received(3)]);
+        channel.done().unwrap();
+    }
+
+    #[test]
+    fn loopback_receives_sent_messages() {
+        let channel = MockChannel::loopback();
+        channel.push_received(1);
+
+        block_on(forward_halves(channel.receiver(), channel.sender(), 3));
+
+        assert_eq!(channel.sent(), [1, 1, 1]);
+        assert_eq!(channel.try_receive(), Ok(1));
+        channel.done().unwrap();
+    }
+
+    #[test]
+    fn loopback_receive_waits_for_send() {
+        let channel = MockChannel::loopback();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut receive = pin!(channel.receive());
+        assert_eq!(receive.as_mut().poll(&mut cx), Poll::Pending);
+
+        channel.try_send(5).unwrap();
+
+        assert_eq!(receive.poll(&mut cx), Poll::Ready(5));
+    }
+
+    #[test]
+    fn loopback_send_waits_while_at_capacity() {
+        let channel = MockChannel::loopback_with_capacity(1);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        block_on(channel.send(1));
+        let mut send = pin!(channel.send(2));
+        assert_eq!(send.as_mut().poll(&mut cx), Poll::Pending);
+
+        assert_eq!(channel.try_receive(), Ok(1));
+
+        assert_eq!(send.poll(&mut cx), Poll::Ready(()));
+        assert_eq!(channel.try_receive(), Ok(2));
+        assert_eq!(channel.sent(), [1, 2]);
+    }
+
+    #[test]
+    fn loopback_producer_and_consumer_across_threads() {
+        let channel = MockChannel::loopback_with_capacity(2);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for message in 0..100 {
+                    block_on(channel.sender().send(message));
+                }
+            });
+            for expected in 0..100 {
+                assert_eq!(block_on(channel.receiver().receive()), expected);
+            }
+        });
+
+        channel.done().unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 2 more message(s) to be received")]
+    fn unreceived_loopback_messages_just_drop() {
+        let channel = MockChannel::loopback();
+        channel.try_send(1).unwrap();
+        channel.try_send(2).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "the test failed first")]
+    fn unreceived_messages_are_not_checked_while_panicking() {
+        let channel = MockChannel::loopback();
+        channel.try_send(1).unwrap();
+
+        panic!("the test failed first");
+    }
+
+    #[test]
+    fn unused_channel_expected_never() {
+        let channel = MockChannel::<u32>::expect_never();
+
+        assert_eq!(channel.done(), Ok(()));
+    }
+
+    #[test]
+    fn receive_is_a_use_even_if_never_polled() {
+        let receiver = MockReceiver::<u32>::expect_never();
+
+        drop(receiver.receive());
+
+        let expected = Err(MockChannelError::UnexpectedUse {
+            method: "Receiver::receive()",
+        });
+        assert_eq!(receiver.done(), expected);
+    }
+
+    #[test]
+    fn first_use_is_reported() {
+        let channel = MockChannel::expect_never();
+
+        block_on(forward_halves(channel.receiver(), channel.sender(), 0));
+        channel.set_full(true);
+        assert!(channel.try_send(1).is_err());
+        let _ = channel.try_receive();
+
+        let expected = Err(MockChannelError::UnexpectedUse {
+            method: "Sender::try_send()",
+        });
+        assert_eq!(channel.done(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to never be used, actually called Sender::send()")]
+    fn used_sender_expected_never_just_drop() {
+        let sender = MockSender::expect_never();
+        block_on(sender.send(1));
+    }
+
+    #[test]
+    fn sends_within_capacity_are_accepted() {
+        let sender = MockSender::new().expect_capacity(2);
+
+        sender.try_send(1).unwrap();
+        sender.try_send(2).unwrap();
+        sender.mark_received(1);
+        sender.try_send(3).unwrap();
+
+        sender.done().unwrap();
+    }
+
+    #[test]
+    fn overflowing_send_is_still_recorded() {
+        let sender = MockSender::new().expect_capacity(1);
+
+        block_on(sender.send(1));
+        block_on(sender.send(2));
+        sender.mark_received(2);
+        block_on(sender.send(3));
+        block_on(sender.send(4));
+
+        assert_eq!(sender.sent(), [1, 2, 3, 4]);
+        let expected = Err(MockChannelError::CapacityExceeded {
+            capacity: 1,
+            index: 1,
+        });
+        assert_eq!(sender.done(), expected);
+    }
+
+    #[test]
+    fn marking_more_than_sent_does_not_make_room() {
+        let sender = MockSender::new().expect_capacity(1);
+        sender.mark_received(5);
+
+        sender.try_send(1).unwrap();
+        sender.try_send(2).unwrap();
+
+        let expected = Err(MockChannelError::CapacityExceeded {
+            capacity: 1,
+            index: 1,
+        });
+        assert_eq!(sender.done(), expected);
+    }
+
+    #[test]
+    fn loopback_receives_drain_the_capacity() {
+        let channel = MockChannel::loopback().expect_capacity(1);
+        channel.try_send(1).unwrap();
+        assert_eq!(channel.try_receive(), Ok(1));
+        channel.try_send(2).unwrap();
+        assert_eq!(channel.try_receive(), Ok(2));
+
+        channel.try_send(3).unwrap();
+        channel.try_send(4).unwrap();
+
+        assert_eq!(channel.try_receive(), Ok(3));
+        assert_eq!(channel.try_receive(), Ok(4));
+        let expected = Err(MockChannelError::CapacityExceeded {
+            capacity: 1,
+            index: 3,
+        });
+        assert_eq!(channel.done(), expected);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected at most 0 unreceived message(s), actually sent message 0 when full"
+    )]
+    fn exceeded_capacity_just_drop() {
+        let sender = MockSender::new().expect_capacity(0);
+        let _ = sender.try_send(1);
+    }
+
+    #[test]
+    fn freed_capacity_lets_sends_through_then_fills() {
+        let channel = MockChannel::<u32>::new();
+        channel.set_full(true);
+
+        channel.free_capacity(2);
+
+        channel.try_send(1).unwrap();
+        channel.try_send(2).unwrap();
+        assert_eq!(channel.try_send(3), Err(TrySendError::Full(3)));
+        assert_eq!(channel.sent(), [1, 2]);
+    }
+
+    #[test]
+    fn freed_capacity_wakes_every_waiting_send() {
+        let sender = MockSender::new();
+        sender.set_full(true);
+        let (mut first, mut second) = (
+            WakeProbe::new(sender.send(1)),
+            WakeProbe::new(sender.send(2)),
+        );
+        first.assert_pending();
+        second.assert_pending();
+
+        sender.free_capacity(1);
+
+        first.assert_ready();
+        second.assert_woken();
+        assert!(second.poll().is_pending());
+        sender.set_full(false);
+        second.assert_ready();
+        drop((first, second));
+        assert_eq!(sender.sent(), [1, 2]);
+    }
+
+    #[test]
+    fn set_full_forgets_freed_capacity() {
+        let sender = MockSender::new();
+        sender.set_full(true);
+        sender.free_capacity(1);
+
+        sender.set_full(true);
+
+        assert_eq!(sender.try_send(1), Err(TrySendError::Full(1)));
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn arrival_waits_for_clock() {
+        let clock = MockClock::new();
+        let receiver = MockReceiver::new();
+        receiver.push_received_at(MockInstant::from_millis(5), 1);
+
+        assert_eq!(receiver.try_receive(), Err(TryReceiveError::Empty));
+        clock.advance(Duration::from_millis(5));
+
+        assert_eq!(receiver.try_receive(), Ok(1));
+        receiver.done().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn arrival_wakes_waiting_receive() {
+        let clock = MockClock::new();
+        let receiver = MockReceiver::new();
+        let mut probe = WakeProbe::new(receiver.receive());
+        probe.assert_pending();
+
+        receiver.push_received_at(MockInstant::from_millis(5), 1);
+        probe.assert_woken();
+        probe.assert_pending();
+        assert!(!probe.is_woken());
+
+        clock.advance(Duration::from_millis(5));
+        assert_eq!(probe.assert_ready(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn arrivals_are_received_in_time_order_after_scripted_messages() {
+        let clock = MockClock::new();
+        let channel = MockChannel::with_received([1]);
+        channel.push_received_at(MockInstant::from_millis(20), 3);
+        channel.push_received_at(MockInstant::from_millis(10), 2);
+
+        let received = clock.block_on(async {
+            [
+                channel.receive().await,
+                channel.receive().await,
+                channel.receive().await,
+            ]
+        });
+
+        assert_eq!(received, [1, 2, 3]);
+        assert_eq!(clock.now(), MockInstant::from_millis(20));
+        channel.done().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn arrivals_are_immediate_without_clock() {
+        let receiver = MockReceiver::new();
+        receiver.push_received_at(MockInstant::from_secs(60), 1);
+
+        assert_eq!(receiver.try_receive(), Ok(1));
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    #[should_panic(expected = "expected 1 more message(s) to be received")]
+    fn unreceived_arrival_just_drop() {
+        let _clock = MockClock::new();
+        let receiver = MockReceiver::new();
+        receiver.push_received_at(MockInstant::from_millis(1), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    fn stream_is_woken_by_pushed_message() {
+        let receiver = MockReceiver::with_received([1]);
+        let mut stream = &receiver;
+        assert_eq!(block_on(stream.next()), Some(1));
+
+        let mut probe = WakeProbe::new(stream.next());
+        probe.assert_pending();
+        receiver.push_received(2);
+
+        assert_eq!(probe.assert_ready(), Some(2));
+    }
+
+    #[test]
+    #[cfg(feature = "stream")]
+    fn polling_stream_is_a_use() {
+        let receiver = MockReceiver::<u8>::expect_never();
+        let mut stream = &receiver;
+
+        assert!(WakeProbe::new(stream.next()).poll().is_pending());
+
+        assert_eq!(
+            receiver.done(),
+            Err(MockChannelError::UnexpectedUse {
+                method: "Stream::poll_next()"
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "sink")]
+    fn sink_is_ready_once_not_full() {
+        let sender = MockSender::new();
+        sender.set_full(true);
+        let mut sink = &sender;
+        let mut probe = WakeProbe::new(SinkExt::send(&mut sink, 1));
+
+        probe.assert_pending();
+        sender.set_full(false);
+
+        assert_eq!(probe.assert_ready(), Ok(()));
+        drop(probe);
+        assert_eq!(sender.sent(), [1]);
+    }
+
+    #[test]
+    #[cfg(feature = "sink")]
+    fn sink_waits_for_loopback_receive() {
+        let channel = MockChannel::loopback_with_capacity(1);
+        let mut sink = channel.sender();
+        block_on(SinkExt::send(&mut sink, 1)).unwrap();
+        let mut probe = WakeProbe::new(sink.feed(2));
+        probe.assert_pending();
+
+        assert_eq!(channel.try_receive(), Ok(1));
+
+        assert_eq!(probe.assert_ready(), Ok(()));
+        drop(probe);
+        assert_eq!(channel.try_receive(), Ok(2));
+        channel.done().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "sink")]
+    fn start_send_while_full_gives_message_back() {
+        let sender = MockSender::new();
+        sender.set_full(true);
+
+        assert_eq!(
+            Pin::new(&mut &sender).start_send(1),
+            Err(TrySendError::Full(1))
+        );
+        assert!(sender.sent().is_empty());
+    }
+
+    async fn send_while_room(output: &(impl Sender<u32> + ChannelLen), limit: usize) -> u32 {
+        let mut count = 0;
+        while output.len() < limit && !output.is_full() {
+            output.send(count).await;
+            count += 1;
+        }
+        count
+    }
+
+    #[test]
+    fn embassy_len_is_shared_by_halves() {
+        let channel = EmbassyChannel::<NoopRawMutex, u32, 3>::new();
+
+        assert_eq!(block_on(send_while_room(&channel.sender(), 2)), 2);
+        assert_eq!(ChannelLen::len(&channel.receiver()), 2);
+        assert_eq!(block_on(send_while_room(&channel, 5)), 1);
+
+        assert!(ChannelLen::is_full(&channel));
+        assert!(!ChannelLen::is_empty(&channel.receiver()));
+    }
+
+    #[test]
+    fn mock_sender_len_counts_unreceived() {
+        let sender = MockSender::new();
+
+        assert_eq!(block_on(send_while_room(&sender, 3)), 3);
+        sender.mark_received(2);
+
+        assert_eq!(sender.len(), 1);
+        assert_eq!(block_on(send_while_room(&sender, 3)), 2);
+        assert_eq!(sender.sent(), [0, 1, 2, 0, 1]);
+    }
+
+    #[test]
+    fn mock_sender_scripted_len_overrides() {
+        let sender = MockSender::new();
+        sender.set_len(Some(3));
+
+        assert_eq!(block_on(send_while_room(&sender, 3)), 0);
+        assert!(!sender.is_empty());
+    }
+
+    #[test]
+    fn mock_sender_is_full_at_expected_capacity() {
+        let sender = MockSender::new().expect_capacity(2);
+
+        assert_eq!(block_on(send_while_room(&sender, 5)), 2);
+        assert!(sender.is_full());
+        sender.done().unwrap();
+    }
+
+    #[test]
+    fn mock_sender_is_full_while_set_full() {
+        let sender = MockSender::<u32>::new();
+        sender.set_full(true);
+        assert!(sender.is_full());
+
+        sender.free_capacity(1);
+        assert!(!sender.is_full());
+    }
+
+    #[test]
+    fn mock_loopback_len_is_shared_by_halves() {
+        let channel = MockChannel::loopback_with_capacity(2);
+        channel.push_received(9);
+
+        assert_eq!(block_on(send_while_room(channel.sender(), 5)), 2);
+        assert_eq!(channel.sender().len(), 2);
+        assert_eq!(channel.receiver().len(), 3);
+        assert!(channel.receiver().is_full());
+
+        assert_eq!(channel.try_receive(), Ok(9));
+        assert_eq!(channel.try_receive(), Ok(0));
+        assert_eq!(channel.len(), 1);
+        assert!(!channel.is_full());
+        assert_eq!(channel.try_receive(), Ok(1));
+        assert!(channel.is_empty());
+        channel.done().unwrap();
+    }
+
+    #[test]
+    fn mock_receiver_len_skips_scripted_pending() {
+        let receiver = MockReceiver::script([Poll::Ready(1), Poll::Pending, Poll::Ready(2)]);
+
+        assert_eq!(receiver.len(), 2);
+        assert_eq!(receiver.try_receive(), Ok(1));
+        assert_eq!(receiver.len(), 1);
+
+        receiver.set_full(true);
+        assert!(receiver.is_full());
+        block_on(receiver.receive());
+    }
+
+    #[test]
+    fn mock_channel_len_ignores_recorded_sends() {
+        let channel = MockChannel::with_received([1]);
+        channel.try_send(2).unwrap();
+
+        assert_eq!(channel.len(), 1);
+        channel.set_len(Some(4));
+        assert_eq!(channel.len(), 4);
+        channel.set_full(true);
+        assert!(channel.is_full());
+
+        assert_eq!(channel.try_receive(), Ok(1));
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn mock_receiver_len_counts_arrived_messages() {
+        let clock = MockClock::new();
+        let receiver = MockReceiver::new();
+        receiver.push_received_at(MockInstant::from_millis(10), 1);
+        receiver.push_received_at(MockInstant::from_millis(20), 2);
+
+        assert_eq!(receiver.len(), 0);
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(receiver.len(), 1);
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(receiver.len(), 2);
+
+        assert_eq!(receiver.try_receive(), Ok(1));
+        assert_eq!(receiver.try_receive(), Ok(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 1 more message(s) to be received")]
+    fn unreceived_messages_just_drop() {
+        let channel = MockChannel::with_received([1, 2]);
+        block_on(channel.receive());
+    }
+}
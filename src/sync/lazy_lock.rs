@@ -0,0 +1,304 @@
+//! Traits and mocked types to allow unit testing functions that use an
+//! [`embassy_sync::lazy_lock::LazyLock`].
+//!
+//! # Examples
+//! ```
+//! use embassy_mock::sync::LazyLock;
+//!
+//! // Generic over the `LazyLock` trait
+//! fn scaled_reading(calibration: &impl LazyLock<u32>, raw: u32) -> u32 {
+//!     raw * calibration.get()
+//! }
+//!
+//! # test_scaled_reading();
+//! // The unit tests that use the `MockLazyLock`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_mock::sync::MockLazyLock;
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_scaled_reading() {
+//!         // Substitute a canned value so the real calibration never runs.
+//!         let calibration = MockLazyLock::with_value(3);
+//!
+//!         assert_eq!(scaled_reading(&calibration, 2), 6);
+//!         assert_eq!(scaled_reading(&calibration, 5), 15);
+//!         assert_eq!(calibration.get_count(), 2);
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use embassy_sync::lazy_lock::LazyLock as EmbassyLazyLock;
+#[cfg(feature = "std")]
+use {
+    core::{
+        fmt,
+        sync::atomic::{AtomicUsize, Ordering},
+    },
+    snafu::prelude::*,
+    std::sync::{self, PoisonError},
+};
+
+/// The trait to replace the [`embassy_sync::lazy_lock::LazyLock`] in code to allow the
+/// [`MockLazyLock`] to be used in its place for tests.
+pub trait LazyLock<T> {
+    /// Wrapper for [`embassy_sync::lazy_lock::LazyLock::get()`].
+    fn get(&self) -> &T;
+}
+
+impl<T, F: FnOnce() -> T> LazyLock<T> for EmbassyLazyLock<T, F> {
+    /// Get a reference to the underlying value, initializing it if it has not been done already.
+    fn get(&self) -> &T {
+        self.get()
+    }
+}
+
+impl<T, L: LazyLock<T> + ?Sized> LazyLock<T> for &L {
+    /// Call [`LazyLock::get()`] of the referenced lock.
+    fn get(&self) -> &T {
+        (**self).get()
+    }
+}
+
+/// The errors that are reported by [`MockLazyLock`].
+#[cfg(feature = "std")]
+#[derive(Debug, Snafu, PartialEq)]
+pub enum MockLazyLockError {
+    /// The initializer did not run the expected number of times.
+    #[snafu(display("expected the initializer to run {expected} time(s), actually ran {actual}"))]
+    WrongNumberOfInits {
+        /// The number of times the initializer was expected to run.
+        expected: usize,
+
+        /// The number of times the initializer ran.
+        actual: usize,
+    },
+}
+
+/// A mocked version of [`embassy_sync::lazy_lock::LazyLock`] that can be used in its place for
+/// unit tests.
+///
+/// Created with [`Self::new()`] it runs the initializer on the first [`LazyLock::get()`] as a real
+/// `LazyLock`, created with [`Self::with_value()`] it returns a canned value and never runs an
+/// initializer. Either way it counts the runs of the initializer and the calls to
+/// [`LazyLock::get()`].
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if the initializer did not run the
+/// number of times given to [`Self::expect_inits()`].
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::sync::{LazyLock, MockLazyLock};
+///
+/// let table = MockLazyLock::new(|| [1, 2, 4, 8]).expect_inits(1);
+///
+/// assert_eq!(table.init_count(), 0);
+/// assert_eq!(table.get()[2], 4);
+/// assert_eq!(table.get()[3], 8);
+/// assert_eq!(table.init_count(), 1);
+/// ```
+#[cfg(feature = "std")]
+pub struct MockLazyLock<T, F = fn() -> T> {
+    /// The value once it is initialized.
+    value: sync::OnceLock<T>,
+
+    /// The initializer until it is run.
+    init_fn: sync::Mutex<Option<F>>,
+
+    /// The number of times the initializer ran.
+    init_count: AtomicUsize,
+
+    /// The number of calls to [`LazyLock::get()`].
+    get_count: AtomicUsize,
+
+    /// The number of times the initializer is expected to run, if checked.
+    expected: Option<usize>,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug, F> fmt::Debug for MockLazyLock<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockLazyLock")
+            .field("value", &self.value)
+            .field("init_count", &self.init_count)
+            .field("get_count", &self.get_count)
+            .field("expected", &self.expected)
+            .field("is_done", &self.is_done)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> MockLazyLock<T> {
+    /// Create a [`MockLazyLock`] that returns the `value` without running an initializer.
+    pub fn with_value(value: T) -> Self {
+        Self {
+            value: sync::OnceLock::from(value),
+            init_fn: sync::Mutex::new(None),
+            init_count: AtomicUsize::new(0),
+            get_count: AtomicUsize::new(0),
+            expected: None,
+            is_done: false,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, F: FnOnce() -> T> MockLazyLock<T, F> {
+    /// Create a [`MockLazyLock`] that runs `init_fn` on the first call to [`LazyLock::get()`].
+    pub const fn new(init_fn: F) -> Self {
+        Self {
+            value: sync::OnceLock::new(),
+            init_fn: sync::Mutex::new(Some(init_fn)),
+            init_count: AtomicUsize::new(0),
+            get_count: AtomicUsize::new(0),
+            expected: None,
+            is_done: false,
+        }
+    }
+
+    /// Expect the initializer to run `expected` times, which is either 0 or 1.
+    pub const fn expect_inits(mut self, expected: usize) -> Self {
+        self.expected = Some(expected);
+        self
+    }
+
+    /// The number of times the initializer ran so far.
+    pub fn init_count(&self) -> usize {
+        self.init_count.load(Ordering::Relaxed)
+    }
+
+    /// The number of calls to [`LazyLock::get()`] so far.
+    pub fn get_count(&self) -> usize {
+        self.get_count.load(Ordering::Relaxed)
+    }
+
+    /// Check that the initializer ran the number of times given to [`Self::expect_inits()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockLazyLock, MockLazyLockError};
+    ///
+    /// let config = MockLazyLock::new(|| 9600).expect_inits(1);
+    ///
+    /// let expected = Err(MockLazyLockError::WrongNumberOfInits { expected: 1, actual: 0 });
+    /// assert_eq!(config.done(), expected);
+    ///
+    /// // This doesn't panic when `config` is dropped as `config.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockLazyLockError> {
+        self.is_done = true;
+        self.check()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, F> MockLazyLock<T, F> {
+    /// Check that the initializer ran the expected number of times.
+    fn check(&self) -> Result<(), MockLazyLockError> {
+        if let Some(expected) = self.expected {
+            let actual = self.init_count.load(Ordering::Relaxed);
+            ensure!(
+                actual == expected,
+                WrongNumberOfInitsSnafu { expected, actual }
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, F> Drop for MockLazyLock<T, F> {
+    /// If [`Self::done()`] has not been called before being dropped then check that the
+    /// initializer ran the expected number of times.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, F: FnOnce() -> T> LazyLock<T> for MockLazyLock<T, F> {
+    /// Get a reference to the value, running the initializer if it has not been run already.
+    fn get(&self) -> &T {
+        self.get_count.fetch_add(1, Ordering::Relaxed);
+        self.value.get_or_init(|| {
+            let init_fn = self
+                .init_fn
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take()
+                .expect("the initializer only runs once");
+            self.init_count.fetch_add(1, Ordering::Relaxed);
+            init_fn()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sum_twice(lock: &impl LazyLock<u32>) -> u32 {
+        lock.get() + lock.get()
+    }
+
+    #[test]
+    fn embassy_lazy_lock() {
+        let lock = EmbassyLazyLock::new(|| 4);
+
+        assert_eq!(sum_twice(&lock), 8);
+    }
+
+    #[test]
+    fn initializer_runs_once() {
+        let lock = MockLazyLock::new(|| 2).expect_inits(1);
+
+        assert_eq!(sum_twice(&lock), 4);
+        assert_eq!(sum_twice(&lock), 4);
+        assert_eq!(lock.init_count(), 1);
+        assert_eq!(lock.get_count(), 4);
+        lock.done().unwrap();
+    }
+
+    #[test]
+    fn canned_value_never_runs_initializer() {
+        let lock = MockLazyLock::with_value(5).expect_inits(0);
+
+        assert_eq!(sum_twice(&lock), 10);
+        assert_eq!(lock.init_count(), 0);
+        lock.done().unwrap();
+    }
+
+    #[test]
+    fn unused_initializer_is_not_run() {
+        let lock = MockLazyLock::new(|| -> u32 { panic!("initializer ran") }).expect_inits(0);
+
+        assert_eq!(lock.get_count(), 0);
+        lock.done().unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "expected the initializer to run 1 time(s), actually ran 0")]
+    fn wrong_number_of_inits_just_drop() {
+        let _lock = MockLazyLock::new(|| 1).expect_inits(1);
+    }
+}
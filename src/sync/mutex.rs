@@ -0,0 +1,770 @@
+//! Traits and mocked types to allow unit testing functions that use an
+//! [`embassy_sync::mutex::Mutex`].
+//!
+//! # Examples
+//! ```
+//! use embassy_mock::sync::Mutex;
+//!
+//! // Generic over the `Mutex` trait
+//! async fn add_readings<M: Mutex<Vec<u16>>>(log: &M, readings: &[u16]) {
+//!     for &reading in readings {
+//!         log.lock().await.push(reading);
+//!     }
+//! }
+//!
+//! # test_add_readings();
+//! // The unit tests that use the `MockMutex`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_futures::block_on;
+//!     use embassy_mock::sync::MockMutex;
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_add_readings() {
+//!         let mut log = MockMutex::new(Vec::new()).expect_locks(2);
+//!
+//!         block_on(add_readings(&log, &[3, 5]));
+//!
+//!         // `get_mut()` doesn't lock the mutex.
+//!         assert_eq!(*log.get_mut(), [3, 5]);
+//!         assert_eq!(log.done(), Ok(()));
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use core::{future::Future, ops::DerefMut};
+use embassy_sync::{
+    blocking_mutex::raw::RawMutex,
+    mutex::{Mutex as EmbassyMutex, MutexGuard, TryLockError},
+};
+#[cfg(feature = "std")]
+use {
+    crate::waiting,
+    core::{
+        fmt,
+        ops::Deref,
+        pin::Pin,
+        sync::atomic::{AtomicUsize, Ordering},
+        task::{Context, Poll, Waker},
+    },
+    snafu::prelude::*,
+    std::{
+        sync::{self, PoisonError},
+        vec::Vec,
+    },
+};
+
+/// The trait to replace the [`embassy_sync::mutex::Mutex`] in code to allow the [`MockMutex`] to
+/// be used in its place for tests.
+pub trait Mutex<T: ?Sized> {
+    /// The guard that gives access to the value while the mutex is locked.
+    ///
+    /// Code that returns the guard, or keeps it in a struct, names it by this type so that it
+    /// works with both the Embassy [`MutexGuard`] and the `MockMutexGuard`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::sync::{MockMutex, Mutex};
+    ///
+    /// // Lock the cleared buffer for the caller to fill.
+    /// async fn claim<M: Mutex<[u8; 4]>>(buffer: &M) -> M::Guard<'_> {
+    ///     let mut guard = buffer.lock().await;
+    ///     guard.fill(0);
+    ///     guard
+    /// }
+    ///
+    /// let buffer = MockMutex::new([1; 4]).expect_locks(2);
+    ///
+    /// let mut guard = block_on(claim(&buffer));
+    /// guard[0] = 9;
+    /// drop(guard);
+    ///
+    /// assert_eq!(*buffer.try_lock().unwrap(), [9, 0, 0, 0]);
+    /// buffer.done().unwrap();
+    /// ```
+    type Guard<'a>: DerefMut<Target = T>
+    where
+        Self: 'a;
+
+    /// Wrapper for [`embassy_sync::mutex::Mutex::lock()`].
+    fn lock(&self) -> impl Future<Output = Self::Guard<'_>>;
+
+    /// Wrapper for [`embassy_sync::mutex::Mutex::try_lock()`].
+    fn try_lock(&self) -> Result<Self::Guard<'_>, TryLockError>;
+
+    /// Wrapper for [`embassy_sync::mutex::Mutex::get_mut()`].
+    fn get_mut(&mut self) -> &mut T;
+}
+
+impl<M: RawMutex, T: ?Sized> Mutex<T> for EmbassyMutex<M, T> {
+    type Guard<'a> = MutexGuard<'a, M, T> where Self: 'a;
+
+    /// Lock the mutex.
+    ///
+    /// This will wait for the mutex to be unlocked if it's already locked.
+    fn lock(&self) -> impl Future<Output = Self::Guard<'_>> {
+        self.lock()
+    }
+
+    /// Attempt to immediately lock the mutex.
+    ///
+    /// If the mutex is already locked, this will return an error instead of waiting.
+    fn try_lock(&self) -> Result<Self::Guard<'_>, TryLockError> {
+        self.try_lock()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the Mutex mutably, no actual locking needs to take place -- the
+    /// mutable borrow statically guarantees no locks exist.
+    fn get_mut(&mut self) -> &mut T {
+        self.get_mut()
+    }
+}
+
+/// The errors that are reported by [`MockMutex`].
+#[cfg(feature = "std")]
+#[derive(Debug, Snafu, PartialEq)]
+pub enum MockMutexError {
+    /// The mutex was not locked the expected number of times.
+    #[snafu(display("expected to lock {expected} time(s), actually locked {actual}"))]
+    WrongNumberOfLocks {
+        /// The number of locks that were expected.
+        expected: usize,
+
+        /// The number of times the mutex was locked.
+        actual: usize,
+    },
+
+    /// A mock that was created to never be used was used.
+    #[snafu(display("expected to never be used, actually called {method}"))]
+    UnexpectedUse {
+        /// The first method that was called.
+        method: &'static str,
+    },
+}
+
+/// A mocked version of [`embassy_sync::mutex::Mutex`] that can be used in its place for unit
+/// tests.
+///
+/// It locks as a real mutex, so [`Mutex::lock()`] waits while the mutex is locked, and it counts
+/// every time it is locked by [`Mutex::lock()`] or [`Mutex::try_lock()`], which is each critical
+/// section of the code under test.
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if the mutex was not locked the
+/// number of times given to [`Self::expect_locks()`], or if it was used after being created by
+/// [`Self::expect_never()`].
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::sync::{MockMutex, Mutex};
+///
+/// let counter = MockMutex::new(0).expect_locks(2);
+///
+/// *block_on(counter.lock()) += 1;
+/// *counter.try_lock().unwrap() += 1;
+///
+/// assert_eq!(counter.lock_count(), 2);
+/// counter.done().unwrap();
+/// ```
+#[cfg(feature = "std")]
+pub struct MockMutex<T> {
+    /// The value, which is locked by the guards.
+    value: sync::Mutex<T>,
+
+    /// The wakers of the locks that are waiting for the mutex to be unlocked.
+    wakers: sync::Mutex<Vec<Waker>>,
+
+    /// The number of times the mutex has been locked.
+    lock_count: AtomicUsize,
+
+    /// The number of times the mutex is expected to be locked, if checked.
+    expected: Option<usize>,
+
+    /// The number of polls each [`Mutex::lock()`] returns [`Poll::Pending`] for before trying to
+    /// lock the mutex.
+    pending_polls: usize,
+
+    /// The number of times [`Mutex::try_lock()`] has been called.
+    try_lock_calls: AtomicUsize,
+
+    /// The zero-based indexes of the calls to [`Mutex::try_lock()`] that fail.
+    try_lock_failures: Vec<usize>,
+
+    /// Does every call to [`Mutex::try_lock()`] fail.
+    fail_all_try_locks: bool,
+
+    /// The first method of [`Mutex`] that was called, if any.
+    first_use: sync::Mutex<Option<&'static str>>,
+
+    /// Is this mock expected to never be used, see [`Self::expect_never()`].
+    never_used: bool,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug> fmt::Debug for MockMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockMutex")
+            .field("value", &self.value)
+            .field("lock_count", &self.lock_count)
+            .field("expected", &self.expected)
+            .field("pending_polls", &self.pending_polls)
+            .field("try_lock_calls", &self.try_lock_calls)
+            .field("try_lock_failures", &self.try_lock_failures)
+            .field("fail_all_try_locks", &self.fail_all_try_locks)
+            .field("first_use", &self.first_use)
+            .field("never_used", &self.never_used)
+            .field("is_done", &self.is_done)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> MockMutex<T> {
+    /// Create an unlocked [`MockMutex`] holding the `value`, without checking how many times it
+    /// is locked.
+    pub const fn new(value: T) -> Self {
+        Self {
+            value: sync::Mutex::new(value),
+            wakers: sync::Mutex::new(Vec::new()),
+            lock_count: AtomicUsize::new(0),
+            expected: None,
+            pending_polls: 0,
+            try_lock_calls: AtomicUsize::new(0),
+            try_lock_failures: Vec::new(),
+            fail_all_try_locks: false,
+            first_use: sync::Mutex::new(None),
+            never_used: false,
+            is_done: false,
+        }
+    }
+
+    /// Create a [`MockMutex`] holding the `value` that is expected to never be used, to check
+    /// that the code under test doesn't lock it, not even with a call that fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockMutex, MockMutexError, Mutex};
+    ///
+    /// let bus = MockMutex::expect_never(0).fail_all_try_locks();
+    /// assert!(bus.try_lock().is_err());
+    ///
+    /// let expected = Err(MockMutexError::UnexpectedUse { method: "Mutex::try_lock()" });
+    /// assert_eq!(bus.done(), expected);
+    /// ```
+    pub const fn expect_never(value: T) -> Self {
+        let mut mutex = Self::new(value);
+        mutex.never_used = true;
+        mutex
+    }
+
+    /// Expect the mutex to be locked `expected` times.
+    pub const fn expect_locks(mut self, expected: usize) -> Self {
+        self.expected = Some(expected);
+        self
+    }
+
+    /// Make each [`Mutex::lock()`] return [`Poll::Pending`] for `polls` polls before trying to
+    /// lock the mutex, as if another task was holding it.
+    ///
+    /// The waker is woken each time so the executor polls it again. This can be used to check
+    /// that code doesn't deadlock or starve while it waits for the lock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::{future::Future, pin::pin, task::{Context, Poll}};
+    /// use embassy_mock::sync::{MockMutex, Mutex};
+    /// use futures_util::task::noop_waker_ref;
+    ///
+    /// let counter = MockMutex::new(0).with_pending_polls(2);
+    /// let mut cx = Context::from_waker(noop_waker_ref());
+    /// let mut lock = pin!(counter.lock());
+    ///
+    /// assert!(lock.as_mut().poll(&mut cx).is_pending());
+    /// assert!(lock.as_mut().poll(&mut cx).is_pending());
+    /// assert!(lock.as_mut().poll(&mut cx).is_ready());
+    /// ```
+    pub const fn with_pending_polls(mut self, polls: usize) -> Self {
+        self.pending_polls = polls;
+        self
+    }
+
+    /// Make the call to [`Mutex::try_lock()`] at the zero-based `index` fail with
+    /// [`TryLockError`], as if the mutex was locked.
+    ///
+    /// This can be called several times to make several calls fail. The failed calls don't lock
+    /// the mutex so they don't count towards the expected number of locks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockMutex, Mutex};
+    ///
+    /// // Skip this cycle if the bus is busy.
+    /// fn poll_sensor(bus: &impl Mutex<u32>) -> bool {
+    ///     match bus.try_lock() {
+    ///         Ok(mut transfers) => {
+    ///             *transfers += 1;
+    ///             true
+    ///         }
+    ///         Err(_) => false,
+    ///     }
+    /// }
+    ///
+    /// let bus = MockMutex::new(0).fail_try_lock(1).expect_locks(2);
+    ///
+    /// assert!(poll_sensor(&bus));
+    /// assert!(!poll_sensor(&bus));
+    /// assert!(poll_sensor(&bus));
+    ///
+    /// bus.done().unwrap();
+    /// ```
+    pub fn fail_try_lock(mut self, index: usize) -> Self {
+        self.try_lock_failures.push(index);
+        self
+    }
+
+    /// Make every call to [`Mutex::try_lock()`] fail with [`TryLockError`], as if the mutex was
+    /// always locked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockMutex, Mutex};
+    ///
+    /// let bus = MockMutex::new(0).fail_all_try_locks();
+    ///
+    /// assert!(bus.try_lock().is_err());
+    /// assert!(bus.try_lock().is_err());
+    /// assert_eq!(bus.lock_count(), 0);
+    /// ```
+    pub const fn fail_all_try_locks(mut self) -> Self {
+        self.fail_all_try_locks = true;
+        self
+    }
+
+    /// The number of times this [`MockMutex`] has been locked by [`Mutex::lock()`] or
+    /// [`Mutex::try_lock()`].
+    pub fn lock_count(&self) -> usize {
+        self.lock_count.load(Ordering::Relaxed)
+    }
+
+    /// Check that the mutex was locked the number of times given to [`Self::expect_locks()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockMutex, MockMutexError, Mutex};
+    ///
+    /// let counter = MockMutex::new(0).expect_locks(2);
+    /// *counter.try_lock().unwrap() += 1;
+    ///
+    /// let expected = Err(MockMutexError::WrongNumberOfLocks { expected: 2, actual: 1 });
+    /// assert_eq!(counter.done(), expected);
+    ///
+    /// // This doesn't panic when `counter` is dropped as `counter.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockMutexError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that the mutex wasn't used if it is expected to never be, and that it was locked the
+    /// expected number of times.
+    fn check(&self) -> Result<(), MockMutexError> {
+        let first_use = *self
+            .first_use
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let (true, Some(method)) = (self.never_used, first_use) {
+            return UnexpectedUseSnafu { method }.fail();
+        }
+
+        if let Some(expected) = self.expected {
+            let actual = self.lock_count();
+            ensure!(
+                actual == expected,
+                WrongNumberOfLocksSnafu { expected, actual }
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Lock the value, counting the lock, unless it is already locked.
+    ///
+    /// The value is still usable after a test panicked while holding the guard.
+    fn lock_value(&self) -> Result<MockMutexGuard<'_, T>, TryLockError> {
+        let guard = match self.value.try_lock() {
+            Ok(guard) => guard,
+            Err(sync::TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(sync::TryLockError::WouldBlock) => return Err(TryLockError),
+        };
+        self.lock_count.fetch_add(1, Ordering::Relaxed);
+
+        Ok(MockMutexGuard {
+            mutex: self,
+            guard: Some(guard),
+        })
+    }
+
+    /// Record that the `method` was called, if it is the first.
+    fn record_use(&self, method: &'static str) {
+        self.first_use
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_or_insert(method);
+    }
+
+    /// Lock the wakers, which are never poisoned as the mock doesn't panic while holding them.
+    fn wakers(&self) -> sync::MutexGuard<'_, Vec<Waker>> {
+        self.wakers.lock().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for MockMutex<T> {
+    /// If [`Self::done()`] has not been called before being dropped then check that the mutex was
+    /// locked the expected number of times.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Mutex<T> for MockMutex<T> {
+    type Guard<'a> = MockMutexGuard<'a, T> where Self: 'a;
+
+    /// Lock the mutex, waiting while it is locked.
+    fn lock(&self) -> impl Future<Output = Self::Guard<'_>> {
+        self.record_use("Mutex::lock()");
+        MockLockFuture {
+            mutex: self,
+            pending_polls: self.pending_polls,
+        }
+    }
+
+    /// Lock the mutex, unless it is already locked or the call is set to fail.
+    fn try_lock(&self) -> Result<Self::Guard<'_>, TryLockError> {
+        self.record_use("Mutex::try_lock()");
+        let index = self.try_lock_calls.fetch_add(1, Ordering::Relaxed);
+        if self.fail_all_try_locks || self.try_lock_failures.contains(&index) {
+            return Err(TryLockError);
+        }
+
+        self.lock_value()
+    }
+
+    /// The value, without locking the mutex.
+    fn get_mut(&mut self) -> &mut T {
+        self.first_use
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_or_insert("Mutex::get_mut()");
+        self.value.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The guard returned by locking a [`MockMutex`], which gives access to the value until it is
+/// dropped.
+#[cfg(feature = "std")]
+pub struct MockMutexGuard<'a, T> {
+    /// The mutex that is locked, to wake the waiting locks when unlocked.
+    mutex: &'a MockMutex<T>,
+
+    /// The guard of the value, until it is dropped.
+    guard: Option<sync::MutexGuard<'a, T>>,
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug> fmt::Debug for MockMutexGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Deref for MockMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.guard.as_ref().expect("the guard is only dropped once")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> DerefMut for MockMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.guard.as_mut().expect("the guard is only dropped once")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for MockMutexGuard<'_, T> {
+    /// Unlock the mutex and wake the locks that are waiting for it.
+    fn drop(&mut self) {
+        drop(self.guard.take());
+        for waker in self.mutex.wakers().drain(..) {
+            waiting::waking("MockMutexGuard::drop()", || waker.wake());
+        }
+    }
+}
+
+/// The future returned by [`Mutex::lock()`] for a [`MockMutex`].
+#[cfg(feature = "std")]
+struct MockLockFuture<'a, T> {
+    /// The mutex that is locked.
+    mutex: &'a MockMutex<T>,
+
+    /// The number of polls left that return [`Poll::Pending`] before trying to lock the mutex.
+    pending_polls: usize,
+}
+
+#[cfg(feature = "std")]
+impl<'a, T> Future for MockLockFuture<'a, T> {
+    type Output = MockMutexGuard<'a, T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.pending_polls > 0 {
+            self.pending_polls -= 1;
+            waiting::waking("MockMutex::lock()", || cx.waker().wake_by_ref());
+            return Poll::Pending;
+        }
+
+        // Register first so that an unlock between the two can't be missed.
+        self.mutex.wakers().push(cx.waker().clone());
+        match self.mutex.lock_value() {
+            Ok(guard) => Poll::Ready(guard),
+            Err(TryLockError) => {
+                waiting::record("MockMutex::lock()");
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::pin::pin;
+    use embassy_futures::block_on;
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    use futures_util::task::noop_waker_ref;
+
+    async fn increment<M: Mutex<u32>>(counter: &M) {
+        *counter.lock().await += 1;
+    }
+
+    /// Holds the guard of the `mutex` to keep it locked for a whole session.
+    struct Session<'a, M: Mutex<u32> + 'a> {
+        guard: M::Guard<'a>,
+    }
+
+    async fn start_session<M: Mutex<u32>>(mutex: &M) -> Session<'_, M> {
+        let mut guard = mutex.lock().await;
+        *guard += 1;
+        Session { guard }
+    }
+
+    fn guard_is_held_by_session<M: Mutex<u32>>(mutex: &M) {
+        let mut session = block_on(start_session(mutex));
+        assert!(mutex.try_lock().is_err());
+
+        *session.guard += 1;
+        drop(session);
+
+        assert_eq!(*mutex.try_lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn embassy_mutex_locks() {
+        let mut counter = EmbassyMutex::<NoopRawMutex, u32>::new(0);
+
+        block_on(increment(&counter));
+        {
+            let _guard = Mutex::try_lock(&counter).unwrap();
+            assert!(Mutex::try_lock(&counter).is_err());
+        }
+        *Mutex::get_mut(&mut counter) += 1;
+
+        assert_eq!(counter.into_inner(), 2);
+    }
+
+    #[test]
+    fn embassy_guard_is_held_by_session() {
+        guard_is_held_by_session(&EmbassyMutex::<NoopRawMutex, u32>::new(0));
+    }
+
+    #[test]
+    fn mock_guard_is_held_by_session() {
+        let mutex = MockMutex::new(0).expect_locks(2);
+
+        guard_is_held_by_session(&mutex);
+
+        mutex.done().unwrap();
+    }
+
+    #[test]
+    fn mock_counts_locks() {
+        let mut counter = MockMutex::new(0).expect_locks(2);
+
+        block_on(increment(&counter));
+        block_on(increment(&counter));
+        *counter.get_mut() += 1;
+
+        assert_eq!(*counter.try_lock().unwrap(), 3);
+        assert_eq!(counter.lock_count(), 3);
+        assert_eq!(
+            counter.done(),
+            Err(MockMutexError::WrongNumberOfLocks {
+                expected: 2,
+                actual: 3
+            })
+        );
+    }
+
+    #[test]
+    fn try_lock_fails_while_locked() {
+        let counter = MockMutex::new(0);
+
+        let guard = counter.try_lock().unwrap();
+        assert_eq!(counter.try_lock().unwrap_err(), TryLockError);
+        drop(guard);
+
+        assert!(counter.try_lock().is_ok());
+        assert_eq!(counter.lock_count(), 2);
+    }
+
+    #[test]
+    fn lock_waits_until_unlocked() {
+        let counter = MockMutex::new(0);
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        let guard = counter.try_lock().unwrap();
+        let mut lock = pin!(counter.lock());
+        assert!(lock.as_mut().poll(&mut cx).is_pending());
+
+        drop(guard);
+        match lock.poll(&mut cx) {
+            Poll::Ready(guard) => assert_eq!(*guard, 0),
+            Poll::Pending => panic!("the mutex was unlocked"),
+        };
+    }
+
+    #[test]
+    fn pending_polls_apply_to_each_lock() {
+        let counter = MockMutex::new(0).with_pending_polls(1).expect_locks(2);
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        for locks in 0..2 {
+            let mut lock = pin!(counter.lock());
+            assert!(lock.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(counter.lock_count(), locks);
+            assert!(lock.as_mut().poll(&mut cx).is_ready());
+        }
+
+        assert!(counter.try_lock().is_ok());
+        assert_eq!(
+            counter.done(),
+            Err(MockMutexError::WrongNumberOfLocks {
+                expected: 2,
+                actual: 3
+            })
+        );
+    }
+
+    #[test]
+    fn pending_polls_wake_for_block_on() {
+        let counter = MockMutex::new(0).with_pending_polls(3).expect_locks(1);
+
+        block_on(increment(&counter));
+
+        counter.done().unwrap();
+    }
+
+    #[test]
+    fn try_lock_fails_at_each_index() {
+        let counter = MockMutex::new(0).fail_try_lock(0).fail_try_lock(2);
+
+        assert!(counter.try_lock().is_err());
+        assert!(counter.try_lock().is_ok());
+        assert!(counter.try_lock().is_err());
+        assert!(counter.try_lock().is_ok());
+
+        assert_eq!(counter.lock_count(), 2);
+    }
+
+    #[test]
+    fn failing_try_locks_do_not_affect_lock() {
+        let counter = MockMutex::new(0).fail_all_try_locks().expect_locks(1);
+
+        assert!(counter.try_lock().is_err());
+        block_on(increment(&counter));
+
+        counter.done().unwrap();
+    }
+
+    #[test]
+    fn mutex_without_expectation_is_not_checked() {
+        let counter = MockMutex::new(0);
+
+        block_on(increment(&counter));
+
+        assert_eq!(counter.done(), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to lock 1 time(s), actually locked 0")]
+    fn unlocked_mutex_just_drop() {
+        let _counter = MockMutex::new(0).expect_locks(1);
+    }
+
+    #[test]
+    fn unused_mutex_expected_never() {
+        let counter = MockMutex::expect_never(0);
+
+        assert_eq!(counter.lock_count(), 0);
+        counter.done().unwrap();
+    }
+
+    #[test]
+    fn get_mut_is_a_use() {
+        let mut counter = MockMutex::expect_never(0);
+
+        *counter.get_mut() += 1;
+
+        let expected = Err(MockMutexError::UnexpectedUse {
+            method: "Mutex::get_mut()",
+        });
+        assert_eq!(counter.done(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to never be used, actually called Mutex::lock()")]
+    fn used_mutex_expected_never_just_drop() {
+        let counter = MockMutex::expect_never(0);
+        block_on(increment(&counter));
+    }
+}
@@ -0,0 +1,403 @@
+//! Traits and mocked types to allow unit testing functions that use an
+//! [`embassy_sync::once_lock::OnceLock`].
+//!
+//! # Examples
+//! ```
+//! use embassy_mock::sync::OnceLock;
+//!
+//! // Generic over the `OnceLock` trait
+//! fn configure(config: &impl OnceLock<u32>, baud_rate: u32) -> bool {
+//!     config.init(baud_rate).is_ok()
+//! }
+//!
+//! # test_configure();
+//! // The unit tests that use the `MockOnceLock`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_futures::block_on;
+//!     use embassy_mock::sync::MockOnceLock;
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_configure() {
+//!         let config = MockOnceLock::new().expect_inits(1);
+//!
+//!         assert!(configure(&config, 115_200));
+//!
+//!         assert_eq!(block_on(config.get()), &115_200);
+//!         assert_eq!(config.done(), Ok(()));
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use core::future::Future;
+use embassy_sync::once_lock::OnceLock as EmbassyOnceLock;
+#[cfg(feature = "std")]
+use {
+    crate::waiting,
+    core::{
+        future::poll_fn,
+        sync::atomic::{AtomicUsize, Ordering},
+        task::{Poll, Waker},
+    },
+    snafu::prelude::*,
+    std::{sync, vec::Vec},
+};
+
+/// The trait to replace the [`embassy_sync::once_lock::OnceLock`] in code to allow the
+/// [`MockOnceLock`] to be used in its place for tests.
+pub trait OnceLock<T> {
+    /// Wrapper for [`embassy_sync::once_lock::OnceLock::get()`].
+    fn get<'a>(&'a self) -> impl Future<Output = &'a T>
+    where
+        T: 'a;
+
+    /// Wrapper for [`embassy_sync::once_lock::OnceLock::try_get()`].
+    fn try_get(&self) -> Option<&T>;
+
+    /// Wrapper for [`embassy_sync::once_lock::OnceLock::init()`].
+    fn init(&self, value: T) -> Result<(), T>;
+
+    /// Wrapper for [`embassy_sync::once_lock::OnceLock::get_or_init()`].
+    fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> &T;
+
+    /// Wrapper for [`embassy_sync::once_lock::OnceLock::is_set()`].
+    fn is_set(&self) -> bool;
+}
+
+impl<T> OnceLock<T> for EmbassyOnceLock<T> {
+    /// Get a reference to the underlying value, waiting for it to be set.
+    /// If the value is already set, this will return immediately.
+    fn get<'a>(&'a self) -> impl Future<Output = &'a T>
+    where
+        T: 'a,
+    {
+        self.get()
+    }
+
+    /// Try to get a reference to the underlying value if it exists.
+    fn try_get(&self) -> Option<&T> {
+        self.try_get()
+    }
+
+    /// Set the underlying value. If the value is already set, this will return an error with the
+    /// given value.
+    fn init(&self, value: T) -> Result<(), T> {
+        self.init(value)
+    }
+
+    /// Get a reference to the underlying value, initializing it if it does not exist.
+    fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> &T {
+        self.get_or_init(f)
+    }
+
+    /// Check if the value has been set.
+    fn is_set(&self) -> bool {
+        self.is_set()
+    }
+}
+
+impl<T, L: OnceLock<T> + ?Sized> OnceLock<T> for &L {
+    /// Call [`OnceLock::get()`] of the referenced lock.
+    fn get<'a>(&'a self) -> impl Future<Output = &'a T>
+    where
+        T: 'a,
+    {
+        (**self).get()
+    }
+
+    /// Call [`OnceLock::try_get()`] of the referenced lock.
+    fn try_get(&self) -> Option<&T> {
+        (**self).try_get()
+    }
+
+    /// Call [`OnceLock::init()`] of the referenced lock.
+    fn init(&self, value: T) -> Result<(), T> {
+        (**self).init(value)
+    }
+
+    /// Call [`OnceLock::get_or_init()`] of the referenced lock.
+    fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> &T {
+        (**self).get_or_init(f)
+    }
+
+    /// Call [`OnceLock::is_set()`] of the referenced lock.
+    fn is_set(&self) -> bool {
+        (**self).is_set()
+    }
+}
+
+/// The errors that are reported by [`MockOnceLock`].
+#[cfg(feature = "std")]
+#[derive(Debug, Snafu, PartialEq)]
+pub enum MockOnceLockError {
+    /// [`OnceLock::init()`] was not called the expected number of times.
+    #[snafu(display("expected to init {expected} time(s), actually init {actual}"))]
+    WrongNumberOfInits {
+        /// The number of calls to [`OnceLock::init()`] that were expected.
+        expected: usize,
+
+        /// The number of calls to [`OnceLock::init()`].
+        actual: usize,
+    },
+}
+
+/// A mocked version of [`embassy_sync::once_lock::OnceLock`] that can be used in its place for
+/// unit tests.
+///
+/// It is set once as a real `OnceLock`, either by the code under test or beforehand with
+/// [`Self::with_value()`], and it counts every call to [`OnceLock::init()`], including the calls
+/// that fail as the value is already set.
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if [`OnceLock::init()`] was not
+/// called the number of times given to [`Self::expect_inits()`].
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::sync::{MockOnceLock, OnceLock};
+///
+/// let id = MockOnceLock::with_value(7).expect_inits(1);
+///
+/// assert_eq!(id.init(8), Err(8));
+/// assert_eq!(id.get_or_init(|| 9), &7);
+/// assert_eq!(id.init_count(), 1);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockOnceLock<T> {
+    /// The value once it is set.
+    value: sync::OnceLock<T>,
+
+    /// The wakers of the futures waiting for the value to be set.
+    wakers: sync::Mutex<Vec<Waker>>,
+
+    /// The number of calls to [`OnceLock::init()`].
+    init_count: AtomicUsize,
+
+    /// The number of calls to [`OnceLock::init()`] that are expected, if checked.
+    expected: Option<usize>,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<T> MockOnceLock<T> {
+    /// Create a [`MockOnceLock`] that isn't set.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            value: sync::OnceLock::new(),
+            wakers: sync::Mutex::new(Vec::new()),
+            init_count: AtomicUsize::new(0),
+            expected: None,
+            is_done: false,
+        }
+    }
+
+    /// Create a [`MockOnceLock`] that is already set to the `value`.
+    pub fn with_value(value: T) -> Self {
+        Self {
+            value: sync::OnceLock::from(value),
+            wakers: sync::Mutex::new(Vec::new()),
+            init_count: AtomicUsize::new(0),
+            expected: None,
+            is_done: false,
+        }
+    }
+
+    /// Expect [`OnceLock::init()`] to be called `expected` times.
+    pub const fn expect_inits(mut self, expected: usize) -> Self {
+        self.expected = Some(expected);
+        self
+    }
+
+    /// The number of calls to [`OnceLock::init()`] so far, including the calls that failed.
+    pub fn init_count(&self) -> usize {
+        self.init_count.load(Ordering::Relaxed)
+    }
+
+    /// Check that [`OnceLock::init()`] was called the number of times given to
+    /// [`Self::expect_inits()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockOnceLock, MockOnceLockError, OnceLock};
+    ///
+    /// let id = MockOnceLock::new().expect_inits(1);
+    /// id.init(1).unwrap();
+    /// id.init(2).unwrap_err();
+    ///
+    /// let expected = Err(MockOnceLockError::WrongNumberOfInits { expected: 1, actual: 2 });
+    /// assert_eq!(id.done(), expected);
+    ///
+    /// // This doesn't panic when `id` is dropped as `id.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockOnceLockError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that [`OnceLock::init()`] was called the expected number of times.
+    fn check(&self) -> Result<(), MockOnceLockError> {
+        if let Some(expected) = self.expected {
+            let actual = self.init_count();
+            ensure!(
+                actual == expected,
+                WrongNumberOfInitsSnafu { expected, actual }
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Wake all of the futures waiting for the value, which was set by the `method`.
+    fn wake_all(&self, method: &'static str) {
+        for waker in self.wakers().drain(..) {
+            waiting::waking(method, || waker.wake());
+        }
+    }
+
+    /// Lock the wakers, which are never poisoned as the mock doesn't panic while holding them.
+    fn wakers(&self) -> sync::MutexGuard<'_, Vec<Waker>> {
+        self.wakers.lock().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for MockOnceLock<T> {
+    /// If [`Self::done()`] has not been called before being dropped then check that
+    /// [`OnceLock::init()`] was called the expected number of times.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> OnceLock<T> for MockOnceLock<T> {
+    /// Get a reference to the value, waiting for it to be set.
+    fn get<'a>(&'a self) -> impl Future<Output = &'a T>
+    where
+        T: 'a,
+    {
+        poll_fn(|cx| match self.value.get() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                self.wakers().push(cx.waker().clone());
+                waiting::record("MockOnceLock::get()");
+                Poll::Pending
+            }
+        })
+    }
+
+    /// Get a reference to the value if it is set.
+    fn try_get(&self) -> Option<&T> {
+        self.value.get()
+    }
+
+    /// Count the call and set the value, returning it as an error if it is already set.
+    fn init(&self, value: T) -> Result<(), T> {
+        self.init_count.fetch_add(1, Ordering::Relaxed);
+        self.value.set(value)?;
+        self.wake_all("MockOnceLock::init()");
+
+        Ok(())
+    }
+
+    /// Get a reference to the value, setting it with `f` if it isn't set.
+    ///
+    /// This doesn't count as a call to [`OnceLock::init()`].
+    fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> &T {
+        let was_set = self.value.get().is_some();
+        let value = self.value.get_or_init(f);
+        if !was_set {
+            self.wake_all("MockOnceLock::get_or_init()");
+        }
+
+        value
+    }
+
+    /// Is the value set.
+    fn is_set(&self) -> bool {
+        self.value.get().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{pin::pin, task::Context};
+    use embassy_futures::block_on;
+    use futures_util::task::noop_waker_ref;
+
+    async fn read_twice(lock: &impl OnceLock<u32>) -> u32 {
+        lock.get().await + lock.get().await
+    }
+
+    #[test]
+    fn embassy_once_lock() {
+        let lock = EmbassyOnceLock::new();
+
+        assert!(!OnceLock::is_set(&lock));
+        assert_eq!(OnceLock::try_get(&lock), None);
+        assert_eq!(OnceLock::init(&lock, 1), Ok(()));
+        assert_eq!(OnceLock::init(&lock, 2), Err(2));
+        assert_eq!(OnceLock::get_or_init(&lock, || 3), &1);
+        assert_eq!(block_on(read_twice(&lock)), 2);
+    }
+
+    #[test]
+    fn pre_initialized_mock() {
+        let lock = MockOnceLock::with_value(5).expect_inits(0);
+
+        assert!(lock.is_set());
+        assert_eq!(block_on(read_twice(&lock)), 10);
+        lock.done().unwrap();
+    }
+
+    #[test]
+    fn get_waits_for_init() {
+        let lock = MockOnceLock::new();
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        {
+            let mut get = pin!(lock.get());
+            assert_eq!(get.as_mut().poll(&mut cx), Poll::Pending);
+
+            lock.init(4).unwrap();
+            assert_eq!(get.as_mut().poll(&mut cx), Poll::Ready(&4));
+        }
+        assert!(lock.wakers().is_empty());
+    }
+
+    #[test]
+    fn get_or_init_is_not_counted() {
+        let lock = MockOnceLock::new().expect_inits(1);
+
+        assert_eq!(lock.get_or_init(|| 1), &1);
+        assert_eq!(lock.init_count(), 0);
+        assert_eq!(lock.init(2), Err(2));
+        assert_eq!(lock.try_get(), Some(&1));
+        lock.done().unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to init 1 time(s), actually init 0")]
+    fn wrong_number_of_inits_just_drop() {
+        let _lock = MockOnceLock::<u32>::new().expect_inits(1);
+    }
+}
@@ -0,0 +1,477 @@
+//! The implementations of the [`Sender`] and [`Receiver`] traits for an
+//! [`embassy_sync::priority_channel::PriorityChannel`] and its halves, and a mocked version of it
+//! to allow unit testing functions that rely on the order messages are delivered in.
+//!
+//! # Examples
+//! ```
+//! use embassy_mock::sync::{Receiver, Sender};
+//!
+//! // Generic over the `Receiver` and `Sender` traits
+//! async fn handle_next(requests: impl Receiver<u8>, handled: impl Sender<u8>) {
+//!     let request = requests.receive().await;
+//!     handled.send(request).await;
+//! }
+//!
+//! # test_handle_next();
+//! // The unit tests that use the `MockPriorityChannel`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_futures::block_on;
+//!     use embassy_mock::sync::{MockPriorityChannel, MockSender};
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_handle_next() {
+//!         let requests: MockPriorityChannel<u8> = MockPriorityChannel::with_queued([1, 9, 4]);
+//!         let handled = MockSender::new();
+//!
+//!         block_on(handle_next(&requests, &handled));
+//!
+//!         // The highest priority request is handled first.
+//!         assert_eq!(handled.sent(), [9]);
+//!         assert_eq!(requests.queued(), [4, 1]);
+//!         requests.done().unwrap_err();
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use super::channel::{Receiver, Sender};
+use core::future::Future;
+use embassy_sync::{
+    blocking_mutex::raw::RawMutex,
+    channel::{TryReceiveError, TrySendError},
+    priority_channel::{
+        Kind, PriorityChannel as EmbassyPriorityChannel, Receiver as EmbassyReceiver,
+        Sender as EmbassySender,
+    },
+};
+#[cfg(feature = "std")]
+use {
+    crate::waiting,
+    core::{
+        fmt,
+        marker::PhantomData,
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    },
+    embassy_sync::priority_channel::Max,
+    snafu::prelude::*,
+    std::{sync::Mutex, vec::Vec},
+};
+
+impl<M: RawMutex, T: Ord, K: Kind, const N: usize> Sender<T>
+    for EmbassyPriorityChannel<M, T, K, N>
+{
+    /// Send a value, waiting until there is capacity.
+    ///
+    /// Sending completes when the value has been pushed to the channel's queue. This doesn't mean
+    /// the value has been received yet.
+    fn send(&self, message: T) -> impl Future<Output = ()> {
+        self.send(message)
+    }
+
+    /// Attempt to immediately send a message.
+    ///
+    /// This method differs from [`Sender::send()`] by returning immediately if the channel's
+    /// buffer is full, instead of waiting.
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        self.try_send(message)
+    }
+}
+
+impl<M: RawMutex, T: Ord, K: Kind, const N: usize> Receiver<T>
+    for EmbassyPriorityChannel<M, T, K, N>
+{
+    /// Receive the next value, the one with the highest priority.
+    ///
+    /// If there are no messages in the channel's buffer, this method will wait until there is
+    /// at least one message.
+    fn receive(&self) -> impl Future<Output = T> {
+        self.receive()
+    }
+
+    /// Attempt to immediately receive the message with the highest priority.
+    ///
+    /// This method will either receive a message from the channel immediately or return an
+    /// error if the channel is empty.
+    fn try_receive(&self) -> Result<T, TryReceiveError> {
+        self.try_receive()
+    }
+}
+
+impl<M: RawMutex, T: Ord, K: Kind, const N: usize> Sender<T> for EmbassySender<'_, M, T, K, N> {
+    /// Sends a value, see [`embassy_sync::priority_channel::PriorityChannel::send()`].
+    fn send(&self, message: T) -> impl Future<Output = ()> {
+        self.send(message)
+    }
+
+    /// Attempt to immediately send a message, see
+    /// [`embassy_sync::priority_channel::PriorityChannel::try_send()`].
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        self.try_send(message)
+    }
+}
+
+impl<M: RawMutex, T: Ord, K: Kind, const N: usize> Receiver<T> for EmbassyReceiver<'_, M, T, K, N> {
+    /// Receive the next value, see
+    /// [`embassy_sync::priority_channel::PriorityChannel::receive()`].
+    fn receive(&self) -> impl Future<Output = T> {
+        self.receive()
+    }
+
+    /// Attempt to immediately receive the next value, see
+    /// [`embassy_sync::priority_channel::PriorityChannel::try_receive()`].
+    fn try_receive(&self) -> Result<T, TryReceiveError> {
+        self.try_receive()
+    }
+}
+
+/// The errors that are reported by [`MockPriorityChannel`].
+#[cfg(feature = "std")]
+#[derive(Debug, Snafu, PartialEq)]
+pub enum MockPriorityChannelError {
+    /// Some of the queued messages were never received.
+    #[snafu(display("expected {remaining} more message(s) to be received"))]
+    UnreceivedMessages {
+        /// The number of messages that were never received.
+        remaining: usize,
+    },
+}
+
+/// A mocked version of [`embassy_sync::priority_channel::PriorityChannel`] that can be used in
+/// its place for unit tests.
+///
+/// Unlike the [`MockChannel`](super::MockChannel), the messages sent to it are queued with the
+/// scripted messages and every message is received in priority order, as by a real
+/// `PriorityChannel` of the same [`Kind`]. The highest message is received first for
+/// [`Max`], which is the default, and the lowest first for
+/// [`Min`](embassy_sync::priority_channel::Min). Messages of equal priority are received in the
+/// order they were queued. The queue is never full.
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if any of the queued messages
+/// were never received.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::sync::{MockPriorityChannel, Receiver, Sender};
+/// use embassy_sync::priority_channel::Min;
+///
+/// let deadlines = MockPriorityChannel::<u32, Min>::new();
+///
+/// block_on(deadlines.send(30));
+/// deadlines.try_send(10).unwrap();
+/// deadlines.push(20);
+///
+/// assert_eq!(block_on(deadlines.receive()), 10);
+/// assert_eq!(deadlines.try_receive(), Ok(20));
+/// assert_eq!(deadlines.try_receive(), Ok(30));
+/// deadlines.done().unwrap();
+/// ```
+#[cfg(feature = "std")]
+pub struct MockPriorityChannel<T, K = Max> {
+    /// The queued messages and the waiting receive, shared by every call.
+    state: Mutex<PriorityChannelState<T>>,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+
+    /// The [`Kind`] of priority the messages are received in.
+    kind: PhantomData<fn() -> K>,
+}
+
+/// The state of a [`MockPriorityChannel`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct PriorityChannelState<T> {
+    /// The messages still to be received, in the order they were queued.
+    queue: Vec<T>,
+
+    /// The waker of the receive that is waiting for a message.
+    waker: Option<Waker>,
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug, K> fmt::Debug for MockPriorityChannel<T, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockPriorityChannel")
+            .field("state", &self.state)
+            .field("is_done", &self.is_done)
+            .finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Ord, K: Kind> MockPriorityChannel<T, K> {
+    /// Create a [`MockPriorityChannel`] with no messages queued.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(PriorityChannelState {
+                queue: Vec::new(),
+                waker: None,
+            }),
+            is_done: false,
+            kind: PhantomData,
+        }
+    }
+
+    /// Create a [`MockPriorityChannel`] with the `messages` already queued, they are received in
+    /// priority order and not the order given.
+    pub fn with_queued(messages: impl IntoIterator<Item = T>) -> Self {
+        let channel = Self::new();
+        channel.lock().queue.extend(messages);
+        channel
+    }
+
+    /// Queue the `message` as if another task sent it, waking the receive that is waiting for it.
+    pub fn push(&self, message: T) {
+        let mut state = self.lock();
+        state.queue.push(message);
+        if let Some(waker) = state.waker.take() {
+            waiting::waking("MockPriorityChannel::push()", || waker.wake());
+        }
+    }
+
+    /// The messages still to be received, in the order they will be received.
+    pub fn queued(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut queue = self.lock().queue.clone();
+        let mut ordered = Vec::with_capacity(queue.len());
+        while let Some(message) = Self::pop(&mut queue) {
+            ordered.push(message);
+        }
+
+        ordered
+    }
+
+    /// Check that all of the queued messages were received.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockPriorityChannel, MockPriorityChannelError, Receiver};
+    ///
+    /// let channel: MockPriorityChannel<u8> = MockPriorityChannel::with_queued([1, 2, 3]);
+    /// assert_eq!(channel.try_receive(), Ok(3));
+    ///
+    /// let expected = Err(MockPriorityChannelError::UnreceivedMessages { remaining: 2 });
+    /// assert_eq!(channel.done(), expected);
+    ///
+    /// // This doesn't panic when `channel` is dropped as `channel.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockPriorityChannelError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Remove the message with the highest priority for `K` from the `queue`, the first queued
+    /// one if several have the same priority.
+    fn pop(queue: &mut Vec<T>) -> Option<T> {
+        let mut next = 0;
+        for (index, message) in queue.iter().enumerate().skip(1) {
+            // `Kind::ordering()` is how a message compares to the ones received after it.
+            if message.cmp(&queue[next]) == K::ordering() {
+                next = index;
+            }
+        }
+
+        (!queue.is_empty()).then(|| queue.remove(next))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, K> MockPriorityChannel<T, K> {
+    /// Check that all of the queued messages were received.
+    fn check(&self) -> Result<(), MockPriorityChannelError> {
+        let remaining = self.lock().queue.len();
+        ensure!(remaining == 0, UnreceivedMessagesSnafu { remaining });
+
+        Ok(())
+    }
+
+    /// Lock the state, which is never poisoned as the mock doesn't panic while holding the lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, PriorityChannelState<T>> {
+        self.state.lock().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, K> Drop for MockPriorityChannel<T, K> {
+    /// If [`Self::done()`] has not been called before being dropped then check that all of the
+    /// queued messages were received.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Ord, K: Kind> Sender<T> for MockPriorityChannel<T, K> {
+    /// Queue the `message`, this completes immediately as the queue is never full.
+    fn send(&self, message: T) -> impl Future<Output = ()> {
+        self.push(message);
+        core::future::ready(())
+    }
+
+    /// Queue the `message`, this never fails as the queue is never full.
+    fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        self.push(message);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Ord, K: Kind> Receiver<T> for MockPriorityChannel<T, K> {
+    /// Receive the queued message with the highest priority, waiting until there is one.
+    fn receive(&self) -> impl Future<Output = T> {
+        MockPriorityReceiveFuture { channel: self }
+    }
+
+    /// Receive the queued message with the highest priority, or [`TryReceiveError::Empty`] if
+    /// there isn't one.
+    fn try_receive(&self) -> Result<T, TryReceiveError> {
+        Self::pop(&mut self.lock().queue).ok_or(TryReceiveError::Empty)
+    }
+}
+
+/// The future returned by [`Receiver::receive()`] for a [`MockPriorityChannel`].
+#[cfg(feature = "std")]
+struct MockPriorityReceiveFuture<'a, T, K> {
+    /// The channel the message is received from.
+    channel: &'a MockPriorityChannel<T, K>,
+}
+
+#[cfg(feature = "std")]
+impl<T: Ord, K: Kind> Future for MockPriorityReceiveFuture<'_, T, K> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.channel.lock();
+        match MockPriorityChannel::<T, K>::pop(&mut state.queue) {
+            Some(message) => Poll::Ready(message),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                waiting::record("MockPriorityChannel::receive()");
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::pin::pin;
+    use embassy_futures::block_on;
+    use embassy_sync::{
+        blocking_mutex::raw::NoopRawMutex,
+        priority_channel::{Max, Min},
+    };
+    use futures_util::task::noop_waker_ref;
+
+    async fn drain(receiver: impl Receiver<u8>, count: usize) -> Vec<u8> {
+        let mut received = Vec::new();
+        for _ in 0..count {
+            received.push(receiver.receive().await);
+        }
+        received
+    }
+
+    #[test]
+    fn embassy_priority_channel_and_halves() {
+        let channel = EmbassyPriorityChannel::<NoopRawMutex, u8, Max, 3>::new();
+
+        block_on(Sender::send(&channel.sender(), 1));
+        Sender::try_send(&channel, 3).unwrap();
+        block_on(Sender::send(&channel, 2));
+        assert_eq!(Sender::try_send(&channel, 4), Err(TrySendError::Full(4)));
+
+        assert_eq!(Receiver::try_receive(&channel.receiver()), Ok(3));
+        assert_eq!(block_on(drain(&channel, 2)), [2, 1]);
+        assert_eq!(Receiver::try_receive(&channel), Err(TryReceiveError::Empty));
+    }
+
+    #[test]
+    fn max_receives_highest_first() {
+        let channel: MockPriorityChannel<u8> = MockPriorityChannel::with_queued([2, 7, 1]);
+        channel.try_send(5).unwrap();
+
+        assert_eq!(channel.queued(), [7, 5, 2, 1]);
+        assert_eq!(block_on(drain(&channel, 4)), [7, 5, 2, 1]);
+        channel.done().unwrap();
+    }
+
+    #[test]
+    fn min_receives_lowest_first() {
+        let channel = MockPriorityChannel::<u8, Min>::with_queued([2, 7, 1]);
+
+        assert_eq!(block_on(drain(&channel, 3)), [1, 2, 7]);
+        channel.done().unwrap();
+    }
+
+    #[test]
+    fn equal_priorities_are_received_in_queued_order() {
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        struct Job {
+            priority: u8,
+            name: &'static str,
+        }
+
+        impl PartialOrd for Job {
+            fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for Job {
+            fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+                self.priority.cmp(&other.priority)
+            }
+        }
+
+        let job = |priority, name| Job { priority, name };
+        let channel: MockPriorityChannel<Job> =
+            MockPriorityChannel::with_queued([job(1, "a"), job(2, "b"), job(1, "c"), job(2, "d")]);
+
+        let names: Vec<_> = channel.queued().into_iter().map(|job| job.name).collect();
+        assert_eq!(names, ["b", "d", "a", "c"]);
+        while channel.try_receive().is_ok() {}
+        channel.done().unwrap();
+    }
+
+    #[test]
+    fn receive_waits_for_push() {
+        let channel: MockPriorityChannel<u8> = MockPriorityChannel::new();
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        {
+            let mut receive = pin!(channel.receive());
+            assert_eq!(receive.as_mut().poll(&mut cx), Poll::Pending);
+
+            channel.push(4);
+            assert_eq!(receive.as_mut().poll(&mut cx), Poll::Ready(4));
+        }
+        assert_eq!(channel.try_receive(), Err(TryReceiveError::Empty));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 2 more message(s) to be received")]
+    fn unreceived_messages_just_drop() {
+        let _channel: MockPriorityChannel<u8> = MockPriorityChannel::with_queued([1, 2]);
+    }
+}
@@ -0,0 +1,1021 @@
+//! Traits and mocked types to allow unit testing functions that use the publishers and
+//! subscribers of an [`embassy_sync::pubsub::PubSubChannel`].
+//!
+//! # Examples
+//! ```
+//! use embassy_mock::sync::{Publisher, Subscriber};
+//! use embassy_sync::pubsub::WaitResult;
+//!
+//! // Generic over the `Subscriber` and `Publisher` traits
+//! async fn count_missed(events: &mut impl Subscriber<u8>, missed: &impl Publisher<u64>) {
+//!     if let WaitResult::Lagged(count) = events.next_message().await {
+//!         missed.publish(count).await;
+//!     }
+//! }
+//!
+//! # test_count_missed();
+//! // The unit tests that use the `MockSubscriber` and `MockPublisher`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_futures::block_on;
+//!     use embassy_mock::sync::{MockPublisher, MockSubscriber};
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_count_missed() {
+//!         let mut events = MockSubscriber::new();
+//!         events.push_lagged(3);
+//!         let missed = MockPublisher::new();
+//!
+//!         block_on(count_missed(&mut events, &missed));
+//!
+//!         assert_eq!(missed.published(), [3]);
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use core::future::Future;
+use embassy_sync::{
+    blocking_mutex::raw::RawMutex,
+    pubsub::{
+        DynPublisher, DynSubscriber, Error, PubSubChannel as EmbassyPubSubChannel,
+        Publisher as EmbassyPublisher, Subscriber as EmbassySubscriber, WaitResult,
+    },
+};
+#[cfg(feature = "std")]
+use {
+    crate::waiting,
+    core::{
+        pin::Pin,
+        sync::atomic::{AtomicUsize, Ordering},
+        task::{Context, Poll, Waker},
+    },
+    snafu::prelude::*,
+    std::{collections::VecDeque, sync::Mutex, vec::Vec},
+};
+
+/// The trait to replace the [`embassy_sync::pubsub::PubSubChannel`] in code to allow the
+/// [`MockPubSubChannel`] to be used in its place for tests.
+pub trait PubSubChannel<T> {
+    /// The publisher created by [`Self::publisher()`].
+    type Publisher<'a>: Publisher<T>
+    where
+        Self: 'a;
+
+    /// The subscriber created by [`Self::subscriber()`].
+    type Subscriber<'a>: Subscriber<T>
+    where
+        Self: 'a;
+
+    /// Wrapper for [`embassy_sync::pubsub::PubSubChannel::publisher()`].
+    fn publisher(&self) -> Result<Self::Publisher<'_>, Error>;
+
+    /// Wrapper for [`embassy_sync::pubsub::PubSubChannel::subscriber()`].
+    fn subscriber(&self) -> Result<Self::Subscriber<'_>, Error>;
+}
+
+/// The trait to replace the [`embassy_sync::pubsub::Publisher`] and
+/// [`embassy_sync::pubsub::DynPublisher`] in code to allow the [`MockPublisher`] to be used in
+/// their place for tests.
+pub trait Publisher<T> {
+    /// Wrapper for `embassy_sync::pubsub::Publisher::publish()`.
+    fn publish(&self, message: T) -> impl Future<Output = ()>;
+
+    /// Wrapper for `embassy_sync::pubsub::Publisher::publish_immediate()`.
+    fn publish_immediate(&self, message: T);
+
+    /// Wrapper for `embassy_sync::pubsub::Publisher::try_publish()`.
+    fn try_publish(&self, message: T) -> Result<(), T>;
+}
+
+/// The trait to replace the [`embassy_sync::pubsub::Subscriber`] and
+/// [`embassy_sync::pubsub::DynSubscriber`] in code to allow the [`MockSubscriber`] to be used in
+/// their place for tests.
+pub trait Subscriber<T> {
+    /// Wrapper for `embassy_sync::pubsub::Subscriber::next_message()`.
+    fn next_message(&mut self) -> impl Future<Output = WaitResult<T>>;
+
+    /// Wrapper for `embassy_sync::pubsub::Subscriber::try_next_message()`.
+    fn try_next_message(&mut self) -> Option<WaitResult<T>>;
+
+    /// Wrapper for `embassy_sync::pubsub::Subscriber::next_message_pure()`, which skips the
+    /// [`WaitResult::Lagged`] results.
+    fn next_message_pure(&mut self) -> impl Future<Output = T> {
+        async {
+            loop {
+                if let WaitResult::Message(message) = self.next_message().await {
+                    break message;
+                }
+            }
+        }
+    }
+
+    /// Wrapper for `embassy_sync::pubsub::Subscriber::try_next_message_pure()`, which skips the
+    /// [`WaitResult::Lagged`] results.
+    fn try_next_message_pure(&mut self) -> Option<T> {
+        loop {
+            match self.try_next_message()? {
+                WaitResult::Lagged(_) => continue,
+                WaitResult::Message(message) => break Some(message),
+            }
+        }
+    }
+}
+
+impl<M: RawMutex, T: Clone, const CAP: usize, const SUBS: usize, const PUBS: usize> PubSubChannel<T>
+    for EmbassyPubSubChannel<M, T, CAP, SUBS, PUBS>
+{
+    type Publisher<'a> = EmbassyPublisher<'a, M, T, CAP, SUBS, PUBS> where Self: 'a;
+    type Subscriber<'a> = EmbassySubscriber<'a, M, T, CAP, SUBS, PUBS> where Self: 'a;
+
+    /// Create a new publisher, unless all `PUBS` of them are in use.
+    fn publisher(&self) -> Result<Self::Publisher<'_>, Error> {
+        self.publisher()
+    }
+
+    /// Create a new subscriber, unless all `SUBS` of them are in use.
+    fn subscriber(&self) -> Result<Self::Subscriber<'_>, Error> {
+        self.subscriber()
+    }
+}
+
+impl<M: RawMutex, T: Clone, const CAP: usize, const SUBS: usize, const PUBS: usize> Publisher<T>
+    for EmbassyPublisher<'_, M, T, CAP, SUBS, PUBS>
+{
+    /// Publish a message, waiting until there is space in the queue.
+    fn publish(&self, message: T) -> impl Future<Output = ()> {
+        (**self).publish(message)
+    }
+
+    /// Publish a message right now even when the queue is full, which may cause a subscriber to
+    /// lag.
+    fn publish_immediate(&self, message: T) {
+        (**self).publish_immediate(message);
+    }
+
+    /// Publish a message if there is space in the message queue.
+    fn try_publish(&self, message: T) -> Result<(), T> {
+        (**self).try_publish(message)
+    }
+}
+
+impl<T: Clone> Publisher<T> for DynPublisher<'_, T> {
+    /// Publish a message, waiting until there is space in the queue.
+    fn publish(&self, message: T) -> impl Future<Output = ()> {
+        (**self).publish(message)
+    }
+
+    /// Publish a message right now even when the queue is full, which may cause a subscriber to
+    /// lag.
+    fn publish_immediate(&self, message: T) {
+        (**self).publish_immediate(message);
+    }
+
+    /// Publish a message if there is space in the message queue.
+    fn try_publish(&self, message: T) -> Result<(), T> {
+        (**self).try_publish(message)
+    }
+}
+
+impl<M: RawMutex, T: Clone, const CAP: usize, const SUBS: usize, const PUBS: usize> Subscriber<T>
+    for EmbassySubscriber<'_, M, T, CAP, SUBS, PUBS>
+{
+    /// Wait for a published message.
+    fn next_message(&mut self) -> impl Future<Output = WaitResult<T>> {
+        (**self).next_message()
+    }
+
+    /// Receive a published message if there is one that hasn't been received yet.
+    fn try_next_message(&mut self) -> Option<WaitResult<T>> {
+        (**self).try_next_message()
+    }
+}
+
+impl<T: Clone> Subscriber<T> for DynSubscriber<'_, T> {
+    /// Wait for a published message.
+    fn next_message(&mut self) -> impl Future<Output = WaitResult<T>> {
+        (**self).next_message()
+    }
+
+    /// Receive a published message if there is one that hasn't been received yet.
+    fn try_next_message(&mut self) -> Option<WaitResult<T>> {
+        (**self).try_next_message()
+    }
+}
+
+impl<T, P: Publisher<T> + ?Sized> Publisher<T> for &P {
+    /// Call [`Publisher::publish()`] of the referenced publisher.
+    fn publish(&self, message: T) -> impl Future<Output = ()> {
+        (**self).publish(message)
+    }
+
+    /// Call [`Publisher::publish_immediate()`] of the referenced publisher.
+    fn publish_immediate(&self, message: T) {
+        (**self).publish_immediate(message);
+    }
+
+    /// Call [`Publisher::try_publish()`] of the referenced publisher.
+    fn try_publish(&self, message: T) -> Result<(), T> {
+        (**self).try_publish(message)
+    }
+}
+
+impl<T, S: Subscriber<T> + ?Sized> Subscriber<T> for &mut S {
+    /// Call [`Subscriber::next_message()`] of the referenced subscriber.
+    fn next_message(&mut self) -> impl Future<Output = WaitResult<T>> {
+        (**self).next_message()
+    }
+
+    /// Call [`Subscriber::try_next_message()`] of the referenced subscriber.
+    fn try_next_message(&mut self) -> Option<WaitResult<T>> {
+        (**self).try_next_message()
+    }
+}
+
+/// The errors that are reported by [`MockSubscriber`].
+#[cfg(feature = "std")]
+#[derive(Debug, Snafu, PartialEq)]
+pub enum MockSubscriberError {
+    /// Some of the scripted messages and lags were never received.
+    #[snafu(display("expected {remaining} more wait result(s) to be received"))]
+    UnreceivedResults {
+        /// The number of messages and lags that were never received.
+        remaining: usize,
+    },
+}
+
+/// The [`Publisher`] methods that publish a message, which behave differently when the message
+/// queue is full.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishMethod {
+    /// [`Publisher::publish()`], which waits until there is space in the queue.
+    Publish,
+
+    /// [`Publisher::publish_immediate()`], which publishes even when the queue is full, making
+    /// the slowest subscriber lag.
+    PublishImmediate,
+
+    /// [`Publisher::try_publish()`], which gives the message back when the queue is full.
+    TryPublish,
+}
+
+/// A mocked version of [`embassy_sync::pubsub::Publisher`] that can be used in its place for unit
+/// tests.
+///
+/// Every message passed to [`Publisher::publish()`], [`Publisher::publish_immediate()`] and
+/// [`Publisher::try_publish()`] is recorded along with the [`PublishMethod`] it was published
+/// with. Publishing completes immediately unless the test has made the queue full with
+/// [`Self::set_full()`].
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::sync::{MockPublisher, PublishMethod, Publisher};
+///
+/// let publisher = MockPublisher::new();
+///
+/// block_on(publisher.publish(1));
+/// publisher.publish_immediate(2);
+/// publisher.try_publish(3).unwrap();
+///
+/// assert_eq!(publisher.published(), [1, 2, 3]);
+/// assert_eq!(publisher.published_by(PublishMethod::PublishImmediate), [2]);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockPublisher<T> {
+    /// The state shared with the futures returned by [`Publisher::publish()`].
+    state: Mutex<PublisherState<T>>,
+}
+
+/// The state of a [`MockPublisher`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct PublisherState<T> {
+    /// The messages that were published and how, in call order.
+    published: Vec<(PublishMethod, T)>,
+
+    /// The number of messages given back by [`Publisher::try_publish()`] as the queue was full.
+    rejected: usize,
+
+    /// Is the message queue full, so [`Publisher::publish()`] waits and
+    /// [`Publisher::try_publish()`] fails.
+    is_full: bool,
+
+    /// The wakers of the publishes waiting for the queue to stop being full.
+    wakers: Vec<Waker>,
+}
+
+#[cfg(feature = "std")]
+impl<T> MockPublisher<T> {
+    /// Create a [`MockPublisher`] that hasn't published any messages.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(PublisherState {
+                published: Vec::new(),
+                rejected: 0,
+                is_full: false,
+                wakers: Vec::new(),
+            }),
+        }
+    }
+
+    /// Set whether the message queue is full.
+    ///
+    /// While it is full [`Publisher::publish()`] waits, [`Publisher::try_publish()`] gives the
+    /// message back and [`Publisher::publish_immediate()`] still publishes, as a real channel
+    /// would by dropping the oldest message. Setting it to not full wakes the waiting publishes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockPublisher, PublishMethod, Publisher};
+    ///
+    /// // Alarms must always get through, readings are dropped when the queue is full.
+    /// fn report(events: &impl Publisher<&'static str>, event: &'static str, is_alarm: bool) {
+    ///     if is_alarm {
+    ///         events.publish_immediate(event);
+    ///     } else {
+    ///         let _ = events.try_publish(event);
+    ///     }
+    /// }
+    ///
+    /// let events = MockPublisher::new();
+    /// events.set_full(true);
+    /// report(&events, "reading", false);
+    /// report(&events, "overheat", true);
+    ///
+    /// assert_eq!(events.published(), ["overheat"]);
+    /// assert_eq!(events.published_by(PublishMethod::TryPublish), [] as [&str; 0]);
+    /// assert_eq!(events.rejected_count(), 1);
+    /// ```
+    pub fn set_full(&self, is_full: bool) {
+        let mut state = self.lock();
+        state.is_full = is_full;
+        if !is_full {
+            for waker in state.wakers.drain(..) {
+                waiting::waking("MockPublisher::set_full()", || waker.wake());
+            }
+        }
+    }
+
+    /// The messages published with this [`MockPublisher`], in call order.
+    pub fn published(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.lock()
+            .published
+            .iter()
+            .map(|(_, message)| message.clone())
+            .collect()
+    }
+
+    /// The messages published with this [`MockPublisher`] by the `method`, in call order.
+    pub fn published_by(&self, method: PublishMethod) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.lock()
+            .published
+            .iter()
+            .filter(|(published_by, _)| *published_by == method)
+            .map(|(_, message)| message.clone())
+            .collect()
+    }
+
+    /// The number of messages that [`Publisher::try_publish()`] gave back as the queue was full.
+    pub fn rejected_count(&self) -> usize {
+        self.lock().rejected
+    }
+
+    /// Record the `message` published by the `method`, unless the queue is full and the `method`
+    /// waits or fails, registering the `waker` to be woken once it isn't.
+    fn record(&self, method: PublishMethod, message: T, waker: Option<&Waker>) -> Result<(), T> {
+        let mut state = self.lock();
+        if state.is_full && method != PublishMethod::PublishImmediate {
+            if let Some(waker) = waker {
+                if !state.wakers.iter().any(|other| other.will_wake(waker)) {
+                    state.wakers.push(waker.clone());
+                }
+            }
+            if method == PublishMethod::TryPublish {
+                state.rejected += 1;
+            }
+            return Err(message);
+        }
+
+        state.published.push((method, message));
+        Ok(())
+    }
+
+    /// Lock the state, which is never poisoned as the mock doesn't panic while holding the lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, PublisherState<T>> {
+        self.state.lock().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Publisher<T> for MockPublisher<T> {
+    /// Record the `message`, waiting while the queue is full.
+    fn publish(&self, message: T) -> impl Future<Output = ()> {
+        MockPublishFuture {
+            publisher: self,
+            message: Some(message),
+        }
+    }
+
+    /// Record the `message`, even when the queue is full.
+    fn publish_immediate(&self, message: T) {
+        // Publishing immediately never fails.
+        let _ = self.record(PublishMethod::PublishImmediate, message, None);
+    }
+
+    /// Record the `message`, unless the queue is full.
+    fn try_publish(&self, message: T) -> Result<(), T> {
+        self.record(PublishMethod::TryPublish, message, None)
+    }
+}
+
+/// The future returned by [`Publisher::publish()`] for a [`MockPublisher`].
+#[cfg(feature = "std")]
+struct MockPublishFuture<'a, T> {
+    /// The publisher the message is published with.
+    publisher: &'a MockPublisher<T>,
+
+    /// The message, until it is published.
+    message: Option<T>,
+}
+
+// The message is moved out without ever being pinned.
+#[cfg(feature = "std")]
+impl<T> Unpin for MockPublishFuture<'_, T> {}
+
+#[cfg(feature = "std")]
+impl<T> Future for MockPublishFuture<'_, T> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let Some(message) = this.message.take() else {
+            return Poll::Ready(());
+        };
+
+        match this
+            .publisher
+            .record(PublishMethod::Publish, message, Some(cx.waker()))
+        {
+            Ok(()) => Poll::Ready(()),
+            Err(message) => {
+                this.message = Some(message);
+                waiting::record("MockPublisher::publish()");
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// A mocked version of [`embassy_sync::pubsub::Subscriber`] that can be used in its place for
+/// unit tests.
+///
+/// The results returned by [`Subscriber::next_message()`] are scripted by the test, including
+/// [`WaitResult::Lagged`] to test the code that resynchronizes after missing messages. Once they
+/// have all been received, [`Subscriber::next_message()`] never completes.
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if any of the scripted results
+/// were never received.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::sync::{MockSubscriber, Subscriber};
+/// use embassy_sync::pubsub::WaitResult;
+///
+/// let mut subscriber = MockSubscriber::with_messages(["ping"]);
+/// subscriber.push_lagged(2);
+/// subscriber.push_message("pong");
+///
+/// assert_eq!(block_on(subscriber.next_message()), WaitResult::Message("ping"));
+/// assert_eq!(block_on(subscriber.next_message()), WaitResult::Lagged(2));
+/// assert_eq!(subscriber.try_next_message(), Some(WaitResult::Message("pong")));
+///
+/// assert_eq!(subscriber.done(), Ok(()));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockSubscriber<T> {
+    /// The results still to be returned by [`Subscriber::next_message()`], in order.
+    to_receive: VecDeque<WaitResult<T>>,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<T> MockSubscriber<T> {
+    /// Create a [`MockSubscriber`] with nothing to receive.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            to_receive: VecDeque::new(),
+            is_done: false,
+        }
+    }
+
+    /// Create a [`MockSubscriber`] that receives the `messages` in order.
+    pub fn with_messages(messages: impl IntoIterator<Item = T>) -> Self {
+        Self::script(messages.into_iter().map(WaitResult::Message))
+    }
+
+    /// Create a [`MockSubscriber`] that receives the results of the `script` in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockSubscriber, Subscriber};
+    /// use embassy_sync::pubsub::WaitResult;
+    ///
+    /// let mut subscriber = MockSubscriber::script([
+    ///     WaitResult::Lagged(4),
+    ///     WaitResult::Message(1),
+    /// ]);
+    ///
+    /// // The lag is skipped.
+    /// assert_eq!(subscriber.try_next_message_pure(), Some(1));
+    /// assert_eq!(subscriber.try_next_message_pure(), None);
+    /// ```
+    pub fn script(script: impl IntoIterator<Item = WaitResult<T>>) -> Self {
+        Self {
+            to_receive: script.into_iter().collect(),
+            is_done: false,
+        }
+    }
+
+    /// Add the `message` to be received after the other results.
+    pub fn push_message(&mut self, message: T) {
+        self.to_receive.push_back(WaitResult::Message(message));
+    }
+
+    /// Add a [`WaitResult::Lagged`] result, as if the subscriber missed `missed` messages, to be
+    /// received after the other results.
+    pub fn push_lagged(&mut self, missed: u64) {
+        self.to_receive.push_back(WaitResult::Lagged(missed));
+    }
+
+    /// Check that all of the scripted messages and lags were received.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockSubscriber, MockSubscriberError, Subscriber};
+    ///
+    /// let mut subscriber = MockSubscriber::with_messages([1, 2]);
+    /// subscriber.push_lagged(1);
+    /// subscriber.try_next_message();
+    ///
+    /// let expected = Err(MockSubscriberError::UnreceivedResults { remaining: 2 });
+    /// assert_eq!(subscriber.done(), expected);
+    ///
+    /// // This doesn't panic when `subscriber` is dropped as `subscriber.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockSubscriberError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that all of the scripted results were received.
+    fn check(&self) -> Result<(), MockSubscriberError> {
+        let remaining = self.to_receive.len();
+        ensure!(remaining == 0, UnreceivedResultsSnafu { remaining });
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for MockSubscriber<T> {
+    /// If [`Self::done()`] has not been called before being dropped then check that all of the
+    /// scripted results were received.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Subscriber<T> for MockSubscriber<T> {
+    /// Return the next scripted result, or never complete if there are none left.
+    fn next_message(&mut self) -> impl Future<Output = WaitResult<T>> {
+        MockNextMessageFuture { subscriber: self }
+    }
+
+    /// Return the next scripted result, if any.
+    fn try_next_message(&mut self) -> Option<WaitResult<T>> {
+        self.to_receive.pop_front()
+    }
+}
+
+/// The future returned by [`Subscriber::next_message()`] for a [`MockSubscriber`].
+#[cfg(feature = "std")]
+struct MockNextMessageFuture<'a, T> {
+    /// The subscriber the result is received from.
+    subscriber: &'a mut MockSubscriber<T>,
+}
+
+#[cfg(feature = "std")]
+impl<T> Future for MockNextMessageFuture<'_, T> {
+    type Output = WaitResult<T>;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Nothing can add a result while the subscriber is borrowed, so there's no need to wake.
+        match self.get_mut().subscriber.try_next_message() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                waiting::record("MockSubscriber::next_message()");
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// A mocked version of [`embassy_sync::pubsub::PubSubChannel`] that can be used in its place for
+/// unit tests.
+///
+/// Every publisher it creates is the same [`MockPublisher`], so the messages published by all of
+/// them are recorded together. The subscribers are prepared by the test with
+/// [`Self::with_subscriber()`] and handed out in order, and once they have all been handed out
+/// the subscribers it creates never receive anything. It counts the publishers and subscribers
+/// that were created, and can refuse to create more than the channel would have room for.
+///
+/// # Panics
+///
+/// The prepared subscribers that were never handed out are dropped with the channel, so this
+/// panics if any of them have scripted results.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::sync::{MockPubSubChannel, MockSubscriber, PubSubChannel, Publisher, Subscriber};
+/// use embassy_sync::pubsub::Error;
+///
+/// // Forward the first message to a second topic.
+/// fn relay(input: &impl PubSubChannel<u8>, output: &impl PubSubChannel<u8>) -> Result<(), Error> {
+///     let mut subscriber = input.subscriber()?;
+///     if let Some(message) = subscriber.try_next_message_pure() {
+///         output.publisher()?.publish_immediate(message);
+///     }
+///     Ok(())
+/// }
+///
+/// let input = MockPubSubChannel::new().with_subscriber(MockSubscriber::with_messages([7]));
+/// let output = MockPubSubChannel::new().with_max_publishers(1);
+///
+/// assert_eq!(relay(&input, &output), Ok(()));
+/// assert_eq!(relay(&input, &output), Ok(()));
+///
+/// assert_eq!(input.subscriber_count(), 2);
+/// assert_eq!(output.publisher_count(), 1);
+/// assert_eq!(output.published(), [7]);
+///
+/// // The output has no room for another publisher.
+/// assert_eq!(output.publisher().err(), Some(Error::MaximumPublishersReached));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockPubSubChannel<T> {
+    /// The publisher shared by all of the publishers created.
+    publisher: MockPublisher<T>,
+
+    /// The subscribers still to be created, in order.
+    subscribers: Mutex<VecDeque<MockSubscriber<T>>>,
+
+    /// The number of publishers created so far.
+    publisher_count: AtomicUsize,
+
+    /// The number of subscribers created so far.
+    subscriber_count: AtomicUsize,
+
+    /// The number of publishers that can be created, if limited.
+    max_publishers: Option<usize>,
+
+    /// The number of subscribers that can be created, if limited.
+    max_subscribers: Option<usize>,
+}
+
+#[cfg(feature = "std")]
+impl<T> MockPubSubChannel<T> {
+    /// Create a [`MockPubSubChannel`] without any prepared subscribers, that creates as many
+    /// publishers and subscribers as asked for.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            publisher: MockPublisher::new(),
+            subscribers: Mutex::new(VecDeque::new()),
+            publisher_count: AtomicUsize::new(0),
+            subscriber_count: AtomicUsize::new(0),
+            max_publishers: None,
+            max_subscribers: None,
+        }
+    }
+
+    /// Add the `subscriber` to be handed out after the other prepared subscribers.
+    pub fn with_subscriber(mut self, subscriber: MockSubscriber<T>) -> Self {
+        self.subscribers.get_mut().unwrap().push_back(subscriber);
+        self
+    }
+
+    /// Refuse to create more than `max` publishers, as a real channel with `PUBS` set to `max`.
+    ///
+    /// The mock can't tell when the code drops a publisher, so every publisher created counts
+    /// towards the limit.
+    pub const fn with_max_publishers(mut self, max: usize) -> Self {
+        self.max_publishers = Some(max);
+        self
+    }
+
+    /// Refuse to create more than `max` subscribers, as a real channel with `SUBS` set to `max`.
+    ///
+    /// Every subscriber created counts towards the limit, even after it is dropped.
+    pub const fn with_max_subscribers(mut self, max: usize) -> Self {
+        self.max_subscribers = Some(max);
+        self
+    }
+
+    /// The number of publishers created so far.
+    pub fn publisher_count(&self) -> usize {
+        self.publisher_count.load(Ordering::Relaxed)
+    }
+
+    /// The number of subscribers created so far.
+    pub fn subscriber_count(&self) -> usize {
+        self.subscriber_count.load(Ordering::Relaxed)
+    }
+
+    /// Set whether the message queue is full, see [`MockPublisher::set_full()`].
+    pub fn set_full(&self, is_full: bool) {
+        self.publisher.set_full(is_full);
+    }
+
+    /// The messages published to this [`MockPubSubChannel`], in call order.
+    pub fn published(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.publisher.published()
+    }
+
+    /// The messages published to this [`MockPubSubChannel`] by the `method`, in call order.
+    pub fn published_by(&self, method: PublishMethod) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.publisher.published_by(method)
+    }
+
+    /// The number of messages that [`Publisher::try_publish()`] gave back as the queue was full.
+    pub fn rejected_count(&self) -> usize {
+        self.publisher.rejected_count()
+    }
+
+    /// Count another creation with the `count`, unless that would be more than the `max`.
+    fn create(count: &AtomicUsize, max: Option<usize>) -> bool {
+        count
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |created| {
+                max.map_or(true, |max| created < max).then_some(created + 1)
+            })
+            .is_ok()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> PubSubChannel<T> for MockPubSubChannel<T> {
+    type Publisher<'a> = &'a MockPublisher<T> where Self: 'a;
+    type Subscriber<'a> = MockSubscriber<T> where Self: 'a;
+
+    /// Create another reference to the shared [`MockPublisher`], unless the maximum number of
+    /// publishers have been created.
+    fn publisher(&self) -> Result<Self::Publisher<'_>, Error> {
+        if !Self::create(&self.publisher_count, self.max_publishers) {
+            return Err(Error::MaximumPublishersReached);
+        }
+
+        Ok(&self.publisher)
+    }
+
+    /// Hand out the next prepared [`MockSubscriber`], or one that never receives anything if
+    /// there are none left, unless the maximum number of subscribers have been created.
+    fn subscriber(&self) -> Result<Self::Subscriber<'_>, Error> {
+        if !Self::create(&self.subscriber_count, self.max_subscribers) {
+            return Err(Error::MaximumSubscribersReached);
+        }
+
+        let prepared = self.subscribers.lock().unwrap().pop_front();
+        Ok(prepared.unwrap_or_else(MockSubscriber::new))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::WakeProbe;
+    use core::pin::pin;
+    use embassy_futures::block_on;
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    use futures_util::task::noop_waker_ref;
+
+    async fn forward_pure(
+        input: &mut impl Subscriber<u32>,
+        output: &impl Publisher<u32>,
+        count: usize,
+    ) {
+        for _ in 0..count {
+            output.publish(input.next_message_pure().await).await;
+        }
+    }
+
+    #[test]
+    fn embassy_publisher_and_subscriber() {
+        let channel = EmbassyPubSubChannel::<NoopRawMutex, u32, 2, 2, 2>::new();
+        let publisher = channel.publisher().unwrap();
+        let mut subscriber = channel.subscriber().unwrap();
+        let mut dyn_subscriber = channel.dyn_subscriber().unwrap();
+
+        block_on(Publisher::publish(&publisher, 1));
+        Publisher::publish_immediate(&channel.dyn_publisher().unwrap(), 2);
+        assert_eq!(Publisher::try_publish(&publisher, 3), Err(3));
+
+        assert_eq!(
+            block_on(Subscriber::next_message(&mut subscriber)),
+            WaitResult::Message(1)
+        );
+        assert_eq!(Subscriber::try_next_message_pure(&mut subscriber), Some(2));
+        assert_eq!(
+            block_on(Subscriber::next_message_pure(&mut dyn_subscriber)),
+            1
+        );
+    }
+
+    #[test]
+    fn embassy_subscriber_lags() {
+        let channel = EmbassyPubSubChannel::<NoopRawMutex, u32, 1, 1, 1>::new();
+        let mut subscriber = channel.subscriber().unwrap();
+        let publisher = channel.immediate_publisher();
+        publisher.publish_immediate(1);
+        publisher.publish_immediate(2);
+
+        assert_eq!(
+            Subscriber::try_next_message(&mut subscriber),
+            Some(WaitResult::Lagged(1))
+        );
+        assert_eq!(
+            Subscriber::try_next_message(&mut subscriber),
+            Some(WaitResult::Message(2))
+        );
+    }
+
+    #[test]
+    fn embassy_channel_limits_publishers_and_subscribers() {
+        let channel = EmbassyPubSubChannel::<NoopRawMutex, u32, 2, 1, 1>::new();
+        let publisher = PubSubChannel::publisher(&channel).unwrap();
+        let mut subscriber = PubSubChannel::subscriber(&channel).unwrap();
+
+        assert_eq!(
+            PubSubChannel::publisher(&channel).err(),
+            Some(Error::MaximumPublishersReached)
+        );
+        assert_eq!(
+            PubSubChannel::subscriber(&channel).err(),
+            Some(Error::MaximumSubscribersReached)
+        );
+
+        Publisher::publish_immediate(&publisher, 5);
+        assert_eq!(Subscriber::try_next_message_pure(&mut subscriber), Some(5));
+    }
+
+    #[test]
+    fn mock_publish_waits_while_full() {
+        let publisher = MockPublisher::new();
+        publisher.set_full(true);
+        let mut probe = WakeProbe::new(publisher.publish(1));
+
+        probe.assert_pending();
+        publisher.publish_immediate(2);
+        publisher.set_full(false);
+
+        probe.assert_ready();
+        assert_eq!(publisher.published(), [2, 1]);
+        assert_eq!(publisher.published_by(PublishMethod::Publish), [1]);
+    }
+
+    #[test]
+    fn mock_try_publish_fails_while_full() {
+        let publisher = MockPublisher::new();
+        publisher.set_full(true);
+
+        assert_eq!(publisher.try_publish(1), Err(1));
+        publisher.set_full(false);
+        assert_eq!(publisher.try_publish(2), Ok(()));
+
+        assert_eq!(publisher.published_by(PublishMethod::TryPublish), [2]);
+        assert_eq!(publisher.rejected_count(), 1);
+    }
+
+    #[test]
+    fn mock_channel_hands_out_prepared_subscribers_in_order() {
+        let channel = MockPubSubChannel::new()
+            .with_subscriber(MockSubscriber::with_messages([1]))
+            .with_subscriber(MockSubscriber::with_messages([2]));
+
+        let mut first = channel.subscriber().unwrap();
+        let mut second = channel.subscriber().unwrap();
+        let mut third = channel.subscriber().unwrap();
+
+        assert_eq!(first.try_next_message_pure(), Some(1));
+        assert_eq!(second.try_next_message_pure(), Some(2));
+        assert_eq!(third.try_next_message_pure(), None);
+        assert_eq!(channel.subscriber_count(), 3);
+    }
+
+    #[test]
+    fn mock_channel_limits_subscribers() {
+        let channel = MockPubSubChannel::<u32>::new().with_max_subscribers(1);
+
+        drop(channel.subscriber().unwrap());
+
+        assert_eq!(
+            channel.subscriber().err(),
+            Some(Error::MaximumSubscribersReached)
+        );
+        assert_eq!(channel.subscriber_count(), 1);
+    }
+
+    #[test]
+    fn mock_channel_publishers_share_the_record() {
+        let channel = MockPubSubChannel::new();
+        channel.set_full(true);
+
+        channel.publisher().unwrap().publish_immediate(1);
+        assert_eq!(channel.publisher().unwrap().try_publish(2), Err(2));
+
+        assert_eq!(channel.publisher_count(), 2);
+        assert_eq!(channel.published_by(PublishMethod::PublishImmediate), [1]);
+        assert_eq!(channel.rejected_count(), 1);
+    }
+
+    #[test]
+    fn mock_skips_lags_for_pure() {
+        let mut input = MockSubscriber::script([
+            WaitResult::Message(1),
+            WaitResult::Lagged(5),
+            WaitResult::Message(2),
+        ]);
+        let output = MockPublisher::new();
+
+        block_on(forward_pure(&mut input, &output, 2));
+
+        assert_eq!(output.published(), [1, 2]);
+        input.done().unwrap();
+    }
+
+    #[test]
+    fn lag_is_returned_by_next_message() {
+        let mut subscriber = MockSubscriber::<u32>::new();
+        subscriber.push_lagged(7);
+
+        assert_eq!(block_on(subscriber.next_message()), WaitResult::Lagged(7));
+        subscriber.done().unwrap();
+    }
+
+    #[test]
+    fn next_message_is_pending_when_empty() {
+        let mut subscriber = MockSubscriber::<u32>::new();
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        let mut next = pin!(subscriber.next_message());
+        assert_eq!(next.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(next.as_mut().poll(&mut cx), Poll::Pending);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 1 more wait result(s) to be received")]
+    fn unreceived_lag_just_drop() {
+        let mut subscriber = MockSubscriber::<u32>::with_messages([1]);
+        subscriber.push_lagged(1);
+        subscriber.try_next_message();
+    }
+}
@@ -0,0 +1,239 @@
+//! A [`RawMutex`] for unit tests, so the real `embassy-sync` types can be used in tests without
+//! mocking them.
+//!
+//! The raw mutexes provided by `embassy-sync` don't suit host tests:
+//! [`NoopRawMutex`](embassy_sync::blocking_mutex::raw::NoopRawMutex) isn't [`Sync`], so the types
+//! using it can't be shared with another thread, and
+//! [`CriticalSectionRawMutex`](embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex) needs
+//! a `critical-section` implementation to be linked in. The [`TestRawMutex`] is built on the
+//! standard library instead, and there are aliases for the common types that use it.
+//!
+//! # Examples
+//! ```
+//! use embassy_mock::sync::{Receiver, Sender};
+//!
+//! async fn forward(input: impl Receiver<u8>, output: impl Sender<u8>) {
+//!     output.send(input.receive().await).await;
+//! }
+//!
+//! # test_forward();
+//! // The unit tests that use real channels instead of mocks.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_futures::block_on;
+//!     use embassy_mock::sync::TestChannel;
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_forward() {
+//!         let input = TestChannel::<u8, 2>::new();
+//!         let output = TestChannel::<u8, 2>::new();
+//!         input.try_send(5).unwrap();
+//!
+//!         block_on(forward(input.receiver(), output.sender()));
+//!
+//!         assert_eq!(output.try_receive(), Ok(5));
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use embassy_sync::{
+    blocking_mutex::raw::RawMutex, channel::Channel, mutex::Mutex, signal::Signal, watch::Watch,
+};
+use std::{
+    sync::{self, Condvar, PoisonError},
+    thread::{self, ThreadId},
+};
+
+/// A [`RawMutex`] built on the standard library for unit tests, to be used as the `M` parameter
+/// of the `embassy-sync` types.
+///
+/// It is [`Send`] and [`Sync`], so the types using it can be shared between the test thread and
+/// the threads it spawns. As required of a [`RawMutex`] it can be locked again by the thread
+/// that has it locked, while other threads wait until it is unlocked.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::sync::{Signal, TestRawMutex};
+/// use embassy_sync::signal::Signal as EmbassySignal;
+/// use std::thread;
+///
+/// static READY: EmbassySignal<TestRawMutex, u8> = EmbassySignal::new();
+///
+/// thread::spawn(|| READY.signal(3)).join().unwrap();
+/// assert_eq!(READY.try_take(), Some(3));
+/// ```
+#[derive(Debug)]
+pub struct TestRawMutex {
+    /// The thread that has the mutex locked and how many times it has locked it.
+    state: sync::Mutex<LockState>,
+
+    /// Notified when the mutex is unlocked, for the threads waiting to lock it.
+    unlocked: Condvar,
+}
+
+/// The state of a [`TestRawMutex`].
+#[derive(Debug)]
+struct LockState {
+    /// The thread that has the mutex locked, [`None`] if it is unlocked.
+    owner: Option<ThreadId>,
+
+    /// The number of times the owner has locked the mutex without unlocking it.
+    depth: usize,
+}
+
+impl TestRawMutex {
+    /// Create an unlocked [`TestRawMutex`].
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            state: sync::Mutex::new(LockState {
+                owner: None,
+                depth: 0,
+            }),
+            unlocked: Condvar::new(),
+        }
+    }
+
+    /// Lock the state, ignoring poisoning as it is always consistent between the statements.
+    fn state(&self) -> sync::MutexGuard<'_, LockState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// SAFETY: Only the thread in `owner` can run a locked section, every other thread waits until
+// `owner` is cleared when the owner leaves its outermost section.
+unsafe impl RawMutex for TestRawMutex {
+    // Required by the trait, it is only used to create new mutexes.
+    #[allow(clippy::declare_interior_mutable_const)]
+    const INIT: Self = Self::new();
+
+    fn lock<R>(&self, f: impl FnOnce() -> R) -> R {
+        let current = thread::current().id();
+        let mut state = self.state();
+        if state.owner != Some(current) {
+            while state.owner.is_some() {
+                state = self
+                    .unlocked
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+            state.owner = Some(current);
+        }
+        state.depth += 1;
+        drop(state);
+
+        // Unlocks even if `f` panics, so a failed assertion doesn't deadlock other threads.
+        let _unlock = Unlock { mutex: self };
+        f()
+    }
+}
+
+/// Leaves a locked section of a [`TestRawMutex`] when dropped.
+struct Unlock<'a> {
+    /// The mutex to unlock.
+    mutex: &'a TestRawMutex,
+}
+
+impl Drop for Unlock<'_> {
+    fn drop(&mut self) {
+        let mut state = self.mutex.state();
+        state.depth -= 1;
+        if state.depth == 0 {
+            state.owner = None;
+            self.mutex.unlocked.notify_one();
+        }
+    }
+}
+
+/// An [`embassy_sync::channel::Channel`] that uses the [`TestRawMutex`].
+pub type TestChannel<T, const N: usize> = Channel<TestRawMutex, T, N>;
+
+/// An [`embassy_sync::mutex::Mutex`] that uses the [`TestRawMutex`].
+pub type TestMutex<T> = Mutex<TestRawMutex, T>;
+
+/// An [`embassy_sync::signal::Signal`] that uses the [`TestRawMutex`].
+pub type TestSignal<T> = Signal<TestRawMutex, T>;
+
+/// An [`embassy_sync::watch::Watch`] that uses the [`TestRawMutex`].
+pub type TestWatch<T, const N: usize> = Watch<TestRawMutex, T, N>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::{Receiver, Sender};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use embassy_futures::block_on;
+    use std::vec::Vec;
+
+    #[test]
+    fn can_lock_reentrantly() {
+        let mutex = TestRawMutex::new();
+
+        let value = mutex.lock(|| mutex.lock(|| mutex.lock(|| 7)));
+
+        assert_eq!(value, 7);
+        assert!(mutex.state().owner.is_none());
+    }
+
+    #[test]
+    fn excludes_other_threads() {
+        static MUTEX: TestRawMutex = TestRawMutex::new();
+        static INSIDE: AtomicUsize = AtomicUsize::new(0);
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                thread::spawn(|| {
+                    for _ in 0..100 {
+                        MUTEX.lock(|| {
+                            assert_eq!(INSIDE.fetch_add(1, Ordering::SeqCst), 0);
+                            thread::yield_now();
+                            INSIDE.fetch_sub(1, Ordering::SeqCst);
+                        });
+                    }
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn unlocks_when_section_panics() {
+        let mutex = TestRawMutex::new();
+
+        let result = std::panic::catch_unwind(|| mutex.lock(|| panic!("failed assertion")));
+
+        assert!(result.is_err());
+        assert!(mutex.state().owner.is_none());
+        thread::scope(|scope| scope.spawn(|| mutex.lock(|| ())).join().unwrap());
+    }
+
+    #[test]
+    fn aliases_work_with_traits() {
+        let channel = TestChannel::<u8, 1>::new();
+        let mutex = TestMutex::new(1);
+        let signal = TestSignal::new();
+        let watch = TestWatch::<u8, 1>::new();
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                block_on(Sender::send(&channel, 2));
+                *block_on(mutex.lock()) += 1;
+                signal.signal(3);
+                watch.sender().send(4);
+            });
+        });
+
+        assert_eq!(block_on(Receiver::receive(&channel)), 2);
+        assert_eq!(*block_on(mutex.lock()), 2);
+        assert_eq!(block_on(signal.wait()), 3);
+        assert_eq!(watch.try_get(), Some(4));
+    }
+}
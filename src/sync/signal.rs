@@ -0,0 +1,767 @@
+//! Traits and mocked types to allow unit testing functions that use an
+//! [`embassy_sync::signal::Signal`].
+//!
+//! # Examples
+//! ```
+//! use embassy_mock::sync::Signal;
+//!
+//! // Generic over the `Signal` trait
+//! fn report_temperatures<S: Signal<i16>>(alarm: &S, readings: &[i16]) {
+//!     for &reading in readings.iter().filter(|&&reading| reading > 50) {
+//!         alarm.signal(reading);
+//!     }
+//! }
+//!
+//! # test_report_temperatures();
+//! // The unit tests that use the `MockSignal`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_mock::sync::MockSignal;
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_report_temperatures() {
+//!         let alarm = MockSignal::expect_values([60, 55]);
+//!
+//!         report_temperatures(&alarm, &[20, 60, 40, 55]);
+//!
+//!         alarm.done().unwrap();
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use core::future::Future;
+use embassy_sync::{blocking_mutex::raw::RawMutex, signal::Signal as EmbassySignal};
+#[cfg(feature = "std")]
+use {
+    crate::waiting,
+    core::{
+        fmt,
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    },
+    snafu::prelude::*,
+    std::{boxed::Box, collections::VecDeque, sync::Mutex, vec::Vec},
+};
+
+/// The trait to replace the [`embassy_sync::signal::Signal`] in code to allow the [`MockSignal`]
+/// to be used in its place for tests.
+pub trait Signal<T> {
+    /// Wrapper for [`embassy_sync::signal::Signal::signal()`].
+    fn signal(&self, value: T);
+
+    /// Wrapper for [`embassy_sync::signal::Signal::reset()`].
+    fn reset(&self);
+
+    /// Wrapper for [`embassy_sync::signal::Signal::wait()`].
+    fn wait(&self) -> impl Future<Output = T>;
+
+    /// Wrapper for [`embassy_sync::signal::Signal::try_take()`].
+    fn try_take(&self) -> Option<T>;
+
+    /// Wrapper for [`embassy_sync::signal::Signal::signaled()`].
+    fn signaled(&self) -> bool;
+}
+
+impl<M: RawMutex, T> Signal<T> for EmbassySignal<M, T> {
+    /// Mark this Signal as signaled.
+    fn signal(&self, value: T) {
+        self.signal(value);
+    }
+
+    /// Remove the queued value in this `Signal`, if any.
+    fn reset(&self) {
+        self.reset();
+    }
+
+    /// Future that completes when this Signal has been signaled, taking the value out of the
+    /// signal.
+    fn wait(&self) -> impl Future<Output = T> {
+        self.wait()
+    }
+
+    /// Non-blocking method to retrieve the value of this signal.
+    fn try_take(&self) -> Option<T> {
+        self.try_take()
+    }
+
+    /// Non-blocking method to check whether this signal has been signaled. This does not clear
+    /// the signal.
+    fn signaled(&self) -> bool {
+        self.signaled()
+    }
+}
+
+/// The errors that are reported by [`MockSignal`].
+#[cfg(feature = "std")]
+#[derive(Debug, Snafu, PartialEq)]
+pub enum MockSignalError {
+    /// [`Signal::signal()`] was not called the expected number of times.
+    #[snafu(display("expected to signal {expected} time(s), actually signalled {actual}"))]
+    WrongNumberOfSignals {
+        /// The number of signals that were expected.
+        expected: usize,
+
+        /// The number of times [`Signal::signal()`] was called.
+        actual: usize,
+    },
+
+    /// The value of a signal didn't match the value or predicate that was expected.
+    #[snafu(display("expected the value of signal {index} to match, actually it didn't"))]
+    UnmatchedValue {
+        /// The index of the signal, in call order.
+        index: usize,
+    },
+
+    /// A mock that was created to never be used was used.
+    #[snafu(display("expected to never be used, actually called {method}"))]
+    UnexpectedUse {
+        /// The first method that was called.
+        method: &'static str,
+    },
+
+    /// Some of the results scripted for [`Signal::try_take()`] were never taken.
+    #[snafu(display("expected {remaining} more try_take() result(s) to be taken"))]
+    UntakenResults {
+        /// The number of scripted results that were never taken.
+        remaining: usize,
+    },
+}
+
+/// A predicate that the value of a signal is expected to match.
+#[cfg(feature = "std")]
+type ValueMatcher<T> = Box<dyn Fn(&T) -> bool + Send + Sync>;
+
+/// A mocked version of [`embassy_sync::signal::Signal`] that can be used in its place for unit
+/// tests.
+///
+/// It behaves as a real signal, so [`Signal::wait()`] completes with the last value signalled,
+/// and it records every value passed to [`Signal::signal()`], including the calls made by the
+/// test itself.
+/// With [`Self::with_manual_trigger()`] the wait only completes when the test calls
+/// [`Self::trigger()`] instead, and with [`Self::script_try_take()`] the results of
+/// [`Signal::try_take()`] are chosen by the test.
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if [`Signal::signal()`] was not
+/// called the expected number of times or with the expected values, if it was used after being
+/// created by [`Self::expect_never()`], or if any of the results given to
+/// [`Self::script_try_take()`] were never taken.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::sync::{MockSignal, Signal};
+///
+/// let signal = MockSignal::expect(2);
+///
+/// signal.signal(1);
+/// signal.signal(2);
+/// assert_eq!(block_on(signal.wait()), 2);
+///
+/// assert_eq!(signal.signals(), [1, 2]);
+/// signal.done().unwrap();
+/// ```
+#[cfg(feature = "std")]
+pub struct MockSignal<T> {
+    /// The signalled value and the waiting wait, shared by every call.
+    state: Mutex<SignalState<T>>,
+
+    /// The number of times [`Signal::signal()`] is expected to be called.
+    expected: usize,
+
+    /// The predicates that the value of each signal is expected to match, by the index of the
+    /// signal.
+    matchers: Vec<(usize, ValueMatcher<T>)>,
+
+    /// Is this mock expected to never be used, see [`Self::expect_never()`].
+    never_used: bool,
+
+    /// Is the value only set by [`Self::trigger()`], see [`Self::with_manual_trigger()`].
+    is_manual: bool,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+/// The state of a [`MockSignal`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct SignalState<T> {
+    /// The value that was signalled and not yet taken.
+    value: Option<T>,
+
+    /// Every value passed to [`Signal::signal()`], in call order.
+    signals: Vec<T>,
+
+    /// The results still to be returned by [`Signal::try_take()`], in order.
+    try_takes: VecDeque<Option<T>>,
+
+    /// The waker of the wait that is waiting for a value.
+    waker: Option<Waker>,
+
+    /// The first method of [`Signal`] that was called, if any.
+    first_use: Option<&'static str>,
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug> fmt::Debug for MockSignal<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockSignal")
+            .field("state", &self.state)
+            .field("expected", &self.expected)
+            .field("matchers", &self.matchers.len())
+            .field("never_used", &self.never_used)
+            .field("is_manual", &self.is_manual)
+            .field("is_done", &self.is_done)
+            .finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> MockSignal<T> {
+    /// Create a [`MockSignal`] that expects [`Signal::signal()`] to be called `expected` times,
+    /// with any values.
+    pub const fn expect(expected: usize) -> Self {
+        Self {
+            state: Mutex::new(SignalState {
+                value: None,
+                signals: Vec::new(),
+                try_takes: VecDeque::new(),
+                waker: None,
+                first_use: None,
+            }),
+            expected,
+            matchers: Vec::new(),
+            never_used: false,
+            is_manual: false,
+            is_done: false,
+        }
+    }
+
+    /// Create a [`MockSignal`] that is expected to never be used, to check that the code under
+    /// test doesn't signal, wait for, or even look at it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockSignal, MockSignalError, Signal};
+    ///
+    /// let signal = MockSignal::<u8>::expect_never();
+    /// signal.signaled();
+    ///
+    /// let expected = Err(MockSignalError::UnexpectedUse { method: "Signal::signaled()" });
+    /// assert_eq!(signal.done(), expected);
+    /// ```
+    pub const fn expect_never() -> Self {
+        let mut signal = Self::expect(0);
+        signal.never_used = true;
+        signal
+    }
+
+    /// Create a [`MockSignal`] that expects [`Signal::signal()`] to be called with exactly the
+    /// `values`, in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockSignal, MockSignalError, Signal};
+    ///
+    /// let signal = MockSignal::expect_values(["start", "stop"]);
+    /// signal.signal("start");
+    /// signal.signal("pause");
+    ///
+    /// let expected = Err(MockSignalError::UnmatchedValue { index: 1 });
+    /// assert_eq!(signal.done(), expected);
+    /// ```
+    pub fn expect_values(values: impl IntoIterator<Item = T>) -> Self
+    where
+        T: PartialEq + Send + Sync + 'static,
+    {
+        let mut signal = Self::expect(0);
+        for (index, value) in values.into_iter().enumerate() {
+            signal = signal.expect_matching(index, move |actual| *actual == value);
+        }
+        signal.expected = signal.matchers.len();
+        signal
+    }
+
+    /// Expect the value of the signal at `index`, in call order, to match the `predicate`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockSignal, Signal};
+    ///
+    /// let signal = MockSignal::expect(2)
+    ///     .expect_matching(0, |value| *value < 10)
+    ///     .expect_matching(1, |value| *value >= 10);
+    ///
+    /// signal.signal(3);
+    /// signal.signal(30);
+    ///
+    /// signal.done().unwrap();
+    /// ```
+    pub fn expect_matching(
+        mut self,
+        index: usize,
+        predicate: impl Fn(&T) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.matchers.push((index, Box::new(predicate)));
+        self
+    }
+
+    /// Make [`Signal::signal()`] only record the value, so [`Signal::wait()`] stays pending until
+    /// the test calls [`Self::trigger()`].
+    ///
+    /// This lets a test step through code that waits for the signal, even when the code signals
+    /// it too, by choosing when and with what value the wait completes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::{future::Future, pin::pin, task::{Context, Poll}};
+    /// use embassy_mock::sync::{MockSignal, Signal};
+    /// use futures_util::task::noop_waker_ref;
+    ///
+    /// async fn acknowledge(request: &impl Signal<u8>) -> u8 {
+    ///     request.signal(1);
+    ///     request.wait().await
+    /// }
+    ///
+    /// let request = MockSignal::expect_values([1]).with_manual_trigger();
+    /// let mut cx = Context::from_waker(noop_waker_ref());
+    /// let mut acknowledge = pin!(acknowledge(&request));
+    /// assert_eq!(acknowledge.as_mut().poll(&mut cx), Poll::Pending);
+    ///
+    /// request.trigger(7);
+    /// assert_eq!(acknowledge.poll(&mut cx), Poll::Ready(7));
+    /// ```
+    pub const fn with_manual_trigger(mut self) -> Self {
+        self.is_manual = true;
+        self
+    }
+
+    /// Make each call to [`Signal::try_take()`] return the next of the `results`, in order,
+    /// instead of taking the signalled value.
+    ///
+    /// The signalled value is left in place, so it is still there for [`Signal::wait()`]. Once
+    /// the results have all been taken, [`Signal::try_take()`] takes the signalled value again.
+    /// This lets a test step a main loop that polls the signal through the iterations where it
+    /// was and wasn't signalled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockSignal, Signal};
+    ///
+    /// // Count the loop iterations until asked to stop.
+    /// fn run(stop: &impl Signal<()>) -> usize {
+    ///     let mut iterations = 0;
+    ///     while stop.try_take().is_none() {
+    ///         iterations += 1;
+    ///     }
+    ///     iterations
+    /// }
+    ///
+    /// let stop = MockSignal::expect(0).script_try_take([None, None, None, Some(())]);
+    ///
+    /// assert_eq!(run(&stop), 3);
+    /// stop.done().unwrap();
+    /// ```
+    pub fn script_try_take(mut self, results: impl IntoIterator<Item = Option<T>>) -> Self {
+        self.state.get_mut().unwrap().try_takes.extend(results);
+        self
+    }
+
+    /// Signal the `value` from the test, waking the wait, without recording it as a call to
+    /// [`Signal::signal()`].
+    pub fn trigger(&self, value: T) {
+        let mut state = self.lock();
+        state.value = Some(value);
+        if let Some(waker) = state.waker.take() {
+            waiting::waking("MockSignal::trigger()", || waker.wake());
+        }
+    }
+
+    /// Every value passed to [`Signal::signal()`] on this [`MockSignal`], in call order.
+    pub fn signals(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.lock().signals.clone()
+    }
+
+    /// The number of times [`Signal::signal()`] has been called on this [`MockSignal`].
+    pub fn times_signalled(&self) -> usize {
+        self.lock().signals.len()
+    }
+
+    /// Check that [`Signal::signal()`] was called the expected number of times with the expected
+    /// values, and that all of the results given to [`Self::script_try_take()`] were taken.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockSignal, MockSignalError, Signal};
+    ///
+    /// let signal = MockSignal::expect(2);
+    /// signal.signal(());
+    ///
+    /// let expected = Err(MockSignalError::WrongNumberOfSignals { expected: 2, actual: 1 });
+    /// assert_eq!(signal.done(), expected);
+    ///
+    /// // This doesn't panic when `signal` is dropped as `signal.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockSignalError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that the signal wasn't used if it is expected to never be, that the values matched,
+    /// that [`Signal::signal()`] was called the expected number of times and that the scripted
+    /// results were taken.
+    fn check(&self) -> Result<(), MockSignalError> {
+        let state = self.lock();
+        if let (true, Some(method)) = (self.never_used, state.first_use) {
+            return UnexpectedUseSnafu { method }.fail();
+        }
+
+        for (index, predicate) in &self.matchers {
+            if let Some(value) = state.signals.get(*index) {
+                ensure!(predicate(value), UnmatchedValueSnafu { index: *index });
+            }
+        }
+
+        let actual = state.signals.len();
+        ensure!(
+            actual == self.expected,
+            WrongNumberOfSignalsSnafu {
+                expected: self.expected,
+                actual,
+            }
+        );
+
+        let remaining = state.try_takes.len();
+        ensure!(remaining == 0, UntakenResultsSnafu { remaining });
+
+        Ok(())
+    }
+
+    /// Lock the state for a call to the `method`, recording the call if it is the first.
+    fn lock_for(&self, method: &'static str) -> std::sync::MutexGuard<'_, SignalState<T>> {
+        let mut state = self.lock();
+        state.first_use.get_or_insert(method);
+        state
+    }
+
+    /// Lock the state, which is never poisoned as the mock doesn't panic while holding the lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, SignalState<T>> {
+        self.state.lock().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for MockSignal<T> {
+    /// If [`Self::done()`] has not been called before being dropped then check that
+    /// [`Signal::signal()`] was called the expected number of times with the expected values, and
+    /// that the scripted results were taken.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> Signal<T> for MockSignal<T> {
+    /// Record the `value` and replace the signalled value with it, waking the wait, unless it is
+    /// only set by [`MockSignal::trigger()`].
+    fn signal(&self, value: T) {
+        let mut state = self.lock_for("Signal::signal()");
+        state.signals.push(value.clone());
+        if !self.is_manual {
+            state.value = Some(value);
+            if let Some(waker) = state.waker.take() {
+                waiting::waking("MockSignal::signal()", || waker.wake());
+            }
+        }
+    }
+
+    /// Remove the signalled value, if any.
+    fn reset(&self) {
+        self.lock_for("Signal::reset()").value = None;
+    }
+
+    /// Take the signalled value, waiting until there is one.
+    fn wait(&self) -> impl Future<Output = T> {
+        drop(self.lock_for("Signal::wait()"));
+        MockWaitFuture { signal: self }
+    }
+
+    /// Return the next result given to [`MockSignal::script_try_take()`], otherwise take the
+    /// signalled value, if any.
+    fn try_take(&self) -> Option<T> {
+        let mut state = self.lock_for("Signal::try_take()");
+        match state.try_takes.pop_front() {
+            Some(result) => result,
+            None => state.value.take(),
+        }
+    }
+
+    /// Is there a signalled value.
+    fn signaled(&self) -> bool {
+        self.lock_for("Signal::signaled()").value.is_some()
+    }
+}
+
+/// The future returned by [`Signal::wait()`] for a [`MockSignal`].
+#[cfg(feature = "std")]
+struct MockWaitFuture<'a, T> {
+    /// The signal that is waited for.
+    signal: &'a MockSignal<T>,
+}
+
+#[cfg(feature = "std")]
+impl<T> Future for MockWaitFuture<'_, T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.signal.lock();
+        match state.value.take() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                waiting::record("MockSignal::wait()");
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::pin::pin;
+    use embassy_futures::block_on;
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+    use futures_util::task::noop_waker_ref;
+
+    fn signal_twice<S: Signal<u32>>(signal: &S) {
+        signal.signal(1);
+        signal.signal(2);
+    }
+
+    #[test]
+    fn embassy_signal_keeps_last_value() {
+        let signal = EmbassySignal::<NoopRawMutex, u32>::new();
+
+        signal_twice(&signal);
+
+        assert!(Signal::signaled(&signal));
+        assert_eq!(block_on(Signal::wait(&signal)), 2);
+        assert_eq!(Signal::try_take(&signal), None);
+    }
+
+    #[test]
+    fn mock_records_every_value() {
+        let signal = MockSignal::expect(2);
+
+        signal_twice(&signal);
+
+        assert_eq!(signal.signals(), [1, 2]);
+        assert_eq!(signal.times_signalled(), 2);
+        assert_eq!(signal.try_take(), Some(2));
+        assert!(!signal.signaled());
+        signal.done().unwrap();
+    }
+
+    #[test]
+    fn wait_is_woken_by_signal() {
+        let signal = MockSignal::expect(1);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        {
+            let mut wait = pin!(signal.wait());
+            assert_eq!(wait.as_mut().poll(&mut cx), Poll::Pending);
+
+            signal.signal(5);
+            assert_eq!(wait.as_mut().poll(&mut cx), Poll::Ready(5));
+        }
+
+        signal.done().unwrap();
+    }
+
+    #[test]
+    fn reset_removes_value_but_not_history() {
+        let signal = MockSignal::expect(1);
+        signal.signal(5);
+
+        signal.reset();
+
+        assert_eq!(signal.try_take(), None);
+        assert_eq!(signal.signals(), [5]);
+        signal.done().unwrap();
+    }
+
+    #[test]
+    fn expected_values_match() {
+        let signal = MockSignal::expect_values([1, 2]);
+
+        signal_twice(&signal);
+
+        signal.done().unwrap();
+    }
+
+    #[test]
+    fn wrong_value_is_reported_before_count() {
+        let signal = MockSignal::expect_values([1, 3, 4]);
+
+        signal_twice(&signal);
+
+        let expected = Err(MockSignalError::UnmatchedValue { index: 1 });
+        assert_eq!(signal.done(), expected);
+    }
+
+    #[test]
+    fn missing_values_are_wrong_count() {
+        let signal = MockSignal::expect(3).expect_matching(2, |value| *value == 3);
+
+        signal_twice(&signal);
+
+        let expected = Err(MockSignalError::WrongNumberOfSignals {
+            expected: 3,
+            actual: 2,
+        });
+        assert_eq!(signal.done(), expected);
+    }
+
+    #[test]
+    fn unused_signal_expected_never() {
+        let signal = MockSignal::<u32>::expect_never();
+
+        assert_eq!(signal.times_signalled(), 0);
+        signal.done().unwrap();
+    }
+
+    #[test]
+    fn wait_is_a_use_even_if_never_polled() {
+        let signal = MockSignal::<u32>::expect_never();
+
+        drop(signal.wait());
+        signal.signal(1);
+
+        let expected = Err(MockSignalError::UnexpectedUse {
+            method: "Signal::wait()",
+        });
+        assert_eq!(signal.done(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to never be used, actually called Signal::signal()")]
+    fn used_signal_expected_never_just_drop() {
+        let signal = MockSignal::expect_never();
+        signal_twice(&signal);
+    }
+
+    #[test]
+    fn manual_signal_only_records() {
+        let signal = MockSignal::expect(2).with_manual_trigger();
+
+        signal_twice(&signal);
+
+        assert!(!signal.signaled());
+        assert_eq!(signal.try_take(), None);
+        assert_eq!(signal.signals(), [1, 2]);
+        signal.done().unwrap();
+    }
+
+    #[test]
+    fn wait_steps_with_each_trigger() {
+        let signal = MockSignal::expect(0).with_manual_trigger();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        {
+            let mut total = pin!(async {
+                let first = signal.wait().await;
+                first + signal.wait().await
+            });
+            assert_eq!(total.as_mut().poll(&mut cx), Poll::Pending);
+
+            signal.trigger(2);
+            assert_eq!(total.as_mut().poll(&mut cx), Poll::Pending);
+
+            signal.trigger(3);
+            assert_eq!(total.poll(&mut cx), Poll::Ready(5));
+        }
+
+        signal.done().unwrap();
+    }
+
+    #[test]
+    fn trigger_is_not_recorded() {
+        let signal = MockSignal::expect(0);
+
+        signal.trigger(4);
+
+        assert_eq!(block_on(signal.wait()), 4);
+        assert_eq!(signal.times_signalled(), 0);
+        signal.done().unwrap();
+    }
+
+    #[test]
+    fn scripted_try_take_leaves_value() {
+        let signal = MockSignal::expect(1).script_try_take([None, Some(5)]);
+        signal.signal(1);
+
+        assert_eq!(signal.try_take(), None);
+        assert_eq!(signal.try_take(), Some(5));
+        assert!(signal.signaled());
+        assert_eq!(signal.try_take(), Some(1));
+        assert_eq!(signal.try_take(), None);
+        signal.done().unwrap();
+    }
+
+    #[test]
+    fn untaken_results_are_reported() {
+        let signal = MockSignal::<u8>::expect(0).script_try_take([Some(1), None]);
+
+        assert_eq!(signal.try_take(), Some(1));
+
+        let expected = Err(MockSignalError::UntakenResults { remaining: 1 });
+        assert_eq!(signal.done(), expected);
+    }
+
+    #[test]
+    fn scripted_try_take_is_a_use() {
+        let signal = MockSignal::<u8>::expect_never().script_try_take([None]);
+
+        assert_eq!(signal.try_take(), None);
+
+        let expected = Err(MockSignalError::UnexpectedUse {
+            method: "Signal::try_take()",
+        });
+        assert_eq!(signal.done(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 1 more try_take() result(s) to be taken")]
+    fn untaken_results_just_drop() {
+        let _signal = MockSignal::<u8>::expect(0).script_try_take([None]);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected the value of signal 0 to match, actually it didn't")]
+    fn unmatched_predicate_just_drop() {
+        let signal = MockSignal::expect(2).expect_matching(0, |value| *value > 1);
+        signal_twice(&signal);
+    }
+}
@@ -0,0 +1,375 @@
+//! Traits and mocked types to allow unit testing functions that use an
+//! [`embassy_sync::waitqueue::AtomicWaker`] to wake the task waiting for an event, such as an
+//! interrupt in a driver.
+//!
+//! # Examples
+//! ```
+//! use core::{future::poll_fn, sync::atomic::{AtomicBool, Ordering}, task::Poll};
+//! use embassy_mock::sync::AtomicWaker;
+//!
+//! // Generic over the `AtomicWaker` trait
+//! async fn wait_for_ready(is_ready: &AtomicBool, waker: &impl AtomicWaker) {
+//!     poll_fn(|cx| {
+//!         waker.register(cx.waker());
+//!         if is_ready.load(Ordering::Acquire) {
+//!             Poll::Ready(())
+//!         } else {
+//!             Poll::Pending
+//!         }
+//!     })
+//!     .await;
+//! }
+//!
+//! // Called from the interrupt handler.
+//! fn on_ready(is_ready: &AtomicBool, waker: &impl AtomicWaker) {
+//!     is_ready.store(true, Ordering::Release);
+//!     waker.wake();
+//! }
+//!
+//! # test_wait_for_ready();
+//! // The unit tests that use the `MockAtomicWaker`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_mock::sync::{MockAtomicWaker, WakeProbe};
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_wait_for_ready() {
+//!         let is_ready = AtomicBool::new(false);
+//!         let waker = MockAtomicWaker::new().expect_wakes(1);
+//!         let mut probe = WakeProbe::new(wait_for_ready(&is_ready, &waker));
+//!
+//!         probe.assert_pending();
+//!         assert!(waker.is_registered());
+//!         on_ready(&is_ready, &waker);
+//!
+//!         probe.assert_ready();
+//!         drop(probe);
+//!         waker.done().unwrap();
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use core::task::Waker;
+use embassy_sync::{
+    blocking_mutex::raw::RawMutex,
+    waitqueue::{AtomicWaker as EmbassyAtomicWaker, GenericAtomicWaker},
+};
+#[cfg(feature = "std")]
+use {
+    crate::waiting,
+    core::sync::atomic::{AtomicUsize, Ordering},
+    snafu::prelude::*,
+    std::sync::Mutex,
+};
+
+/// The trait to replace the [`embassy_sync::waitqueue::AtomicWaker`] and
+/// [`embassy_sync::waitqueue::GenericAtomicWaker`] in code to allow the [`MockAtomicWaker`] to be
+/// used in their place for tests.
+pub trait AtomicWaker {
+    /// Wrapper for [`embassy_sync::waitqueue::AtomicWaker::register()`].
+    fn register(&self, waker: &Waker);
+
+    /// Wrapper for [`embassy_sync::waitqueue::AtomicWaker::wake()`].
+    fn wake(&self);
+}
+
+impl AtomicWaker for EmbassyAtomicWaker {
+    /// Register a waker. Overwrites the previous waker, if any.
+    fn register(&self, waker: &Waker) {
+        self.register(waker);
+    }
+
+    /// Wake the registered waker, if any.
+    fn wake(&self) {
+        self.wake();
+    }
+}
+
+impl<M: RawMutex> AtomicWaker for GenericAtomicWaker<M> {
+    /// Register a waker. Overwrites the previous waker, if any.
+    fn register(&self, waker: &Waker) {
+        self.register(waker);
+    }
+
+    /// Wake the registered waker, if any.
+    fn wake(&self) {
+        self.wake();
+    }
+}
+
+impl<W: AtomicWaker + ?Sized> AtomicWaker for &W {
+    /// Call [`AtomicWaker::register()`] of the referenced waker.
+    fn register(&self, waker: &Waker) {
+        (**self).register(waker);
+    }
+
+    /// Call [`AtomicWaker::wake()`] of the referenced waker.
+    fn wake(&self) {
+        (**self).wake();
+    }
+}
+
+/// The errors that are reported by [`MockAtomicWaker`].
+#[cfg(feature = "std")]
+#[derive(Debug, Snafu, PartialEq)]
+pub enum MockAtomicWakerError {
+    /// [`AtomicWaker::wake()`] was not called the expected number of times.
+    #[snafu(display("expected to wake {expected} time(s), actually woke {actual}"))]
+    WrongNumberOfWakes {
+        /// The number of wakes that were expected.
+        expected: usize,
+
+        /// The number of times [`AtomicWaker::wake()`] was called.
+        actual: usize,
+    },
+}
+
+/// A mocked version of [`embassy_sync::waitqueue::AtomicWaker`] that can be used in its place for
+/// unit tests.
+///
+/// It wakes the registered waker as a real `AtomicWaker`, keeping it registered, and counts the
+/// calls to [`AtomicWaker::register()`] and [`AtomicWaker::wake()`].
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if [`AtomicWaker::wake()`] was not
+/// called the number of times given to [`Self::expect_wakes()`].
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::sync::{AtomicWaker, MockAtomicWaker};
+/// use futures_util::task::noop_waker_ref;
+///
+/// let waker = MockAtomicWaker::new();
+/// assert!(!waker.is_registered());
+///
+/// waker.register(noop_waker_ref());
+/// waker.wake();
+///
+/// assert!(waker.is_registered());
+/// assert_eq!(waker.register_count(), 1);
+/// assert_eq!(waker.wake_count(), 1);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockAtomicWaker {
+    /// The registered waker, if any.
+    waker: Mutex<Option<Waker>>,
+
+    /// The number of calls to [`AtomicWaker::register()`].
+    register_count: AtomicUsize,
+
+    /// The number of calls to [`AtomicWaker::wake()`].
+    wake_count: AtomicUsize,
+
+    /// The number of times [`AtomicWaker::wake()`] is expected to be called, if checked.
+    expected: Option<usize>,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+#[cfg(feature = "std")]
+impl MockAtomicWaker {
+    /// Create a [`MockAtomicWaker`] without a registered waker, without checking how many times it
+    /// is woken.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            waker: Mutex::new(None),
+            register_count: AtomicUsize::new(0),
+            wake_count: AtomicUsize::new(0),
+            expected: None,
+            is_done: false,
+        }
+    }
+
+    /// Expect [`AtomicWaker::wake()`] to be called `expected` times.
+    pub const fn expect_wakes(mut self, expected: usize) -> Self {
+        self.expected = Some(expected);
+        self
+    }
+
+    /// Is a waker registered, to be woken by [`AtomicWaker::wake()`].
+    pub fn is_registered(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    /// The number of calls to [`AtomicWaker::register()`] so far.
+    pub fn register_count(&self) -> usize {
+        self.register_count.load(Ordering::Relaxed)
+    }
+
+    /// The number of calls to [`AtomicWaker::wake()`] so far.
+    pub fn wake_count(&self) -> usize {
+        self.wake_count.load(Ordering::Relaxed)
+    }
+
+    /// Check that [`AtomicWaker::wake()`] was called the number of times given to
+    /// [`Self::expect_wakes()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{AtomicWaker, MockAtomicWaker, MockAtomicWakerError};
+    ///
+    /// let waker = MockAtomicWaker::new().expect_wakes(2);
+    /// waker.wake();
+    ///
+    /// let expected = Err(MockAtomicWakerError::WrongNumberOfWakes { expected: 2, actual: 1 });
+    /// assert_eq!(waker.done(), expected);
+    ///
+    /// // This doesn't panic when `waker` is dropped as `waker.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockAtomicWakerError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that [`AtomicWaker::wake()`] was called the expected number of times.
+    fn check(&self) -> Result<(), MockAtomicWakerError> {
+        if let Some(expected) = self.expected {
+            let actual = self.wake_count();
+            ensure!(
+                actual == expected,
+                WrongNumberOfWakesSnafu { expected, actual }
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Lock the waker, which is never poisoned as the mock doesn't panic while holding the lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Waker>> {
+        self.waker.lock().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for MockAtomicWaker {
+    /// If [`Self::done()`] has not been called before being dropped then check that
+    /// [`AtomicWaker::wake()`] was called the expected number of times.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl AtomicWaker for MockAtomicWaker {
+    /// Register the `waker`, replacing the previous one unless they wake the same task.
+    fn register(&self, waker: &Waker) {
+        self.register_count.fetch_add(1, Ordering::Relaxed);
+        let mut registered = self.lock();
+        if !registered
+            .as_ref()
+            .is_some_and(|registered| registered.will_wake(waker))
+        {
+            *registered = Some(waker.clone());
+        }
+    }
+
+    /// Wake the registered waker, if any, which stays registered.
+    fn wake(&self) {
+        self.wake_count.fetch_add(1, Ordering::Relaxed);
+        if let Some(waker) = &*self.lock() {
+            waiting::waking("MockAtomicWaker::wake()", || waker.wake_by_ref());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::{TestRawMutex, WakeProbe};
+    use core::{
+        future::poll_fn,
+        sync::atomic::{AtomicBool, Ordering},
+        task::Poll,
+    };
+    use futures_util::task::noop_waker_ref;
+
+    async fn wait_for_flag(flag: &AtomicBool, waker: &impl AtomicWaker) {
+        poll_fn(|cx| {
+            waker.register(cx.waker());
+            if flag.load(Ordering::Acquire) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+    }
+
+    fn raise_flag(flag: &AtomicBool, waker: &impl AtomicWaker) {
+        flag.store(true, Ordering::Release);
+        waker.wake();
+    }
+
+    #[test]
+    fn embassy_generic_atomic_waker() {
+        let flag = AtomicBool::new(false);
+        let waker = GenericAtomicWaker::new(TestRawMutex::new());
+        let mut probe = WakeProbe::new(wait_for_flag(&flag, &waker));
+
+        probe.assert_pending();
+        raise_flag(&flag, &waker);
+
+        probe.assert_ready();
+    }
+
+    #[test]
+    fn mock_wakes_registered_waker() {
+        let flag = AtomicBool::new(false);
+        let waker = MockAtomicWaker::new().expect_wakes(1);
+        {
+            let mut probe = WakeProbe::new(wait_for_flag(&flag, &waker));
+
+            probe.assert_pending();
+            raise_flag(&flag, &waker);
+
+            probe.assert_ready();
+        }
+
+        assert_eq!(waker.register_count(), 2);
+        waker.done().unwrap();
+    }
+
+    #[test]
+    fn wake_without_registered_waker_is_counted() {
+        let waker = MockAtomicWaker::new();
+
+        waker.wake();
+
+        assert!(!waker.is_registered());
+        assert_eq!(waker.wake_count(), 1);
+    }
+
+    #[test]
+    fn register_keeps_waker_of_same_task() {
+        let waker = MockAtomicWaker::new();
+
+        waker.register(noop_waker_ref());
+        waker.register(noop_waker_ref());
+
+        assert!(waker.is_registered());
+        assert_eq!(waker.register_count(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to wake 1 time(s), actually woke 0")]
+    fn wrong_number_of_wakes_just_drop() {
+        let _waker = MockAtomicWaker::new().expect_wakes(1);
+    }
+}
@@ -0,0 +1,336 @@
+//! A [`WakeProbe`] to check that the code under test is woken when the mocks it waits for become
+//! ready, to catch lost wakeups.
+//!
+//! A future that returns [`Poll::Pending`] must make sure its waker is woken once it can make
+//! progress, or an executor never polls it again. A test that drives the code with
+//! `embassy_futures::block_on()` doesn't notice when it isn't, as that polls in a loop
+//! without waiting for a wake, but the code would hang on the device. The [`WakeProbe`] polls the
+//! future step by step with a waker that records whether it is held and whether it was woken.
+//!
+//! # Examples
+//! ```
+//! use embassy_mock::sync::Receiver;
+//!
+//! async fn next_command(commands: impl Receiver<u8>) -> u8 {
+//!     commands.receive().await
+//! }
+//!
+//! # test_next_command_is_woken();
+//! // The unit tests that check the wakeups.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_mock::sync::{MockReceiver, WakeProbe};
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_next_command_is_woken() {
+//!         let commands = MockReceiver::new();
+//!         let mut probe = WakeProbe::new(next_command(&commands));
+//!
+//!         probe.assert_pending();
+//!         commands.push_received(3);
+//!
+//!         assert_eq!(probe.assert_ready(), 3);
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use core::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicUsize, Ordering},
+    task::{Context, Poll, Waker},
+};
+use std::{boxed::Box, sync::Arc, task::Wake};
+
+/// Drives a future one poll at a time with a waker that records if it was woken, to check that
+/// the future registers its waker while it is pending and makes progress once woken.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::sync::{MockSignal, Signal, WakeProbe};
+///
+/// let ready = MockSignal::expect(0).with_manual_trigger();
+/// let mut probe = WakeProbe::new(ready.wait());
+///
+/// probe.assert_pending();
+/// assert!(probe.is_waker_registered());
+/// assert!(!probe.is_woken());
+///
+/// ready.trigger(5);
+/// assert!(probe.is_woken());
+/// assert_eq!(probe.assert_ready(), 5);
+/// ```
+pub struct WakeProbe<F: Future> {
+    /// The future under test.
+    future: Pin<Box<F>>,
+
+    /// Records the wakes of the `waker`.
+    wakes: Arc<WakeCount>,
+
+    /// The waker given to each poll of the future.
+    waker: Waker,
+}
+
+/// A [`Wake`] implementation that counts the times it is woken.
+#[derive(Debug, Default)]
+pub(super) struct WakeCount(AtomicUsize);
+
+impl WakeCount {
+    /// Has it been woken since the last call, forgetting the wakes.
+    pub(super) fn take(&self) -> bool {
+        self.0.swap(0, Ordering::SeqCst) > 0
+    }
+
+    /// Has it been woken since the last call to [`Self::take()`].
+    pub(super) fn is_woken(&self) -> bool {
+        self.0.load(Ordering::SeqCst) > 0
+    }
+}
+
+impl Wake for WakeCount {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl<F: Future> fmt::Debug for WakeProbe<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WakeProbe")
+            .field("wakes", &self.wakes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F: Future> WakeProbe<F> {
+    /// Create a [`WakeProbe`] for the `future`, which isn't polled until asked to.
+    pub fn new(future: F) -> Self {
+        let wakes = Arc::new(WakeCount::default());
+        Self {
+            future: Box::pin(future),
+            waker: Waker::from(Arc::clone(&wakes)),
+            wakes,
+        }
+    }
+
+    /// Poll the future once, forgetting the wakes since the last poll.
+    ///
+    /// Once this returns [`Poll::Ready`] the future must not be polled again.
+    pub fn poll(&mut self) -> Poll<F::Output> {
+        self.wakes.0.store(0, Ordering::SeqCst);
+        self.future
+            .as_mut()
+            .poll(&mut Context::from_waker(&self.waker))
+    }
+
+    /// Has the waker been woken since the last poll.
+    pub fn is_woken(&self) -> bool {
+        self.wakes.0.load(Ordering::SeqCst) > 0
+    }
+
+    /// Is a clone of the waker held to be woken later, or has it already been woken since the
+    /// last poll.
+    ///
+    /// This is what a pending future must have arranged, otherwise nothing would ever wake it.
+    pub fn is_waker_registered(&self) -> bool {
+        // Held by this probe and by its own waker, any more are clones kept by the future.
+        Arc::strong_count(&self.wakes) > 2 || self.is_woken()
+    }
+
+    /// Poll the future once and assert that it is pending and has registered its waker.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the future completed, or if it is pending without registering its waker, as
+    /// then an executor would never poll it again.
+    #[track_caller]
+    pub fn assert_pending(&mut self) {
+        assert!(
+            self.poll().is_pending(),
+            "expected the future to be pending, actually it completed"
+        );
+        assert!(
+            self.is_waker_registered(),
+            "expected the pending future to register its waker, actually it didn't"
+        );
+    }
+
+    /// Assert that the waker has been woken since the last poll, then poll the future once and
+    /// assert that it completed, returning its output.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the waker wasn't woken, which is a lost wakeup, or if the future is still
+    /// pending after it was.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use core::{future::poll_fn, task::Poll};
+    /// use embassy_mock::sync::WakeProbe;
+    ///
+    /// let mut is_ready = false;
+    /// // A buggy future that is pending the first time without arranging to be woken.
+    /// let mut probe = WakeProbe::new(poll_fn(|_| {
+    ///     if is_ready {
+    ///         Poll::Ready(())
+    ///     } else {
+    ///         is_ready = true;
+    ///         Poll::Pending
+    ///     }
+    /// }));
+    ///
+    /// assert!(probe.poll().is_pending());
+    /// // Panics: expected the waker to be woken, actually it wasn't
+    /// probe.assert_ready();
+    /// ```
+    #[track_caller]
+    pub fn assert_ready(&mut self) -> F::Output {
+        self.assert_woken();
+        match self.poll() {
+            Poll::Ready(output) => output,
+            Poll::Pending => {
+                panic!("expected the future to complete once woken, actually it is still pending")
+            }
+        }
+    }
+
+    /// Assert that the waker has been woken since the last poll.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the waker wasn't woken.
+    #[track_caller]
+    pub fn assert_woken(&self) {
+        assert!(
+            self.is_woken(),
+            "expected the waker to be woken, actually it wasn't"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::{
+        MockChannel, MockMutex, MockReceiver, MockSender, MockSignal, Mutex, Receiver, Sender,
+        Signal,
+    };
+    use core::future::poll_fn;
+
+    #[test]
+    fn receive_is_woken_by_pushed_message() {
+        let receiver = MockReceiver::new();
+        let mut probe = WakeProbe::new(receiver.receive());
+
+        probe.assert_pending();
+        assert!(!probe.is_woken());
+        receiver.push_received(1);
+
+        assert_eq!(probe.assert_ready(), 1);
+    }
+
+    #[test]
+    fn send_is_woken_when_not_full() {
+        let sender = MockSender::new();
+        sender.set_full(true);
+        let mut probe = WakeProbe::new(sender.send(2));
+
+        probe.assert_pending();
+        sender.set_full(false);
+
+        probe.assert_ready();
+        assert_eq!(sender.sent(), [2]);
+    }
+
+    #[test]
+    fn loopback_halves_wake_each_other() {
+        let channel = MockChannel::loopback_with_capacity(1);
+        channel.try_send(1).unwrap();
+        let mut send = WakeProbe::new(channel.send(2));
+        send.assert_pending();
+
+        let mut receive = WakeProbe::new(channel.receive());
+        assert_eq!(receive.poll(), Poll::Ready(1));
+
+        send.assert_ready();
+        drop((send, receive));
+        assert_eq!(channel.try_receive(), Ok(2));
+        channel.done().unwrap();
+    }
+
+    #[test]
+    fn lock_is_woken_by_unlock() {
+        let mutex = MockMutex::new(0);
+        let guard = mutex.try_lock().unwrap();
+        let mut probe = WakeProbe::new(mutex.lock());
+
+        probe.assert_pending();
+        drop(guard);
+
+        *probe.assert_ready() += 1;
+    }
+
+    #[test]
+    fn wait_is_woken_by_signal() {
+        let signal = MockSignal::expect(1);
+        let mut probe = WakeProbe::new(signal.wait());
+
+        probe.assert_pending();
+        signal.signal(4);
+
+        assert_eq!(probe.assert_ready(), 4);
+    }
+
+    #[test]
+    fn waking_immediately_counts_as_registered() {
+        let receiver = MockReceiver::script([Poll::Pending, Poll::Ready(1)]);
+        let mut probe = WakeProbe::new(receiver.receive());
+
+        probe.assert_pending();
+
+        assert!(probe.is_woken());
+        assert_eq!(probe.assert_ready(), 1);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected the pending future to register its waker, actually it didn't"
+    )]
+    fn pending_without_waker_is_reported() {
+        let mut probe = WakeProbe::new(poll_fn(|_| Poll::<()>::Pending));
+
+        probe.assert_pending();
+    }
+
+    #[test]
+    #[should_panic(expected = "expected the future to be pending, actually it completed")]
+    fn ready_future_is_not_pending() {
+        let mut probe = WakeProbe::new(async {});
+
+        probe.assert_pending();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected the future to complete once woken, actually it is still pending"
+    )]
+    fn spurious_wake_is_not_progress() {
+        let mut probe = WakeProbe::new(poll_fn(|cx| {
+            cx.waker().wake_by_ref();
+            Poll::<()>::Pending
+        }));
+        assert!(probe.poll().is_pending());
+
+        probe.assert_ready();
+    }
+}
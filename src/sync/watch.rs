@@ -0,0 +1,965 @@
+//! Traits and mocked types to allow unit testing functions that use the senders and receivers of
+//! an [`embassy_sync::watch::Watch`].
+//!
+//! # Examples
+//! ```
+//! use embassy_mock::sync::{WatchReceiver, WatchSender};
+//!
+//! // Generic over the `WatchReceiver` and `WatchSender` traits
+//! async fn mirror_state(state: &mut impl WatchReceiver<u8>, mirror: &impl WatchSender<u8>) {
+//!     let value = state.changed().await;
+//!     mirror.send(value);
+//! }
+//!
+//! # test_mirror_state();
+//! // The unit tests that use the `MockWatchReceiver` and `MockWatchSender`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_futures::block_on;
+//!     use embassy_mock::sync::{MockWatchReceiver, MockWatchSender};
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_mirror_state() {
+//!         let mut state = MockWatchReceiver::with_changes([4]);
+//!         let mirror = MockWatchSender::new();
+//!
+//!         block_on(mirror_state(&mut state, &mirror));
+//!
+//!         assert_eq!(mirror.sent(), [4]);
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use core::future::Future;
+use embassy_sync::{
+    blocking_mutex::raw::RawMutex,
+    watch::{DynReceiver, DynSender, Receiver, Sender},
+};
+#[cfg(feature = "std")]
+use {
+    crate::waiting,
+    core::{
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    },
+    snafu::prelude::*,
+    std::{collections::VecDeque, sync::Mutex, vec::Vec},
+};
+
+/// The trait to replace the [`embassy_sync::watch::Sender`] and
+/// [`embassy_sync::watch::DynSender`] in code to allow the [`MockWatchSender`] to be used in
+/// their place for tests.
+pub trait WatchSender<T> {
+    /// Wrapper for `embassy_sync::watch::Sender::send()`.
+    fn send(&self, value: T);
+
+    /// Wrapper for `embassy_sync::watch::Sender::clear()`.
+    fn clear(&self);
+
+    /// Wrapper for `embassy_sync::watch::Sender::try_get()`.
+    fn try_get(&self) -> Option<T>;
+
+    /// Wrapper for `embassy_sync::watch::Sender::contains_value()`.
+    fn contains_value(&self) -> bool;
+}
+
+/// The trait to replace the [`embassy_sync::watch::Receiver`] and
+/// [`embassy_sync::watch::DynReceiver`] in code to allow the [`MockWatchReceiver`] to be used in
+/// their place for tests.
+pub trait WatchReceiver<T> {
+    /// Wrapper for `embassy_sync::watch::Receiver::get()`.
+    fn get(&mut self) -> impl Future<Output = T>;
+
+    /// Wrapper for `embassy_sync::watch::Receiver::try_get()`.
+    fn try_get(&mut self) -> Option<T>;
+
+    /// Wrapper for `embassy_sync::watch::Receiver::changed()`.
+    fn changed(&mut self) -> impl Future<Output = T>;
+
+    /// Wrapper for `embassy_sync::watch::Receiver::try_changed()`.
+    fn try_changed(&mut self) -> Option<T>;
+
+    /// Wrapper for `embassy_sync::watch::Receiver::contains_value()`.
+    fn contains_value(&self) -> bool;
+}
+
+impl<M: RawMutex, T: Clone, const N: usize> WatchSender<T> for Sender<'_, M, T, N> {
+    /// Sends a new value to the `Watch`.
+    fn send(&self, value: T) {
+        (**self).send(value);
+    }
+
+    /// Clears the value of the `Watch`. This will cause calls to `get` to be pending.
+    fn clear(&self) {
+        (**self).clear();
+    }
+
+    /// Returns the current value of the `Watch` if it is initialized, without marking it as seen.
+    fn try_get(&self) -> Option<T> {
+        (**self).try_get()
+    }
+
+    /// Returns true if the `Watch` contains a value.
+    fn contains_value(&self) -> bool {
+        (**self).contains_value()
+    }
+}
+
+impl<T: Clone> WatchSender<T> for DynSender<'_, T> {
+    /// Sends a new value to the `Watch`.
+    fn send(&self, value: T) {
+        (**self).send(value);
+    }
+
+    /// Clears the value of the `Watch`. This will cause calls to `get` to be pending.
+    fn clear(&self) {
+        (**self).clear();
+    }
+
+    /// Returns the current value of the `Watch` if it is initialized, without marking it as seen.
+    fn try_get(&self) -> Option<T> {
+        (**self).try_get()
+    }
+
+    /// Returns true if the `Watch` contains a value.
+    fn contains_value(&self) -> bool {
+        (**self).contains_value()
+    }
+}
+
+impl<M: RawMutex, T: Clone, const N: usize> WatchReceiver<T> for Receiver<'_, M, T, N> {
+    /// Returns the current value of the `Watch` once it is initialized, marking it as seen.
+    fn get(&mut self) -> impl Future<Output = T> {
+        (**self).get()
+    }
+
+    /// Tries to get the current value of the `Watch` without waiting, marking it as seen.
+    fn try_get(&mut self) -> Option<T> {
+        (**self).try_get()
+    }
+
+    /// Waits for the `Watch` to change and returns the new value, marking it as seen.
+    fn changed(&mut self) -> impl Future<Output = T> {
+        (**self).changed()
+    }
+
+    /// Tries to get the new value of the `Watch` without waiting, marking it as seen.
+    fn try_changed(&mut self) -> Option<T> {
+        (**self).try_changed()
+    }
+
+    /// Checks if the `Watch` contains a value.
+    fn contains_value(&self) -> bool {
+        (**self).contains_value()
+    }
+}
+
+impl<T: Clone> WatchReceiver<T> for DynReceiver<'_, T> {
+    /// Returns the current value of the `Watch` once it is initialized, marking it as seen.
+    fn get(&mut self) -> impl Future<Output = T> {
+        (**self).get()
+    }
+
+    /// Tries to get the current value of the `Watch` without waiting, marking it as seen.
+    fn try_get(&mut self) -> Option<T> {
+        (**self).try_get()
+    }
+
+    /// Waits for the `Watch` to change and returns the new value, marking it as seen.
+    fn changed(&mut self) -> impl Future<Output = T> {
+        (**self).changed()
+    }
+
+    /// Tries to get the new value of the `Watch` without waiting, marking it as seen.
+    fn try_changed(&mut self) -> Option<T> {
+        (**self).try_changed()
+    }
+
+    /// Checks if the `Watch` contains a value.
+    fn contains_value(&self) -> bool {
+        (**self).contains_value()
+    }
+}
+
+impl<T, S: WatchSender<T> + ?Sized> WatchSender<T> for &S {
+    /// Call [`WatchSender::send()`] of the referenced sender.
+    fn send(&self, value: T) {
+        (**self).send(value);
+    }
+
+    /// Call [`WatchSender::clear()`] of the referenced sender.
+    fn clear(&self) {
+        (**self).clear();
+    }
+
+    /// Call [`WatchSender::try_get()`] of the referenced sender.
+    fn try_get(&self) -> Option<T> {
+        (**self).try_get()
+    }
+
+    /// Call [`WatchSender::contains_value()`] of the referenced sender.
+    fn contains_value(&self) -> bool {
+        (**self).contains_value()
+    }
+}
+
+impl<T, R: WatchReceiver<T> + ?Sized> WatchReceiver<T> for &mut R {
+    /// Call [`WatchReceiver::get()`] of the referenced receiver.
+    fn get(&mut self) -> impl Future<Output = T> {
+        (**self).get()
+    }
+
+    /// Call [`WatchReceiver::try_get()`] of the referenced receiver.
+    fn try_get(&mut self) -> Option<T> {
+        (**self).try_get()
+    }
+
+    /// Call [`WatchReceiver::changed()`] of the referenced receiver.
+    fn changed(&mut self) -> impl Future<Output = T> {
+        (**self).changed()
+    }
+
+    /// Call [`WatchReceiver::try_changed()`] of the referenced receiver.
+    fn try_changed(&mut self) -> Option<T> {
+        (**self).try_changed()
+    }
+
+    /// Call [`WatchReceiver::contains_value()`] of the referenced receiver.
+    fn contains_value(&self) -> bool {
+        (**self).contains_value()
+    }
+}
+
+/// The errors that are reported by [`MockWatchReceiver`].
+#[cfg(feature = "std")]
+#[derive(Debug, Snafu, PartialEq)]
+pub enum MockWatchReceiverError {
+    /// Some of the scripted changes were never received.
+    #[snafu(display("expected {remaining} more change(s) to be received"))]
+    UnreceivedChanges {
+        /// The number of changes that were never received.
+        remaining: usize,
+    },
+}
+
+/// A mocked version of [`embassy_sync::watch::Sender`] that can be used in its place for unit
+/// tests.
+///
+/// Every value passed to [`WatchSender::send()`] is recorded, and the last one is the current
+/// value until [`WatchSender::clear()`] is called.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::sync::{MockWatchSender, WatchSender};
+///
+/// let sender = MockWatchSender::new();
+///
+/// sender.send("idle");
+/// sender.send("running");
+/// assert_eq!(sender.try_get(), Some("running"));
+///
+/// sender.clear();
+/// assert!(!sender.contains_value());
+/// assert_eq!(sender.sent(), ["idle", "running"]);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockWatchSender<T> {
+    /// The sent values and the current value, shared by every call.
+    state: Mutex<WatchSenderState<T>>,
+}
+
+/// The state of a [`MockWatchSender`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct WatchSenderState<T> {
+    /// Every value passed to [`WatchSender::send()`], in call order.
+    sent: Vec<T>,
+
+    /// Is there a current value, which is the last one sent.
+    contains_value: bool,
+}
+
+#[cfg(feature = "std")]
+impl<T> MockWatchSender<T> {
+    /// Create a [`MockWatchSender`] that hasn't sent any values.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(WatchSenderState {
+                sent: Vec::new(),
+                contains_value: false,
+            }),
+        }
+    }
+
+    /// Every value sent with this [`MockWatchSender`], in call order.
+    pub fn sent(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.lock().sent.clone()
+    }
+
+    /// Lock the state, which is never poisoned as the mock doesn't panic while holding the lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, WatchSenderState<T>> {
+        self.state.lock().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> WatchSender<T> for MockWatchSender<T> {
+    /// Record the `value`, which becomes the current value.
+    fn send(&self, value: T) {
+        let mut state = self.lock();
+        state.sent.push(value);
+        state.contains_value = true;
+    }
+
+    /// Remove the current value, it is still recorded.
+    fn clear(&self) {
+        self.lock().contains_value = false;
+    }
+
+    /// The current value, if any.
+    fn try_get(&self) -> Option<T> {
+        let state = self.lock();
+        state
+            .contains_value
+            .then(|| state.sent.last().cloned())
+            .flatten()
+    }
+
+    /// Is there a current value.
+    fn contains_value(&self) -> bool {
+        self.lock().contains_value
+    }
+}
+
+/// A mocked version of [`embassy_sync::watch::Receiver`] that can be used in its place for unit
+/// tests.
+///
+/// The changes of the value are scripted by the test. Each call to [`WatchReceiver::changed()`]
+/// receives the next change, which becomes the current value returned by
+/// [`WatchReceiver::get()`]. Once all of the changes have been received,
+/// [`WatchReceiver::changed()`] never completes.
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if any of the scripted changes
+/// were never received.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::sync::{MockWatchReceiver, WatchReceiver};
+///
+/// let mut receiver = MockWatchReceiver::with_changes([1, 2]);
+///
+/// assert_eq!(block_on(receiver.get()), 1);
+/// assert_eq!(block_on(receiver.get()), 1);
+/// assert_eq!(block_on(receiver.changed()), 2);
+/// assert_eq!(receiver.try_changed(), None);
+///
+/// receiver.done().unwrap();
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockWatchReceiver<T> {
+    /// The value that was received last.
+    current: Option<T>,
+
+    /// The changes still to be received, in order.
+    changes: VecDeque<T>,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<T> MockWatchReceiver<T> {
+    /// Create a [`MockWatchReceiver`] without a value or any changes to receive.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            current: None,
+            changes: VecDeque::new(),
+            is_done: false,
+        }
+    }
+
+    /// Create a [`MockWatchReceiver`] that receives the `changes` in order.
+    pub fn with_changes(changes: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            current: None,
+            changes: changes.into_iter().collect(),
+            is_done: false,
+        }
+    }
+
+    /// Add the `value` as a change to be received after the others.
+    pub fn push_change(&mut self, value: T) {
+        self.changes.push_back(value);
+    }
+
+    /// Check that all of the scripted changes were received.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockWatchReceiver, MockWatchReceiverError, WatchReceiver};
+    ///
+    /// let mut receiver = MockWatchReceiver::with_changes([1, 2, 3]);
+    /// receiver.try_changed();
+    ///
+    /// let expected = Err(MockWatchReceiverError::UnreceivedChanges { remaining: 2 });
+    /// assert_eq!(receiver.done(), expected);
+    ///
+    /// // This doesn't panic when `receiver` is dropped as `receiver.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockWatchReceiverError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that all of the scripted changes were received.
+    fn check(&self) -> Result<(), MockWatchReceiverError> {
+        let remaining = self.changes.len();
+        ensure!(remaining == 0, UnreceivedChangesSnafu { remaining });
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for MockWatchReceiver<T> {
+    /// If [`Self::done()`] has not been called before being dropped then check that all of the
+    /// scripted changes were received.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> WatchReceiver<T> for MockWatchReceiver<T> {
+    /// The current value, or the next change if nothing has been received yet.
+    fn get(&mut self) -> impl Future<Output = T> {
+        MockChangedFuture {
+            receiver: self,
+            keep_current: true,
+        }
+    }
+
+    /// The current value, or the next change if nothing has been received yet.
+    fn try_get(&mut self) -> Option<T> {
+        match &self.current {
+            Some(value) => Some(value.clone()),
+            None => self.try_changed(),
+        }
+    }
+
+    /// Receive the next change, or never complete if there are none left.
+    fn changed(&mut self) -> impl Future<Output = T> {
+        MockChangedFuture {
+            receiver: self,
+            keep_current: false,
+        }
+    }
+
+    /// Receive the next change, if any.
+    fn try_changed(&mut self) -> Option<T> {
+        let value = self.changes.pop_front()?;
+        self.current = Some(value.clone());
+        Some(value)
+    }
+
+    /// Is there a current value or a change to receive.
+    fn contains_value(&self) -> bool {
+        self.current.is_some() || !self.changes.is_empty()
+    }
+}
+
+/// The future returned by [`WatchReceiver::get()`] and [`WatchReceiver::changed()`] for a
+/// [`MockWatchReceiver`].
+#[cfg(feature = "std")]
+struct MockChangedFuture<'a, T> {
+    /// The receiver the value is received from.
+    receiver: &'a mut MockWatchReceiver<T>,
+
+    /// Does this return the current value, if there is one, instead of the next change.
+    keep_current: bool,
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> Future for MockChangedFuture<'_, T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let value = if this.keep_current {
+            this.receiver.try_get()
+        } else {
+            this.receiver.try_changed()
+        };
+
+        // Nothing can add a change while the receiver is borrowed, so there's no need to wake.
+        match value {
+            Some(value) => Poll::Ready(value),
+            None if this.keep_current => {
+                waiting::record("MockWatchReceiver::get()");
+                Poll::Pending
+            }
+            None => {
+                waiting::record("MockWatchReceiver::changed()");
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// A mocked version of [`embassy_sync::watch::Watch`] that can be used in its place for unit
+/// tests.
+///
+/// Unlike the separate [`MockWatchSender`] and [`MockWatchReceiver`], the values sent to it are
+/// received by its receivers, created with [`Self::receiver()`], as with a real `Watch`. A
+/// receiver only sees the latest value, so the values sent between two of its calls to
+/// [`WatchReceiver::changed()`] are skipped. Each receiver records the values it observed so the
+/// test can check what each consumer actually saw.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::sync::{MockWatch, WatchReceiver, WatchSender};
+///
+/// let watch = MockWatch::new();
+/// let mut fast = watch.receiver();
+/// let mut slow = watch.receiver();
+///
+/// for value in 1..=3 {
+///     watch.send(value);
+///     fast.try_changed();
+/// }
+/// slow.try_changed();
+///
+/// assert_eq!(fast.observed(), [1, 2, 3]);
+/// assert_eq!(slow.observed(), [3]);
+/// assert_eq!(watch.skipped(slow.index()), [1, 2]);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockWatch<T> {
+    /// The values and what each receiver observed, shared by the receivers.
+    state: Mutex<WatchState<T>>,
+}
+
+/// The state of a [`MockWatch`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct WatchState<T> {
+    /// The current value, [`None`] before the first send or after a clear.
+    value: Option<T>,
+
+    /// Every value passed to [`WatchSender::send()`], in call order.
+    ///
+    /// The id of each value is its position in this, counting from 1, and the id of the current
+    /// value is the number of values sent, as with a real `Watch`.
+    sent: Vec<T>,
+
+    /// The ids of the values each receiver observed, in order, by the index of the receiver.
+    observed: Vec<Vec<usize>>,
+
+    /// The wakers of the receivers that are waiting for a value.
+    wakers: Vec<Waker>,
+}
+
+#[cfg(feature = "std")]
+impl<T> WatchState<T> {
+    /// The id of the current value.
+    fn current_id(&self) -> usize {
+        self.sent.len()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> MockWatch<T> {
+    /// Create a [`MockWatch`] without a value or any receivers.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(WatchState {
+                value: None,
+                sent: Vec::new(),
+                observed: Vec::new(),
+                wakers: Vec::new(),
+            }),
+        }
+    }
+
+    /// Create a receiver of this [`MockWatch`] that hasn't seen any values, so it receives the
+    /// current value, if any, as a change.
+    pub fn receiver(&self) -> MockWatchReceiverHandle<'_, T> {
+        let mut state = self.lock();
+        state.observed.push(Vec::new());
+        MockWatchReceiverHandle {
+            watch: self,
+            index: state.observed.len() - 1,
+            at_id: 0,
+        }
+    }
+
+    /// Every value sent to this [`MockWatch`], in call order.
+    pub fn sent(&self) -> Vec<T> {
+        self.lock().sent.clone()
+    }
+
+    /// The values observed by the receiver at `index`, see [`MockWatchReceiverHandle::index()`],
+    /// in the order it observed them.
+    ///
+    /// A value appears once for every call that returned it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there isn't a receiver at `index`.
+    pub fn observed(&self, index: usize) -> Vec<T> {
+        let state = self.lock();
+        state.observed[index]
+            .iter()
+            .map(|id| state.sent[id - 1].clone())
+            .collect()
+    }
+
+    /// The values sent that the receiver at `index`, see [`MockWatchReceiverHandle::index()`],
+    /// never observed, in the order they were sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there isn't a receiver at `index`.
+    pub fn skipped(&self, index: usize) -> Vec<T> {
+        let state = self.lock();
+        let observed = &state.observed[index];
+        state
+            .sent
+            .iter()
+            .enumerate()
+            .filter(|(position, _)| !observed.contains(&(position + 1)))
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+
+    /// Lock the state, which is never poisoned as the mock doesn't panic while holding the lock.
+    fn lock(&self) -> std::sync::MutexGuard<'_, WatchState<T>> {
+        self.state.lock().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> WatchSender<T> for MockWatch<T> {
+    /// Record the `value`, which becomes the current value, waking the receivers.
+    fn send(&self, value: T) {
+        let mut state = self.lock();
+        state.sent.push(value.clone());
+        state.value = Some(value);
+        for waker in state.wakers.drain(..) {
+            waiting::waking("MockWatch::send()", || waker.wake());
+        }
+    }
+
+    /// Remove the current value, it is still recorded.
+    fn clear(&self) {
+        self.lock().value = None;
+    }
+
+    /// The current value, if any, without marking it as seen by any receiver.
+    fn try_get(&self) -> Option<T> {
+        self.lock().value.clone()
+    }
+
+    /// Is there a current value.
+    fn contains_value(&self) -> bool {
+        self.lock().value.is_some()
+    }
+}
+
+/// A receiver of a [`MockWatch`], created with [`MockWatch::receiver()`], that records the values
+/// it observes.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockWatchReceiverHandle<'a, T> {
+    /// The watch the values are received from.
+    watch: &'a MockWatch<T>,
+
+    /// The index of this receiver in the watch.
+    index: usize,
+
+    /// The id of the last value this receiver has seen.
+    at_id: usize,
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> MockWatchReceiverHandle<'_, T> {
+    /// The index of this receiver, to get what it observed from the [`MockWatch`] once the
+    /// receiver has been given away.
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The values observed by this receiver, see [`MockWatch::observed()`].
+    pub fn observed(&self) -> Vec<T> {
+        self.watch.observed(self.index)
+    }
+
+    /// Return the current value if it is newer than the last one seen, or `changed` is false,
+    /// marking it as seen. Otherwise register the `waker`, if any, to be woken when a value is
+    /// sent.
+    fn poll_value(&mut self, changed: bool, waker: Option<&Waker>) -> Option<T> {
+        let mut state = self.watch.lock();
+        let current_id = state.current_id();
+        match &state.value {
+            Some(value) if !changed || current_id > self.at_id => {
+                let value = value.clone();
+                self.at_id = current_id;
+                state.observed[self.index].push(current_id);
+                Some(value)
+            }
+            _ => {
+                if let Some(waker) = waker {
+                    state.wakers.push(waker.clone());
+                }
+                None
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> WatchReceiver<T> for MockWatchReceiverHandle<'_, T> {
+    /// The current value, waiting until there is one, marking it as seen.
+    fn get(&mut self) -> impl Future<Output = T> {
+        MockWatchFuture {
+            receiver: self,
+            changed: false,
+        }
+    }
+
+    /// The current value, if any, marking it as seen.
+    fn try_get(&mut self) -> Option<T> {
+        let mut state = self.watch.lock();
+        self.at_id = state.current_id();
+        let value = state.value.clone();
+        if value.is_some() {
+            state.observed[self.index].push(self.at_id);
+        }
+        value
+    }
+
+    /// The current value once it is newer than the last one seen, marking it as seen.
+    fn changed(&mut self) -> impl Future<Output = T> {
+        MockWatchFuture {
+            receiver: self,
+            changed: true,
+        }
+    }
+
+    /// The current value if it is newer than the last one seen, marking it as seen.
+    fn try_changed(&mut self) -> Option<T> {
+        self.poll_value(true, None)
+    }
+
+    /// Is there a current value.
+    fn contains_value(&self) -> bool {
+        self.watch.contains_value()
+    }
+}
+
+/// The future returned by [`WatchReceiver::get()`] and [`WatchReceiver::changed()`] for a
+/// [`MockWatchReceiverHandle`].
+#[cfg(feature = "std")]
+struct MockWatchFuture<'a, 'b, T> {
+    /// The receiver the value is received with.
+    receiver: &'a mut MockWatchReceiverHandle<'b, T>,
+
+    /// Does this wait for a value newer than the last one seen, instead of any value.
+    changed: bool,
+}
+
+#[cfg(feature = "std")]
+impl<T: Clone> Future for MockWatchFuture<'_, '_, T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        match this.receiver.poll_value(this.changed, Some(cx.waker())) {
+            Some(value) => Poll::Ready(value),
+            None if this.changed => {
+                waiting::record("MockWatchReceiverHandle::changed()");
+                Poll::Pending
+            }
+            None => {
+                waiting::record("MockWatchReceiverHandle::get()");
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::pin::pin;
+    use embassy_futures::block_on;
+    use embassy_sync::{blocking_mutex::raw::NoopRawMutex, watch::Watch};
+    use futures_util::task::noop_waker_ref;
+
+    async fn double_changes(
+        input: &mut impl WatchReceiver<u32>,
+        output: &impl WatchSender<u32>,
+        count: usize,
+    ) {
+        for _ in 0..count {
+            output.send(input.changed().await * 2);
+        }
+    }
+
+    #[test]
+    fn embassy_sender_and_receiver() {
+        let watch = Watch::<NoopRawMutex, u32, 2>::new();
+        let sender = watch.sender();
+        let mut receiver = watch.receiver().unwrap();
+        let mut dyn_receiver = watch.dyn_receiver().unwrap();
+
+        assert!(!WatchSender::contains_value(&sender));
+        WatchSender::send(&sender, 1);
+        assert_eq!(block_on(WatchReceiver::get(&mut receiver)), 1);
+        assert_eq!(WatchReceiver::try_changed(&mut receiver), None);
+
+        WatchSender::send(&watch.dyn_sender(), 2);
+        assert_eq!(WatchReceiver::try_changed(&mut receiver), Some(2));
+        assert_eq!(block_on(WatchReceiver::changed(&mut dyn_receiver)), 2);
+        assert_eq!(WatchSender::try_get(&sender), Some(2));
+
+        WatchSender::clear(&sender);
+        assert!(!WatchReceiver::contains_value(&dyn_receiver));
+    }
+
+    #[test]
+    fn mock_scripts_changes_and_records_sent() {
+        let mut input = MockWatchReceiver::with_changes([1, 2]);
+        let output = MockWatchSender::new();
+
+        block_on(double_changes(&mut input, &output, 2));
+
+        assert_eq!(output.sent(), [2, 4]);
+        assert_eq!(output.try_get(), Some(4));
+        input.done().unwrap();
+    }
+
+    #[test]
+    fn get_keeps_current_value() {
+        let mut receiver = MockWatchReceiver::new();
+        assert!(!receiver.contains_value());
+        assert_eq!(receiver.try_get(), None);
+
+        receiver.push_change(1);
+        assert!(receiver.contains_value());
+        assert_eq!(receiver.try_get(), Some(1));
+        receiver.push_change(2);
+        assert_eq!(receiver.try_get(), Some(1));
+        assert_eq!(receiver.try_changed(), Some(2));
+        assert_eq!(receiver.try_get(), Some(2));
+    }
+
+    #[test]
+    fn changed_is_pending_when_no_changes_left() {
+        let mut receiver = MockWatchReceiver::with_changes([1]);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        assert_eq!(block_on(receiver.changed()), 1);
+
+        let mut changed = pin!(receiver.changed());
+        assert_eq!(changed.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(changed.as_mut().poll(&mut cx), Poll::Pending);
+    }
+
+    #[test]
+    fn watch_receivers_observe_latest_values() {
+        let watch = MockWatch::new();
+        let mut first = watch.receiver();
+        let mut second = watch.receiver();
+
+        watch.send(1);
+        block_on(double_changes(&mut first, &watch, 1));
+        watch.send(3);
+        watch.send(4);
+        assert_eq!(second.try_changed(), Some(4));
+        assert_eq!(second.try_changed(), None);
+
+        assert_eq!(watch.sent(), [1, 2, 3, 4]);
+        assert_eq!(first.observed(), [1]);
+        assert_eq!(second.observed(), [4]);
+        assert_eq!(watch.skipped(first.index()), [2, 3, 4]);
+        assert_eq!(watch.skipped(second.index()), [1, 2, 3]);
+    }
+
+    #[test]
+    fn watch_get_repeats_current_value() {
+        let watch = MockWatch::new();
+        let mut receiver = watch.receiver();
+        assert_eq!(receiver.try_get(), None);
+
+        watch.send(5);
+        assert_eq!(block_on(receiver.get()), 5);
+        assert_eq!(receiver.try_get(), Some(5));
+        assert_eq!(receiver.try_changed(), None);
+
+        assert_eq!(receiver.observed(), [5, 5]);
+    }
+
+    #[test]
+    fn watch_changed_is_woken_by_send() {
+        let watch = MockWatch::new();
+        let mut receiver = watch.receiver();
+        let mut cx = Context::from_waker(noop_waker_ref());
+        {
+            let mut changed = pin!(receiver.changed());
+            assert_eq!(changed.as_mut().poll(&mut cx), Poll::Pending);
+
+            watch.send(1);
+            watch.send(2);
+            assert_eq!(changed.poll(&mut cx), Poll::Ready(2));
+        }
+
+        assert_eq!(receiver.observed(), [2]);
+    }
+
+    #[test]
+    fn watch_clear_hides_value_but_keeps_history() {
+        let watch = MockWatch::new();
+        let mut receiver = watch.receiver();
+        watch.send(1);
+
+        watch.clear();
+
+        assert!(!receiver.contains_value());
+        assert_eq!(receiver.try_changed(), None);
+        assert_eq!(watch.sent(), [1]);
+        assert_eq!(receiver.observed(), []);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 1 more change(s) to be received")]
+    fn unreceived_changes_just_drop() {
+        let _receiver = MockWatchReceiver::with_changes([1]);
+    }
+}
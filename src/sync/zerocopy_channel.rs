@@ -0,0 +1,564 @@
+//! Traits and mocked types to allow unit testing functions that use the
+//! [`embassy_sync::zerocopy_channel::Sender`] and [`embassy_sync::zerocopy_channel::Receiver`]
+//! halves of a zero-copy channel.
+//!
+//! # Examples
+//! ```
+//! use embassy_mock::sync::ZeroCopySender;
+//!
+//! // Generic over the `ZeroCopySender` trait
+//! async fn send_reading(output: &mut impl ZeroCopySender<[u8; 4]>, reading: u32) {
+//!     let buffer = output.send().await;
+//!     *buffer = reading.to_be_bytes();
+//!     output.send_done();
+//! }
+//!
+//! # test_send_reading();
+//! // The unit tests that use the `MockZeroCopySender`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_futures::block_on;
+//!     use embassy_mock::sync::MockZeroCopySender;
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_send_reading() {
+//!         let mut output = MockZeroCopySender::new([[0; 4]]);
+//!
+//!         block_on(send_reading(&mut output, 0x0102_0304));
+//!
+//!         assert_eq!(output.sent(), [[1, 2, 3, 4]]);
+//!         assert_eq!(output.done(), Ok(()));
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use core::future::Future;
+use embassy_sync::{
+    blocking_mutex::raw::RawMutex,
+    zerocopy_channel::{Receiver as EmbassyReceiver, Sender as EmbassySender},
+};
+#[cfg(feature = "std")]
+use {
+    crate::waiting,
+    core::{
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    snafu::prelude::*,
+    std::{collections::VecDeque, vec::Vec},
+};
+
+/// The trait to replace the [`embassy_sync::zerocopy_channel::Sender`] in code to allow the
+/// [`MockZeroCopySender`] to be used in its place for tests.
+pub trait ZeroCopySender<T> {
+    /// Wrapper for [`embassy_sync::zerocopy_channel::Sender::send()`].
+    fn send<'a>(&'a mut self) -> impl Future<Output = &'a mut T>
+    where
+        T: 'a;
+
+    /// Wrapper for [`embassy_sync::zerocopy_channel::Sender::try_send()`].
+    fn try_send(&mut self) -> Option<&mut T>;
+
+    /// Wrapper for [`embassy_sync::zerocopy_channel::Sender::send_done()`].
+    fn send_done(&mut self);
+}
+
+/// The trait to replace the [`embassy_sync::zerocopy_channel::Receiver`] in code to allow the
+/// [`MockZeroCopyReceiver`] to be used in its place for tests.
+pub trait ZeroCopyReceiver<T> {
+    /// Wrapper for [`embassy_sync::zerocopy_channel::Receiver::receive()`].
+    fn receive<'a>(&'a mut self) -> impl Future<Output = &'a mut T>
+    where
+        T: 'a;
+
+    /// Wrapper for [`embassy_sync::zerocopy_channel::Receiver::try_receive()`].
+    fn try_receive(&mut self) -> Option<&mut T>;
+
+    /// Wrapper for [`embassy_sync::zerocopy_channel::Receiver::receive_done()`].
+    fn receive_done(&mut self);
+}
+
+impl<M: RawMutex, T> ZeroCopySender<T> for EmbassySender<'_, M, T> {
+    /// Asynchronously borrow the next free buffer of the channel to write the value into.
+    fn send<'a>(&'a mut self) -> impl Future<Output = &'a mut T>
+    where
+        T: 'a,
+    {
+        self.send()
+    }
+
+    /// Attempts to borrow the next free buffer of the channel to write the value into.
+    fn try_send(&mut self) -> Option<&mut T> {
+        self.try_send()
+    }
+
+    /// Notify the channel that the sending of the value has been finalized.
+    fn send_done(&mut self) {
+        self.send_done();
+    }
+}
+
+impl<M: RawMutex, T> ZeroCopyReceiver<T> for EmbassyReceiver<'_, M, T> {
+    /// Asynchronously borrow the buffer of the next value in the channel.
+    fn receive<'a>(&'a mut self) -> impl Future<Output = &'a mut T>
+    where
+        T: 'a,
+    {
+        self.receive()
+    }
+
+    /// Attempts to borrow the buffer of the next value in the channel.
+    fn try_receive(&mut self) -> Option<&mut T> {
+        self.try_receive()
+    }
+
+    /// Notify the channel that the receiving of the value has been finalized.
+    fn receive_done(&mut self) {
+        self.receive_done();
+    }
+}
+
+impl<T, S: ZeroCopySender<T> + ?Sized> ZeroCopySender<T> for &mut S {
+    /// Call [`ZeroCopySender::send()`] of the referenced sender.
+    fn send<'a>(&'a mut self) -> impl Future<Output = &'a mut T>
+    where
+        T: 'a,
+    {
+        (**self).send()
+    }
+
+    /// Call [`ZeroCopySender::try_send()`] of the referenced sender.
+    fn try_send(&mut self) -> Option<&mut T> {
+        (**self).try_send()
+    }
+
+    /// Call [`ZeroCopySender::send_done()`] of the referenced sender.
+    fn send_done(&mut self) {
+        (**self).send_done();
+    }
+}
+
+impl<T, R: ZeroCopyReceiver<T> + ?Sized> ZeroCopyReceiver<T> for &mut R {
+    /// Call [`ZeroCopyReceiver::receive()`] of the referenced receiver.
+    fn receive<'a>(&'a mut self) -> impl Future<Output = &'a mut T>
+    where
+        T: 'a,
+    {
+        (**self).receive()
+    }
+
+    /// Call [`ZeroCopyReceiver::try_receive()`] of the referenced receiver.
+    fn try_receive(&mut self) -> Option<&mut T> {
+        (**self).try_receive()
+    }
+
+    /// Call [`ZeroCopyReceiver::receive_done()`] of the referenced receiver.
+    fn receive_done(&mut self) {
+        (**self).receive_done();
+    }
+}
+
+/// The errors that are reported by [`MockZeroCopySender`] and [`MockZeroCopyReceiver`].
+#[cfg(feature = "std")]
+#[derive(Debug, Snafu, PartialEq)]
+pub enum MockZeroCopyChannelError {
+    /// Some of the buffers given to the [`MockZeroCopySender`] were never sent.
+    #[snafu(display("expected {remaining} more buffer(s) to be sent"))]
+    UnsentBuffers {
+        /// The number of buffers that were never sent.
+        remaining: usize,
+    },
+
+    /// Some of the buffers given to the [`MockZeroCopyReceiver`] were never received.
+    #[snafu(display("expected {remaining} more buffer(s) to be received"))]
+    UnreceivedBuffers {
+        /// The number of buffers that were never received.
+        remaining: usize,
+    },
+}
+
+/// A mocked version of [`embassy_sync::zerocopy_channel::Sender`] that can be used in its place
+/// for unit tests.
+///
+/// It hands out the buffers owned by the test in order. [`ZeroCopySender::send()`] and
+/// [`ZeroCopySender::try_send()`] borrow the next buffer, and [`ZeroCopySender::send_done()`]
+/// records it with what the code under test wrote into it. Once all of the buffers have been
+/// sent the channel is full, so [`ZeroCopySender::send()`] never completes.
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if any of the buffers were never
+/// sent.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::sync::{MockZeroCopySender, ZeroCopySender};
+///
+/// let mut sender = MockZeroCopySender::new([0, 0]);
+///
+/// *block_on(sender.send()) = 4;
+/// sender.send_done();
+/// *sender.try_send().unwrap() = 2;
+/// sender.send_done();
+///
+/// assert_eq!(sender.try_send(), None);
+/// assert_eq!(sender.sent(), [4, 2]);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockZeroCopySender<T> {
+    /// The buffers still to be sent, in order.
+    buffers: VecDeque<T>,
+
+    /// The buffers that were sent, in order.
+    sent: Vec<T>,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<T> MockZeroCopySender<T> {
+    /// Create a [`MockZeroCopySender`] that hands out the `buffers` in order.
+    pub fn new(buffers: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            buffers: buffers.into_iter().collect(),
+            sent: Vec::new(),
+            is_done: false,
+        }
+    }
+
+    /// The buffers that were sent, with what was written into them, in order.
+    pub fn sent(&self) -> &[T] {
+        &self.sent
+    }
+
+    /// Check that all of the buffers were sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockZeroCopyChannelError, MockZeroCopySender, ZeroCopySender};
+    ///
+    /// let mut sender = MockZeroCopySender::new([0, 0, 0]);
+    /// *sender.try_send().unwrap() = 1;
+    /// sender.send_done();
+    ///
+    /// let expected = Err(MockZeroCopyChannelError::UnsentBuffers { remaining: 2 });
+    /// assert_eq!(sender.done(), expected);
+    ///
+    /// // This doesn't panic when `sender` is dropped as `sender.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockZeroCopyChannelError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that all of the buffers were sent.
+    fn check(&self) -> Result<(), MockZeroCopyChannelError> {
+        let remaining = self.buffers.len();
+        ensure!(remaining == 0, UnsentBuffersSnafu { remaining });
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for MockZeroCopySender<T> {
+    /// If [`Self::done()`] has not been called before being dropped then check that all of the
+    /// buffers were sent.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> ZeroCopySender<T> for MockZeroCopySender<T> {
+    /// Borrow the next buffer, waiting forever if they have all been sent.
+    fn send<'a>(&'a mut self) -> impl Future<Output = &'a mut T>
+    where
+        T: 'a,
+    {
+        MockBufferFuture {
+            buffer: self.buffers.front_mut(),
+            method: "MockZeroCopySender::send()",
+        }
+    }
+
+    /// Borrow the next buffer, or [`None`] if they have all been sent.
+    fn try_send(&mut self) -> Option<&mut T> {
+        self.buffers.front_mut()
+    }
+
+    /// Record the next buffer as sent, this does nothing if they have all been sent.
+    fn send_done(&mut self) {
+        if let Some(buffer) = self.buffers.pop_front() {
+            self.sent.push(buffer);
+        }
+    }
+}
+
+/// A mocked version of [`embassy_sync::zerocopy_channel::Receiver`] that can be used in its
+/// place for unit tests.
+///
+/// It hands out the buffers owned by the test in order. [`ZeroCopyReceiver::receive()`] and
+/// [`ZeroCopyReceiver::try_receive()`] borrow the next buffer, and
+/// [`ZeroCopyReceiver::receive_done()`] records it, with anything the code under test changed in
+/// it. Once all of the buffers have been received the channel is empty, so
+/// [`ZeroCopyReceiver::receive()`] never completes.
+///
+/// # Panics
+///
+/// If dropped without calling [`Self::done()`] then this panics if any of the buffers were never
+/// received.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::sync::{MockZeroCopyReceiver, ZeroCopyReceiver};
+///
+/// let mut receiver = MockZeroCopyReceiver::with_received([*b"ab", *b"cd"]);
+///
+/// assert_eq!(block_on(receiver.receive()), b"ab");
+/// receiver.receive_done();
+/// receiver.try_receive().unwrap().reverse();
+/// receiver.receive_done();
+///
+/// assert_eq!(receiver.try_receive(), None);
+/// assert_eq!(receiver.received(), [*b"ab", *b"dc"]);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockZeroCopyReceiver<T> {
+    /// The buffers still to be received, in order.
+    buffers: VecDeque<T>,
+
+    /// The buffers that were received, in order.
+    received: Vec<T>,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+#[cfg(feature = "std")]
+impl<T> MockZeroCopyReceiver<T> {
+    /// Create a [`MockZeroCopyReceiver`] that hands out the `buffers` in order.
+    pub fn with_received(buffers: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            buffers: buffers.into_iter().collect(),
+            received: Vec::new(),
+            is_done: false,
+        }
+    }
+
+    /// The buffers that were received, with anything changed in them, in order.
+    pub fn received(&self) -> &[T] {
+        &self.received
+    }
+
+    /// Check that all of the buffers were received.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::sync::{MockZeroCopyChannelError, MockZeroCopyReceiver};
+    ///
+    /// let receiver = MockZeroCopyReceiver::with_received([1, 2]);
+    ///
+    /// let expected = Err(MockZeroCopyChannelError::UnreceivedBuffers { remaining: 2 });
+    /// assert_eq!(receiver.done(), expected);
+    ///
+    /// // This doesn't panic when `receiver` is dropped as `receiver.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockZeroCopyChannelError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that all of the buffers were received.
+    fn check(&self) -> Result<(), MockZeroCopyChannelError> {
+        let remaining = self.buffers.len();
+        ensure!(remaining == 0, UnreceivedBuffersSnafu { remaining });
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Drop for MockZeroCopyReceiver<T> {
+    /// If [`Self::done()`] has not been called before being dropped then check that all of the
+    /// buffers were received.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> ZeroCopyReceiver<T> for MockZeroCopyReceiver<T> {
+    /// Borrow the next buffer, waiting forever if they have all been received.
+    fn receive<'a>(&'a mut self) -> impl Future<Output = &'a mut T>
+    where
+        T: 'a,
+    {
+        MockBufferFuture {
+            buffer: self.buffers.front_mut(),
+            method: "MockZeroCopyReceiver::receive()",
+        }
+    }
+
+    /// Borrow the next buffer, or [`None`] if they have all been received.
+    fn try_receive(&mut self) -> Option<&mut T> {
+        self.buffers.front_mut()
+    }
+
+    /// Record the next buffer as received, this does nothing if they have all been received.
+    fn receive_done(&mut self) {
+        if let Some(buffer) = self.buffers.pop_front() {
+            self.received.push(buffer);
+        }
+    }
+}
+
+/// The future returned by [`ZeroCopySender::send()`] for a [`MockZeroCopySender`] and by
+/// [`ZeroCopyReceiver::receive()`] for a [`MockZeroCopyReceiver`].
+#[cfg(feature = "std")]
+struct MockBufferFuture<'a, T> {
+    /// The borrowed buffer, [`None`] if there wasn't one or it has already been returned.
+    buffer: Option<&'a mut T>,
+
+    /// The method that returned this future, recorded while it waits.
+    method: &'static str,
+}
+
+#[cfg(feature = "std")]
+impl<'a, T> Future for MockBufferFuture<'a, T> {
+    type Output = &'a mut T;
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Nothing can add a buffer while the mock is borrowed, so there's no need to wake.
+        match self.buffer.take() {
+            Some(buffer) => Poll::Ready(buffer),
+            None => {
+                waiting::record(self.method);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::pin::pin;
+    use embassy_futures::block_on;
+    use embassy_sync::{blocking_mutex::raw::NoopRawMutex, zerocopy_channel::Channel};
+    use futures_util::task::noop_waker_ref;
+
+    async fn forward_incremented(
+        input: &mut impl ZeroCopyReceiver<u8>,
+        output: &mut impl ZeroCopySender<u8>,
+    ) {
+        let value = *input.receive().await;
+        input.receive_done();
+        *output.send().await = value + 1;
+        output.send_done();
+    }
+
+    #[test]
+    fn embassy_sender_and_receiver() {
+        let mut buffers = [0; 2];
+        let mut channel = Channel::<NoopRawMutex, u8>::new(&mut buffers);
+        let (mut sender, mut receiver) = channel.split();
+
+        *block_on(ZeroCopySender::send(&mut sender)) = 1;
+        ZeroCopySender::send_done(&mut sender);
+        *ZeroCopySender::try_send(&mut sender).unwrap() = 2;
+        ZeroCopySender::send_done(&mut sender);
+        assert_eq!(ZeroCopySender::try_send(&mut sender), None);
+
+        assert_eq!(*block_on(ZeroCopyReceiver::receive(&mut receiver)), 1);
+        ZeroCopyReceiver::receive_done(&mut receiver);
+        assert_eq!(ZeroCopyReceiver::try_receive(&mut receiver), Some(&mut 2));
+        ZeroCopyReceiver::receive_done(&mut receiver);
+        assert_eq!(ZeroCopyReceiver::try_receive(&mut receiver), None);
+    }
+
+    #[test]
+    fn mocks_record_buffers() {
+        let mut input = MockZeroCopyReceiver::with_received([3, 7]);
+        let mut output = MockZeroCopySender::new([0, 0]);
+
+        block_on(forward_incremented(&mut input, &mut output));
+        block_on(forward_incremented(&mut input, &mut output));
+
+        assert_eq!(input.received(), [3, 7]);
+        assert_eq!(output.sent(), [4, 8]);
+        input.done().unwrap();
+        output.done().unwrap();
+    }
+
+    #[test]
+    fn unfinished_send_is_not_recorded() {
+        let mut sender = MockZeroCopySender::new([0]);
+
+        *sender.try_send().unwrap() = 5;
+        assert_eq!(sender.try_send(), Some(&mut 5));
+        assert!(sender.sent().is_empty());
+
+        sender.send_done();
+        sender.send_done();
+        assert_eq!(sender.sent(), [5]);
+    }
+
+    #[test]
+    fn send_is_pending_when_full() {
+        let mut sender = MockZeroCopySender::<u8>::new([]);
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        let mut send = pin!(sender.send());
+        assert_eq!(send.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(send.as_mut().poll(&mut cx), Poll::Pending);
+    }
+
+    #[test]
+    fn receive_is_pending_when_empty() {
+        let mut receiver = MockZeroCopyReceiver::<u8>::with_received([]);
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        let mut receive = pin!(receiver.receive());
+        assert_eq!(receive.as_mut().poll(&mut cx), Poll::Pending);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 1 more buffer(s) to be sent")]
+    fn unsent_buffers_just_drop() {
+        let _sender = MockZeroCopySender::new([0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 2 more buffer(s) to be received")]
+    fn unreceived_buffers_just_drop() {
+        let _receiver = MockZeroCopyReceiver::with_received([0, 1]);
+    }
+}
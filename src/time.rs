@@ -1,7 +1,69 @@
 //! A mocked version of the `embassy-time` crate.
 
+#[cfg(feature = "std")]
+pub mod cadence;
+#[cfg(feature = "std")]
+pub mod clock;
+pub mod delay;
+#[cfg(feature = "driver")]
+pub mod driver;
+#[cfg(feature = "alloc")]
+pub mod dynamic;
+#[cfg(feature = "std")]
+pub mod expectations;
+pub mod instant;
+pub mod matcher;
+#[cfg(feature = "registry")]
+pub mod registry;
 pub mod ticker;
+pub mod timeout;
 pub mod timer;
 
-pub use ticker::{MockTicker, MockTickerError, Ticker};
+#[cfg(feature = "std")]
+pub use cadence::{assert_cadence, Cadence, CadenceError};
+#[cfg(feature = "std")]
+pub use clock::{block_on_with_virtual_time, MockClock};
+pub use delay::{BlockingDelay, Delay, MockDelay};
+#[cfg(feature = "alloc")]
+pub use dynamic::{DynTicker, DynTimer, TimerSource};
+#[cfg(feature = "std")]
+pub use expectations::{MockTickerExpectations, MockTimerExpectations};
+pub use instant::{Instant, MockInstant};
+pub use matcher::DurationMatcher;
+#[cfg(feature = "registry")]
+pub use ticker::MockTickerRecord;
+pub use ticker::{
+    MockTick, MockTickOffset, MockTicker, MockTickerBuilder, MockTickerError, MockTickerReset,
+    Ticker,
+};
+pub use timeout::{MockTimeout, WithTimeout};
+#[cfg(feature = "registry")]
+pub use timer::MockTimerRecord;
 pub use timer::{MockTimer, Timer};
+#[cfg(feature = "std")]
+pub use timer::{MockTimerError, MockTimerSequence};
+
+// The real `embassy-time` types are `Send` and `Sync`, so a mock that isn't would make the futures
+// of the code under test `!Send` only in its tests. `MockClock` and `Cadence` are deliberately
+// neither as they belong to the clock of the thread they were created on.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<DurationMatcher>();
+    assert_send_sync::<MockDelay>();
+    assert_send_sync::<MockInstant>();
+    assert_send_sync::<MockTicker>();
+    assert_send_sync::<MockTickerBuilder>();
+    assert_send_sync::<MockTimeout>();
+    assert_send_sync::<MockTimer>();
+    #[cfg(feature = "std")]
+    assert_send_sync::<MockTickerExpectations>();
+    #[cfg(feature = "std")]
+    assert_send_sync::<MockTimerExpectations>();
+    #[cfg(feature = "std")]
+    assert_send_sync::<MockTimerSequence>();
+    #[cfg(feature = "registry")]
+    assert_send_sync::<MockTickerRecord>();
+    #[cfg(feature = "registry")]
+    assert_send_sync::<MockTimerRecord>();
+};
@@ -0,0 +1,271 @@
+//! Helpers to check that a periodic task runs its body at the expected cadence under virtual
+//! time.
+//!
+//! A [`Cadence`] records the time of the [`MockClock`] each time the body of the task runs, the
+//! intervals between the runs can then be checked against a [`DurationMatcher`].
+//! [`assert_cadence()`] does both, running the task for a fixed amount of virtual time.
+//!
+//! # Examples
+//! ```
+//! use embassy_mock::time::{assert_cadence, DurationMatcher, MockTicker, Ticker};
+//! use embassy_time::Duration;
+//!
+//! // Generic over the `Ticker` trait
+//! async fn blink<T: Ticker>(mut on_blink: impl FnMut()) {
+//!     let mut ticker = T::every(Duration::from_secs(1));
+//!     loop {
+//!         ticker.next().await;
+//!         on_blink();
+//!     }
+//! }
+//!
+//! assert_cadence(
+//!     Duration::from_secs(60),
+//!     DurationMatcher::within(Duration::from_secs(1), Duration::from_millis(10)),
+//!     |cadence| blink::<MockTicker>(move || cadence.record()),
+//! );
+//! ```
+
+use super::{clock, DurationMatcher, Instant, MockClock, MockInstant};
+use core::{cell::RefCell, future::Future};
+use embassy_time::Duration;
+use snafu::prelude::*;
+use std::{rc::Rc, vec::Vec};
+
+/// The errors that are reported by [`Cadence::check()`].
+#[derive(Debug, Snafu, PartialEq)]
+pub enum CadenceError {
+    /// The body ran too few times to have an interval between runs.
+    #[snafu(display("expected the body to run at least twice, actually ran {actual} time(s)"))]
+    TooFewRuns {
+        /// The number of times the body ran.
+        actual: usize,
+    },
+
+    /// The interval between two runs of the body was wrong.
+    #[snafu(display(
+        "expected the interval before run {index} to be {expected}, actually {actual}"
+    ))]
+    WrongInterval {
+        /// The position of the run after the interval, starting from zero.
+        index: usize,
+
+        /// The [`DurationMatcher`] the interval was expected to match.
+        expected: DurationMatcher,
+
+        /// The actual interval between the runs.
+        actual: Duration,
+    },
+
+    /// The body stopped running, the last run was longer ago than the interval.
+    #[snafu(display("expected the last run to be at most {max} ago, actually {actual} ago"))]
+    Stalled {
+        /// The longest interval that matches.
+        max: Duration,
+
+        /// The time of the [`MockClock`] since the last run.
+        actual: Duration,
+    },
+}
+
+/// A recording of the times that the body of a periodic task runs.
+///
+/// Clones share the same recording so one can be moved into the task while another is kept by
+/// the test.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::time::{Cadence, MockClock, MockTicker, Ticker};
+/// use embassy_time::Duration;
+///
+/// let clock = MockClock::new();
+/// let cadence = Cadence::new();
+/// let recorder = cadence.clone();
+///
+/// clock.run_for(Duration::from_secs(5), async move {
+///     let mut ticker = MockTicker::every(Duration::from_millis(500));
+///     loop {
+///         ticker.next().await;
+///         recorder.record();
+///     }
+/// });
+///
+/// assert_eq!(cadence.runs().len(), 10);
+/// cadence.check(Duration::from_millis(500)).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Cadence {
+    /// The time of each run of the body, in order.
+    runs: Rc<RefCell<Vec<MockInstant>>>,
+}
+
+impl Cadence {
+    /// Create a [`Cadence`] that has not recorded any runs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a run of the body at the current time of the [`MockClock`].
+    pub fn record(&self) {
+        self.runs.borrow_mut().push(MockInstant::now());
+    }
+
+    /// The time of each recorded run of the body, in order.
+    pub fn runs(&self) -> Vec<MockInstant> {
+        self.runs.borrow().clone()
+    }
+
+    /// Check that the body ran at least twice, that every interval between the runs matches
+    /// `interval` and that the last run was no longer ago than the longest interval that matches,
+    /// which catches a task that stalls after running a few times.
+    ///
+    /// The time since the last run is measured to the current time of the [`MockClock`], so this
+    /// should be called before the clock is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{Cadence, CadenceError, MockClock};
+    /// use embassy_time::Duration;
+    ///
+    /// let clock = MockClock::new();
+    /// let cadence = Cadence::new();
+    /// cadence.record();
+    /// clock.advance(Duration::from_secs(1));
+    /// cadence.record();
+    /// clock.advance(Duration::from_secs(2));
+    /// cadence.record();
+    ///
+    /// let expected = Err(CadenceError::WrongInterval {
+    ///     index: 2,
+    ///     expected: Duration::from_secs(1).into(),
+    ///     actual: Duration::from_secs(2),
+    /// });
+    /// assert_eq!(cadence.check(Duration::from_secs(1)), expected);
+    /// ```
+    pub fn check(&self, interval: impl Into<DurationMatcher>) -> Result<(), CadenceError> {
+        let expected = interval.into();
+        let runs = self.runs.borrow();
+        ensure!(runs.len() >= 2, TooFewRunsSnafu { actual: runs.len() });
+
+        let mismatch = runs
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .enumerate()
+            .find(|(_, actual)| !expected.matches(*actual));
+        if let Some((index, actual)) = mismatch {
+            return Err(CadenceError::WrongInterval {
+                index: index + 1,
+                expected,
+                actual,
+            });
+        }
+
+        if let (Some(max), Some(&last)) = (expected.max(), runs.last()) {
+            let actual = clock::now() - last;
+            ensure!(actual <= max, StalledSnafu { max, actual });
+        }
+
+        Ok(())
+    }
+}
+
+/// Run the periodic task created by `task` with a new [`MockClock`] for `run_for` of virtual time
+/// and assert that it ran its body at the expected `interval`.
+///
+/// The task is given a [`Cadence`] to call [`Cadence::record()`] on each time its body runs, see
+/// [`MockClock::run_for()`] for how the task is run.
+///
+/// # Panics
+///
+/// Panics if a [`MockClock`] is already alive on the current thread, if the task is stalled and
+/// is not waiting for any time to pass or if [`Cadence::check()`] returns an error, including if
+/// the task stops running its body before the end of `run_for`.
+///
+/// # Examples
+///
+/// ```should_panic
+/// use embassy_mock::time::{assert_cadence, MockTimer, Timer};
+/// use embassy_time::Duration;
+///
+/// async fn poll_sensor<T: Timer>(mut on_poll: impl FnMut()) {
+///     loop {
+///         on_poll();
+///         T::after(Duration::from_millis(1100)).await; // Drifts from the intended 1 second.
+///     }
+/// }
+///
+/// assert_cadence(Duration::from_secs(10), Duration::from_secs(1), |cadence| {
+///     poll_sensor::<MockTimer>(move || cadence.record())
+/// });
+/// ```
+pub fn assert_cadence<F: Future>(
+    run_for: Duration,
+    interval: impl Into<DurationMatcher>,
+    task: impl FnOnce(Cadence) -> F,
+) {
+    let cadence = Cadence::new();
+    let clock = MockClock::new();
+    clock.run_for(run_for, task(cadence.clone()));
+
+    if let Err(error) = cadence.check(interval) {
+        panic!("{error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::{MockTicker, Ticker};
+
+    #[test]
+    fn check_needs_two_runs() {
+        let cadence = Cadence::new();
+        cadence.record();
+
+        assert_eq!(
+            cadence.check(DurationMatcher::any()),
+            Err(CadenceError::TooFewRuns { actual: 1 })
+        );
+    }
+
+    #[test]
+    fn assert_cadence_passes_for_ticker() {
+        assert_cadence(Duration::from_secs(5), Duration::from_secs(1), |cadence| {
+            let mut ticker = MockTicker::every(Duration::from_secs(1));
+            async move {
+                loop {
+                    ticker.next().await;
+                    cadence.record();
+                }
+            }
+        });
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected the last run to be at most 1000000 ticks ago, actually 7000000 ticks ago"
+    )]
+    fn assert_cadence_fails_if_task_stalls() {
+        assert_cadence(Duration::from_secs(10), Duration::from_secs(1), |cadence| {
+            let mut ticker = MockTicker::every(Duration::from_secs(1));
+            async move {
+                for _ in 0..3 {
+                    ticker.next().await;
+                    cadence.record();
+                }
+                // Waits on something that never happens, without stopping the clock.
+                MockTicker::every(Duration::from_secs(60)).next().await;
+            }
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "expected the body to run at least twice, actually ran 0 time(s)")]
+    fn assert_cadence_fails_if_body_never_runs() {
+        assert_cadence(Duration::from_secs(5), Duration::from_secs(1), |_| async {
+            MockTicker::every(Duration::from_secs(10)).next().await;
+        });
+    }
+}
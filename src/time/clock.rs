@@ -0,0 +1,946 @@
+//! A mocked clock that is shared by all of the mocked time types on the current thread.
+//!
+//! Creating a [`MockClock`] attaches the mocked time types to it:
+//! [`MockInstant::now()`](super::Instant::now) returns the time of the clock, and
+//! [`MockTimer`](super::MockTimer)s and [`MockTicker`](super::MockTicker)s created with
+//! [`Timer`](super::Timer) and [`Ticker`](super::Ticker) only complete once the clock has been
+//! advanced past their deadline. This allows testing code that mixes timers, tickers and instants
+//! with a consistent view of time.
+//!
+//! # Examples
+//! ```
+//! use core::{
+//!     future::Future,
+//!     pin::pin,
+//!     task::{Context, Poll},
+//! };
+//! use embassy_mock::time::{Instant, MockClock, MockInstant, MockTimer, Timer};
+//! use embassy_time::Duration;
+//! use futures_util::task::noop_waker_ref;
+//!
+//! // Generic over the `Instant` and `Timer` traits
+//! async fn measure_sleep<I: Instant, T: Timer>() -> Duration {
+//!     let start = I::now();
+//!     T::after(Duration::from_secs(2)).await;
+//!     I::now() - start
+//! }
+//!
+//! let clock = MockClock::new();
+//! let mut cx = Context::from_waker(noop_waker_ref());
+//! let mut fut = pin!(measure_sleep::<MockInstant, MockTimer>());
+//!
+//! assert_eq!(fut.as_mut().poll(&mut cx), Poll::Pending);
+//!
+//! clock.advance(Duration::from_secs(1));
+//! assert_eq!(fut.as_mut().poll(&mut cx), Poll::Pending);
+//!
+//! clock.advance(Duration::from_secs(1));
+//! assert_eq!(fut.as_mut().poll(&mut cx), Poll::Ready(Duration::from_secs(2)));
+//! ```
+
+use super::{Instant as _, MockInstant};
+use crate::waiting;
+use core::{
+    cell::RefCell,
+    future::{poll_fn, Future},
+    marker::PhantomData,
+    pin::pin,
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context, Poll, Waker},
+};
+use embassy_time::{Duration, TICK_HZ};
+use std::{sync::Arc, task::Wake, vec::Vec};
+
+std::thread_local! {
+    /// The state of the mocked clock of the current thread.
+    static CLOCK: RefCell<ClockState> = const {
+        RefCell::new(ClockState {
+            now: MockInstant::MIN,
+            tick_hz: TICK_HZ,
+            is_attached: false,
+            is_strict: false,
+            wakers: Vec::new(),
+            last_observed: None,
+            first_rewind: None,
+        })
+    };
+}
+
+/// The state of the mocked clock of a thread.
+#[derive(Debug)]
+struct ClockState {
+    /// The current time of the clock, before it is rounded down to a whole tick at `tick_hz`.
+    now: MockInstant,
+
+    /// The number of ticks per second of the clock, see [`MockClock::with_tick_hz()`].
+    tick_hz: u64,
+
+    /// Is a [`MockClock`] alive on this thread.
+    is_attached: bool,
+
+    /// Does setting the clock to an earlier time panic.
+    is_strict: bool,
+
+    /// The wakers of pending futures and the time that they are waiting for.
+    wakers: Vec<(MockInstant, Waker)>,
+
+    /// The last time returned by [`MockInstant::now()`](super::Instant::now).
+    last_observed: Option<MockInstant>,
+
+    /// The first time [`MockInstant::now()`](super::Instant::now) returned an earlier time than
+    /// the time before, with both times.
+    first_rewind: Option<(MockInstant, MockInstant)>,
+}
+
+impl ClockState {
+    /// The time that the clock reads, which is `now` rounded down to a whole tick.
+    fn reading(&self) -> MockInstant {
+        MockInstant::from_ticks(read_counter(self.now.as_ticks(), self.tick_hz))
+    }
+}
+
+/// Convert `ticks` at `from_hz` ticks per second to ticks at `to_hz` ticks per second, rounding
+/// down or up, or [`None`] if the result doesn't fit in a `u64`.
+fn convert_ticks(ticks: u64, from_hz: u64, to_hz: u64, round_up: bool) -> Option<u64> {
+    let scaled = u128::from(ticks) * u128::from(to_hz);
+    let converted = if round_up {
+        scaled.div_ceil(u128::from(from_hz))
+    } else {
+        scaled / u128::from(from_hz)
+    };
+    u64::try_from(converted).ok()
+}
+
+/// The ticks of `embassy-time` that a counter running at `tick_hz` ticks per second reads at
+/// `ticks`, which is `ticks` rounded down to a whole tick of the counter.
+pub(super) fn read_counter(ticks: u64, tick_hz: u64) -> u64 {
+    let counted = convert_ticks(ticks, TICK_HZ, tick_hz, false)
+        .expect("the tick rate is at most embassy_time::TICK_HZ");
+    convert_ticks(counted, tick_hz, TICK_HZ, false).expect("rounding down can't overflow")
+}
+
+/// The earliest ticks of `embassy-time` that a counter running at `tick_hz` ticks per second reads
+/// `deadline` or later at, or [`None`] if the counter overflows first.
+pub(super) fn counter_reaches(deadline: u64, tick_hz: u64) -> Option<u64> {
+    let counted = convert_ticks(deadline, TICK_HZ, tick_hz, true)?;
+    convert_ticks(counted, tick_hz, TICK_HZ, true)
+}
+
+/// Check that a tick rate can be counted with the ticks of `embassy-time`.
+///
+/// # Panics
+///
+/// Panics if `tick_hz` is zero or faster than [`TICK_HZ`].
+pub(super) const fn assert_tick_hz(tick_hz: u64) {
+    assert!(
+        tick_hz > 0 && tick_hz <= TICK_HZ,
+        "the tick rate must be between 1 and embassy_time::TICK_HZ"
+    );
+}
+
+/// The current time of the mocked clock of the current thread.
+pub(crate) fn now() -> MockInstant {
+    CLOCK.with(|clock| clock.borrow().reading())
+}
+
+/// The current time of the mocked clock of the current thread, recording it as observed by the
+/// code under test.
+pub(crate) fn observe_now() -> MockInstant {
+    CLOCK.with(|clock| {
+        let mut clock = clock.borrow_mut();
+        let now = clock.reading();
+        if let Some(last) = clock.last_observed {
+            if now < last && clock.first_rewind.is_none() {
+                clock.first_rewind = Some((last, now));
+            }
+        }
+        clock.last_observed = Some(now);
+        now
+    })
+}
+
+/// Take the first time that [`observe_now()`] returned an earlier time than the time before, with
+/// both times, or [`None`] if the times have never decreased.
+pub(crate) fn take_first_rewind() -> Option<(MockInstant, MockInstant)> {
+    CLOCK.with(|clock| clock.borrow_mut().first_rewind.take())
+}
+
+/// Set if setting the mocked clock of the current thread to an earlier time panics.
+pub(crate) fn set_strict(is_strict: bool) {
+    CLOCK.with(|clock| clock.borrow_mut().is_strict = is_strict);
+}
+
+/// Set the current time of the mocked clock of the current thread, waking any futures that are
+/// waiting for a time that has now been reached.
+///
+/// # Panics
+///
+/// Panics if the clock is strict and `now` is earlier than the current time.
+pub(crate) fn set_now(now: MockInstant) {
+    let due = CLOCK.with(|clock| {
+        let mut clock = clock.borrow_mut();
+        assert!(
+            !clock.is_strict || now >= clock.now,
+            "time went backwards from {} to {now}",
+            clock.now
+        );
+        clock.now = now;
+
+        let reading = clock.reading();
+        let (due, waiting) = core::mem::take(&mut clock.wakers)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(deadline, _)| *deadline <= reading);
+        clock.wakers = waiting;
+        due
+    });
+
+    // Wake outside of the borrow in case waking polls a future that uses the clock.
+    for (_, waker) in due {
+        waiting::waking("MockClock", || waker.wake());
+    }
+}
+
+/// Move the current time of the mocked clock of the current thread forward by `duration`, waking
+/// any futures that are waiting for a time that has now been reached.
+///
+/// # Panics
+///
+/// Panics if advancing the clock overflows [`MockInstant::MAX`].
+pub(crate) fn advance(duration: Duration) {
+    let now = CLOCK.with(|clock| clock.borrow().now);
+    set_now(now + duration);
+}
+
+/// The time to set the mocked clock of the current thread to for it to reach the earliest time
+/// that a pending future is waiting for, or [`None`] if nothing is waiting.
+///
+/// # Panics
+///
+/// Panics if the clock can't reach the deadline before [`MockInstant::MAX`].
+pub(crate) fn next_deadline() -> Option<MockInstant> {
+    CLOCK.with(|clock| {
+        let clock = clock.borrow();
+        let deadline = clock.wakers.iter().map(|(deadline, _)| *deadline).min()?;
+        let reached = counter_reaches(deadline.as_ticks(), clock.tick_hz)
+            .expect("overflow when adding duration to instant");
+        Some(MockInstant::from_ticks(reached))
+    })
+}
+
+/// Is a [`MockClock`] alive on the current thread.
+pub(crate) fn is_attached() -> bool {
+    CLOCK.with(|clock| clock.borrow().is_attached)
+}
+
+/// Check if `deadline` has been reached by the mocked clock of the current thread, if not then
+/// register `waker` to be woken once it has.
+pub(crate) fn poll_deadline(deadline: MockInstant, waker: &Waker) -> bool {
+    CLOCK.with(|clock| {
+        let mut clock = clock.borrow_mut();
+        if clock.reading() >= deadline {
+            return true;
+        }
+
+        let is_registered = clock
+            .wakers
+            .iter()
+            .any(|(registered, other)| *registered == deadline && other.will_wake(waker));
+        if !is_registered {
+            clock.wakers.push((deadline, waker.clone()));
+        }
+        false
+    })
+}
+
+/// A [`Wake`] implementation that records if it has been woken.
+#[derive(Debug, Default)]
+struct WokenFlag(AtomicBool);
+
+impl Wake for WokenFlag {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Run `fut` to completion on the current thread, calling `advance` to move time forward whenever
+/// the future is pending and hasn't woken its waker.
+///
+/// `advance` returns `false` if there is nothing to move time forward to.
+///
+/// # Panics
+///
+/// Panics if the future is stalled and `advance` returns `false`, as it would never complete.
+pub(crate) fn block_on_advancing<F: Future>(
+    fut: F,
+    mut advance: impl FnMut() -> bool,
+) -> F::Output {
+    let woken = Arc::new(WokenFlag(AtomicBool::new(true)));
+    let waker = Waker::from(Arc::clone(&woken));
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = pin!(fut);
+
+    loop {
+        if woken.0.swap(false, Ordering::SeqCst) {
+            if let Poll::Ready(output) = fut.as_mut().poll(&mut cx) {
+                return output;
+            }
+        } else {
+            assert!(
+                advance(),
+                "future is stalled and is not waiting for any time to pass"
+            );
+        }
+    }
+}
+
+/// Run `fut` to completion with a new [`MockClock`], jumping the clock to the next deadline
+/// whenever the future is stalled waiting for time to pass.
+///
+/// This is the same as calling [`MockClock::block_on()`] on a new [`MockClock`], see it for more
+/// details.
+///
+/// # Panics
+///
+/// Panics if a [`MockClock`] is already alive on the current thread or if the future is stalled
+/// and is not waiting for any time to pass.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::time::{block_on_with_virtual_time, MockTicker, Ticker};
+/// use embassy_time::Duration;
+///
+/// async fn count_ticks<T: Ticker>() -> usize {
+///     let mut ticker = T::every(Duration::from_secs(60));
+///     let mut ticks = 0;
+///     while ticks < 1000 {
+///         ticker.next().await;
+///         ticks += 1;
+///     }
+///     ticks
+/// }
+///
+/// // Finishes instantly even though 1000 minutes of virtual time pass.
+/// let ticks = block_on_with_virtual_time(count_ticks::<MockTicker>());
+///
+/// assert_eq!(ticks, 1000);
+/// ```
+pub fn block_on_with_virtual_time<F: Future>(fut: F) -> F::Output {
+    MockClock::new().block_on(fut)
+}
+
+/// A mocked clock that drives all of the mocked time types on the current thread.
+///
+/// While a [`MockClock`] is alive, [`MockTimer`](super::MockTimer)s and
+/// [`MockTicker`](super::MockTicker)s created with [`Timer`](super::Timer) and
+/// [`Ticker`](super::Ticker) wait for the clock to reach their deadline instead of completing
+/// immediately. Advancing the clock wakes any timers and tickers that are due and moves
+/// [`MockInstant::now()`](super::Instant::now) forward, the same as [`MockInstant::advance()`].
+///
+/// The clock is per thread so tests running in parallel don't interfere with each other. Only
+/// one [`MockClock`] can be alive on a thread at a time.
+///
+/// # Panics
+///
+/// Panics if created while another [`MockClock`] is alive on the current thread.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::time::{Instant, MockClock, MockInstant};
+/// use embassy_time::Duration;
+///
+/// let clock = MockClock::new();
+/// clock.advance(Duration::from_secs(3));
+///
+/// assert_eq!(clock.now(), MockInstant::from_secs(3));
+/// assert_eq!(MockInstant::now(), MockInstant::from_secs(3));
+/// ```
+#[derive(Debug)]
+pub struct MockClock {
+    /// The clock is per thread so the handle must stay on the thread it was created on.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl MockClock {
+    /// Create a [`MockClock`] starting at [`MockInstant::MIN`] and attach the mocked time types
+    /// on the current thread to it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{Instant, MockClock, MockInstant};
+    ///
+    /// MockInstant::set_now(MockInstant::from_secs(10));
+    /// let clock = MockClock::new();
+    ///
+    /// assert_eq!(clock.now(), MockInstant::MIN);
+    /// ```
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self::starting_at(MockInstant::MIN)
+    }
+
+    /// Create a [`MockClock`] starting at `start` and attach the mocked time types on the current
+    /// thread to it.
+    ///
+    /// Starting the clock close to [`MockInstant::MAX`] reproduces the tick counter running for a
+    /// long time, to find code that assumes adding to an [`Instant`](super::Instant) can't
+    /// overflow.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use embassy_mock::time::{Instant, MockClock, MockInstant, MockTimer, Timer};
+    /// use embassy_time::Duration;
+    ///
+    /// async fn production_code<T: Timer>() {
+    ///     T::after(Duration::from_secs(1)).await;
+    /// }
+    ///
+    /// let clock = MockClock::starting_at(MockInstant::from_ticks(u64::MAX - 10));
+    ///
+    /// clock.block_on(production_code::<MockTimer>()); // Panics as the deadline overflows.
+    /// ```
+    pub fn starting_at(start: MockInstant) -> Self {
+        CLOCK.with(|clock| {
+            let mut clock = clock.borrow_mut();
+            assert!(
+                !clock.is_attached,
+                "a MockClock already exists on this thread"
+            );
+
+            clock.is_attached = true;
+            clock.now = start;
+            clock.tick_hz = TICK_HZ;
+            clock.wakers.clear();
+        });
+
+        Self {
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Make the clock count `tick_hz` ticks per second instead of [`embassy_time::TICK_HZ`], as
+    /// the time driver of the target would.
+    ///
+    /// The times read from the clock are rounded down to a whole tick, so timers and tickers
+    /// complete on the first tick at or after their deadline and [`Self::advance_ticks()`] steps
+    /// by the ticks of the target. This finds code that assumes a finer resolution than the target
+    /// has, such as expecting a timer of less than one tick to take any time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tick_hz` is zero or faster than [`embassy_time::TICK_HZ`], as times are still
+    /// counted with the ticks of `embassy-time`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{Instant, MockClock, MockInstant};
+    /// use embassy_time::Duration;
+    ///
+    /// // A target with a 1 kHz tick.
+    /// let clock = MockClock::new().with_tick_hz(1000);
+    ///
+    /// clock.advance(Duration::from_micros(1500));
+    /// assert_eq!(MockInstant::now(), MockInstant::from_millis(1));
+    ///
+    /// clock.advance(Duration::from_micros(500));
+    /// assert_eq!(MockInstant::now(), MockInstant::from_millis(2));
+    ///
+    /// clock.advance_ticks(3);
+    /// assert_eq!(MockInstant::now(), MockInstant::from_millis(5));
+    /// ```
+    pub fn with_tick_hz(self, tick_hz: u64) -> Self {
+        assert_tick_hz(tick_hz);
+        CLOCK.with(|clock| clock.borrow_mut().tick_hz = tick_hz);
+        self
+    }
+
+    /// The current time of the clock.
+    pub fn now(&self) -> MockInstant {
+        now()
+    }
+
+    /// Set the current time of the clock, waking any timers and tickers that are now due.
+    ///
+    /// The time read from the clock is rounded down to a whole tick, see [`Self::with_tick_hz()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{Instant, MockClock, MockInstant};
+    ///
+    /// let clock = MockClock::new();
+    /// clock.set_now(MockInstant::from_millis(1500));
+    ///
+    /// assert_eq!(MockInstant::now(), MockInstant::from_millis(1500));
+    /// ```
+    pub fn set_now(&self, now: MockInstant) {
+        set_now(now);
+    }
+
+    /// Move the clock forward by `duration`, waking any timers and tickers that are now due.
+    ///
+    /// # Panics
+    ///
+    /// Panics if advancing the clock overflows [`MockInstant::MAX`].
+    pub fn advance(&self, duration: Duration) {
+        advance(duration);
+    }
+
+    /// Move the clock forward by a number of ticks, waking any timers and tickers that are now
+    /// due.
+    ///
+    /// There are [`embassy_time::TICK_HZ`] ticks per second unless the clock is made to count the
+    /// ticks of the target with [`Self::with_tick_hz()`]. The clock moves to the start of the tick,
+    /// so advancing it by one tick from part way through a tick only moves it to the next one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if advancing the clock overflows [`MockInstant::MAX`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{Instant, MockClock, MockInstant};
+    ///
+    /// let clock = MockClock::starting_at(MockInstant::from_ticks(u64::MAX - 1));
+    /// clock.advance_ticks(1);
+    ///
+    /// assert_eq!(clock.now(), MockInstant::MAX);
+    /// ```
+    pub fn advance_ticks(&self, ticks: u64) {
+        let (now, tick_hz) = CLOCK.with(|clock| {
+            let clock = clock.borrow();
+            (clock.now, clock.tick_hz)
+        });
+
+        let counted = convert_ticks(now.as_ticks(), TICK_HZ, tick_hz, false)
+            .and_then(|counted| counted.checked_add(ticks))
+            .and_then(|counted| convert_ticks(counted, tick_hz, TICK_HZ, true))
+            .expect("overflow when adding duration to instant");
+        set_now(MockInstant::from_ticks(counted));
+    }
+
+    /// Run `fut` to completion, jumping the clock to the next deadline whenever the future is
+    /// stalled waiting for a timer or ticker.
+    ///
+    /// This lets tests of long periodic loops finish instantly while keeping the order that the
+    /// timers and tickers complete in. The clock is only moved forward when the future is pending
+    /// and hasn't woken its waker, so anything that can make progress does so first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the future is stalled and is not waiting for any time to pass, as it would
+    /// never complete.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{Instant, MockClock, MockInstant, MockTimer, Timer};
+    /// use embassy_time::Duration;
+    ///
+    /// let clock = MockClock::new();
+    /// clock.block_on(async {
+    ///     MockTimer::after(Duration::from_secs(1)).await;
+    ///     MockTimer::after(Duration::from_secs(2)).await;
+    /// });
+    ///
+    /// assert_eq!(clock.now(), MockInstant::from_secs(3));
+    /// ```
+    pub fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        block_on_advancing(fut, || match next_deadline() {
+            Some(deadline) => {
+                set_now(deadline.max(now()));
+                true
+            }
+            None => false,
+        })
+    }
+
+    /// Run `fut` the same as [`Self::block_on()`] until it completes or the clock has moved
+    /// forward by `duration`, returning the output or [`None`] if it didn't complete in time.
+    ///
+    /// This allows running a periodic task that never completes for a fixed amount of virtual
+    /// time. Anything that is due at the end of the `duration` runs before `fut` is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if advancing the clock overflows [`MockInstant::MAX`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{Instant, MockClock, MockInstant, MockTicker, Ticker};
+    /// use embassy_time::Duration;
+    ///
+    /// let clock = MockClock::new();
+    /// let mut ticks = 0;
+    /// let output = clock.run_for(Duration::from_secs(10), async {
+    ///     let mut ticker = MockTicker::every(Duration::from_secs(1));
+    ///     loop {
+    ///         ticker.next().await;
+    ///         ticks += 1;
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(output, None);
+    /// assert_eq!(ticks, 10);
+    /// assert_eq!(clock.now(), MockInstant::from_secs(10));
+    /// ```
+    pub fn run_for<F: Future>(&self, duration: Duration, fut: F) -> Option<F::Output> {
+        let end = now() + duration;
+        let mut fut = pin!(fut);
+
+        self.block_on(poll_fn(|cx| {
+            if let Poll::Ready(output) = fut.as_mut().poll(cx) {
+                return Poll::Ready(Some(output));
+            }
+
+            if poll_deadline(end, cx.waker()) {
+                Poll::Ready(None)
+            } else {
+                Poll::Pending
+            }
+        }))
+    }
+}
+
+/// Assert that a future does not complete within a [`Duration`] of virtual time.
+///
+/// The future is run with a new [`MockClock`] using [`MockClock::run_for()`], so this finishes
+/// instantly however long the [`Duration`] is.
+///
+/// # Panics
+///
+/// Panics if the future completes within the [`Duration`], if a [`MockClock`] is already alive
+/// on the current thread or if the future is stalled and is not waiting for any time to pass.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::{assert_times_out, time::{MockTimer, Timer}};
+/// use embassy_time::Duration;
+///
+/// async fn wait_for_response<T: Timer>() {
+///     T::after(Duration::from_secs(30)).await;
+/// }
+///
+/// assert_times_out!(Duration::from_secs(10), wait_for_response::<MockTimer>());
+/// ```
+#[macro_export]
+macro_rules! assert_times_out {
+    ($duration:expr, $fut:expr $(,)?) => {{
+        let duration = $duration;
+        if $crate::time::MockClock::new()
+            .run_for(duration, $fut)
+            .is_some()
+        {
+            panic!("expected the future to time out after {duration}, it completed");
+        }
+    }};
+}
+
+/// Assert that a future completes within a [`Duration`] of virtual time, evaluating to its
+/// output.
+///
+/// The future is run with a new [`MockClock`] using [`MockClock::run_for()`], so this finishes
+/// instantly however long the [`Duration`] is.
+///
+/// # Panics
+///
+/// Panics if the future doesn't complete within the [`Duration`], if a [`MockClock`] is already
+/// alive on the current thread or if the future is stalled and is not waiting for any time to
+/// pass.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::{assert_completes_within, time::{MockTimer, Timer}};
+/// use embassy_time::Duration;
+///
+/// async fn debounce<T: Timer>() -> bool {
+///     T::after(Duration::from_millis(50)).await;
+///     true
+/// }
+///
+/// let pressed = assert_completes_within!(Duration::from_millis(100), debounce::<MockTimer>());
+///
+/// assert!(pressed);
+/// ```
+#[macro_export]
+macro_rules! assert_completes_within {
+    ($duration:expr, $fut:expr $(,)?) => {{
+        let duration = $duration;
+        match $crate::time::MockClock::new().run_for(duration, $fut) {
+            Some(output) => output,
+            None => panic!("expected the future to complete within {duration}, it timed out"),
+        }
+    }};
+}
+
+impl Drop for MockClock {
+    /// Detach the mocked time types on the current thread from the clock, timers and tickers
+    /// created afterwards complete immediately again.
+    fn drop(&mut self) {
+        CLOCK.with(|clock| {
+            let mut clock = clock.borrow_mut();
+            clock.is_attached = false;
+            clock.wakers.clear();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::{Instant, MockTicker, MockTimer, Ticker, Timer};
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll},
+    };
+    use embassy_futures::block_on;
+    use futures_util::task::noop_waker_ref;
+
+    #[test]
+    fn advance_updates_mock_instant() {
+        let clock = MockClock::new();
+        clock.advance(Duration::from_secs(2));
+
+        assert_eq!(MockInstant::now(), MockInstant::from_secs(2));
+    }
+
+    #[test]
+    fn mock_instant_advance_updates_clock() {
+        let clock = MockClock::new();
+        MockInstant::advance(Duration::from_secs(2));
+
+        assert_eq!(clock.now(), MockInstant::from_secs(2));
+    }
+
+    #[test]
+    fn timer_after_completes_when_clock_reaches_deadline() {
+        let clock = MockClock::new();
+        clock.advance(Duration::from_secs(1));
+        let mut timer = pin!(MockTimer::after(Duration::from_secs(2)));
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        assert_eq!(timer.as_mut().poll(&mut cx), Poll::Pending);
+        clock.advance(Duration::from_millis(1999));
+        assert_eq!(timer.as_mut().poll(&mut cx), Poll::Pending);
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(timer.as_mut().poll(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    fn timer_at_completes_when_clock_reaches_deadline() {
+        let clock = MockClock::new();
+        let mut timer = pin!(MockTimer::at(MockInstant::from_secs(5).into()));
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        assert_eq!(timer.as_mut().poll(&mut cx), Poll::Pending);
+        clock.set_now(MockInstant::from_secs(5));
+        assert_eq!(timer.as_mut().poll(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    fn ticker_ticks_with_clock() {
+        let clock = MockClock::new();
+        let mut ticker = MockTicker::every(Duration::from_secs(1));
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        {
+            let mut tick = pin!(ticker.next());
+            assert_eq!(tick.as_mut().poll(&mut cx), Poll::Pending);
+            clock.advance(Duration::from_secs(1));
+            assert_eq!(tick.as_mut().poll(&mut cx), Poll::Ready(()));
+        }
+
+        let mut tick = pin!(ticker.next());
+        assert_eq!(tick.as_mut().poll(&mut cx), Poll::Pending);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(tick.as_mut().poll(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    fn timers_complete_immediately_after_clock_dropped() {
+        drop(MockClock::new());
+
+        block_on(MockTimer::after(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn block_on_jumps_to_each_deadline_in_order() {
+        let clock = MockClock::new();
+        let mut ticker = MockTicker::every(Duration::from_secs(3));
+        let order = RefCell::new(Vec::new());
+
+        clock.block_on(async {
+            embassy_futures::join::join(
+                async {
+                    MockTimer::after(Duration::from_secs(5)).await;
+                    order.borrow_mut().push(("timer", MockInstant::now()));
+                },
+                async {
+                    for _ in 0..2 {
+                        ticker.next().await;
+                        order.borrow_mut().push(("ticker", MockInstant::now()));
+                    }
+                },
+            )
+            .await;
+        });
+
+        assert_eq!(
+            order.into_inner(),
+            [
+                ("ticker", MockInstant::from_secs(3)),
+                ("timer", MockInstant::from_secs(5)),
+                ("ticker", MockInstant::from_secs(6)),
+            ]
+        );
+    }
+
+    #[test]
+    fn block_on_with_virtual_time_runs_long_loops() {
+        let ticks = block_on_with_virtual_time(async {
+            let mut ticker = MockTicker::every(Duration::from_secs(1));
+            for _ in 0..10_000 {
+                ticker.next().await;
+            }
+            MockInstant::now()
+        });
+
+        assert_eq!(ticks, MockInstant::from_secs(10_000));
+    }
+
+    #[test]
+    #[should_panic(expected = "future is stalled and is not waiting for any time to pass")]
+    fn block_on_stalled_future_panics() {
+        block_on_with_virtual_time(core::future::pending::<()>());
+    }
+
+    #[test]
+    fn run_for_returns_output_if_complete_in_time() {
+        let clock = MockClock::new();
+        let output = clock.run_for(Duration::from_secs(5), async {
+            MockTimer::after(Duration::from_secs(2)).await;
+            42
+        });
+
+        assert_eq!(output, Some(42));
+        assert_eq!(clock.now(), MockInstant::from_secs(2));
+    }
+
+    #[test]
+    fn run_for_stops_at_end_of_duration() {
+        let clock = MockClock::new();
+        clock.advance(Duration::from_secs(1));
+        let output = clock.run_for(
+            Duration::from_secs(5),
+            MockTimer::after(Duration::from_secs(7)),
+        );
+
+        assert_eq!(output, None);
+        assert_eq!(clock.now(), MockInstant::from_secs(6));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected the future to time out after 1000000 ticks, it completed")]
+    fn assert_times_out_panics_if_complete() {
+        assert_times_out!(
+            Duration::from_secs(1),
+            MockTimer::after(Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected the future to complete within 1000000 ticks, it timed out")]
+    fn assert_completes_within_panics_if_timed_out() {
+        assert_completes_within!(
+            Duration::from_secs(1),
+            MockTimer::after(Duration::from_millis(1001))
+        );
+    }
+
+    #[test]
+    fn starting_at_sets_now() {
+        let clock = MockClock::starting_at(MockInstant::from_secs(100));
+        let timer = MockTimer::after(Duration::from_secs(1));
+        clock.block_on(timer);
+
+        assert_eq!(MockInstant::now(), MockInstant::from_secs(101));
+    }
+
+    #[test]
+    #[should_panic(expected = "overflow when adding duration to instant")]
+    fn timer_deadline_past_max_panics() {
+        let _clock = MockClock::starting_at(MockInstant::MAX);
+        let _timer = MockTimer::after(Duration::from_ticks(1));
+    }
+
+    #[test]
+    fn advance_ticks_uses_tick_rate_of_embassy() {
+        let clock = MockClock::new();
+        clock.advance_ticks(embassy_time::TICK_HZ);
+
+        assert_eq!(clock.now(), MockInstant::from_secs(1));
+    }
+
+    #[test]
+    fn with_tick_hz_rounds_now_down_to_a_tick() {
+        let clock = MockClock::new().with_tick_hz(1000);
+        clock.advance(Duration::from_micros(999));
+        assert_eq!(MockInstant::now(), MockInstant::MIN);
+
+        clock.advance(Duration::from_micros(1));
+        assert_eq!(MockInstant::now(), MockInstant::from_millis(1));
+    }
+
+    #[test]
+    fn with_tick_hz_completes_timers_on_the_next_tick() {
+        let clock = MockClock::new().with_tick_hz(1000);
+        clock.block_on(MockTimer::after(Duration::from_micros(1500)));
+
+        assert_eq!(clock.now(), MockInstant::from_millis(2));
+    }
+
+    #[test]
+    fn advance_ticks_moves_to_the_start_of_a_tick() {
+        let clock = MockClock::new().with_tick_hz(1000);
+        clock.advance(Duration::from_micros(1500));
+        clock.advance_ticks(1);
+        clock.advance(Duration::from_micros(999));
+
+        assert_eq!(clock.now(), MockInstant::from_millis(2));
+    }
+
+    #[test]
+    fn new_clock_uses_tick_rate_of_embassy() {
+        drop(MockClock::new().with_tick_hz(1));
+        let clock = MockClock::new();
+        clock.advance_ticks(1);
+
+        assert_eq!(clock.now(), MockInstant::from_ticks(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "the tick rate must be between 1 and embassy_time::TICK_HZ")]
+    fn zero_tick_hz_panics() {
+        let _clock = MockClock::new().with_tick_hz(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "a MockClock already exists on this thread")]
+    fn only_one_clock_per_thread() {
+        let _clock = MockClock::new();
+        let _other = MockClock::new();
+    }
+}
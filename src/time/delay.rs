@@ -0,0 +1,314 @@
+//! Traits and mocked types to allow unit testing functions that require an
+//! [`embassy_time::Delay`].
+//!
+//! # Examples
+//! ```
+//! # #![feature(type_alias_impl_trait)]
+//! #
+//! use embassy_mock::time::Delay;
+//! use embedded_hal_async::delay::DelayNs;
+//!
+//! // Generic over the `Delay` trait
+//! async fn reset_device<D: Delay>() {
+//!     let mut delay = D::new();
+//!     // Pull the reset pin low...
+//!     delay.delay_ms(10).await;
+//!     // Release the reset pin...
+//! }
+//!
+//! // The real task that runs on the Embassy executor.
+//! #[embassy_executor::task]
+//! async fn some_task() {
+//!     reset_device::<embassy_time::Delay>().await;
+//! }
+//!
+//! # test_reset_device();
+//! // The unit tests that use the `MockDelay`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_futures::block_on;
+//!     use embassy_mock::time::MockDelay;
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_reset_device() {
+//!         block_on(reset_device::<MockDelay>());
+//!     }
+//! # mod closing {
+//! }
+//! ```
+//!
+//! Driver code that takes any [`DelayNs`] can be given a [`MockDelay`] directly, which records
+//! the requested delays so they can be checked:
+//!
+//! ```
+//! use embassy_futures::block_on;
+//! use embassy_mock::time::MockDelay;
+//! use embassy_time::Duration;
+//! use embedded_hal_async::delay::DelayNs;
+//!
+//! async fn reset_device(delay: &mut impl DelayNs) {
+//!     delay.delay_ms(10).await;
+//!     delay.delay_us(500).await;
+//! }
+//!
+//! let mut delay = MockDelay::new();
+//! block_on(reset_device(&mut delay));
+//!
+//! assert_eq!(
+//!     delay.recorded_delays(),
+//!     [Duration::from_millis(10), Duration::from_micros(500)]
+//! );
+//! ```
+//!
+//! Drivers that take the blocking [`embedded_hal::delay::DelayNs`] can be tested the same way,
+//! either generic over the [`BlockingDelay`] trait or given a [`MockDelay`] directly:
+//!
+//! ```
+//! use embassy_mock::time::{BlockingDelay, MockDelay};
+//! use embassy_time::Duration;
+//! use embedded_hal::delay::DelayNs;
+//!
+//! fn reset_device<D: BlockingDelay>() -> D {
+//!     let mut delay = D::new();
+//!     delay.delay_ms(10);
+//!     delay
+//! }
+//!
+//! let delay = reset_device::<MockDelay>();
+//!
+//! assert_eq!(delay.recorded_delays(), [Duration::from_millis(10)]);
+//! ```
+
+use embassy_time::{Delay as EmbassyDelay, Duration};
+use embedded_hal::delay::DelayNs as BlockingDelayNs;
+use embedded_hal_async::delay::DelayNs;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+/// The trait to replace the [`embassy_time::Delay`] in code to allow the [`MockDelay`] to be used
+/// in its place for tests.
+pub trait Delay: DelayNs {
+    /// Wrapper for creating an [`embassy_time::Delay`].
+    fn new() -> Self;
+}
+
+impl Delay for EmbassyDelay {
+    /// Create a [`embassy_time::Delay`].
+    fn new() -> Self {
+        Self
+    }
+}
+
+/// The trait to replace the [`embassy_time::Delay`] in code that uses it as a blocking
+/// [`embedded_hal::delay::DelayNs`] to allow the [`MockDelay`] to be used in its place for tests.
+pub trait BlockingDelay: BlockingDelayNs {
+    /// Wrapper for creating an [`embassy_time::Delay`].
+    fn new() -> Self;
+}
+
+impl BlockingDelay for EmbassyDelay {
+    /// Create a [`embassy_time::Delay`].
+    fn new() -> Self {
+        Self
+    }
+}
+
+/// A mocked version of [`embassy_time::Delay`] that can be used in its place for unit tests.
+///
+/// This mocked version implements both the async [`DelayNs`] and the blocking
+/// [`embedded_hal::delay::DelayNs`] and completes every delay immediately, recording the
+/// requested length of each delay so that it can be checked by the test.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::time::MockDelay;
+/// use embassy_time::Duration;
+/// use embedded_hal_async::delay::DelayNs;
+///
+/// let mut delay = MockDelay::new();
+/// block_on(delay.delay_ms(100));
+/// block_on(delay.delay_ms(50));
+///
+/// assert_eq!(delay.delay_count(), 2);
+/// assert_eq!(delay.total_delay(), Duration::from_millis(150));
+/// ```
+#[derive(Debug, Default)]
+pub struct MockDelay {
+    /// The number of delays that have been requested.
+    delay_count: usize,
+
+    /// The sum of all of the requested delays.
+    total_delay: Duration,
+
+    /// The length of every requested delay, in the order they were requested.
+    #[cfg(feature = "std")]
+    recorded_delays: Vec<Duration>,
+}
+
+impl MockDelay {
+    /// Create a [`MockDelay`] that has not recorded any delays.
+    pub const fn new() -> Self {
+        Self {
+            delay_count: 0,
+            total_delay: Duration::from_ticks(0),
+            #[cfg(feature = "std")]
+            recorded_delays: Vec::new(),
+        }
+    }
+
+    /// The number of delays that have been requested from this [`MockDelay`].
+    pub const fn delay_count(&self) -> usize {
+        self.delay_count
+    }
+
+    /// The sum of all of the delays that have been requested from this [`MockDelay`].
+    pub const fn total_delay(&self) -> Duration {
+        self.total_delay
+    }
+
+    /// The length of every delay that has been requested from this [`MockDelay`], in the order
+    /// they were requested.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::MockDelay;
+    /// use embassy_time::Duration;
+    /// use embedded_hal_async::delay::DelayNs;
+    ///
+    /// let mut delay = MockDelay::new();
+    /// block_on(delay.delay_us(10));
+    /// block_on(delay.delay_ns(200));
+    ///
+    /// assert_eq!(
+    ///     delay.recorded_delays(),
+    ///     [Duration::from_micros(10), Duration::from_nanos(200)]
+    /// );
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn recorded_delays(&self) -> &[Duration] {
+        &self.recorded_delays
+    }
+
+    /// Record a requested delay.
+    fn record(&mut self, delay: Duration) {
+        self.delay_count = self.delay_count.checked_add(1).unwrap();
+        self.total_delay = self.total_delay.checked_add(delay).unwrap();
+
+        #[cfg(feature = "std")]
+        self.recorded_delays.push(delay);
+    }
+}
+
+impl Delay for MockDelay {
+    /// Create a [`MockDelay`] with [`MockDelay::new()`].
+    fn new() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockingDelay for MockDelay {
+    /// Create a [`MockDelay`] with [`MockDelay::new()`].
+    fn new() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockingDelayNs for MockDelay {
+    /// Record the delay in nanoseconds and return immediately.
+    fn delay_ns(&mut self, ns: u32) {
+        self.record(Duration::from_nanos(ns.into()));
+    }
+
+    /// Record the delay in microseconds and return immediately.
+    fn delay_us(&mut self, us: u32) {
+        self.record(Duration::from_micros(us.into()));
+    }
+
+    /// Record the delay in milliseconds and return immediately.
+    fn delay_ms(&mut self, ms: u32) {
+        self.record(Duration::from_millis(ms.into()));
+    }
+}
+
+impl DelayNs for MockDelay {
+    /// Record the delay in nanoseconds and complete immediately.
+    async fn delay_ns(&mut self, ns: u32) {
+        self.record(Duration::from_nanos(ns.into()));
+    }
+
+    /// Record the delay in microseconds and complete immediately.
+    async fn delay_us(&mut self, us: u32) {
+        self.record(Duration::from_micros(us.into()));
+    }
+
+    /// Record the delay in milliseconds and complete immediately.
+    async fn delay_ms(&mut self, ms: u32) {
+        self.record(Duration::from_millis(ms.into()));
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::{DelayNs, Duration, MockDelay};
+    use embassy_futures::block_on;
+
+    #[test]
+    fn new_has_no_delays() {
+        let delay = MockDelay::new();
+
+        assert_eq!(delay.delay_count(), 0);
+        assert_eq!(delay.total_delay(), Duration::from_ticks(0));
+        assert!(delay.recorded_delays().is_empty());
+    }
+
+    #[test]
+    fn delays_are_recorded_in_order() {
+        let mut delay = MockDelay::new();
+        block_on(delay.delay_ms(3));
+        block_on(delay.delay_us(2));
+        block_on(delay.delay_ns(1000));
+
+        assert_eq!(
+            delay.recorded_delays(),
+            [
+                Duration::from_millis(3),
+                Duration::from_micros(2),
+                Duration::from_micros(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn blocking_and_async_delays_are_recorded_together() {
+        let mut delay = MockDelay::new();
+        embedded_hal::delay::DelayNs::delay_ms(&mut delay, 1);
+        block_on(delay.delay_us(2));
+        embedded_hal::delay::DelayNs::delay_ns(&mut delay, 3);
+
+        assert_eq!(
+            delay.recorded_delays(),
+            [
+                Duration::from_millis(1),
+                Duration::from_micros(2),
+                Duration::from_nanos(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn total_delay_is_sum_of_delays() {
+        let mut delay = MockDelay::new();
+        block_on(delay.delay_ms(1));
+        block_on(delay.delay_ms(2));
+
+        assert_eq!(delay.delay_count(), 2);
+        assert_eq!(delay.total_delay(), Duration::from_millis(3));
+    }
+}
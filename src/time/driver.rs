@@ -0,0 +1,410 @@
+//! A host-side time driver with virtual time, allowing the real [`embassy_time`] types to be used
+//! in host tests without waiting for wall-clock time to pass.
+//!
+//! This complements the trait-based mocks for integration-style tests: the code under test uses
+//! the real [`embassy_time::Timer`], [`embassy_time::Ticker`] and [`embassy_time::Instant`] and
+//! the test moves time forward with [`VirtualDriver::advance()`].
+//!
+//! The [`VirtualDriver`] must be registered as the global time driver of the test binary, which
+//! means the `std` feature of `embassy-time` (which provides its own driver) must not be enabled.
+//! The real [`embassy_time::Timer`] and [`embassy_time::Ticker`] also need a timer queue, so either
+//! the `generic-queue` feature of `embassy-time` or the `integrated-timers` feature of
+//! `embassy-executor` must be enabled, otherwise the test binary fails to link:
+//!
+//! ```ignore
+//! use embassy_mock::time::driver::VirtualDriver;
+//!
+//! embassy_time_driver::time_driver_impl!(static DRIVER: VirtualDriver = VirtualDriver::new());
+//!
+//! #[test]
+//! fn timer_expires_after_advancing() {
+//!     let start = embassy_time::Instant::now();
+//!     DRIVER.advance(embassy_time::Duration::from_secs(1));
+//!
+//!     assert_eq!(start.elapsed(), embassy_time::Duration::from_secs(1));
+//! }
+//! ```
+//!
+//! As there is a single global time driver, tests that advance the [`VirtualDriver`] affect
+//! each other if they run in parallel.
+//!
+//! The `driver-tests` crate of the repository registers the [`VirtualDriver`] this way and is
+//! kept out of the workspace, as the dev-dependencies of this crate enable `embassy-time/std`.
+
+use super::clock;
+use core::future::Future;
+use embassy_time::{Duration, Instant, TICK_HZ};
+use embassy_time_driver::{AlarmHandle, Driver};
+use std::{sync::Mutex, vec::Vec};
+
+/// The callback of an alarm and the context pointer to call it with.
+type AlarmCallback = (fn(*mut ()), *mut ());
+
+/// An alarm allocated from a [`VirtualDriver`].
+#[derive(Debug)]
+struct Alarm {
+    /// The timestamp the alarm is set to fire at, if it is set.
+    timestamp: Option<u64>,
+
+    /// The callback and context pointer to call when the alarm fires.
+    callback: Option<AlarmCallback>,
+}
+
+// SAFETY: The context pointer is never dereferenced by the driver, it is only passed back to the
+// callback it was registered with, which embassy requires to be callable from any context.
+unsafe impl Send for Alarm {}
+
+/// The state of a [`VirtualDriver`].
+#[derive(Debug)]
+struct DriverState {
+    /// The current time in ticks.
+    now: u64,
+
+    /// The alarms that have been allocated.
+    alarms: Vec<Alarm>,
+}
+
+/// An implementation of [`embassy_time_driver::Driver`] where time only moves when the test
+/// advances it.
+///
+/// Alarms set by `embassy-time` fire when the time is advanced to or past their timestamp.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::time::driver::VirtualDriver;
+/// use embassy_time::{Duration, Instant};
+/// use embassy_time_driver::Driver;
+///
+/// let driver = VirtualDriver::new();
+/// driver.advance(Duration::from_millis(1500));
+///
+/// assert_eq!(driver.now(), Instant::from_millis(1500).as_ticks());
+/// ```
+#[derive(Debug)]
+pub struct VirtualDriver {
+    /// The state of the driver, shared between all threads as there is one global driver.
+    state: Mutex<DriverState>,
+
+    /// The number of ticks per second of the driver, see [`Self::with_tick_hz()`].
+    tick_hz: u64,
+}
+
+impl VirtualDriver {
+    /// Create a [`VirtualDriver`] starting at [`Instant::MIN`] with no alarms.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(DriverState {
+                now: 0,
+                alarms: Vec::new(),
+            }),
+            tick_hz: TICK_HZ,
+        }
+    }
+
+    /// Make the driver count `tick_hz` ticks per second instead of [`TICK_HZ`], as the time
+    /// driver of the target would.
+    ///
+    /// The same as [`MockClock::with_tick_hz()`](super::MockClock::with_tick_hz), [`Driver::now()`]
+    /// is rounded down to a whole tick and alarms fire on the first tick at or after their
+    /// timestamp.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tick_hz` is zero or faster than [`TICK_HZ`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::driver::VirtualDriver;
+    /// use embassy_time::{Duration, Instant};
+    ///
+    /// let driver = VirtualDriver::new().with_tick_hz(1000);
+    /// driver.advance(Duration::from_micros(2500));
+    ///
+    /// assert_eq!(driver.instant(), Instant::from_millis(2));
+    /// ```
+    pub const fn with_tick_hz(mut self, tick_hz: u64) -> Self {
+        clock::assert_tick_hz(tick_hz);
+        self.tick_hz = tick_hz;
+        self
+    }
+
+    /// Move time forward by `duration`, firing any alarms that are now due.
+    ///
+    /// # Panics
+    ///
+    /// Panics if advancing the time overflows [`Instant::MAX`].
+    pub fn advance(&self, duration: Duration) {
+        let now = Instant::from_ticks(self.state.lock().unwrap().now) + duration;
+        self.advance_to(now);
+    }
+
+    /// Move time forward to `instant`, firing any alarms that are now due.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `instant` is before the current time, as a time driver must be monotonic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::driver::VirtualDriver;
+    /// use embassy_time::Instant;
+    ///
+    /// let driver = VirtualDriver::new();
+    /// driver.advance_to(Instant::from_secs(5));
+    ///
+    /// assert_eq!(driver.instant(), Instant::from_secs(5));
+    /// ```
+    pub fn advance_to(&self, instant: Instant) {
+        let due = {
+            let mut state = self.state.lock().unwrap();
+            let now = instant.as_ticks();
+            assert!(
+                now >= state.now,
+                "time driver must be monotonic, tried to move from {} ticks back to {now} ticks",
+                state.now
+            );
+            state.now = now;
+
+            let reading = clock::read_counter(now, self.tick_hz);
+            state
+                .alarms
+                .iter_mut()
+                .filter(|alarm| {
+                    alarm
+                        .timestamp
+                        .is_some_and(|timestamp| timestamp <= reading)
+                })
+                .filter_map(|alarm| {
+                    alarm.timestamp = None;
+                    alarm.callback
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Call outside of the lock as the callbacks call back into the driver.
+        for (callback, ctx) in due {
+            callback(ctx);
+        }
+    }
+
+    /// The current time of the driver.
+    pub fn instant(&self) -> Instant {
+        Instant::from_ticks(self.now())
+    }
+
+    /// The time of the earliest alarm that is set, or [`None`] if no alarms are set.
+    ///
+    /// This allows a test to jump straight to the next time that something is waiting for.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::driver::VirtualDriver;
+    /// use embassy_time::Instant;
+    /// use embassy_time_driver::Driver;
+    ///
+    /// let driver = VirtualDriver::new();
+    /// assert_eq!(driver.next_alarm(), None);
+    ///
+    /// let alarm = unsafe { driver.allocate_alarm() }.unwrap();
+    /// driver.set_alarm_callback(alarm, |_| {}, core::ptr::null_mut());
+    /// driver.set_alarm(alarm, Instant::from_secs(2).as_ticks());
+    ///
+    /// assert_eq!(driver.next_alarm(), Some(Instant::from_secs(2)));
+    /// ```
+    pub fn next_alarm(&self) -> Option<Instant> {
+        let state = self.state.lock().unwrap();
+        state
+            .alarms
+            .iter()
+            .filter_map(|alarm| alarm.timestamp)
+            .min()
+            .map(Instant::from_ticks)
+    }
+
+    /// Run `fut` to completion on the current thread, advancing time to the next alarm whenever
+    /// the future is stalled waiting for time to pass.
+    ///
+    /// This lets tests of long periodic loops using the real `embassy-time` types finish
+    /// instantly while keeping the order that the timers complete in.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the future is stalled and no alarms are set, as it would never complete.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use embassy_mock::time::driver::VirtualDriver;
+    /// use embassy_time::{Duration, Instant, Ticker};
+    ///
+    /// embassy_time_driver::time_driver_impl!(static DRIVER: VirtualDriver = VirtualDriver::new());
+    ///
+    /// #[test]
+    /// fn ticks_every_minute() {
+    ///     let start = Instant::now();
+    ///     DRIVER.block_on(async {
+    ///         let mut ticker = Ticker::every(Duration::from_secs(60));
+    ///         for _ in 0..1000 {
+    ///             ticker.next().await;
+    ///         }
+    ///     });
+    ///
+    ///     assert_eq!(start.elapsed(), Duration::from_secs(60_000));
+    /// }
+    /// ```
+    pub fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        clock::block_on_advancing(fut, || match self.next_alarm() {
+            Some(alarm) => {
+                let reached = clock::counter_reaches(alarm.as_ticks(), self.tick_hz)
+                    .expect("overflow when adding duration to instant");
+                let now = self.state.lock().unwrap().now;
+                self.advance_to(Instant::from_ticks(reached.max(now)));
+                true
+            }
+            None => false,
+        })
+    }
+}
+
+impl Driver for VirtualDriver {
+    /// The current virtual time in ticks, rounded down to a whole tick of the driver.
+    fn now(&self) -> u64 {
+        clock::read_counter(self.state.lock().unwrap().now, self.tick_hz)
+    }
+
+    /// Allocate a new alarm with no callback set, or [`None`] if all alarm IDs are in use.
+    unsafe fn allocate_alarm(&self) -> Option<AlarmHandle> {
+        let mut state = self.state.lock().unwrap();
+        let id = u8::try_from(state.alarms.len()).ok()?;
+        state.alarms.push(Alarm {
+            timestamp: None,
+            callback: None,
+        });
+
+        // SAFETY: The handle is created by this driver for the alarm it just allocated.
+        Some(unsafe { AlarmHandle::new(id) })
+    }
+
+    /// Set the callback to call when the alarm fires.
+    fn set_alarm_callback(&self, alarm: AlarmHandle, callback: fn(*mut ()), ctx: *mut ()) {
+        let mut state = self.state.lock().unwrap();
+        state.alarms[usize::from(alarm.id())].callback = Some((callback, ctx));
+    }
+
+    /// Set the alarm to fire when time is advanced to or past `timestamp`.
+    ///
+    /// Returns `false` without setting the alarm if `timestamp` has already been reached.
+    fn set_alarm(&self, alarm: AlarmHandle, timestamp: u64) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let is_future = timestamp > clock::read_counter(state.now, self.tick_hz);
+        let alarm = &mut state.alarms[usize::from(alarm.id())];
+        alarm.timestamp = is_future.then_some(timestamp);
+
+        is_future
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    fn count_calls(ctx: *mut ()) {
+        // SAFETY: The tests always register a pointer to an `AtomicUsize`.
+        let calls = unsafe { &*ctx.cast::<AtomicUsize>() };
+        calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn starts_at_zero() {
+        let driver = VirtualDriver::new();
+
+        assert_eq!(driver.now(), 0);
+    }
+
+    #[test]
+    fn advance_moves_time_forward() {
+        let driver = VirtualDriver::new();
+        driver.advance(Duration::from_secs(1));
+        driver.advance(Duration::from_millis(500));
+
+        assert_eq!(driver.instant(), Instant::from_millis(1500));
+    }
+
+    #[test]
+    #[should_panic(expected = "time driver must be monotonic")]
+    fn advance_to_past_panics() {
+        let driver = VirtualDriver::new();
+        driver.advance(Duration::from_secs(2));
+        driver.advance_to(Instant::from_secs(1));
+    }
+
+    #[test]
+    fn alarm_fires_when_due() {
+        let driver = VirtualDriver::new();
+        let calls = AtomicUsize::new(0);
+        let alarm = unsafe { driver.allocate_alarm() }.unwrap();
+        driver.set_alarm_callback(
+            alarm,
+            count_calls,
+            (&calls as *const AtomicUsize).cast_mut().cast(),
+        );
+
+        assert!(driver.set_alarm(alarm, Instant::from_secs(2).as_ticks()));
+        driver.advance(Duration::from_secs(1));
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+
+        driver.advance(Duration::from_secs(1));
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        driver.advance(Duration::from_secs(1));
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert_eq!(driver.next_alarm(), None);
+    }
+
+    #[test]
+    fn set_alarm_in_past_returns_false() {
+        let driver = VirtualDriver::new();
+        driver.advance(Duration::from_secs(1));
+        let alarm = unsafe { driver.allocate_alarm() }.unwrap();
+
+        assert!(!driver.set_alarm(alarm, Instant::from_secs(1).as_ticks()));
+        assert_eq!(driver.next_alarm(), None);
+    }
+
+    #[test]
+    fn alarm_fires_on_first_tick_after_timestamp() {
+        let driver = VirtualDriver::new().with_tick_hz(1000);
+        let calls = AtomicUsize::new(0);
+        let alarm = unsafe { driver.allocate_alarm() }.unwrap();
+        driver.set_alarm_callback(
+            alarm,
+            count_calls,
+            (&calls as *const AtomicUsize).cast_mut().cast(),
+        );
+
+        assert!(driver.set_alarm(alarm, Instant::from_micros(1500).as_ticks()));
+        driver.advance(Duration::from_micros(1500));
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+        assert_eq!(driver.instant(), Instant::from_millis(1));
+
+        driver.advance(Duration::from_micros(500));
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn next_alarm_is_earliest() {
+        let driver = VirtualDriver::new();
+        let first = unsafe { driver.allocate_alarm() }.unwrap();
+        let second = unsafe { driver.allocate_alarm() }.unwrap();
+        driver.set_alarm(first, Instant::from_secs(3).as_ticks());
+        driver.set_alarm(second, Instant::from_secs(2).as_ticks());
+
+        assert_eq!(driver.next_alarm(), Some(Instant::from_secs(2)));
+    }
+}
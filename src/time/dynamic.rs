@@ -0,0 +1,183 @@
+//! Object-safe versions of the [`Ticker`] and [`Timer`] traits, so a struct can hold a ticker or
+//! create timers without being generic over them.
+//!
+//! The futures are boxed, so these need the `alloc` feature.
+//!
+//! # Examples
+//! ```
+//! use embassy_futures::block_on;
+//! use embassy_mock::time::{DynTicker, DynTimer, MockTicker, MockTimer, TimerSource};
+//! use embassy_time::Duration;
+//!
+//! // Not generic over the ticker or the timer.
+//! struct Blinker<'a> {
+//!     ticker: &'a mut dyn DynTicker,
+//!     timer: &'a dyn DynTimer,
+//! }
+//!
+//! impl Blinker<'_> {
+//!     async fn blink(&mut self) {
+//!         self.ticker.next().await;
+//!         // Turn the LED on...
+//!         self.timer.after(Duration::from_millis(100)).await;
+//!         // Turn the LED off...
+//!     }
+//! }
+//!
+//! let mut ticker = MockTicker::expect(1);
+//! let timers = TimerSource::<MockTimer>::new();
+//! let mut blinker = Blinker {
+//!     ticker: &mut ticker,
+//!     timer: &timers,
+//! };
+//!
+//! block_on(blinker.blink());
+//!
+//! ticker.done().unwrap();
+//! ```
+
+use super::{Ticker, Timer};
+use alloc::boxed::Box;
+use core::{fmt, future::Future, marker::PhantomData, pin::Pin};
+use embassy_time::{Duration, Instant};
+
+/// An object-safe version of the [`Ticker`] trait that is implemented for every [`Ticker`].
+pub trait DynTicker {
+    /// Boxed wrapper for [`Ticker::next()`].
+    fn next(&mut self) -> Pin<Box<dyn Future<Output = ()> + '_>>;
+
+    /// Wrapper for [`Ticker::reset()`].
+    fn reset(&mut self);
+
+    /// Wrapper for [`Ticker::reset_at()`].
+    fn reset_at(&mut self, deadline: Instant);
+
+    /// Wrapper for [`Ticker::reset_after()`].
+    fn reset_after(&mut self, after: Duration);
+}
+
+impl<T: Ticker> DynTicker for T {
+    /// Box the future returned by [`Ticker::next()`].
+    fn next(&mut self) -> Pin<Box<dyn Future<Output = ()> + '_>> {
+        Box::pin(Ticker::next(self))
+    }
+
+    /// Call [`Ticker::reset()`].
+    fn reset(&mut self) {
+        Ticker::reset(self);
+    }
+
+    /// Call [`Ticker::reset_at()`].
+    fn reset_at(&mut self, deadline: Instant) {
+        Ticker::reset_at(self, deadline);
+    }
+
+    /// Call [`Ticker::reset_after()`].
+    fn reset_after(&mut self, after: Duration) {
+        Ticker::reset_after(self, after);
+    }
+}
+
+/// An object-safe source of timers, as the constructors of the [`Timer`] trait can't be called
+/// through a trait object.
+///
+/// This is implemented by [`TimerSource`] for every [`Timer`].
+pub trait DynTimer {
+    /// Boxed wrapper for [`Timer::at()`].
+    fn at(&self, expires_at: Instant) -> Pin<Box<dyn Future<Output = ()>>>;
+
+    /// Boxed wrapper for [`Timer::after()`].
+    fn after(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()>>>;
+}
+
+/// A [`DynTimer`] that creates timers of type `T`.
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::time::{DynTimer, MockTimer, TimerSource};
+/// use embassy_time::Duration;
+///
+/// let timers: &dyn DynTimer = &TimerSource::<MockTimer>::new();
+/// block_on(timers.after(Duration::from_secs(1)));
+///
+/// assert_eq!(MockTimer::recorded_durations(), [Duration::from_secs(1)]);
+/// ```
+pub struct TimerSource<T> {
+    /// The type of the timers that are created.
+    _timer: PhantomData<fn() -> T>,
+}
+
+impl<T> TimerSource<T> {
+    /// Create a [`TimerSource`] of timers of type `T`.
+    pub const fn new() -> Self {
+        Self {
+            _timer: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for TimerSource<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for TimerSource<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TimerSource<T> {}
+
+impl<T> fmt::Debug for TimerSource<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimerSource").finish()
+    }
+}
+
+impl<T: Timer + 'static> DynTimer for TimerSource<T> {
+    /// Box a timer created with [`Timer::at()`].
+    fn at(&self, expires_at: Instant) -> Pin<Box<dyn Future<Output = ()>>> {
+        Box::pin(T::at(expires_at))
+    }
+
+    /// Box a timer created with [`Timer::after()`].
+    fn after(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()>>> {
+        Box::pin(T::after(duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::{MockTicker, MockTickerReset, MockTimer};
+    use embassy_futures::block_on;
+
+    #[test]
+    fn dyn_ticker_forwards_to_ticker() {
+        let mut ticker = MockTicker::expect(2);
+        {
+            let dyn_ticker: &mut dyn DynTicker = &mut ticker;
+            block_on(dyn_ticker.next());
+            dyn_ticker.reset_after(Duration::from_secs(1));
+            block_on(dyn_ticker.next());
+        }
+
+        assert_eq!(
+            ticker.resets(),
+            [MockTickerReset::After(Duration::from_secs(1))]
+        );
+        ticker.done().unwrap();
+    }
+
+    #[test]
+    fn timer_source_creates_timers() {
+        let timers: &dyn DynTimer = &TimerSource::<MockTimer>::new();
+        block_on(timers.at(Instant::from_secs(2)));
+
+        assert_eq!(MockTimer::recorded_deadlines(), [Instant::from_secs(2)]);
+    }
+}
@@ -0,0 +1,581 @@
+//! Scoped expectations for the mocks that are created by the code under test with associated
+//! functions, such as [`Timer::after()`] and [`Ticker::every()`].
+//!
+//! The expectations are installed for the current thread when the guard is created and are
+//! checked when the guard is dropped, or earlier with `done()`.
+//!
+//! # Examples
+//! ```
+//! use embassy_futures::block_on;
+//! use embassy_mock::time::{MockTicker, MockTimer, Ticker, Timer};
+//! use embassy_time::Duration;
+//!
+//! async fn production_code<T: Timer, K: Ticker>() {
+//!     T::after(Duration::from_millis(10)).await;
+//!     let mut ticker = K::every(Duration::from_secs(1));
+//!     ticker.next().await;
+//! }
+//!
+//! let _timers = MockTimer::expectations().durations([Duration::from_millis(10)]);
+//! let _tickers = MockTicker::expectations().every(Duration::from_secs(1), 1);
+//!
+//! block_on(production_code::<MockTimer, MockTicker>());
+//!
+//! // The expectations are checked when `_timers` and `_tickers` are dropped.
+//! ```
+
+use super::{DurationMatcher, MockTicker, MockTickerError, MockTimer, MockTimerError};
+#[cfg(doc)]
+use super::{Ticker, Timer, WithTimeout};
+use embassy_time::Instant;
+use std::vec::Vec;
+
+impl MockTimer {
+    /// Create a [`MockTimerExpectations`] guard to set the expectations of the [`MockTimer`]s
+    /// created on the current thread while it is alive.
+    ///
+    /// Any durations, deadlines, poll counts and incomplete timers recorded before this call are
+    /// discarded so only the timers created after it are checked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTimer, Timer};
+    /// use embassy_time::{Duration, Instant};
+    ///
+    /// async fn production_code<T: Timer>() {
+    ///     T::after(Duration::from_secs(1)).await;
+    ///     T::at(Instant::from_secs(5)).await;
+    /// }
+    ///
+    /// let timers = MockTimer::expectations()
+    ///     .durations([Duration::from_secs(1)])
+    ///     .deadlines([Instant::from_secs(5)]);
+    /// block_on(production_code::<MockTimer>());
+    ///
+    /// timers.done().unwrap();
+    /// ```
+    pub fn expectations() -> MockTimerExpectations {
+        Self::recorded_durations();
+        Self::recorded_deadlines();
+        Self::recorded_poll_counts();
+        Self::clear_incomplete_timers();
+
+        MockTimerExpectations {
+            durations: None,
+            deadlines: None,
+            max_polls: None,
+            awaited: false,
+            is_done: false,
+        }
+    }
+}
+
+/// The expectations of the [`MockTimer`]s created on the current thread, see
+/// [`MockTimer::expectations()`].
+///
+/// Only the expectations that are set are checked. The expectations can be checked using
+/// [`Self::done()`]. If [`Self::done()`] is not called then they are checked when dropped which
+/// causes a panic if incorrect.
+///
+/// # Panics
+///
+/// Panics if the [`MockTimer`]s created don't match the expectations and [`Self`] is dropped
+/// before calling [`Self::done()`].
+///
+/// # Examples
+///
+/// ```should_panic
+/// use embassy_futures::block_on;
+/// use embassy_mock::time::{MockTimer, Timer};
+/// use embassy_time::Duration;
+///
+/// let _timers = MockTimer::expectations().durations([Duration::from_secs(1)]);
+/// block_on(MockTimer::after(Duration::from_secs(5))); // Created with the wrong duration.
+///
+/// // `_timers` is dropped and will panic.
+/// ```
+#[derive(Debug)]
+#[must_use = "the expectations are checked when this is dropped"]
+pub struct MockTimerExpectations {
+    /// The expected [`Duration`] of each [`MockTimer`] created with [`Timer::after()`], in order.
+    durations: Option<Vec<DurationMatcher>>,
+
+    /// The expected [`Instant`] of each [`MockTimer`] created with [`Timer::at()`], in order.
+    deadlines: Option<Vec<Instant>>,
+
+    /// The maximum number of times each [`MockTimer`] is expected to be polled before completing.
+    max_polls: Option<usize>,
+
+    /// Is every [`MockTimer`] expected to be awaited to completion.
+    awaited: bool,
+
+    /// Have the expectations been checked with a call to [`Self::done()`].
+    /// If true they are not checked when dropped.
+    is_done: bool,
+}
+
+impl MockTimerExpectations {
+    /// Expect the [`MockTimer`]s created with [`Timer::after()`] to have these durations, in
+    /// order.
+    pub fn durations(
+        mut self,
+        durations: impl IntoIterator<Item = impl Into<DurationMatcher>>,
+    ) -> Self {
+        self.durations = Some(durations.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Expect the [`MockTimer`]s created with [`Timer::at()`] to have these deadlines, in order.
+    pub fn deadlines(mut self, deadlines: impl IntoIterator<Item = Instant>) -> Self {
+        self.deadlines = Some(deadlines.into_iter().collect());
+        self
+    }
+
+    /// Expect every [`MockTimer`] that completes to be polled at most `max` times, see
+    /// [`MockTimer::recorded_poll_counts()`].
+    ///
+    /// This catches code that busy-polls a timer instead of waiting to be woken.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::{future::Future, pin::Pin, task::Context};
+    /// use embassy_mock::time::{MockTimer, MockTimerError, Timer};
+    /// use embassy_time::Duration;
+    /// use futures_util::task::noop_waker_ref;
+    ///
+    /// // Polls the timer in a loop without waiting for the waker.
+    /// fn busy_wait<T: Timer + Unpin>(mut timer: T) {
+    ///     let mut cx = Context::from_waker(noop_waker_ref());
+    ///     while Pin::new(&mut timer).poll(&mut cx).is_pending() {}
+    /// }
+    ///
+    /// let timers = MockTimer::expectations().max_polls(2);
+    /// MockTimer::set_pending_polls(5);
+    /// busy_wait(MockTimer::after(Duration::from_secs(1)));
+    ///
+    /// let expected = Err(MockTimerError::TooManyPolls {
+    ///     index: 0,
+    ///     max: 2,
+    ///     actual: 6,
+    /// });
+    /// assert_eq!(timers.done(), expected);
+    /// ```
+    pub fn max_polls(mut self, max: usize) -> Self {
+        self.max_polls = Some(max);
+        self
+    }
+
+    /// Expect every [`MockTimer`] created with [`Timer::after()`] or [`Timer::at()`] to be
+    /// awaited, which catches code that creates a timer but forgets to `.await` it.
+    ///
+    /// A timer counts as awaited once it has been polled, so a timer that is polled but loses a
+    /// race, for example in `embassy_futures::select::select()`, passes. The timeouts created by
+    /// [`WithTimeout::with_timeout()`] and [`WithTimeout::with_deadline()`] are never counted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTimer, MockTimerError, Timer};
+    /// use embassy_time::Duration;
+    ///
+    /// async fn production_code<T: Timer>() {
+    ///     let _ = T::after(Duration::from_secs(1)); // Forgot to `.await` the timer.
+    /// }
+    ///
+    /// let timers = MockTimer::expectations().awaited();
+    /// block_on(production_code::<MockTimer>());
+    ///
+    /// assert_eq!(timers.done(), Err(MockTimerError::NotAwaited { count: 1 }));
+    /// ```
+    pub fn awaited(mut self) -> Self {
+        self.awaited = true;
+        self
+    }
+
+    /// Mark the [`MockTimerExpectations`] as done and check if the expected [`MockTimer`]s were
+    /// created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTimer, MockTimerError, Timer};
+    /// use embassy_time::Instant;
+    ///
+    /// let timers = MockTimer::expectations().deadlines([Instant::from_secs(1)]);
+    /// block_on(MockTimer::at(Instant::from_secs(2)));
+    ///
+    /// let expected = Err(MockTimerError::WrongDeadline {
+    ///     index: 0,
+    ///     expected: Instant::from_secs(1),
+    ///     actual: Instant::from_secs(2),
+    /// });
+    /// assert_eq!(timers.done(), expected);
+    /// ```
+    pub fn done(mut self) -> Result<(), MockTimerError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check the [`MockTimer`]s recorded since the expectations were created.
+    fn check(&self) -> Result<(), MockTimerError> {
+        let durations = MockTimer::recorded_durations();
+        let deadlines = MockTimer::recorded_deadlines();
+
+        if let Some(expected) = &self.durations {
+            if durations.len() != expected.len() {
+                return Err(MockTimerError::WrongNumberOfTimers {
+                    expected: expected.len(),
+                    actual: durations.len(),
+                });
+            }
+            if let Some((index, (&expected, &actual))) =
+                first_mismatch(expected, &durations, |expected, &actual| {
+                    expected.matches(actual)
+                })
+            {
+                return Err(MockTimerError::WrongDuration {
+                    index,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        if let Some(expected) = &self.deadlines {
+            if deadlines.len() != expected.len() {
+                return Err(MockTimerError::WrongNumberOfDeadlines {
+                    expected: expected.len(),
+                    actual: deadlines.len(),
+                });
+            }
+            if let Some((index, (&expected, &actual))) =
+                first_mismatch(expected, &deadlines, PartialEq::eq)
+            {
+                return Err(MockTimerError::WrongDeadline {
+                    index,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        let polls = MockTimer::recorded_poll_counts();
+        if let Some(max) = self.max_polls {
+            if let Some((index, &actual)) = polls.iter().enumerate().find(|(_, &polls)| polls > max)
+            {
+                return Err(MockTimerError::TooManyPolls { index, max, actual });
+            }
+        }
+
+        let count = MockTimer::unawaited_timers();
+        if self.awaited && count > 0 {
+            return Err(MockTimerError::NotAwaited { count });
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for MockTimerExpectations {
+    /// If [`Self::done()`] has not been called before being dropped then check that the expected
+    /// [`MockTimer`]s were created.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+/// The first position where `actual` doesn't match `expected`, with both values.
+fn first_mismatch<'a, E, A>(
+    expected: &'a [E],
+    actual: &'a [A],
+    matches: impl Fn(&E, &A) -> bool,
+) -> Option<(usize, (&'a E, &'a A))> {
+    expected
+        .iter()
+        .zip(actual)
+        .enumerate()
+        .find(|(_, (expected, actual))| !matches(expected, actual))
+}
+
+impl MockTicker {
+    /// Create a [`MockTickerExpectations`] guard to set the expectations of the [`MockTicker`]s
+    /// created with [`Ticker::every()`] on the current thread while it is alive.
+    ///
+    /// Any expectations set with [`Self::expect_every()`] before this call are discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTicker, Ticker};
+    /// use embassy_time::Duration;
+    ///
+    /// async fn production_code<T: Ticker>() {
+    ///     let mut ticker = T::every(Duration::from_millis(100));
+    ///     ticker.next().await;
+    ///     ticker.next().await;
+    /// }
+    ///
+    /// let tickers = MockTicker::expectations().every(Duration::from_millis(100), 2);
+    /// block_on(production_code::<MockTicker>());
+    ///
+    /// tickers.done().unwrap();
+    /// ```
+    pub fn expectations() -> MockTickerExpectations {
+        Self::clear_expect_every();
+        Self::take_every_count();
+
+        MockTickerExpectations {
+            expected: 0,
+            is_done: false,
+        }
+    }
+}
+
+/// The expectations of the [`MockTicker`]s created with [`Ticker::every()`] on the current thread,
+/// see [`MockTicker::expectations()`].
+///
+/// Each ticker checks its own interval and number of ticks when it is dropped, see
+/// [`MockTicker::expect_every()`]. This checks that all of the expected tickers were created,
+/// using [`Self::done()`] or when dropped which causes a panic if incorrect.
+///
+/// # Panics
+///
+/// Panics if fewer [`MockTicker`]s were created than expected and [`Self`] is dropped before
+/// calling [`Self::done()`].
+///
+/// # Examples
+///
+/// ```should_panic
+/// use embassy_mock::time::MockTicker;
+/// use embassy_time::Duration;
+///
+/// let _tickers = MockTicker::expectations().every(Duration::from_secs(1), 1);
+///
+/// // No ticker is created so `_tickers` panics when dropped.
+/// ```
+#[derive(Debug)]
+#[must_use = "the expectations are checked when this is dropped"]
+pub struct MockTickerExpectations {
+    /// The number of [`MockTicker`]s that are expected to be created.
+    expected: usize,
+
+    /// Have the expectations been checked with a call to [`Self::done()`].
+    /// If true they are not checked when dropped.
+    is_done: bool,
+}
+
+impl MockTickerExpectations {
+    /// Expect the next [`MockTicker`] created with [`Ticker::every()`] to be created with the
+    /// `interval` and for [`Ticker::next()`] to be called `ticks` times, see
+    /// [`MockTicker::expect_every()`].
+    pub fn every(mut self, interval: impl Into<DurationMatcher>, ticks: usize) -> Self {
+        MockTicker::expect_every(interval, ticks);
+        self.expected = self.expected.checked_add(1).unwrap();
+        self
+    }
+
+    /// Mark the [`MockTickerExpectations`] as done and check if the expected [`MockTicker`]s
+    /// were created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{MockTicker, MockTickerError, Ticker};
+    /// use embassy_time::Duration;
+    ///
+    /// let tickers = MockTicker::expectations()
+    ///     .every(Duration::from_secs(1), 0)
+    ///     .every(Duration::from_secs(2), 0);
+    /// let _ticker = MockTicker::every(Duration::from_secs(1));
+    ///
+    /// let expected = Err(MockTickerError::WrongNumberOfTickers {
+    ///     expected: 2,
+    ///     actual: 1,
+    /// });
+    /// assert_eq!(tickers.done(), expected);
+    /// ```
+    pub fn done(mut self) -> Result<(), MockTickerError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that at least the expected number of [`MockTicker`]s were created, removing the
+    /// expectations that were not used.
+    fn check(&self) -> Result<(), MockTickerError> {
+        MockTicker::clear_expect_every();
+        let actual = MockTicker::take_every_count();
+        if actual < self.expected {
+            return Err(MockTickerError::WrongNumberOfTickers {
+                expected: self.expected,
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for MockTickerExpectations {
+    /// If [`Self::done()`] has not been called before being dropped then check that the expected
+    /// [`MockTicker`]s were created.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::{MockTimeout, Ticker, Timer, WithTimeout};
+    use embassy_futures::{
+        block_on,
+        select::{select, Either},
+    };
+    use embassy_time::{Duration, TimeoutError};
+
+    #[test]
+    fn timer_expectations_return_ok() {
+        let timers = MockTimer::expectations()
+            .durations([Duration::from_secs(1), Duration::from_secs(2)])
+            .deadlines([Instant::from_secs(3)]);
+        block_on(MockTimer::after(Duration::from_secs(1)));
+        block_on(MockTimer::at(Instant::from_secs(3)));
+        block_on(MockTimer::after(Duration::from_secs(2)));
+
+        assert_eq!(timers.done(), Ok(()));
+    }
+
+    #[test]
+    fn timer_expectations_discard_earlier_timers() {
+        block_on(MockTimer::after(Duration::from_secs(5)));
+        let timers = MockTimer::expectations().durations(Vec::<Duration>::new());
+
+        assert_eq!(timers.done(), Ok(()));
+    }
+
+    #[test]
+    fn timer_expectations_only_check_what_is_set() {
+        let timers = MockTimer::expectations().deadlines([]);
+        block_on(MockTimer::after(Duration::from_secs(5)));
+
+        assert_eq!(timers.done(), Ok(()));
+    }
+
+    #[test]
+    fn timer_expectations_wrong_number_of_deadlines() {
+        let timers = MockTimer::expectations().deadlines([Instant::from_secs(1)]);
+
+        let expected = Err(MockTimerError::WrongNumberOfDeadlines {
+            expected: 1,
+            actual: 0,
+        });
+        assert_eq!(timers.done(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to create 1 timer(s), actually created 2")]
+    fn timer_expectations_checked_on_drop() {
+        let _timers = MockTimer::expectations().durations([Duration::from_secs(1)]);
+        block_on(MockTimer::after(Duration::from_secs(1)));
+        block_on(MockTimer::after(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn timer_expectations_awaited_ignores_earlier_timers() {
+        let old = MockTimer::after(Duration::from_secs(1));
+        let timers = MockTimer::expectations().awaited();
+        let _new = MockTimer::after(Duration::from_secs(2));
+        block_on(old);
+
+        assert_eq!(timers.done(), Err(MockTimerError::NotAwaited { count: 1 }));
+    }
+
+    #[test]
+    fn timer_expectations_awaited_ignores_timeouts() {
+        let timers = MockTimer::expectations().awaited();
+
+        let res = block_on(MockTimer::with_timeout(Duration::from_secs(1), async { 1 }));
+        assert_eq!(res, Ok(1));
+        MockTimer::set_timeout(MockTimeout::Expire);
+        let res = block_on(MockTimer::with_deadline(Instant::from_secs(1), async { 2 }));
+        assert_eq!(res, Err(TimeoutError));
+        MockTimer::set_timeout(MockTimeout::Race);
+
+        assert_eq!(timers.done(), Ok(()));
+    }
+
+    #[test]
+    fn timer_expectations_awaited_ignores_timers_that_lose_select() {
+        let timers = MockTimer::expectations().awaited();
+
+        let timer = MockTimer::after(Duration::from_secs(1)).with_pending_polls(1);
+        let res = block_on(select(timer, async { 3 }));
+        assert!(matches!(res, Either::Second(3)));
+
+        assert_eq!(timers.done(), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "1 timer(s) were never awaited")]
+    fn timer_expectations_awaited_checked_on_drop() {
+        let _timers = MockTimer::expectations().awaited();
+        let _timer = MockTimer::at(Instant::from_secs(1));
+    }
+
+    #[test]
+    fn ticker_expectations_return_ok() {
+        let tickers = MockTicker::expectations().every(Duration::from_secs(1), 1);
+        let mut ticker = MockTicker::every(Duration::from_secs(1));
+        block_on(ticker.next());
+
+        assert_eq!(ticker.done(), Ok(()));
+        assert_eq!(tickers.done(), Ok(()));
+    }
+
+    #[test]
+    fn ticker_expectations_clear_unused_expectations() {
+        let tickers = MockTicker::expectations().every(Duration::from_secs(1), 1);
+        let _ = tickers.done();
+
+        // Not checked as the expectation was removed.
+        drop(MockTicker::every(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn ticker_expectations_count_created_tickers() {
+        let tickers = MockTicker::expectations().every(Duration::from_secs(1), 0);
+        // More expectations than the guard knows of, which are removed unused.
+        MockTicker::expect_every(Duration::from_secs(2), 0);
+        MockTicker::expect_every(Duration::from_secs(3), 0);
+
+        let expected = Err(MockTickerError::WrongNumberOfTickers {
+            expected: 1,
+            actual: 0,
+        });
+        assert_eq!(tickers.done(), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to create 1 ticker(s), actually created 0")]
+    fn ticker_expectations_checked_on_drop() {
+        let _tickers = MockTicker::expectations().every(Duration::from_secs(1), 0);
+    }
+}
@@ -0,0 +1,631 @@
+//! Traits and mocked types to allow unit testing functions that require an
+//! [`embassy_time::Instant`].
+//!
+//! # Examples
+//! ```
+//! # #![feature(type_alias_impl_trait)]
+//! #
+//! use embassy_mock::time::Instant;
+//! use embassy_time::Duration;
+//!
+//! // Generic over the `Instant` trait
+//! fn is_overdue<I: Instant>(deadline: I) -> bool {
+//!     I::now() > deadline
+//! }
+//!
+//! // The real task that runs on the Embassy executor.
+//! #[embassy_executor::task]
+//! async fn some_task() {
+//!     let deadline = embassy_time::Instant::now() + Duration::from_secs(1);
+//!     is_overdue(deadline);
+//! }
+//!
+//! # test_is_overdue();
+//! // The unit tests that use the `MockInstant`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_mock::time::MockInstant;
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_is_overdue() {
+//!         let deadline = MockInstant::from_secs(5);
+//!
+//!         MockInstant::set_now(MockInstant::from_secs(4));
+//!         assert!(!is_overdue(deadline));
+//!
+//!         MockInstant::set_now(MockInstant::from_secs(6));
+//!         assert!(is_overdue(deadline));
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+#[cfg(feature = "std")]
+use super::clock;
+use core::{
+    fmt,
+    ops::{Add, AddAssign, Sub, SubAssign},
+};
+use embassy_time::{Duration, Instant as EmbassyInstant};
+
+/// The trait to replace the [`embassy_time::Instant`] in code to allow the [`MockInstant`] to
+/// be used in its place for tests.
+///
+/// Comparison and arithmetic with [`Duration`] are provided through the supertraits, the same as
+/// [`embassy_time::Instant`].
+pub trait Instant:
+    Copy
+    + Ord
+    + Add<Duration, Output = Self>
+    + AddAssign<Duration>
+    + Sub<Duration, Output = Self>
+    + SubAssign<Duration>
+    + Sub<Self, Output = Duration>
+{
+    /// Wrapper for [`embassy_time::Instant::now()`].
+    fn now() -> Self;
+
+    /// Wrapper for [`embassy_time::Instant::from_ticks()`].
+    fn from_ticks(ticks: u64) -> Self;
+
+    /// Wrapper for [`embassy_time::Instant::from_micros()`].
+    fn from_micros(micros: u64) -> Self;
+
+    /// Wrapper for [`embassy_time::Instant::from_millis()`].
+    fn from_millis(millis: u64) -> Self;
+
+    /// Wrapper for [`embassy_time::Instant::from_secs()`].
+    fn from_secs(seconds: u64) -> Self;
+
+    /// Wrapper for [`embassy_time::Instant::as_ticks()`].
+    fn as_ticks(&self) -> u64;
+
+    /// Wrapper for [`embassy_time::Instant::as_secs()`].
+    fn as_secs(&self) -> u64;
+
+    /// Wrapper for [`embassy_time::Instant::as_millis()`].
+    fn as_millis(&self) -> u64;
+
+    /// Wrapper for [`embassy_time::Instant::as_micros()`].
+    fn as_micros(&self) -> u64;
+
+    /// Wrapper for [`embassy_time::Instant::duration_since()`].
+    fn duration_since(&self, earlier: Self) -> Duration;
+
+    /// Wrapper for [`embassy_time::Instant::checked_duration_since()`].
+    fn checked_duration_since(&self, earlier: Self) -> Option<Duration>;
+
+    /// Wrapper for [`embassy_time::Instant::elapsed()`].
+    fn elapsed(&self) -> Duration;
+
+    /// Wrapper for [`embassy_time::Instant::checked_add()`].
+    fn checked_add(&self, duration: Duration) -> Option<Self>;
+
+    /// Wrapper for [`embassy_time::Instant::checked_sub()`].
+    fn checked_sub(&self, duration: Duration) -> Option<Self>;
+}
+
+impl Instant for EmbassyInstant {
+    /// Returns an Instant representing the current time.
+    fn now() -> Self {
+        Self::now()
+    }
+
+    /// Create an Instant from a tick count since system boot.
+    fn from_ticks(ticks: u64) -> Self {
+        Self::from_ticks(ticks)
+    }
+
+    /// Create an Instant from a microsecond count since system boot.
+    fn from_micros(micros: u64) -> Self {
+        Self::from_micros(micros)
+    }
+
+    /// Create an Instant from a millisecond count since system boot.
+    fn from_millis(millis: u64) -> Self {
+        Self::from_millis(millis)
+    }
+
+    /// Create an Instant from a second count since system boot.
+    fn from_secs(seconds: u64) -> Self {
+        Self::from_secs(seconds)
+    }
+
+    /// Tick count since system boot.
+    fn as_ticks(&self) -> u64 {
+        self.as_ticks()
+    }
+
+    /// Seconds since system boot.
+    fn as_secs(&self) -> u64 {
+        self.as_secs()
+    }
+
+    /// Milliseconds since system boot.
+    fn as_millis(&self) -> u64 {
+        self.as_millis()
+    }
+
+    /// Microseconds since system boot.
+    fn as_micros(&self) -> u64 {
+        self.as_micros()
+    }
+
+    /// Duration between this Instant and another Instant. Panics on over/underflow.
+    fn duration_since(&self, earlier: Self) -> Duration {
+        self.duration_since(earlier)
+    }
+
+    /// Duration between this Instant and another Instant.
+    fn checked_duration_since(&self, earlier: Self) -> Option<Duration> {
+        self.checked_duration_since(earlier)
+    }
+
+    /// Returns how much time has elapsed since this Instant.
+    fn elapsed(&self) -> Duration {
+        self.elapsed()
+    }
+
+    /// Adds one Duration to self, returning a new `Instant` or None in the event of an overflow.
+    fn checked_add(&self, duration: Duration) -> Option<Self> {
+        self.checked_add(duration)
+    }
+
+    /// Subtracts one Duration to self, returning a new `Instant` or None in the event of an
+    /// overflow.
+    fn checked_sub(&self, duration: Duration) -> Option<Self> {
+        self.checked_sub(duration)
+    }
+}
+
+/// A mocked version of [`embassy_time::Instant`] that can be used in its place for unit tests.
+///
+/// This mocked version behaves the same as [`embassy_time::Instant`] except that
+/// [`Instant::now()`] returns a time that is set by the test with [`Self::set_now()`] instead of
+/// reading a time driver. The time is kept per thread so tests running in parallel don't
+/// interfere with each other, it is the time of the thread's [`MockClock`](super::MockClock).
+/// Without the `std` feature [`Instant::now()`] always returns [`MockInstant::MIN`].
+///
+/// A [`MockInstant`] can be converted to and from an [`embassy_time::Instant`], for example to
+/// create a [`MockTimer`](super::MockTimer) with [`Timer::at()`](super::Timer::at).
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::time::{Instant, MockInstant};
+/// use embassy_time::Duration;
+///
+/// MockInstant::set_now(MockInstant::from_millis(1500));
+/// let now = MockInstant::now();
+///
+/// assert_eq!(now, MockInstant::from_millis(1500));
+/// assert_eq!(now + Duration::from_millis(500), MockInstant::from_secs(2));
+/// assert_eq!(MockInstant::from_secs(2) - now, Duration::from_millis(500));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MockInstant(EmbassyInstant);
+
+impl MockInstant {
+    /// The smallest (earliest) value that can be represented by the [`MockInstant`] type.
+    pub const MIN: Self = Self(EmbassyInstant::MIN);
+
+    /// The largest (latest) value that can be represented by the [`MockInstant`] type.
+    pub const MAX: Self = Self(EmbassyInstant::MAX);
+
+    /// Set the time returned by [`Instant::now()`] on the current thread.
+    ///
+    /// This is the same as [`MockClock::set_now()`](super::MockClock::set_now) so any timers and
+    /// tickers that are now due are woken.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{Instant, MockInstant};
+    ///
+    /// assert_eq!(MockInstant::now(), MockInstant::MIN);
+    ///
+    /// MockInstant::set_now(MockInstant::from_secs(3));
+    ///
+    /// assert_eq!(MockInstant::now(), MockInstant::from_secs(3));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn set_now(now: MockInstant) {
+        clock::set_now(now);
+    }
+
+    /// Move the time returned by [`Instant::now()`] on the current thread forward by `duration`.
+    ///
+    /// This allows stepping time forward between calls into the code under test to check
+    /// elapsed-time logic deterministically. This is the same as
+    /// [`MockClock::advance()`](super::MockClock::advance).
+    ///
+    /// # Panics
+    ///
+    /// Panics if advancing the time overflows [`MockInstant::MAX`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{Instant, MockInstant};
+    /// use embassy_time::Duration;
+    ///
+    /// MockInstant::set_now(MockInstant::from_secs(1));
+    /// MockInstant::advance(Duration::from_millis(500));
+    ///
+    /// assert_eq!(MockInstant::now(), MockInstant::from_millis(1500));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn advance(duration: Duration) {
+        clock::advance(duration);
+    }
+
+    /// Make setting the time on the current thread to an earlier time panic, with
+    /// [`Self::set_now()`] or any of the [`MockClock`](super::MockClock) methods.
+    ///
+    /// Real time never goes backwards, so this catches tests that accidentally rewind time and
+    /// make the code under test see an impossible sequence of times.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use embassy_mock::time::{Instant, MockInstant};
+    ///
+    /// MockInstant::set_strict(true);
+    /// MockInstant::set_now(MockInstant::from_secs(2));
+    ///
+    /// MockInstant::set_now(MockInstant::from_secs(1)); // Panics as time went backwards.
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn set_strict(is_strict: bool) {
+        clock::set_strict(is_strict);
+    }
+
+    /// Assert that the times returned by [`Instant::now()`] on the current thread have never
+    /// decreased since the last call to this method.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`Instant::now()`] returned an earlier time than the time before.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use embassy_mock::time::{Instant, MockInstant};
+    ///
+    /// fn production_code<I: Instant>() -> I {
+    ///     I::now()
+    /// }
+    ///
+    /// MockInstant::set_now(MockInstant::from_secs(2));
+    /// production_code::<MockInstant>();
+    /// MockInstant::set_now(MockInstant::from_secs(1));
+    /// production_code::<MockInstant>();
+    ///
+    /// MockInstant::assert_monotonic(); // Panics as the code saw time go backwards.
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn assert_monotonic() {
+        if let Some((earlier, later)) = clock::take_first_rewind() {
+            panic!("MockInstant::now() went backwards from {earlier} to {later}");
+        }
+    }
+}
+
+impl Instant for MockInstant {
+    /// Returns the [`MockInstant`] set with [`Self::set_now()`] on the current thread, or
+    /// [`Self::MIN`] if it has not been set.
+    fn now() -> Self {
+        #[cfg(feature = "std")]
+        {
+            clock::observe_now()
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            Self::MIN
+        }
+    }
+
+    /// Create a [`MockInstant`] from a tick count since system boot.
+    fn from_ticks(ticks: u64) -> Self {
+        Self(EmbassyInstant::from_ticks(ticks))
+    }
+
+    /// Create a [`MockInstant`] from a microsecond count since system boot.
+    fn from_micros(micros: u64) -> Self {
+        Self(EmbassyInstant::from_micros(micros))
+    }
+
+    /// Create a [`MockInstant`] from a millisecond count since system boot.
+    fn from_millis(millis: u64) -> Self {
+        Self(EmbassyInstant::from_millis(millis))
+    }
+
+    /// Create a [`MockInstant`] from a second count since system boot.
+    fn from_secs(seconds: u64) -> Self {
+        Self(EmbassyInstant::from_secs(seconds))
+    }
+
+    /// Tick count since system boot.
+    fn as_ticks(&self) -> u64 {
+        self.0.as_ticks()
+    }
+
+    /// Seconds since system boot.
+    fn as_secs(&self) -> u64 {
+        self.0.as_secs()
+    }
+
+    /// Milliseconds since system boot.
+    fn as_millis(&self) -> u64 {
+        self.0.as_millis()
+    }
+
+    /// Microseconds since system boot.
+    fn as_micros(&self) -> u64 {
+        self.0.as_micros()
+    }
+
+    /// The [`Duration`] between this [`MockInstant`] and an `earlier` one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `earlier` is later than this [`MockInstant`].
+    fn duration_since(&self, earlier: Self) -> Duration {
+        self.0.duration_since(earlier.0)
+    }
+
+    /// The [`Duration`] between this [`MockInstant`] and an `earlier` one, or [`None`] if
+    /// `earlier` is later than this [`MockInstant`].
+    fn checked_duration_since(&self, earlier: Self) -> Option<Duration> {
+        self.0.checked_duration_since(earlier.0)
+    }
+
+    /// The [`Duration`] between this [`MockInstant`] and [`Self::now()`].
+    ///
+    /// This lets code that measures how long an operation took be tested by moving the time
+    /// forward with [`Self::advance()`] or a [`MockClock`](super::MockClock).
+    ///
+    /// # Panics
+    ///
+    /// Panics if this [`MockInstant`] is later than [`Self::now()`], the same as
+    /// [`embassy_time::Instant::elapsed()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{Instant, MockInstant};
+    /// use embassy_time::Duration;
+    ///
+    /// fn measure<I: Instant>(operation: impl FnOnce()) -> Duration {
+    ///     let start = I::now();
+    ///     operation();
+    ///     start.elapsed()
+    /// }
+    ///
+    /// let took = measure::<MockInstant>(|| MockInstant::advance(Duration::from_millis(30)));
+    ///
+    /// assert_eq!(took, Duration::from_millis(30));
+    /// ```
+    fn elapsed(&self) -> Duration {
+        Self::now().duration_since(*self)
+    }
+
+    /// Adds one [`Duration`] to self, returning a new [`MockInstant`] or [`None`] in the event of
+    /// an overflow.
+    fn checked_add(&self, duration: Duration) -> Option<Self> {
+        self.0.checked_add(duration).map(Self)
+    }
+
+    /// Subtracts one [`Duration`] from self, returning a new [`MockInstant`] or [`None`] in the
+    /// event of an overflow.
+    fn checked_sub(&self, duration: Duration) -> Option<Self> {
+        self.0.checked_sub(duration).map(Self)
+    }
+}
+
+impl Add<Duration> for MockInstant {
+    type Output = Self;
+
+    fn add(self, other: Duration) -> Self {
+        Self(self.0 + other)
+    }
+}
+
+impl AddAssign<Duration> for MockInstant {
+    fn add_assign(&mut self, other: Duration) {
+        self.0 += other;
+    }
+}
+
+impl Sub<Duration> for MockInstant {
+    type Output = Self;
+
+    fn sub(self, other: Duration) -> Self {
+        Self(self.0 - other)
+    }
+}
+
+impl SubAssign<Duration> for MockInstant {
+    fn sub_assign(&mut self, other: Duration) {
+        self.0 -= other;
+    }
+}
+
+impl Sub<MockInstant> for MockInstant {
+    type Output = Duration;
+
+    fn sub(self, other: MockInstant) -> Duration {
+        self.0 - other.0
+    }
+}
+
+impl From<EmbassyInstant> for MockInstant {
+    fn from(instant: EmbassyInstant) -> Self {
+        Self(instant)
+    }
+}
+
+impl From<MockInstant> for EmbassyInstant {
+    fn from(instant: MockInstant) -> Self {
+        instant.0
+    }
+}
+
+impl fmt::Display for MockInstant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn now_defaults_to_min() {
+        assert_eq!(MockInstant::now(), MockInstant::MIN);
+    }
+
+    #[test]
+    fn now_returns_set_now() {
+        MockInstant::set_now(MockInstant::from_millis(250));
+
+        assert_eq!(MockInstant::now(), MockInstant::from_millis(250));
+    }
+
+    #[test]
+    fn advance_moves_now_forward() {
+        MockInstant::set_now(MockInstant::from_secs(1));
+        MockInstant::advance(Duration::from_secs(2));
+        MockInstant::advance(Duration::from_millis(250));
+
+        assert_eq!(MockInstant::now(), MockInstant::from_millis(3250));
+    }
+
+    #[test]
+    #[should_panic(expected = "overflow when adding duration to instant")]
+    fn advance_past_max_panics() {
+        MockInstant::set_now(MockInstant::MAX);
+        MockInstant::advance(Duration::from_ticks(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "time went backwards from 2000000 ticks to 1000000 ticks")]
+    fn strict_set_now_panics_when_rewinding() {
+        MockInstant::set_strict(true);
+        MockInstant::set_now(MockInstant::from_secs(2));
+        MockInstant::set_now(MockInstant::from_secs(1));
+    }
+
+    #[test]
+    fn strict_set_now_allows_same_time() {
+        MockInstant::set_strict(true);
+        MockInstant::set_now(MockInstant::from_secs(2));
+        MockInstant::set_now(MockInstant::from_secs(2));
+        MockInstant::advance(Duration::from_ticks(0));
+    }
+
+    #[test]
+    fn increasing_now_is_monotonic() {
+        MockInstant::now();
+        MockInstant::advance(Duration::from_secs(1));
+        MockInstant::now();
+        MockInstant::now();
+
+        MockInstant::assert_monotonic();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "MockInstant::now() went backwards from 3000000 ticks to 1000000 ticks"
+    )]
+    fn rewound_now_is_not_monotonic() {
+        MockInstant::set_now(MockInstant::from_secs(3));
+        MockInstant::now();
+        MockInstant::set_now(MockInstant::from_secs(1));
+        MockInstant::now();
+
+        MockInstant::assert_monotonic();
+    }
+
+    #[test]
+    fn assert_monotonic_only_checks_since_last_call() {
+        MockInstant::set_now(MockInstant::from_secs(3));
+        MockInstant::now();
+        MockInstant::set_now(MockInstant::from_secs(1));
+        MockInstant::now();
+        let _ = std::panic::catch_unwind(MockInstant::assert_monotonic);
+
+        MockInstant::assert_monotonic();
+    }
+
+    #[test]
+    fn can_compare_instants() {
+        assert!(MockInstant::from_secs(1) < MockInstant::from_secs(2));
+        assert!(MockInstant::from_millis(2000) == MockInstant::from_secs(2));
+    }
+
+    #[test]
+    fn can_do_arithmetic_with_durations() {
+        let mut instant = MockInstant::from_secs(1);
+        instant += Duration::from_secs(2);
+        instant -= Duration::from_millis(500);
+
+        assert_eq!(instant, MockInstant::from_millis(2500));
+        assert_eq!(
+            instant - MockInstant::from_secs(1),
+            Duration::from_millis(1500)
+        );
+    }
+
+    #[test]
+    fn duration_since_earlier_instant() {
+        let later = MockInstant::from_secs(3);
+
+        assert_eq!(
+            later.duration_since(MockInstant::from_secs(1)),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            later.checked_duration_since(MockInstant::from_secs(1)),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            later.checked_duration_since(MockInstant::from_secs(4)),
+            None
+        );
+    }
+
+    #[test]
+    fn elapsed_follows_now() {
+        MockInstant::set_now(MockInstant::from_secs(1));
+        let start = MockInstant::now();
+        MockInstant::advance(Duration::from_millis(1500));
+
+        assert_eq!(start.elapsed(), Duration::from_millis(1500));
+    }
+
+    #[test]
+    #[should_panic(expected = "checked_sub(earlier.ticks)` failed")]
+    fn elapsed_of_future_instant_panics() {
+        MockInstant::set_now(MockInstant::from_secs(1));
+
+        MockInstant::from_secs(2).elapsed();
+    }
+
+    #[test]
+    fn checked_arithmetic_detects_overflow() {
+        assert_eq!(MockInstant::MAX.checked_add(Duration::from_ticks(1)), None);
+        assert_eq!(MockInstant::MIN.checked_sub(Duration::from_ticks(1)), None);
+    }
+
+    #[test]
+    fn can_convert_to_and_from_embassy_instant() {
+        let instant = MockInstant::from(EmbassyInstant::from_secs(4));
+
+        assert_eq!(EmbassyInstant::from(instant), EmbassyInstant::from_secs(4));
+    }
+}
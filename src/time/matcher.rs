@@ -0,0 +1,178 @@
+//! Matchers to allow the durations expected of the mocked time types to be less exact.
+//!
+//! Code that computes its intervals, for example from a rate, can produce durations that are
+//! slightly different to the values written in the test. A [`DurationMatcher`] can be used
+//! anywhere a [`Duration`] is expected by a mock, a plain [`Duration`] must match exactly.
+//!
+//! # Examples
+//! ```
+//! use embassy_futures::block_on;
+//! use embassy_mock::time::{DurationMatcher, MockTimer, Timer};
+//! use embassy_time::Duration;
+//!
+//! async fn production_code<T: Timer>(rate_hz: u64) {
+//!     T::after(Duration::from_hz(rate_hz)).await;
+//!     T::after(Duration::from_secs(1)).await;
+//! }
+//!
+//! let timers = MockTimer::expect_sequence([
+//!     DurationMatcher::within(Duration::from_millis(333), Duration::from_millis(1)),
+//!     Duration::from_secs(1).into(),
+//! ]);
+//! block_on(production_code::<MockTimer>(3));
+//!
+//! timers.done().unwrap();
+//! ```
+
+use core::fmt;
+use embassy_time::Duration;
+
+/// The expectation of a [`Duration`] used by a mock.
+///
+/// A [`Duration`] can be converted into [`DurationMatcher::Exact`] with [`Into`].
+///
+/// # Examples
+///
+/// ```
+/// use embassy_mock::time::DurationMatcher;
+/// use embassy_time::Duration;
+///
+/// let matcher = DurationMatcher::at_least(Duration::from_secs(1));
+///
+/// assert!(matcher.matches(Duration::from_secs(2)));
+/// assert!(!matcher.matches(Duration::from_millis(999)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationMatcher {
+    /// Matches only this [`Duration`].
+    Exact(Duration),
+
+    /// Matches any [`Duration`] that differs from `expected` by at most `tolerance`.
+    Within {
+        /// The [`Duration`] to compare against.
+        expected: Duration,
+
+        /// The largest difference from `expected` that matches.
+        tolerance: Duration,
+    },
+
+    /// Matches any [`Duration`] that is this long or longer.
+    AtLeast(Duration),
+
+    /// Matches any [`Duration`] that is this long or shorter.
+    AtMost(Duration),
+
+    /// Matches any [`Duration`].
+    Any,
+}
+
+impl DurationMatcher {
+    /// Match any [`Duration`] that differs from `expected` by at most `tolerance`.
+    pub const fn within(expected: Duration, tolerance: Duration) -> Self {
+        Self::Within {
+            expected,
+            tolerance,
+        }
+    }
+
+    /// Match any [`Duration`] that is at least `min` long.
+    pub const fn at_least(min: Duration) -> Self {
+        Self::AtLeast(min)
+    }
+
+    /// Match any [`Duration`] that is at most `max` long.
+    pub const fn at_most(max: Duration) -> Self {
+        Self::AtMost(max)
+    }
+
+    /// Match any [`Duration`].
+    pub const fn any() -> Self {
+        Self::Any
+    }
+
+    /// Does `actual` match this [`DurationMatcher`].
+    pub fn matches(&self, actual: Duration) -> bool {
+        match *self {
+            Self::Exact(expected) => actual == expected,
+            Self::Within {
+                expected,
+                tolerance,
+            } => actual.max(expected) - actual.min(expected) <= tolerance,
+            Self::AtLeast(min) => actual >= min,
+            Self::AtMost(max) => actual <= max,
+            Self::Any => true,
+        }
+    }
+
+    /// The longest [`Duration`] that matches, or [`None`] if there is no limit.
+    #[cfg(feature = "std")]
+    pub(super) fn max(&self) -> Option<Duration> {
+        match *self {
+            Self::Exact(max) | Self::AtMost(max) => Some(max),
+            Self::Within {
+                expected,
+                tolerance,
+            } => expected.checked_add(tolerance),
+            Self::AtLeast(_) | Self::Any => None,
+        }
+    }
+}
+
+impl From<Duration> for DurationMatcher {
+    /// Match exactly the [`Duration`].
+    fn from(duration: Duration) -> Self {
+        Self::Exact(duration)
+    }
+}
+
+impl fmt::Display for DurationMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exact(expected) => write!(f, "{expected}"),
+            Self::Within {
+                expected,
+                tolerance,
+            } => write!(f, "{expected} (+/- {tolerance})"),
+            Self::AtLeast(min) => write!(f, "at least {min}"),
+            Self::AtMost(max) => write!(f, "at most {max}"),
+            Self::Any => write!(f, "any duration"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_matches_only_same_duration() {
+        let matcher = DurationMatcher::from(Duration::from_secs(1));
+
+        assert!(matcher.matches(Duration::from_secs(1)));
+        assert!(!matcher.matches(Duration::from_millis(1001)));
+    }
+
+    #[test]
+    fn within_matches_either_side_of_expected() {
+        let matcher = DurationMatcher::within(Duration::from_secs(1), Duration::from_millis(10));
+
+        assert!(matcher.matches(Duration::from_millis(990)));
+        assert!(matcher.matches(Duration::from_millis(1010)));
+        assert!(!matcher.matches(Duration::from_millis(989)));
+        assert!(!matcher.matches(Duration::from_millis(1011)));
+    }
+
+    #[test]
+    fn at_most_matches_shorter_durations() {
+        let matcher = DurationMatcher::at_most(Duration::from_secs(1));
+
+        assert!(matcher.matches(Duration::from_ticks(0)));
+        assert!(matcher.matches(Duration::from_secs(1)));
+        assert!(!matcher.matches(Duration::from_millis(1001)));
+    }
+
+    #[test]
+    fn any_matches_everything() {
+        assert!(DurationMatcher::any().matches(Duration::MAX));
+    }
+}
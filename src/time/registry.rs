@@ -0,0 +1,215 @@
+//! Registries of the mocks created by the code under test, so they can be checked afterwards.
+//!
+//! The mocks created with associated functions, such as [`Timer::after()`](super::Timer::after),
+//! are owned by the code under test so the test can't keep a handle to them. When the `registry`
+//! feature is enabled, a record of each of these mocks is kept in a registry instead.
+//!
+//! With the `std` feature the registries are per thread, so tests running in parallel don't
+//! interfere with each other. Without it they are global and hold at most
+//! [`REGISTRY_CAPACITY`] records, so they should be cleared at the start of each test.
+
+#[cfg(not(feature = "std"))]
+use core::cell::RefCell;
+#[cfg(feature = "std")]
+use std::{thread::LocalKey, vec::Vec};
+
+/// The maximum number of records kept by each registry without the `std` feature, any mocks
+/// created after this are not recorded.
+pub const REGISTRY_CAPACITY: usize = 64;
+
+/// The records of the mocks of one type, in creation order.
+#[derive(Debug)]
+pub(crate) struct Registry<T> {
+    /// Incremented when the registry is cleared so the mocks created before then can't update
+    /// the records created after.
+    generation: usize,
+
+    /// The records, in creation order.
+    #[cfg(feature = "std")]
+    records: Vec<T>,
+
+    /// The records, in creation order, the first `len` are set.
+    #[cfg(not(feature = "std"))]
+    records: [Option<T>; REGISTRY_CAPACITY],
+
+    /// The number of records that are set.
+    #[cfg(not(feature = "std"))]
+    len: usize,
+}
+
+/// The position of a record in a [`Registry`], kept by the mock to update its record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Slot {
+    /// The generation of the [`Registry`] when the record was created.
+    generation: usize,
+
+    /// The index of the record in the [`Registry`].
+    index: usize,
+}
+
+impl<T: Copy> Registry<T> {
+    /// Create an empty [`Registry`].
+    pub(crate) const fn new() -> Self {
+        Self {
+            generation: 0,
+            #[cfg(feature = "std")]
+            records: Vec::new(),
+            #[cfg(not(feature = "std"))]
+            records: [None; REGISTRY_CAPACITY],
+            #[cfg(not(feature = "std"))]
+            len: 0,
+        }
+    }
+
+    /// Add a record, returning its [`Slot`] or [`None`] if the registry is full.
+    pub(crate) fn register(&mut self, record: T) -> Option<Slot> {
+        let index = self.len();
+
+        #[cfg(feature = "std")]
+        self.records.push(record);
+        #[cfg(not(feature = "std"))]
+        {
+            *self.records.get_mut(index)? = Some(record);
+            self.len += 1;
+        }
+
+        Some(Slot {
+            generation: self.generation,
+            index,
+        })
+    }
+
+    /// Update the record in the `slot`, unless the registry has been cleared since it was added.
+    pub(crate) fn update(&mut self, slot: Slot, update: impl FnOnce(&mut T)) {
+        if slot.generation != self.generation {
+            return;
+        }
+
+        #[cfg(feature = "std")]
+        let record = self.records.get_mut(slot.index);
+        #[cfg(not(feature = "std"))]
+        let record = self.records.get_mut(slot.index).and_then(Option::as_mut);
+
+        if let Some(record) = record {
+            update(record);
+        }
+    }
+
+    /// The record at `index`, or [`None`] if there are not that many records.
+    #[cfg(feature = "std")]
+    pub(crate) fn get(&self, index: usize) -> Option<T> {
+        self.records.get(index).copied()
+    }
+
+    /// The number of records.
+    pub(crate) fn len(&self) -> usize {
+        #[cfg(feature = "std")]
+        {
+            self.records.len()
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            self.len
+        }
+    }
+
+    /// Remove all of the records.
+    pub(crate) fn clear(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+
+        #[cfg(feature = "std")]
+        self.records.clear();
+        #[cfg(not(feature = "std"))]
+        {
+            self.records = [None; REGISTRY_CAPACITY];
+            self.len = 0;
+        }
+    }
+
+    /// A copy of all of the records, in creation order.
+    #[cfg(feature = "std")]
+    pub(crate) fn records(&self) -> Vec<T> {
+        self.records.clone()
+    }
+}
+
+/// The storage of a [`Registry`], declared with [`registry!`].
+#[cfg(feature = "std")]
+pub(crate) type RegistryKey<T> = LocalKey<core::cell::RefCell<Registry<T>>>;
+
+/// The storage of a [`Registry`], declared with [`registry!`].
+#[cfg(not(feature = "std"))]
+pub(crate) type RegistryKey<T> = critical_section::Mutex<RefCell<Registry<T>>>;
+
+/// Run `f` with the [`Registry`] stored in `key`.
+pub(crate) fn with<T, R>(key: &'static RegistryKey<T>, f: impl FnOnce(&mut Registry<T>) -> R) -> R {
+    #[cfg(feature = "std")]
+    {
+        key.with(|registry| f(&mut registry.borrow_mut()))
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        critical_section::with(|cs| f(&mut key.borrow_ref_mut(cs)))
+    }
+}
+
+/// Declare the storage of a [`Registry`], which is per thread with the `std` feature and global
+/// without it.
+macro_rules! registry {
+    ($(#[$attr:meta])* static $name:ident: $record:ty;) => {
+        #[cfg(feature = "std")]
+        std::thread_local! {
+            $(#[$attr])*
+            static $name: core::cell::RefCell<$crate::time::registry::Registry<$record>> =
+                const { core::cell::RefCell::new($crate::time::registry::Registry::new()) };
+        }
+
+        #[cfg(not(feature = "std"))]
+        $(#[$attr])*
+        static $name: $crate::time::registry::RegistryKey<$record> =
+            critical_section::Mutex::new(core::cell::RefCell::new(
+                $crate::time::registry::Registry::new(),
+            ));
+    };
+}
+pub(crate) use registry;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_adds_records_in_order() {
+        let mut registry = Registry::new();
+        registry.register(1);
+        registry.register(2);
+
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry.get(0), Some(1));
+        assert_eq!(registry.get(1), Some(2));
+        assert_eq!(registry.get(2), None);
+    }
+
+    #[test]
+    fn update_changes_record_in_slot() {
+        let mut registry = Registry::new();
+        registry.register(1);
+        let slot = registry.register(2).unwrap();
+
+        registry.update(slot, |record| *record += 10);
+
+        assert_eq!(registry.records(), [1, 12]);
+    }
+
+    #[test]
+    fn update_after_clear_is_ignored() {
+        let mut registry = Registry::new();
+        let slot = registry.register(1).unwrap();
+        registry.clear();
+        registry.register(2);
+
+        registry.update(slot, |record| *record += 10);
+
+        assert_eq!(registry.records(), [2]);
+    }
+}
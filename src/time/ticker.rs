@@ -41,12 +41,55 @@
 //! }
 //! ```
 
+#[cfg(feature = "registry")]
+use super::registry::{self, Slot};
+use super::DurationMatcher;
 use core::{
-    future::{poll_fn, Future},
-    task::Poll,
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicUsize, Ordering},
+    task::{Context, Poll},
 };
-use embassy_time::{Duration, Ticker as EmbassyTicker};
+use embassy_time::{Duration, Instant, Ticker as EmbassyTicker};
+#[cfg(feature = "stream")]
+use futures_core::{stream::FusedStream, Stream};
 use snafu::prelude::*;
+#[cfg(feature = "std")]
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    vec::Vec,
+};
+#[cfg(feature = "std")]
+use {
+    super::{clock, Instant as _, MockInstant},
+    crate::waiting,
+};
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    /// The interval and number of ticks expected of each of the next [`MockTicker`]s created with
+    /// [`Ticker::every()`] on the current thread, in creation order.
+    static EXPECTED_EVERY: RefCell<VecDeque<(DurationMatcher, usize)>> =
+        const { RefCell::new(VecDeque::new()) };
+
+    /// The number of [`MockTicker`]s created with [`Ticker::every()`] on the current thread since
+    /// it was last taken with [`MockTicker::take_every_count()`].
+    static EVERY_COUNT: Cell<usize> = const { Cell::new(0) };
+
+    /// The jitter of every [`MockTicker`] created with [`Ticker::every()`] on the current thread.
+    static JITTER: RefCell<Vec<MockTickOffset>> = const { RefCell::new(Vec::new()) };
+
+    /// The drift of every [`MockTicker`] created with [`Ticker::every()`] on the current thread.
+    static DRIFT: Cell<MockTickOffset> = const { Cell::new(MockTickOffset::NONE) };
+}
+
+#[cfg(feature = "registry")]
+registry::registry! {
+    /// The records of the [`MockTicker`]s created with [`Ticker::every()`].
+    static REGISTRY: MockTickerRecord;
+}
 
 /// The trait to replace the [`embassy_time::Ticker`] in code to allow the [`MockTicker`] to
 /// be used in its place for tests.
@@ -56,6 +99,15 @@ pub trait Ticker {
 
     /// Wrapper for [`embassy_time::Ticker::next()`].
     fn next(&mut self) -> impl Future<Output = ()> + '_;
+
+    /// Wrapper for [`embassy_time::Ticker::reset()`].
+    fn reset(&mut self);
+
+    /// Wrapper for [`embassy_time::Ticker::reset_at()`].
+    fn reset_at(&mut self, deadline: Instant);
+
+    /// Wrapper for [`embassy_time::Ticker::reset_after()`].
+    fn reset_after(&mut self, after: Duration);
 }
 
 impl Ticker for EmbassyTicker {
@@ -68,9 +120,78 @@ impl Ticker for EmbassyTicker {
     fn next(&mut self) -> impl Future<Output = ()> + '_ {
         self.next()
     }
+
+    /// Resets the ticker back to its original state.
+    /// This causes the ticker to go back to zero, even if the current tick isn't over yet.
+    fn reset(&mut self) {
+        self.reset()
+    }
+
+    /// Reset the ticker at the deadline.
+    /// If the deadline is in the past, the ticker will fire instantly.
+    fn reset_at(&mut self, deadline: Instant) {
+        self.reset_at(deadline)
+    }
+
+    /// Resets the ticker, after the specified duration has passed.
+    /// If the specified duration is zero, the next tick will be after the duration of the ticker.
+    fn reset_after(&mut self, after: Duration) {
+        self.reset_after(after)
+    }
+}
+
+/// How a call to [`MockTicker::next()`] resolves, set with [`MockTicker::script()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockTick {
+    /// The tick completes the first time it is polled.
+    Ready,
+
+    /// The tick returns [`Poll::Pending`] for this many polls before completing, waking the
+    /// waker each time so the executor polls it again.
+    Pending(usize),
+}
+
+/// How far from its schedule a tick of a [`MockTicker`] arrives on the
+/// [`MockClock`](super::MockClock), see [`MockTicker::set_jitter()`] and
+/// [`MockTicker::set_drift()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockTickOffset {
+    /// The tick arrives this much earlier.
+    Early(Duration),
+
+    /// The tick arrives this much later.
+    Late(Duration),
+}
+
+impl MockTickOffset {
+    /// No offset, the tick arrives on its schedule.
+    pub const NONE: Self = Self::Late(Duration::from_ticks(0));
+
+    /// Move `instant` by this offset, saturating at the limits of [`MockInstant`].
+    #[cfg(feature = "std")]
+    fn apply(self, instant: MockInstant) -> MockInstant {
+        match self {
+            Self::Early(offset) => instant.checked_sub(offset).unwrap_or(MockInstant::MIN),
+            Self::Late(offset) => instant.checked_add(offset).unwrap_or(MockInstant::MAX),
+        }
+    }
+}
+
+/// A call to one of the reset methods of the [`Ticker`] trait, recorded by [`MockTicker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockTickerReset {
+    /// [`Ticker::reset()`] was called.
+    Reset,
+
+    /// [`Ticker::reset_at()`] was called with this deadline.
+    At(Instant),
+
+    /// [`Ticker::reset_after()`] was called with this duration.
+    After(Duration),
 }
 
-/// The errors that are reported by [`MockTicker`].
+/// The errors that are reported by [`MockTicker`] and
+/// [`MockTickerExpectations`](super::MockTickerExpectations).
 #[derive(Debug, Snafu, PartialEq)]
 pub enum MockTickerError {
     /// The [`MockTicker::next()`] method was called the wrong number of times.
@@ -82,6 +203,74 @@ pub enum MockTickerError {
         /// The actual number of times [`MockTicker::next()`] was called.
         actual: usize,
     },
+
+    /// The [`MockTicker`] was created with the wrong interval.
+    #[snafu(display(
+        "expected ticker to be created with an interval of {expected}, actually created with {actual}"
+    ))]
+    WrongInterval {
+        /// The [`DurationMatcher`] the interval passed to [`Ticker::every()`] was expected to
+        /// match.
+        expected: DurationMatcher,
+
+        /// The actual interval passed to [`Ticker::every()`].
+        actual: Duration,
+    },
+
+    /// The wrong number of [`MockTicker`]s were created with [`Ticker::every()`].
+    #[snafu(display("expected to create {expected} ticker(s), actually created {actual}"))]
+    WrongNumberOfTickers {
+        /// The expected number of [`MockTicker`]s.
+        expected: usize,
+
+        /// The actual number of [`MockTicker`]s created.
+        actual: usize,
+    },
+}
+
+/// The state of a [`MockTicker`] that is changed through a shared reference.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct SharedState {
+    /// The calls to the reset methods, in the order they were made.
+    resets: Vec<MockTickerReset>,
+
+    /// How each of the following calls to [`MockTicker::next()`] resolve, the remaining calls
+    /// are [`MockTick::Ready`].
+    script: VecDeque<MockTick>,
+
+    /// The number of the following calls to [`MockTicker::next()`] that resolve immediately as
+    /// the ticks were missed, before the script and clock are used.
+    missed_ticks: usize,
+
+    /// The time of the next tick of the [`MockClock`](super::MockClock) and the interval between
+    /// ticks, or [`None`] if this ticker was created without a [`MockClock`](super::MockClock).
+    clock_ticks: Option<(MockInstant, Duration)>,
+
+    /// The offsets of the ticks from their schedule, used in turn and repeated.
+    jitter: Vec<MockTickOffset>,
+
+    /// The position in `jitter` of the offset of the next tick.
+    next_jitter: usize,
+
+    /// The offset added to the interval between each scheduled tick.
+    drift: MockTickOffset,
+}
+
+#[cfg(feature = "std")]
+impl SharedState {
+    /// The state of a [`MockTicker`] that has not been used yet.
+    const fn new(clock_ticks: Option<(MockInstant, Duration)>) -> Self {
+        Self {
+            resets: Vec::new(),
+            script: VecDeque::new(),
+            missed_ticks: 0,
+            clock_ticks,
+            jitter: Vec::new(),
+            next_jitter: 0,
+            drift: MockTickOffset::NONE,
+        }
+    }
 }
 
 /// A mocked version of [`embassy_time::Ticker`] that can be used in its place for unit tests.
@@ -133,17 +322,122 @@ pub enum MockTickerError {
 ///
 /// // `ticker` is dropped and will panic.
 /// ```
+///
+/// The counters use interior mutability so a [`MockTicker`] can be shared with an [`Arc`], which
+/// also implements [`Ticker`]. This allows the same mock to be handed to multiple functions under
+/// test and observed from the test while they own it:
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::time::{MockTicker, Ticker};
+/// use std::sync::Arc;
+///
+/// async fn production_code<T: Ticker>(mut ticker: T) {
+///     ticker.next().await;
+/// }
+///
+/// let ticker = Arc::new(MockTicker::expect(2));
+/// block_on(production_code(Arc::clone(&ticker)));
+/// assert_eq!(ticker.times_called(), 1);
+///
+/// block_on(production_code(Arc::clone(&ticker)));
+/// assert_eq!(ticker.times_called(), 2);
+/// ```
 #[derive(Debug)]
 pub struct MockTicker {
     /// The number of expected calls to [`Self::next()`].
     expected: usize,
 
     /// The number of times [`Self::next()`] has been called.
-    times_called: usize,
+    times_called: AtomicUsize,
 
     /// Has this mock been checked with a call to [`Self::done()`].
     /// If true it is not checked when dropped.
     is_done: bool,
+
+    /// The interval this ticker was created with, or [`None`] if it was not created with
+    /// [`Ticker::every()`].
+    interval: Option<Duration>,
+
+    /// The interval this ticker is expected to be created with, if set with
+    /// [`Self::expect_every()`].
+    expected_interval: Option<DurationMatcher>,
+
+    /// The number of times any of the reset methods have been called.
+    reset_count: AtomicUsize,
+
+    /// The state that is changed by calls to [`Self::next()`] and the reset methods.
+    #[cfg(feature = "std")]
+    state: Mutex<SharedState>,
+
+    /// The position of the record of this ticker in the registry, or [`None`] if it isn't
+    /// recorded.
+    #[cfg(feature = "registry")]
+    registry_slot: Option<Slot>,
+
+    /// The tick that is being polled by [`Stream::poll_next()`], or [`None`] if the next poll
+    /// starts a new tick.
+    #[cfg(feature = "stream")]
+    pending_tick: Option<MockTickFuture>,
+}
+
+/// The future returned by [`MockTicker::next()`].
+#[derive(Debug)]
+struct MockTickFuture {
+    /// The number of times to return [`Poll::Pending`] before checking the `deadline`.
+    #[cfg(feature = "std")]
+    pending_polls: usize,
+
+    /// The time of the [`MockClock`](super::MockClock) to wait for, or [`None`] if the tick
+    /// doesn't wait for the clock.
+    #[cfg(feature = "std")]
+    deadline: Option<MockInstant>,
+}
+
+impl Future for MockTickFuture {
+    type Output = ();
+
+    /// Return [`Poll::Pending`] for the remaining pending polls and then until the clock reaches
+    /// the deadline.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        #[cfg(feature = "std")]
+        {
+            let this = self.get_mut();
+            if this.pending_polls > 0 {
+                this.pending_polls -= 1;
+                waiting::waking("MockTicker::next()", || cx.waker().wake_by_ref());
+                return Poll::Pending;
+            }
+
+            if let Some(deadline) = this.deadline {
+                if !clock::poll_deadline(deadline, cx.waker()) {
+                    waiting::record("MockTicker::next()");
+                    return Poll::Pending;
+                }
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = (self, cx);
+
+        Poll::Ready(())
+    }
+}
+
+/// The record of a [`MockTicker`] created with [`Ticker::every()`], kept in the registry when the
+/// `registry` feature is enabled.
+///
+/// See [`MockTicker::registered_tickers()`].
+#[cfg(feature = "registry")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MockTickerRecord {
+    /// The interval the ticker was created with.
+    pub interval: Duration,
+
+    /// The number of times [`Ticker::next()`] has been called on the ticker.
+    pub ticks: usize,
+
+    /// The number of times any of the reset methods have been called on the ticker.
+    pub resets: usize,
 }
 
 impl MockTicker {
@@ -160,11 +454,169 @@ impl MockTicker {
     pub const fn expect(expected: usize) -> Self {
         Self {
             expected,
-            times_called: 0,
+            times_called: AtomicUsize::new(0),
             is_done: false,
+            interval: None,
+            expected_interval: None,
+            reset_count: AtomicUsize::new(0),
+            #[cfg(feature = "std")]
+            state: Mutex::new(SharedState::new(None)),
+            #[cfg(feature = "registry")]
+            registry_slot: None,
+            #[cfg(feature = "stream")]
+            pending_tick: None,
         }
     }
 
+    /// Create a [`MockTicker`] that controls how each call to [`Self::next()`] resolves, expecting
+    /// [`Self::next()`] to be called once for each step of the `script`.
+    ///
+    /// This allows testing code that does work between ticks while the ticker is pending.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::{future::Future, pin::pin, task::{Context, Poll}};
+    /// use embassy_mock::time::{MockTick, MockTicker, Ticker};
+    /// use futures_util::task::noop_waker_ref;
+    ///
+    /// let mut ticker = MockTicker::script([MockTick::Ready, MockTick::Pending(1)]);
+    /// let mut cx = Context::from_waker(noop_waker_ref());
+    ///
+    /// assert_eq!(pin!(ticker.next()).poll(&mut cx), Poll::Ready(()));
+    ///
+    /// {
+    ///     let mut tick = pin!(ticker.next());
+    ///     assert_eq!(tick.as_mut().poll(&mut cx), Poll::Pending);
+    ///     assert_eq!(tick.as_mut().poll(&mut cx), Poll::Ready(()));
+    /// }
+    ///
+    /// ticker.done().unwrap();
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn script(script: impl IntoIterator<Item = MockTick>) -> Self {
+        Self::builder().script(script).build()
+    }
+
+    /// Create a [`MockTickerBuilder`] to configure a [`MockTicker`] with several options.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTicker, Ticker};
+    /// use embassy_time::Duration;
+    ///
+    /// let mut ticker = MockTicker::builder()
+    ///     .ticks(2)
+    ///     .interval(Duration::from_secs(1))
+    ///     .build();
+    /// block_on(ticker.next());
+    /// block_on(ticker.next());
+    ///
+    /// assert_eq!(ticker.interval(), Some(Duration::from_secs(1)));
+    /// ticker.done().unwrap();
+    /// ```
+    pub fn builder() -> MockTickerBuilder {
+        MockTickerBuilder::default()
+    }
+
+    /// Simulate the code using this ticker falling behind by `ticks` ticks, so the following
+    /// `ticks` calls to [`Self::next()`] resolve immediately, back-to-back.
+    ///
+    /// A real ticker fires immediately for each tick that was missed while the code was busy
+    /// until it has caught up. The missed ticks are counted as calls to [`Self::next()`] but don't
+    /// use the steps of the script or wait for the [`MockClock`](super::MockClock), after them the
+    /// ticker continues on its schedule. This allows testing catch-up and rate-limiting logic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::{future::Future, pin::pin, task::{Context, Poll}};
+    /// use embassy_mock::time::{MockClock, MockTicker, Ticker};
+    /// use embassy_time::Duration;
+    /// use futures_util::task::noop_waker_ref;
+    ///
+    /// let _clock = MockClock::new();
+    /// let mut ticker = MockTicker::every(Duration::from_secs(1));
+    /// let mut cx = Context::from_waker(noop_waker_ref());
+    ///
+    /// ticker.miss_ticks(2);
+    ///
+    /// assert_eq!(pin!(ticker.next()).poll(&mut cx), Poll::Ready(()));
+    /// assert_eq!(pin!(ticker.next()).poll(&mut cx), Poll::Ready(()));
+    /// assert_eq!(pin!(ticker.next()).poll(&mut cx), Poll::Pending);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn miss_ticks(&self, ticks: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.missed_ticks = state.missed_ticks.checked_add(ticks).unwrap();
+    }
+
+    /// Make the ticks of every [`MockTicker`] created with [`Ticker::every()`] on the current
+    /// thread from now on arrive early or late by the offsets in `jitter`, used in turn and
+    /// repeated.
+    ///
+    /// The jitter of a tick doesn't move the schedule of the following ticks. It only has an
+    /// effect while a [`MockClock`](super::MockClock) is alive, otherwise ticks resolve
+    /// immediately. This allows testing that code tolerates ticks that don't arrive exactly on
+    /// time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{Instant, MockClock, MockInstant, MockTickOffset, MockTicker, Ticker};
+    /// use embassy_time::Duration;
+    ///
+    /// MockTicker::set_jitter([
+    ///     MockTickOffset::Late(Duration::from_millis(5)),
+    ///     MockTickOffset::Early(Duration::from_millis(5)),
+    /// ]);
+    ///
+    /// let clock = MockClock::new();
+    /// let mut ticker = MockTicker::every(Duration::from_secs(1));
+    ///
+    /// clock.block_on(ticker.next());
+    /// assert_eq!(clock.now(), MockInstant::from_millis(1005));
+    /// clock.block_on(ticker.next());
+    /// assert_eq!(clock.now(), MockInstant::from_millis(1995));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn set_jitter(jitter: impl IntoIterator<Item = MockTickOffset>) {
+        JITTER.with(|current| *current.borrow_mut() = jitter.into_iter().collect());
+    }
+
+    /// Make the interval between the ticks of every [`MockTicker`] created with
+    /// [`Ticker::every()`] on the current thread from now on longer or shorter by `drift`.
+    ///
+    /// Unlike jitter the drift builds up, each tick is further from its schedule than the last.
+    /// It only has an effect while a [`MockClock`](super::MockClock) is alive, otherwise ticks
+    /// resolve immediately. It is undone by setting it to [`MockTickOffset::NONE`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{Instant, MockClock, MockInstant, MockTickOffset, MockTicker, Ticker};
+    /// use embassy_time::Duration;
+    ///
+    /// MockTicker::set_drift(MockTickOffset::Late(Duration::from_millis(1)));
+    ///
+    /// let clock = MockClock::new();
+    /// let mut ticker = MockTicker::every(Duration::from_secs(1));
+    ///
+    /// clock.block_on(async {
+    ///     for _ in 0..10 {
+    ///         ticker.next().await;
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(clock.now(), MockInstant::from_millis(10_010));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn set_drift(drift: MockTickOffset) {
+        DRIFT.with(|current| current.set(drift));
+    }
+
     /// Mark the [`MockTicker`] as done and check if [`Self::next()`] was called the correct
     /// number of times.
     ///
@@ -203,30 +655,474 @@ impl MockTicker {
     /// // This doesn't panic when `ticker` is dropped as `ticker.done()` was called.
     /// ```
     pub fn done(mut self) -> Result<(), MockTickerError> {
-        let res = if self.times_called != self.expected {
-            Err(MockTickerError::WrongNumberOfTicks {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check that this [`MockTicker`] was created with the expected interval and that
+    /// [`Self::next()`] was called the expected number of times.
+    fn check(&self) -> Result<(), MockTickerError> {
+        if let (Some(expected), Some(actual)) = (self.expected_interval, self.interval) {
+            ensure!(
+                expected.matches(actual),
+                WrongIntervalSnafu { expected, actual }
+            );
+        }
+
+        let times_called = self.times_called();
+        ensure!(
+            times_called == self.expected,
+            WrongNumberOfTicksSnafu {
                 expected: self.expected,
-                actual: self.times_called,
+                actual: times_called,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Expect the next [`MockTicker`] created with [`Ticker::every()`] on the current thread to
+    /// be created with the `interval` and for [`Self::next()`] to be called `ticks` times.
+    ///
+    /// This allows checking the interval chosen by code that creates its own ticker. Unlike a
+    /// [`MockTicker`] created with [`Ticker::every()`] normally, the created ticker is checked
+    /// when it is dropped and panics if either expectation is not met. Calling this multiple
+    /// times sets the expectations for the following tickers in the order they are created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTicker, Ticker};
+    /// use embassy_time::Duration;
+    ///
+    /// async fn production_code<T: Ticker>() {
+    ///     let mut ticker = T::every(Duration::from_millis(100));
+    ///     ticker.next().await;
+    /// }
+    ///
+    /// MockTicker::expect_every(Duration::from_millis(100), 1);
+    /// block_on(production_code::<MockTicker>());
+    /// ```
+    ///
+    /// ```should_panic
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTicker, Ticker};
+    /// use embassy_time::Duration;
+    ///
+    /// async fn production_code<T: Ticker>() {
+    ///     let mut ticker = T::every(Duration::from_millis(10)); // Wrong interval.
+    ///     ticker.next().await;
+    /// }
+    ///
+    /// MockTicker::expect_every(Duration::from_millis(100), 1);
+    /// block_on(production_code::<MockTicker>()); // Panics when the ticker is dropped.
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn expect_every(interval: impl Into<DurationMatcher>, ticks: usize) {
+        let interval = interval.into();
+        EXPECTED_EVERY.with(|expected| expected.borrow_mut().push_back((interval, ticks)));
+    }
+
+    /// Remove the expectations set with [`Self::expect_every()`] that have not been used by a
+    /// ticker yet, returning how many there were.
+    #[cfg(feature = "std")]
+    pub(super) fn clear_expect_every() -> usize {
+        EXPECTED_EVERY.with(|expected| expected.take().len())
+    }
+
+    /// The number of [`MockTicker`]s created with [`Ticker::every()`] on the current thread since
+    /// the last call, resetting the count.
+    #[cfg(feature = "std")]
+    pub(super) fn take_every_count() -> usize {
+        EVERY_COUNT.with(|count| count.replace(0))
+    }
+
+    /// The interval this [`MockTicker`] was created with, or [`None`] if it was created with
+    /// [`Self::expect()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{MockTicker, Ticker};
+    /// use embassy_time::Duration;
+    ///
+    /// let ticker = MockTicker::every(Duration::from_secs(1));
+    ///
+    /// assert_eq!(ticker.interval(), Some(Duration::from_secs(1)));
+    /// ```
+    pub const fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    /// The number of times [`Ticker::reset()`], [`Ticker::reset_at()`] or
+    /// [`Ticker::reset_after()`] have been called on this [`MockTicker`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{MockTicker, Ticker};
+    /// use embassy_time::Duration;
+    ///
+    /// let mut ticker = MockTicker::expect(0);
+    /// ticker.reset();
+    /// ticker.reset_after(Duration::from_secs(1));
+    ///
+    /// assert_eq!(ticker.reset_count(), 2);
+    /// ```
+    pub fn reset_count(&self) -> usize {
+        self.reset_count.load(Ordering::Relaxed)
+    }
+
+    /// The number of times [`Self::next()`] has been called on this [`MockTicker`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTicker, Ticker};
+    ///
+    /// let mut ticker = MockTicker::expect(2);
+    /// block_on(ticker.next());
+    ///
+    /// assert_eq!(ticker.times_called(), 1);
+    /// # block_on(ticker.next());
+    /// ```
+    pub fn times_called(&self) -> usize {
+        self.times_called.load(Ordering::Relaxed)
+    }
+
+    /// The calls to [`Ticker::reset()`], [`Ticker::reset_at()`] and [`Ticker::reset_after()`]
+    /// on this [`MockTicker`] with their arguments, in the order they were made.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{MockTicker, MockTickerReset, Ticker};
+    /// use embassy_time::{Duration, Instant};
+    ///
+    /// let mut ticker = MockTicker::expect(0);
+    /// ticker.reset_at(Instant::from_secs(5));
+    /// ticker.reset();
+    ///
+    /// assert_eq!(
+    ///     ticker.resets(),
+    ///     [MockTickerReset::At(Instant::from_secs(5)), MockTickerReset::Reset]
+    /// );
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn resets(&self) -> Vec<MockTickerReset> {
+        self.state.lock().unwrap().resets.clone()
+    }
+
+    /// The records of every [`MockTicker`] created with [`Ticker::every()`] since the registry was
+    /// last cleared, in the order they were created.
+    ///
+    /// This gives a handle on the tickers created by the code under test, to check their interval
+    /// and how many times they ticked after the code has finished with them.
+    ///
+    /// This needs the `std` feature as well as `registry`, as the records are collected into a
+    /// [`Vec`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTicker, MockTickerRecord, Ticker};
+    /// use embassy_time::Duration;
+    ///
+    /// async fn production_code<T: Ticker>() {
+    ///     let mut ticker = T::every(Duration::from_secs(1));
+    ///     ticker.next().await;
+    ///     ticker.next().await;
+    /// }
+    ///
+    /// block_on(production_code::<MockTicker>());
+    ///
+    /// assert_eq!(
+    ///     MockTicker::registered_tickers(),
+    ///     [MockTickerRecord {
+    ///         interval: Duration::from_secs(1),
+    ///         ticks: 2,
+    ///         resets: 0,
+    ///     }]
+    /// );
+    /// ```
+    #[cfg(all(feature = "registry", feature = "std"))]
+    pub fn registered_tickers() -> Vec<MockTickerRecord> {
+        registry::with(&REGISTRY, |registry| registry.records())
+    }
+
+    /// The record of the [`MockTicker`] at `index` in the registry, or [`None`] if fewer tickers
+    /// have been registered.
+    ///
+    /// Like [`Self::registered_tickers()`], this needs the `std` feature as well as `registry`.
+    #[cfg(all(feature = "registry", feature = "std"))]
+    pub fn registered_ticker(index: usize) -> Option<MockTickerRecord> {
+        registry::with(&REGISTRY, |registry| registry.get(index))
+    }
+
+    /// The number of [`MockTicker`]s in the registry.
+    ///
+    /// See [`Self::registered_tickers()`].
+    #[cfg(feature = "registry")]
+    pub fn registered_ticker_count() -> usize {
+        registry::with(&REGISTRY, |registry| registry.len())
+    }
+
+    /// Remove all of the records from the registry.
+    ///
+    /// Tickers created before this is called are no longer recorded. Without the `std` feature
+    /// the registry is shared by all tests, so this should be called at the start of each test.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{MockTicker, Ticker};
+    /// use embassy_time::Duration;
+    ///
+    /// let _ticker = MockTicker::every(Duration::from_secs(1));
+    /// MockTicker::clear_registry();
+    ///
+    /// assert_eq!(MockTicker::registered_ticker_count(), 0);
+    /// ```
+    #[cfg(feature = "registry")]
+    pub fn clear_registry() {
+        registry::with(&REGISTRY, |registry| registry.clear());
+    }
+
+    /// Update the record of this ticker in the registry, if it has one.
+    #[cfg(feature = "registry")]
+    fn update_record(&self, update: impl FnOnce(&mut MockTickerRecord)) {
+        if let Some(slot) = self.registry_slot {
+            registry::with(&REGISTRY, |registry| registry.update(slot, update));
+        }
+    }
+
+    /// Increment a counter, panicking if it overflows.
+    fn increment(counter: &AtomicUsize) {
+        counter
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                count.checked_add(1)
             })
-        } else {
-            Ok(())
+            .unwrap();
+    }
+
+    /// Count a call to [`Self::next()`] and return a future that resolves immediately for a missed
+    /// tick, otherwise as the next step of the script and the next tick of the
+    /// [`MockClock`](super::MockClock).
+    fn tick(&self) -> MockTickFuture {
+        Self::increment(&self.times_called);
+        #[cfg(feature = "registry")]
+        self.update_record(|record| record.ticks = record.ticks.saturating_add(1));
+
+        #[cfg(feature = "std")]
+        let (pending_polls, deadline) = 'state: {
+            let mut state = self.state.lock().unwrap();
+            if state.missed_ticks > 0 {
+                state.missed_ticks -= 1;
+                break 'state (0, None);
+            }
+
+            let pending_polls = match state.script.pop_front() {
+                Some(MockTick::Pending(polls)) => polls,
+                Some(MockTick::Ready) | None => 0,
+            };
+            let drift = state.drift;
+            let scheduled = state.clock_ticks.as_mut().map(|(next_tick, interval)| {
+                let scheduled = *next_tick;
+                *next_tick = drift.apply(*next_tick + *interval);
+                scheduled
+            });
+            let deadline = scheduled.map(|scheduled| match state.jitter.get(state.next_jitter) {
+                Some(jitter) => {
+                    let deadline = jitter.apply(scheduled);
+                    state.next_jitter = (state.next_jitter + 1) % state.jitter.len();
+                    deadline
+                }
+                None => scheduled,
+            });
+
+            (pending_polls, deadline)
         };
 
-        self.is_done = true;
-        res
+        MockTickFuture {
+            #[cfg(feature = "std")]
+            pending_polls,
+            #[cfg(feature = "std")]
+            deadline,
+        }
+    }
+
+    /// Count and record a call to one of the reset methods, moving the next tick of the
+    /// [`MockClock`](super::MockClock) to `next_tick` plus the interval if it is attached.
+    fn record_reset(&self, reset: MockTickerReset) {
+        Self::increment(&self.reset_count);
+        #[cfg(feature = "registry")]
+        self.update_record(|record| record.resets = record.resets.saturating_add(1));
+
+        #[cfg(feature = "std")]
+        {
+            let mut state = self.state.lock().unwrap();
+            state.resets.push(reset);
+
+            let drift = state.drift;
+            if let Some((next_tick, interval)) = state.clock_ticks.as_mut() {
+                *next_tick = drift.apply(
+                    match reset {
+                        MockTickerReset::Reset => clock::now(),
+                        MockTickerReset::At(deadline) => deadline.into(),
+                        MockTickerReset::After(after) => clock::now() + after,
+                    } + *interval,
+                );
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = reset;
+    }
+}
+
+/// A builder to configure a [`MockTicker`], see [`MockTicker::builder()`].
+///
+/// The options that are not set keep the same defaults as [`MockTicker::expect()`].
+#[derive(Debug, Clone, Default)]
+#[must_use = "the builder does nothing until `build()` is called"]
+pub struct MockTickerBuilder {
+    /// The expected number of calls to [`MockTicker::next()`], if set.
+    ticks: Option<usize>,
+
+    /// The interval of the ticker, if set.
+    interval: Option<Duration>,
+
+    /// How each call to [`MockTicker::next()`] resolves, if set.
+    #[cfg(feature = "std")]
+    script: Option<VecDeque<MockTick>>,
+
+    /// The number of ticks that were missed before the first call to [`MockTicker::next()`].
+    #[cfg(feature = "std")]
+    missed_ticks: usize,
+
+    /// The offsets of the ticks from their schedule.
+    #[cfg(feature = "std")]
+    jitter: Vec<MockTickOffset>,
+
+    /// The offset added to the interval between each scheduled tick.
+    #[cfg(feature = "std")]
+    drift: Option<MockTickOffset>,
+}
+
+impl MockTickerBuilder {
+    /// Expect [`MockTicker::next()`] to be called `ticks` times.
+    ///
+    /// Defaults to the number of steps in the script, or zero without a script.
+    pub fn ticks(mut self, ticks: usize) -> Self {
+        self.ticks = Some(ticks);
+        self
+    }
+
+    /// Give the ticker an interval, the same as a ticker created with [`Ticker::every()`].
+    ///
+    /// If the ticker is built while a [`MockClock`](super::MockClock) is alive then it ticks with
+    /// the clock at this interval.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Control how each call to [`MockTicker::next()`] resolves, see [`MockTicker::script()`].
+    #[cfg(feature = "std")]
+    pub fn script(mut self, script: impl IntoIterator<Item = MockTick>) -> Self {
+        self.script = Some(script.into_iter().collect());
+        self
+    }
+
+    /// Start the ticker having missed `ticks` ticks, see [`MockTicker::miss_ticks()`].
+    #[cfg(feature = "std")]
+    pub fn missed_ticks(mut self, ticks: usize) -> Self {
+        self.missed_ticks = ticks;
+        self
+    }
+
+    /// Make the ticks arrive early or late by the offsets in `jitter` when ticking with a
+    /// [`MockClock`](super::MockClock), see [`MockTicker::set_jitter()`].
+    #[cfg(feature = "std")]
+    pub fn jitter(mut self, jitter: impl IntoIterator<Item = MockTickOffset>) -> Self {
+        self.jitter = jitter.into_iter().collect();
+        self
+    }
+
+    /// Make the interval between the ticks longer or shorter by `drift` when ticking with a
+    /// [`MockClock`](super::MockClock), see [`MockTicker::set_drift()`].
+    #[cfg(feature = "std")]
+    pub fn drift(mut self, drift: MockTickOffset) -> Self {
+        self.drift = Some(drift);
+        self
+    }
+
+    /// Create the configured [`MockTicker`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTick, MockTicker, Ticker};
+    ///
+    /// let mut ticker = MockTicker::builder()
+    ///     .script([MockTick::Pending(2), MockTick::Ready])
+    ///     .missed_ticks(1)
+    ///     .ticks(3)
+    ///     .build();
+    /// block_on(ticker.next()); // The missed tick.
+    /// block_on(ticker.next()); // Pending twice.
+    /// block_on(ticker.next());
+    ///
+    /// ticker.done().unwrap();
+    /// ```
+    pub fn build(self) -> MockTicker {
+        #[cfg(feature = "std")]
+        let script = self.script.unwrap_or_default();
+        #[cfg(feature = "std")]
+        let default_ticks = script.len();
+        #[cfg(not(feature = "std"))]
+        let default_ticks = 0;
+
+        let mut ticker = MockTicker::expect(self.ticks.unwrap_or(default_ticks));
+        ticker.interval = self.interval;
+
+        #[cfg(feature = "std")]
+        {
+            let drift = self.drift.unwrap_or(MockTickOffset::NONE);
+            let clock_ticks = self
+                .interval
+                .filter(|_| clock::is_attached())
+                .map(|interval| (drift.apply(clock::now() + interval), interval));
+            let state = ticker.state.get_mut().unwrap();
+            state.script = script;
+            state.missed_ticks = self.missed_ticks;
+            state.clock_ticks = clock_ticks;
+            state.jitter = self.jitter;
+            state.drift = drift;
+        }
+
+        ticker
     }
 }
 
 impl Drop for MockTicker {
-    /// If [`Self::done()`] has not been called before being dropped then check that the number of
-    /// times [`Self::next()`] was called is as expected.
+    /// If [`Self::done()`] has not been called before being dropped then check that the interval
+    /// and the number of times [`Self::next()`] was called are as expected.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
     fn drop(&mut self) {
-        if !self.is_done {
-            assert_eq!(
-                self.expected, self.times_called,
-                "expected to call next {} time(s), actually called {}",
-                self.expected, self.times_called
-            );
+        #[cfg(feature = "std")]
+        let is_panicking = std::thread::panicking();
+        #[cfg(not(feature = "std"))]
+        let is_panicking = false;
+
+        if !self.is_done && !is_panicking {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
         }
     }
 }
@@ -235,6 +1131,10 @@ impl Ticker for MockTicker {
     /// Create a [`MockTicker`] that doesn't require [`Self::done()`] to be called.
     /// This allows a [`MockTicker`] to be created in production code instead of in the test.
     ///
+    /// The ticker is only checked when dropped if its expectations were set beforehand with
+    /// [`Self::expect_every()`]. With the `registry` feature its interval and number of ticks are
+    /// also recorded, see [`Self::registered_tickers()`].
+    ///
     /// # Examples
     /// ```
     /// use embassy_mock::time::Ticker;
@@ -259,31 +1159,173 @@ impl Ticker for MockTicker {
     ///     #[test]
     ///     # fn hidden_fake_test(){}
     ///     fn test_creating_ticker() {
-    ///         // Can't set expectations but at least it is testable
     ///         block_on(production_code::<MockTicker>());
+    ///
+    ///         let record = MockTicker::registered_ticker(0).unwrap();
+    ///         assert_eq!(record.interval, Duration::from_secs(1));
+    ///         assert_eq!(record.ticks, 1);
     ///     }
     /// # mod closing {
     /// }
     /// ```
-    fn every(_duration: Duration) -> Self {
+    fn every(duration: Duration) -> Self {
+        #[cfg(feature = "std")]
+        let expected_every = EXPECTED_EVERY.with(|expected| expected.borrow_mut().pop_front());
+        #[cfg(feature = "std")]
+        EVERY_COUNT.with(|count| count.set(count.get().saturating_add(1)));
+        #[cfg(not(feature = "std"))]
+        let expected_every: Option<(DurationMatcher, usize)> = None;
+        #[cfg(feature = "std")]
+        let drift = DRIFT.with(Cell::get);
+
         Self {
-            expected: 0,
-            times_called: 0,
-            is_done: true, // Mark as done so it won't be checked.
+            expected: expected_every.map_or(0, |(_, ticks)| ticks),
+            times_called: AtomicUsize::new(0),
+            // Only checked if the expectations were set with `expect_every()`.
+            is_done: expected_every.is_none(),
+            interval: Some(duration),
+            expected_interval: expected_every.map(|(interval, _)| interval),
+            reset_count: AtomicUsize::new(0),
+            #[cfg(feature = "std")]
+            state: Mutex::new(SharedState {
+                jitter: JITTER.with(|jitter| jitter.borrow().clone()),
+                drift,
+                ..SharedState::new(
+                    clock::is_attached().then(|| (drift.apply(clock::now() + duration), duration)),
+                )
+            }),
+            #[cfg(feature = "registry")]
+            registry_slot: registry::with(&REGISTRY, |registry| {
+                registry.register(MockTickerRecord {
+                    interval: duration,
+                    ticks: 0,
+                    resets: 0,
+                })
+            }),
+            #[cfg(feature = "stream")]
+            pending_tick: None,
         }
     }
 
-    /// Increment an internal counter of how many times this method is called and return [`Poll::Ready`].
+    /// Increment an internal counter of how many times this method is called and return
+    /// [`Poll::Ready`].
+    ///
+    /// If this ticker was created with [`Self::script()`] then the returned future resolves as
+    /// the next step of the script.
+    ///
+    /// If this ticker was created with [`Self::every()`] while a [`MockClock`](super::MockClock)
+    /// is alive then [`Poll::Ready`] is only returned once the clock has reached the next tick.
     fn next(&mut self) -> impl Future<Output = ()> + '_ {
-        self.times_called = self.times_called.checked_add(1).unwrap();
-        poll_fn(|_cx| Poll::Ready(()))
+        self.tick()
+    }
+
+    /// Count and record the reset.
+    ///
+    /// If this ticker was created with [`Self::every()`] while a [`MockClock`](super::MockClock)
+    /// is alive then the next tick is moved to one interval from the current time of the clock.
+    fn reset(&mut self) {
+        self.record_reset(MockTickerReset::Reset);
+    }
+
+    /// Count and record the reset with the `deadline`.
+    ///
+    /// If this ticker was created with [`Self::every()`] while a [`MockClock`](super::MockClock)
+    /// is alive then the next tick is moved to one interval after `deadline`.
+    fn reset_at(&mut self, deadline: Instant) {
+        self.record_reset(MockTickerReset::At(deadline));
+    }
+
+    /// Count and record the reset with the `after` duration.
+    ///
+    /// If this ticker was created with [`Self::every()`] while a [`MockClock`](super::MockClock)
+    /// is alive then the next tick is moved to one interval after `after` has passed on the clock.
+    fn reset_after(&mut self, after: Duration) {
+        self.record_reset(MockTickerReset::After(after));
+    }
+}
+
+#[cfg(feature = "std")]
+impl Ticker for Arc<MockTicker> {
+    /// Create a shared [`MockTicker`] with [`MockTicker::every()`].
+    fn every(duration: Duration) -> Self {
+        Self::new(MockTicker::every(duration))
+    }
+
+    /// Count the call on the shared [`MockTicker`], see [`MockTicker::next()`].
+    fn next(&mut self) -> impl Future<Output = ()> + '_ {
+        self.tick()
+    }
+
+    /// Record the reset on the shared [`MockTicker`], see [`MockTicker::reset()`].
+    fn reset(&mut self) {
+        self.record_reset(MockTickerReset::Reset);
+    }
+
+    /// Record the reset on the shared [`MockTicker`], see [`MockTicker::reset_at()`].
+    fn reset_at(&mut self, deadline: Instant) {
+        self.record_reset(MockTickerReset::At(deadline));
+    }
+
+    /// Record the reset on the shared [`MockTicker`], see [`MockTicker::reset_after()`].
+    fn reset_after(&mut self, after: Duration) {
+        self.record_reset(MockTickerReset::After(after));
+    }
+}
+
+/// Each item of the stream is a call to [`MockTicker::next()`], so it is counted and resolves in
+/// the same way. The stream never ends.
+///
+/// [`embassy_time::Ticker`] is also a [`Stream`] with the same items, so production code can
+/// require `T: Ticker + Stream<Item = ()>` to use stream combinators on either ticker.
+///
+/// # Examples
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::time::{MockTicker, Ticker};
+/// use futures_core::Stream;
+/// use futures_util::StreamExt;
+///
+/// async fn production_code<T: Ticker + Stream<Item = ()> + Unpin>(ticker: &mut T) -> usize {
+///     ticker.take(3).count().await
+/// }
+///
+/// let mut ticker = MockTicker::expect(3);
+/// assert_eq!(block_on(production_code(&mut ticker)), 3);
+///
+/// ticker.done().unwrap();
+/// ```
+#[cfg(feature = "stream")]
+impl Stream for MockTicker {
+    type Item = ();
+
+    /// Start a new tick if there isn't one pending and poll it.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let mut tick = this.pending_tick.take().unwrap_or_else(|| this.tick());
+        let poll = Pin::new(&mut tick).poll(cx);
+        if poll.is_pending() {
+            this.pending_tick = Some(tick);
+        }
+
+        poll.map(Some)
+    }
+}
+
+#[cfg(feature = "stream")]
+impl FusedStream for MockTicker {
+    /// Always `false` as the stream never ends.
+    fn is_terminated(&self) -> bool {
+        false
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::time::MockClock;
+    use core::{pin::pin, task::Context};
     use embassy_futures::block_on;
+    use futures_util::task::noop_waker_ref;
 
     #[test]
     fn can_tick_once_just_drop() {
@@ -340,4 +1382,362 @@ mod tests {
         });
         assert_eq!(res, expected);
     }
+
+    #[test]
+    fn expect_every_returns_ok() {
+        MockTicker::expect_every(Duration::from_secs(1), 2);
+        let mut ticker = MockTicker::every(Duration::from_secs(1));
+        block_on(ticker.next());
+        block_on(ticker.next());
+
+        assert_eq!(ticker.done(), Ok(()));
+    }
+
+    #[test]
+    fn expect_every_wrong_interval_returns_err() {
+        MockTicker::expect_every(Duration::from_secs(1), 0);
+        let ticker = MockTicker::every(Duration::from_millis(100));
+
+        let expected = Err(MockTickerError::WrongInterval {
+            expected: Duration::from_secs(1).into(),
+            actual: Duration::from_millis(100),
+        });
+        assert_eq!(ticker.done(), expected);
+    }
+
+    #[test]
+    fn expect_every_accepts_duration_matcher() {
+        MockTicker::expect_every(
+            DurationMatcher::within(Duration::from_millis(100), Duration::from_millis(1)),
+            0,
+        );
+        let ticker = MockTicker::every(Duration::from_micros(100_500));
+
+        assert_eq!(ticker.done(), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to call next 2 time(s), actually called 1")]
+    fn expect_every_wrong_ticks_just_drop() {
+        MockTicker::expect_every(Duration::from_secs(1), 2);
+        let mut ticker = MockTicker::every(Duration::from_secs(1));
+        block_on(ticker.next());
+    }
+
+    #[test]
+    fn expect_every_applies_in_creation_order() {
+        MockTicker::expect_every(Duration::from_secs(1), 0);
+        MockTicker::expect_every(Duration::from_secs(2), 0);
+
+        let first = MockTicker::every(Duration::from_secs(1));
+        let second = MockTicker::every(Duration::from_secs(2));
+        let unexpected = MockTicker::every(Duration::from_secs(3));
+
+        assert_eq!(first.done(), Ok(()));
+        assert_eq!(second.done(), Ok(()));
+        drop(unexpected); // Not checked as there were no more expectations.
+    }
+
+    #[test]
+    fn script_resolves_each_tick_in_order() {
+        let mut ticker = MockTicker::script([MockTick::Pending(2), MockTick::Ready]);
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        {
+            let mut tick = pin!(ticker.next());
+            assert_eq!(tick.as_mut().poll(&mut cx), Poll::Pending);
+            assert_eq!(tick.as_mut().poll(&mut cx), Poll::Pending);
+            assert_eq!(tick.as_mut().poll(&mut cx), Poll::Ready(()));
+        }
+
+        assert_eq!(pin!(ticker.next()).poll(&mut cx), Poll::Ready(()));
+        assert_eq!(ticker.done(), Ok(()));
+    }
+
+    #[test]
+    fn script_pending_ticks_complete_with_block_on() {
+        let mut ticker = MockTicker::script([MockTick::Pending(3)]);
+
+        block_on(ticker.next());
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to call next 3 time(s), actually called 1")]
+    fn script_expects_a_tick_per_step() {
+        let mut ticker = MockTicker::script([MockTick::Ready; 3]);
+        block_on(ticker.next());
+    }
+
+    #[test]
+    fn shared_ticker_counts_ticks_from_all_clones() {
+        let ticker = Arc::new(MockTicker::expect(3));
+        let mut first = Arc::clone(&ticker);
+        let mut second = Arc::clone(&ticker);
+
+        block_on(first.next());
+        block_on(second.next());
+        block_on(first.next());
+        second.reset();
+
+        assert_eq!(ticker.times_called(), 3);
+        assert_eq!(ticker.reset_count(), 1);
+        drop((first, second));
+        assert_eq!(Arc::into_inner(ticker).unwrap().done(), Ok(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to call next 2 time(s), actually called 1")]
+    fn shared_ticker_checked_when_last_clone_dropped() {
+        let ticker = Arc::new(MockTicker::expect(2));
+        let mut clone = Arc::clone(&ticker);
+        drop(ticker);
+
+        block_on(clone.next());
+    }
+
+    #[test]
+    fn resets_are_counted_and_recorded() {
+        let mut ticker = MockTicker::expect(0);
+        ticker.reset();
+        ticker.reset_at(Instant::from_secs(2));
+        ticker.reset_after(Duration::from_millis(500));
+
+        assert_eq!(ticker.reset_count(), 3);
+        assert_eq!(
+            ticker.resets(),
+            [
+                MockTickerReset::Reset,
+                MockTickerReset::At(Instant::from_secs(2)),
+                MockTickerReset::After(Duration::from_millis(500)),
+            ]
+        );
+    }
+
+    #[test]
+    fn reset_rephases_clock_ticks() {
+        let clock = MockClock::new();
+        let mut ticker = MockTicker::every(Duration::from_secs(10));
+        clock.advance(Duration::from_secs(4));
+        ticker.reset_after(Duration::from_secs(1));
+
+        clock.block_on(ticker.next());
+
+        assert_eq!(Instant::from(clock.now()), Instant::from_secs(15));
+    }
+
+    #[test]
+    fn registry_records_tickers_created_by_trait() {
+        let mut ticker = MockTicker::every(Duration::from_secs(1));
+        block_on(ticker.next());
+        ticker.reset();
+        let _unrecorded = MockTicker::expect(0);
+
+        assert_eq!(
+            MockTicker::registered_tickers(),
+            [MockTickerRecord {
+                interval: Duration::from_secs(1),
+                ticks: 1,
+                resets: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn cleared_registry_ignores_older_tickers() {
+        let mut old = MockTicker::every(Duration::from_secs(1));
+        MockTicker::clear_registry();
+        let _new = MockTicker::every(Duration::from_secs(2));
+        block_on(old.next());
+
+        assert_eq!(MockTicker::registered_ticker_count(), 1);
+        let record = MockTicker::registered_ticker(0).unwrap();
+        assert_eq!(record.interval, Duration::from_secs(2));
+        assert_eq!(record.ticks, 0);
+    }
+
+    #[test]
+    fn missed_ticks_skip_script_and_clock() {
+        let clock = MockClock::new();
+        let mut ticker = MockTicker::script([MockTick::Pending(1), MockTick::Ready]);
+        let mut cx = Context::from_waker(noop_waker_ref());
+        ticker.miss_ticks(1);
+
+        assert_eq!(pin!(ticker.next()).poll(&mut cx), Poll::Ready(()));
+        {
+            let mut tick = pin!(ticker.next());
+            assert_eq!(tick.as_mut().poll(&mut cx), Poll::Pending);
+            assert_eq!(tick.as_mut().poll(&mut cx), Poll::Ready(()));
+        }
+        assert_eq!(pin!(ticker.next()).poll(&mut cx), Poll::Ready(()));
+
+        assert_eq!(clock.now(), MockInstant::MIN);
+        // The missed tick is still a call to `next()` on top of the steps of the script.
+        let expected = Err(MockTickerError::WrongNumberOfTicks {
+            expected: 2,
+            actual: 3,
+        });
+        assert_eq!(ticker.done(), expected);
+    }
+
+    #[test]
+    fn ticker_continues_on_schedule_after_missed_ticks() {
+        let clock = MockClock::new();
+        let mut ticker = MockTicker::every(Duration::from_secs(1));
+        ticker.miss_ticks(3);
+
+        clock.block_on(async {
+            for _ in 0..4 {
+                ticker.next().await;
+            }
+        });
+
+        assert_eq!(Instant::from(clock.now()), Instant::from_secs(1));
+    }
+
+    #[test]
+    fn builder_defaults_match_expect() {
+        let ticker = MockTicker::builder().build();
+
+        assert_eq!(ticker.interval(), None);
+        ticker.done().unwrap();
+    }
+
+    #[test]
+    fn builder_ticks_default_to_script_length() {
+        let mut ticker = MockTicker::builder()
+            .script([MockTick::Ready, MockTick::Ready])
+            .build();
+        block_on(ticker.next());
+
+        let expected = Err(MockTickerError::WrongNumberOfTicks {
+            expected: 2,
+            actual: 1,
+        });
+        assert_eq!(ticker.done(), expected);
+    }
+
+    #[test]
+    fn builder_interval_ticks_with_clock() {
+        let clock = MockClock::new();
+        let mut ticker = MockTicker::builder()
+            .ticks(2)
+            .interval(Duration::from_millis(250))
+            .build();
+
+        clock.block_on(async {
+            ticker.next().await;
+            ticker.next().await;
+        });
+
+        assert_eq!(Instant::from(clock.now()), Instant::from_millis(500));
+        ticker.done().unwrap();
+    }
+
+    #[test]
+    fn shared_ticker_can_tick_on_another_thread() {
+        let ticker = Arc::new(MockTicker::expect(2));
+        let mut shared = Arc::clone(&ticker);
+
+        std::thread::spawn(move || {
+            block_on(shared.next());
+            block_on(shared.next());
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(ticker.times_called(), 2);
+        Arc::into_inner(ticker).unwrap().done().unwrap();
+    }
+
+    #[test]
+    fn shared_ticker_counts_every_tick_from_many_threads() {
+        let ticker = Arc::new(MockTicker::expect(400));
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let mut shared = Arc::clone(&ticker);
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        block_on(shared.next());
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(ticker.times_called(), 400);
+        Arc::into_inner(ticker).unwrap().done().unwrap();
+    }
+
+    #[test]
+    fn jitter_does_not_move_schedule() {
+        let clock = MockClock::new();
+        let mut ticker = MockTicker::builder()
+            .ticks(3)
+            .interval(Duration::from_secs(1))
+            .jitter([MockTickOffset::Early(Duration::from_millis(100))])
+            .build();
+
+        for expected in [900, 1900, 2900] {
+            clock.block_on(ticker.next());
+            assert_eq!(clock.now(), MockInstant::from_millis(expected));
+        }
+    }
+
+    #[test]
+    fn early_drift_shortens_interval() {
+        let clock = MockClock::new();
+        let mut ticker = MockTicker::builder()
+            .ticks(2)
+            .interval(Duration::from_secs(1))
+            .drift(MockTickOffset::Early(Duration::from_millis(10)))
+            .build();
+
+        clock.block_on(ticker.next());
+        assert_eq!(clock.now(), MockInstant::from_millis(990));
+        clock.block_on(ticker.next());
+        assert_eq!(clock.now(), MockInstant::from_millis(1980));
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn stream_counts_one_call_per_item() {
+        let mut ticker = MockTicker::script([MockTick::Pending(2), MockTick::Ready]);
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        assert_eq!(Pin::new(&mut ticker).poll_next(&mut cx), Poll::Pending);
+        assert_eq!(Pin::new(&mut ticker).poll_next(&mut cx), Poll::Pending);
+        assert_eq!(
+            Pin::new(&mut ticker).poll_next(&mut cx),
+            Poll::Ready(Some(()))
+        );
+        assert_eq!(
+            Pin::new(&mut ticker).poll_next(&mut cx),
+            Poll::Ready(Some(()))
+        );
+
+        assert_eq!(ticker.times_called(), 2);
+        ticker.done().unwrap();
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn stream_ticks_on_clock() {
+        use futures_util::StreamExt;
+
+        let clock = MockClock::new();
+        let mut ticker = MockTicker::builder()
+            .ticks(2)
+            .interval(Duration::from_secs(1))
+            .build();
+
+        clock.block_on(StreamExt::next(&mut ticker));
+        assert_eq!(clock.now(), MockInstant::from_secs(1));
+        clock.block_on(StreamExt::next(&mut ticker));
+        assert_eq!(clock.now(), MockInstant::from_secs(2));
+
+        ticker.done().unwrap();
+    }
 }
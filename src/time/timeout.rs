@@ -0,0 +1,307 @@
+//! Traits and mocked implementations to allow unit testing functions that use
+//! [`embassy_time::with_timeout()`] and [`embassy_time::with_deadline()`].
+//!
+//! # Examples
+//! ```
+//! # #![feature(type_alias_impl_trait)]
+//! #
+//! use embassy_mock::time::WithTimeout;
+//! use embassy_time::Duration;
+//!
+//! // Generic over the `WithTimeout` trait
+//! async fn read_or_default<T: WithTimeout>(read: impl core::future::Future<Output = u8>) -> u8 {
+//!     T::with_timeout(Duration::from_millis(100), read)
+//!         .await
+//!         .unwrap_or_default()
+//! }
+//!
+//! // The real task that runs on the Embassy executor.
+//! #[embassy_executor::task]
+//! async fn some_task() {
+//!     read_or_default::<embassy_time::Timer>(async { 42 }).await;
+//! }
+//!
+//! # test_read_times_out();
+//! // The unit tests that use the `MockTimer`.
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::*;
+//! # }
+//!     use embassy_futures::block_on;
+//!     use embassy_mock::time::{MockTimeout, MockTimer};
+//!
+//!     #[test]
+//!     # fn hidden_fake_test(){}
+//!     fn test_read_times_out() {
+//!         MockTimer::set_timeout(MockTimeout::Expire);
+//!
+//!         assert_eq!(block_on(read_or_default::<MockTimer>(async { 42 })), 0);
+//!     }
+//! # mod closing {
+//! }
+//! ```
+
+use super::{MockTimer, Timer};
+use core::{
+    future::{poll_fn, Future},
+    pin::{pin, Pin},
+    task::Poll,
+};
+use embassy_time::{Duration, Instant, TimeoutError, Timer as EmbassyTimer};
+#[cfg(feature = "std")]
+use std::cell::Cell;
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    /// How [`WithTimeout`] behaves for [`MockTimer`] on the current thread.
+    static TIMEOUT: Cell<MockTimeout> = const { Cell::new(MockTimeout::Race) };
+}
+
+/// The trait to replace [`embassy_time::with_timeout()`] and [`embassy_time::with_deadline()`] in
+/// code to allow the [`MockTimer`] to be used in its place for tests.
+///
+/// This is implemented for the timer types so code that is generic over [`Timer`] can also use
+/// the timeout of the same timer type.
+pub trait WithTimeout {
+    /// Wrapper for [`embassy_time::with_timeout()`].
+    fn with_timeout<F: Future>(
+        timeout: Duration,
+        fut: F,
+    ) -> impl Future<Output = Result<F::Output, TimeoutError>>;
+
+    /// Wrapper for [`embassy_time::with_deadline()`].
+    fn with_deadline<F: Future>(
+        at: Instant,
+        fut: F,
+    ) -> impl Future<Output = Result<F::Output, TimeoutError>>;
+}
+
+impl WithTimeout for EmbassyTimer {
+    /// Runs a given future with a timeout.
+    ///
+    /// If the future completes before the timeout, its output is returned. Otherwise, on timeout,
+    /// work on the future is stopped (`poll` is no longer called), the future is dropped and
+    /// `Err(TimeoutError)` is returned.
+    fn with_timeout<F: Future>(
+        timeout: Duration,
+        fut: F,
+    ) -> impl Future<Output = Result<F::Output, TimeoutError>> {
+        embassy_time::with_timeout(timeout, fut)
+    }
+
+    /// Runs a given future with a deadline time.
+    ///
+    /// If the future completes before the deadline, its output is returned. Otherwise, on timeout,
+    /// work on the future is stopped (`poll` is no longer called), the future is dropped and
+    /// `Err(TimeoutError)` is returned.
+    fn with_deadline<F: Future>(
+        at: Instant,
+        fut: F,
+    ) -> impl Future<Output = Result<F::Output, TimeoutError>> {
+        embassy_time::with_deadline(at, fut)
+    }
+}
+
+/// How [`WithTimeout`] behaves for [`MockTimer`], set with [`MockTimer::set_timeout()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockTimeout {
+    /// Race the future against a [`MockTimer`] created with [`Timer::after()`] or [`Timer::at()`],
+    /// the same as the real implementation. The future is polled first so it completes if it is
+    /// ready, otherwise the outcome depends on how the [`MockTimer`] is configured, for example
+    /// with a [`MockClock`](super::MockClock). This is the default.
+    Race,
+
+    /// The future is always run to completion, the timeout never fires.
+    Complete,
+
+    /// The timeout or deadline always fires immediately, the future is dropped without being
+    /// polled.
+    Expire,
+}
+
+impl MockTimer {
+    /// Set how [`WithTimeout`] behaves for [`MockTimer`] on the current thread from now on.
+    ///
+    /// This lets a test choose whether the future completes or the timeout fires to test both
+    /// paths of the code under test.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTimeout, MockTimer, WithTimeout};
+    /// use embassy_time::{Duration, TimeoutError};
+    ///
+    /// MockTimer::set_timeout(MockTimeout::Complete);
+    /// let res = block_on(MockTimer::with_timeout(Duration::from_secs(1), async { 42 }));
+    /// assert_eq!(res, Ok(42));
+    ///
+    /// MockTimer::set_timeout(MockTimeout::Expire);
+    /// let res = block_on(MockTimer::with_timeout(Duration::from_secs(1), async { 42 }));
+    /// assert_eq!(res, Err(TimeoutError));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn set_timeout(timeout: MockTimeout) {
+        TIMEOUT.with(|behaviour| behaviour.set(timeout));
+    }
+
+    /// How [`WithTimeout`] behaves for [`MockTimer`] on the current thread.
+    fn timeout() -> MockTimeout {
+        #[cfg(feature = "std")]
+        {
+            TIMEOUT.with(Cell::get)
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            MockTimeout::Race
+        }
+    }
+}
+
+/// Run `fut` with the `timer` as its timeout, as configured by `behaviour`.
+async fn run_with_timer<F: Future>(
+    behaviour: MockTimeout,
+    mut timer: MockTimer,
+    fut: F,
+) -> Result<F::Output, TimeoutError> {
+    // The timeout is part of awaiting `fut`, even when it isn't polled.
+    #[cfg(feature = "std")]
+    timer.mark_awaited();
+
+    match behaviour {
+        MockTimeout::Complete => Ok(fut.await),
+        MockTimeout::Expire => Err(TimeoutError),
+        MockTimeout::Race => {
+            let mut fut = pin!(fut);
+            poll_fn(|cx| {
+                if let Poll::Ready(output) = fut.as_mut().poll(cx) {
+                    return Poll::Ready(Ok(output));
+                }
+
+                Pin::new(&mut timer).poll(cx).map(|()| Err(TimeoutError))
+            })
+            .await
+        }
+    }
+}
+
+impl WithTimeout for MockTimer {
+    /// Run the future with a timeout as configured with [`MockTimer::set_timeout()`].
+    ///
+    /// The timeout is created with [`Timer::after()`] so its [`Duration`] is recorded the same
+    /// as any other [`MockTimer`].
+    fn with_timeout<F: Future>(
+        timeout: Duration,
+        fut: F,
+    ) -> impl Future<Output = Result<F::Output, TimeoutError>> {
+        run_with_timer(Self::timeout(), Self::after(timeout), fut)
+    }
+
+    /// Run the future with a deadline as configured with [`MockTimer::set_timeout()`].
+    ///
+    /// The deadline is created with [`Timer::at()`] so its [`Instant`] is recorded the same as any
+    /// other [`MockTimer`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTimeout, MockTimer, WithTimeout};
+    /// use embassy_time::{Instant, TimeoutError};
+    ///
+    /// MockTimer::set_timeout(MockTimeout::Expire);
+    /// let res = block_on(MockTimer::with_deadline(Instant::from_secs(5), async { 42 }));
+    ///
+    /// assert_eq!(res, Err(TimeoutError));
+    /// assert_eq!(MockTimer::recorded_deadlines(), [Instant::from_secs(5)]);
+    /// ```
+    fn with_deadline<F: Future>(
+        at: Instant,
+        fut: F,
+    ) -> impl Future<Output = Result<F::Output, TimeoutError>> {
+        run_with_timer(Self::timeout(), Self::at(at), fut)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::future::pending;
+    use embassy_futures::block_on;
+
+    #[test]
+    fn race_returns_ready_future() {
+        let res = block_on(MockTimer::with_timeout(Duration::from_secs(1), async {
+            42
+        }));
+
+        assert_eq!(res, Ok(42));
+    }
+
+    #[test]
+    fn race_times_out_pending_future() {
+        let res = block_on(MockTimer::with_timeout(
+            Duration::from_secs(1),
+            pending::<()>(),
+        ));
+
+        assert_eq!(res, Err(TimeoutError));
+    }
+
+    #[test]
+    fn complete_waits_for_future() {
+        MockTimer::set_timeout(MockTimeout::Complete);
+        let res = block_on(MockTimer::with_timeout(Duration::from_secs(1), async {
+            MockTimer::after(Duration::from_secs(5))
+                .with_pending_polls(3)
+                .await;
+            42
+        }));
+
+        assert_eq!(res, Ok(42));
+    }
+
+    #[test]
+    fn expire_times_out_ready_future() {
+        MockTimer::set_timeout(MockTimeout::Expire);
+        let res = block_on(MockTimer::with_timeout(Duration::from_secs(1), async {
+            42
+        }));
+
+        assert_eq!(res, Err(TimeoutError));
+    }
+
+    #[test]
+    fn timeout_duration_is_recorded() {
+        let _ = block_on(MockTimer::with_timeout(Duration::from_secs(3), async {}));
+
+        assert_eq!(MockTimer::recorded_durations(), [Duration::from_secs(3)]);
+    }
+
+    #[test]
+    fn deadline_race_returns_ready_future() {
+        let res = block_on(MockTimer::with_deadline(Instant::from_secs(1), async {
+            42
+        }));
+
+        assert_eq!(res, Ok(42));
+    }
+
+    #[test]
+    fn deadline_expire_times_out_ready_future() {
+        MockTimer::set_timeout(MockTimeout::Expire);
+        let res = block_on(MockTimer::with_deadline(Instant::from_secs(1), async {
+            42
+        }));
+
+        assert_eq!(res, Err(TimeoutError));
+    }
+
+    #[test]
+    fn deadline_is_recorded() {
+        let _ = block_on(MockTimer::with_deadline(Instant::from_secs(3), async {}));
+
+        assert_eq!(MockTimer::recorded_deadlines(), [Instant::from_secs(3)]);
+        assert!(MockTimer::recorded_durations().is_empty());
+    }
+}
@@ -38,21 +38,140 @@
 //! }
 //! ```
 
+#[cfg(feature = "registry")]
+use super::registry::{self, Slot};
 use core::{
     future::Future,
     pin::Pin,
     task::{Context, Poll},
 };
-use embassy_time::{Duration, Timer as EmbassyTimer};
+use embassy_time::{Duration, Instant, Timer as EmbassyTimer};
+#[cfg(feature = "std")]
+use {
+    super::{clock, DurationMatcher, MockInstant},
+    crate::waiting,
+    snafu::prelude::*,
+    std::{
+        cell::{Cell, RefCell},
+        vec::Vec,
+    },
+};
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    /// The [`Duration`] of every [`MockTimer`] created on the current thread, in creation order.
+    static RECORDED_DURATIONS: RefCell<Vec<Duration>> = const { RefCell::new(Vec::new()) };
+
+    /// The [`Instant`] of every [`MockTimer`] created with [`Timer::at()`] on the current thread,
+    /// in creation order.
+    static RECORDED_DEADLINES: RefCell<Vec<Instant>> = const { RefCell::new(Vec::new()) };
+
+    /// The [`Duration`] of every call to [`Timer::block_for()`] for [`MockTimer`] on the current
+    /// thread, in call order.
+    static RECORDED_BLOCKS: RefCell<Vec<Duration>> = const { RefCell::new(Vec::new()) };
+
+    /// The number of times every [`MockTimer`] that completed on the current thread was polled,
+    /// in completion order.
+    static RECORDED_POLLS: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+
+    /// The number of polls that every [`MockTimer`] created on the current thread returns
+    /// [`Poll::Pending`] for before completing, or [`None`] if they never complete.
+    static PENDING_POLLS: Cell<Option<usize>> = const { Cell::new(Some(0)) };
+
+    /// The number of [`MockTimer`]s created with [`Timer::after()`] or [`Timer::at()`] on the
+    /// current thread that haven't been polled to completion.
+    static INCOMPLETE_TIMERS: Cell<usize> = const { Cell::new(0) };
+
+    /// The number of [`MockTimer`]s created with [`Timer::after()`] or [`Timer::at()`] on the
+    /// current thread that have never been polled, not counting the timeouts of
+    /// [`WithTimeout`](super::WithTimeout).
+    static UNAWAITED_TIMERS: Cell<usize> = const { Cell::new(0) };
+
+    /// Incremented when [`INCOMPLETE_TIMERS`] is cleared so the timers created before then are no
+    /// longer counted.
+    static INCOMPLETE_GENERATION: Cell<usize> = const { Cell::new(0) };
+}
+
+#[cfg(feature = "registry")]
+registry::registry! {
+    /// The records of the [`MockTimer`]s created with [`Timer::after()`] or [`Timer::at()`].
+    static REGISTRY: MockTimerRecord;
+}
 
 /// The trait to replace the [`embassy_time::Timer`] in code to allow the [`MockTimer`] to
 /// be used in its place for tests.
-pub trait Timer: Future {
+///
+/// The timer is the future itself, so its type can be named and a pending timer can be kept in a
+/// struct field, for example in a state machine that is polled manually.
+///
+/// # Examples
+///
+/// ```
+/// use core::{future::Future, pin::Pin, task::{Context, Poll}};
+/// use embassy_mock::time::{MockTimer, Timer};
+/// use embassy_time::Duration;
+/// use futures_util::task::noop_waker_ref;
+///
+/// // Generic over the `Timer` trait
+/// struct Debouncer<T: Timer> {
+///     settle: Option<T>,
+/// }
+///
+/// impl<T: Timer + Unpin> Debouncer<T> {
+///     fn on_edge(&mut self) {
+///         self.settle = Some(T::after(Duration::from_millis(20)));
+///     }
+///
+///     fn poll_settled(&mut self, cx: &mut Context<'_>) -> bool {
+///         match self.settle.as_mut().map(|timer| Pin::new(timer).poll(cx)) {
+///             Some(Poll::Ready(())) => {
+///                 self.settle = None;
+///                 true
+///             }
+///             _ => false,
+///         }
+///     }
+/// }
+///
+/// let mut debouncer = Debouncer::<MockTimer> { settle: None };
+/// let mut cx = Context::from_waker(noop_waker_ref());
+///
+/// assert!(!debouncer.poll_settled(&mut cx));
+/// debouncer.on_edge();
+/// assert!(debouncer.poll_settled(&mut cx));
+/// ```
+pub trait Timer: Future<Output = ()> {
+    /// Wrapper for [`embassy_time::Timer::at()`].
+    fn at(expires_at: Instant) -> Self;
+
     /// Wrapper for [`embassy_time::Timer::after()`].
     fn after(duration: Duration) -> Self;
+
+    /// Wrapper for [`embassy_time::Timer::after_ticks()`].
+    fn after_ticks(ticks: u64) -> Self;
+
+    /// Wrapper for [`embassy_time::Timer::after_nanos()`].
+    fn after_nanos(nanos: u64) -> Self;
+
+    /// Wrapper for [`embassy_time::Timer::after_micros()`].
+    fn after_micros(micros: u64) -> Self;
+
+    /// Wrapper for [`embassy_time::Timer::after_millis()`].
+    fn after_millis(millis: u64) -> Self;
+
+    /// Wrapper for [`embassy_time::Timer::after_secs()`].
+    fn after_secs(secs: u64) -> Self;
+
+    /// Wrapper for [`embassy_time::block_for()`].
+    fn block_for(duration: Duration);
 }
 
 impl Timer for EmbassyTimer {
+    /// Expire at specified [`Instant`].
+    fn at(expires_at: Instant) -> Self {
+        Self::at(expires_at)
+    }
+
     /// Expire after specified [`Duration`].
     /// This can be used as a sleep abstraction.
     ///
@@ -72,11 +191,48 @@ impl Timer for EmbassyTimer {
     fn after(duration: Duration) -> Self {
         Self::after(duration)
     }
+
+    /// Expire after the specified number of ticks.
+    fn after_ticks(ticks: u64) -> Self {
+        Self::after_ticks(ticks)
+    }
+
+    /// Expire after the specified number of nanoseconds.
+    fn after_nanos(nanos: u64) -> Self {
+        Self::after_nanos(nanos)
+    }
+
+    /// Expire after the specified number of microseconds.
+    fn after_micros(micros: u64) -> Self {
+        Self::after_micros(micros)
+    }
+
+    /// Expire after the specified number of milliseconds.
+    fn after_millis(millis: u64) -> Self {
+        Self::after_millis(millis)
+    }
+
+    /// Expire after the specified number of seconds.
+    fn after_secs(secs: u64) -> Self {
+        Self::after_secs(secs)
+    }
+
+    /// Blocks for at least `duration`.
+    fn block_for(duration: Duration) {
+        embassy_time::block_for(duration)
+    }
 }
 
 /// A mocked version of [`embassy_time::Timer`] that can be used in its place for unit tests.
 ///
-/// This mocked version just immediately returns [`Poll::Ready`] when `await`'ed on.
+/// This mocked version by default immediately returns [`Poll::Ready`] when `await`'ed on, or if it
+/// was created while a [`MockClock`](super::MockClock) is alive, once the clock has reached the
+/// timer's deadline. It can instead be made to return [`Poll::Pending`] a number of times first
+/// with [`Self::with_pending_polls()`] or [`Self::set_pending_polls()`], or to never complete with
+/// [`Self::never()`] or [`Self::set_pending_forever()`]. The [`Duration`] or [`Instant`] it was
+/// created with is kept and can be read back with [`Self::duration()`] or [`Self::deadline()`].
+/// With the `std` feature enabled these are also recorded for every [`MockTimer`] created on the
+/// current thread, see [`Self::recorded_durations()`] and [`Self::recorded_deadlines()`].
 ///
 /// # Examples
 ///
@@ -86,21 +242,919 @@ impl Timer for EmbassyTimer {
 /// use embassy_time::Duration;
 ///
 /// let timer = MockTimer::after(Duration::from_secs(1));
+/// assert_eq!(timer.duration(), Some(Duration::from_secs(1)));
 /// block_on(timer);
 /// ```
-#[derive(Debug, PartialEq, Eq)]
-pub struct MockTimer;
+#[derive(Debug)]
+pub struct MockTimer {
+    /// When this timer was requested to expire.
+    expiry: Expiry,
+
+    /// The number of remaining polls that return [`Poll::Pending`] before completing, or [`None`]
+    /// if this timer never completes.
+    pending_polls: Option<usize>,
+
+    /// The number of times this timer has been polled.
+    polls: usize,
+
+    /// Has this timer been polled to completion.
+    is_complete: bool,
+
+    /// The time of the [`MockClock`](super::MockClock) this timer waits for before completing,
+    /// or [`None`] if it was created without a [`MockClock`](super::MockClock).
+    #[cfg(feature = "std")]
+    clock_deadline: Option<MockInstant>,
+
+    /// The generation of the incomplete timers of the current thread this timer is counted in
+    /// until it completes, or [`None`] if it isn't counted.
+    #[cfg(feature = "std")]
+    incomplete_generation: Option<usize>,
+
+    /// The generation of the incomplete timers of the current thread this timer is counted as
+    /// not awaited in until it is first polled, or [`None`] if it isn't counted.
+    #[cfg(feature = "std")]
+    unawaited_generation: Option<usize>,
+
+    /// The position of the record of this timer in the registry, or [`None`] if it isn't
+    /// recorded.
+    #[cfg(feature = "registry")]
+    registry_slot: Option<Slot>,
+}
+
+/// The record of a [`MockTimer`] created with [`Timer::after()`] or [`Timer::at()`], kept in the
+/// registry when the `registry` feature is enabled.
+///
+/// See [`MockTimer::registered_timers()`].
+#[cfg(feature = "registry")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MockTimerRecord {
+    /// The [`Duration`] the timer was created with, or [`None`] if it was created with
+    /// [`Timer::at()`].
+    pub duration: Option<Duration>,
+
+    /// The [`Instant`] the timer was created with, or [`None`] if it was created with
+    /// [`Timer::after()`].
+    pub deadline: Option<Instant>,
+
+    /// The number of times the timer has been polled.
+    pub polls: usize,
+
+    /// Has the timer been polled to completion.
+    pub is_complete: bool,
+}
+
+impl PartialEq for MockTimer {
+    /// Compare how the timers were created and configured, ignoring how they have been polled
+    /// and their place in the registry.
+    fn eq(&self, other: &Self) -> bool {
+        #[cfg(feature = "std")]
+        if self.clock_deadline != other.clock_deadline {
+            return false;
+        }
+
+        self.expiry == other.expiry && self.pending_polls == other.pending_polls
+    }
+}
+
+impl Eq for MockTimer {}
+
+/// When a [`MockTimer`] was requested to expire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Expiry {
+    /// Created with [`Timer::after()`] or one of its convenience wrappers.
+    After(Duration),
+
+    /// Created with [`Timer::at()`].
+    At(Instant),
+
+    /// Created with [`MockTimer::never()`].
+    Never,
+}
+
+impl Expiry {
+    /// The [`Duration`] of [`Expiry::After`].
+    const fn duration(self) -> Option<Duration> {
+        match self {
+            Self::After(duration) => Some(duration),
+            Self::At(_) | Self::Never => None,
+        }
+    }
+
+    /// The [`Instant`] of [`Expiry::At`].
+    const fn deadline(self) -> Option<Instant> {
+        match self {
+            Self::After(_) | Self::Never => None,
+            Self::At(deadline) => Some(deadline),
+        }
+    }
+}
+
+impl MockTimer {
+    /// Create a [`MockTimer`] with the given [`Expiry`], using the pending polls configured for
+    /// the current thread.
+    fn new(expiry: Expiry) -> Self {
+        #[cfg(feature = "std")]
+        let pending_polls = PENDING_POLLS.with(Cell::get);
+        #[cfg(not(feature = "std"))]
+        let pending_polls = Some(0);
+        #[cfg(feature = "std")]
+        let generation = INCOMPLETE_GENERATION.with(|generation| {
+            INCOMPLETE_TIMERS.with(|count| count.set(count.get().checked_add(1).unwrap()));
+            UNAWAITED_TIMERS.with(|count| count.set(count.get().checked_add(1).unwrap()));
+            generation.get()
+        });
+
+        Self {
+            expiry,
+            pending_polls,
+            polls: 0,
+            is_complete: false,
+            #[cfg(feature = "std")]
+            clock_deadline: Self::clock_deadline(expiry),
+            #[cfg(feature = "std")]
+            incomplete_generation: Some(generation),
+            #[cfg(feature = "std")]
+            unawaited_generation: Some(generation),
+            #[cfg(feature = "registry")]
+            registry_slot: registry::with(&REGISTRY, |registry| {
+                registry.register(MockTimerRecord {
+                    duration: expiry.duration(),
+                    deadline: expiry.deadline(),
+                    polls: 0,
+                    is_complete: false,
+                })
+            }),
+        }
+    }
+
+    /// The time of the [`MockClock`](super::MockClock) that a timer with the given [`Expiry`]
+    /// should wait for, or [`None`] if there is no [`MockClock`](super::MockClock).
+    #[cfg(feature = "std")]
+    fn clock_deadline(expiry: Expiry) -> Option<MockInstant> {
+        if !clock::is_attached() {
+            return None;
+        }
+
+        match expiry {
+            Expiry::After(duration) => Some(clock::now() + duration),
+            Expiry::At(deadline) => Some(deadline.into()),
+            Expiry::Never => None,
+        }
+    }
+
+    /// Make this [`MockTimer`] return [`Poll::Pending`] for the given number of polls before
+    /// returning [`Poll::Ready`].
+    ///
+    /// The waker is woken every time [`Poll::Pending`] is returned so the timer is polled again.
+    /// This can be used to check that code doesn't assume a timer completes on the first poll.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::{future::Future, pin::pin, task::{Context, Poll}};
+    /// use embassy_mock::time::{MockTimer, Timer};
+    /// use embassy_time::Duration;
+    /// use futures_util::task::noop_waker_ref;
+    ///
+    /// let mut cx = Context::from_waker(noop_waker_ref());
+    /// let mut timer = pin!(MockTimer::after(Duration::from_secs(1)).with_pending_polls(2));
+    ///
+    /// assert_eq!(timer.as_mut().poll(&mut cx), Poll::Pending);
+    /// assert_eq!(timer.as_mut().poll(&mut cx), Poll::Pending);
+    /// assert_eq!(timer.as_mut().poll(&mut cx), Poll::Ready(()));
+    /// ```
+    #[must_use]
+    pub const fn with_pending_polls(mut self, polls: usize) -> Self {
+        self.pending_polls = Some(polls);
+        self
+    }
+
+    /// Create a [`MockTimer`] that never completes, it always returns [`Poll::Pending`].
+    ///
+    /// This allows testing the path where a timer races against another future and doesn't fire,
+    /// for example in `embassy_futures::select::select()`. As the timer never completes the
+    /// waker is never woken.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::{
+    ///     block_on,
+    ///     select::{select, Either},
+    /// };
+    /// use embassy_mock::time::MockTimer;
+    ///
+    /// let res = block_on(select(MockTimer::never(), async { 42 }));
+    ///
+    /// assert!(matches!(res, Either::Second(42)));
+    /// ```
+    pub const fn never() -> Self {
+        Self {
+            expiry: Expiry::Never,
+            pending_polls: None,
+            polls: 0,
+            is_complete: false,
+            #[cfg(feature = "std")]
+            clock_deadline: None,
+            #[cfg(feature = "std")]
+            incomplete_generation: None,
+            #[cfg(feature = "std")]
+            unawaited_generation: None,
+            #[cfg(feature = "registry")]
+            registry_slot: None,
+        }
+    }
+
+    /// Make every [`MockTimer`] created on the current thread from now on return
+    /// [`Poll::Pending`] for the given number of polls before returning [`Poll::Ready`].
+    ///
+    /// This is the same as [`Self::with_pending_polls()`] but also applies to the timers created
+    /// by the code under test with [`Timer::after()`] or [`Timer::at()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTimer, Timer};
+    /// use embassy_time::Duration;
+    ///
+    /// async fn production_code<T: Timer>() {
+    ///     T::after(Duration::from_secs(1)).await;
+    /// }
+    ///
+    /// MockTimer::set_pending_polls(3);
+    /// block_on(production_code::<MockTimer>()); // The timer is pending for three polls.
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn set_pending_polls(polls: usize) {
+        PENDING_POLLS.with(|pending_polls| pending_polls.set(Some(polls)));
+    }
+
+    /// Make every [`MockTimer`] created on the current thread from now on never complete, the
+    /// same as a timer created with [`Self::never()`].
+    ///
+    /// This allows forcing the "timer did not fire" path of code under test that creates its own
+    /// timers with [`Timer::after()`] or [`Timer::at()`]. It is undone by calling
+    /// [`Self::set_pending_polls()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::{
+    ///     block_on,
+    ///     select::{select, Either},
+    /// };
+    /// use embassy_mock::time::{MockTimer, Timer};
+    /// use embassy_time::Duration;
+    ///
+    /// async fn production_code<T: Timer>() -> bool {
+    ///     let res = select(T::after(Duration::from_secs(1)), async { 42 }).await;
+    ///     matches!(res, Either::First(_))
+    /// }
+    ///
+    /// MockTimer::set_pending_forever();
+    /// let timed_out = block_on(production_code::<MockTimer>());
+    ///
+    /// assert!(!timed_out);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn set_pending_forever() {
+        PENDING_POLLS.with(|pending_polls| pending_polls.set(None));
+    }
+
+    /// The [`Duration`] that this [`MockTimer`] was created with, or [`None`] if it was created
+    /// with [`Timer::at()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{MockTimer, Timer};
+    /// use embassy_time::Duration;
+    ///
+    /// let timer = MockTimer::after(Duration::from_millis(250));
+    ///
+    /// assert_eq!(timer.duration(), Some(Duration::from_millis(250)));
+    /// ```
+    pub const fn duration(&self) -> Option<Duration> {
+        self.expiry.duration()
+    }
+
+    /// The [`Instant`] that this [`MockTimer`] was created with, or [`None`] if it was created
+    /// with [`Timer::after()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{MockTimer, Timer};
+    /// use embassy_time::Instant;
+    ///
+    /// let timer = MockTimer::at(Instant::from_secs(10));
+    ///
+    /// assert_eq!(timer.deadline(), Some(Instant::from_secs(10)));
+    /// assert_eq!(timer.duration(), None);
+    /// ```
+    pub const fn deadline(&self) -> Option<Instant> {
+        self.expiry.deadline()
+    }
+
+    /// The number of times this [`MockTimer`] has been polled, including the poll that completed
+    /// it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use core::{future::Future, pin::pin, task::Context};
+    /// use embassy_mock::time::{MockTimer, Timer};
+    /// use embassy_time::Duration;
+    /// use futures_util::task::noop_waker_ref;
+    ///
+    /// let mut cx = Context::from_waker(noop_waker_ref());
+    /// let mut timer = pin!(MockTimer::after(Duration::from_secs(1)).with_pending_polls(1));
+    /// let _ = timer.as_mut().poll(&mut cx);
+    /// let _ = timer.as_mut().poll(&mut cx);
+    ///
+    /// assert_eq!(timer.poll_count(), 2);
+    /// assert!(timer.is_complete());
+    /// ```
+    pub const fn poll_count(&self) -> usize {
+        self.polls
+    }
+
+    /// Has this [`MockTimer`] been polled to completion.
+    pub const fn is_complete(&self) -> bool {
+        self.is_complete
+    }
+
+    /// Take the [`Duration`] of every [`MockTimer`] created on the current thread since the last
+    /// call to this method, in the order they were created.
+    ///
+    /// This allows checking the durations used by code that creates its own timers with
+    /// [`Timer::after()`]. As the recording is per thread, tests running in parallel don't
+    /// interfere with each other.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTimer, Timer};
+    /// use embassy_time::Duration;
+    ///
+    /// async fn production_code<T: Timer>() {
+    ///     T::after(Duration::from_secs(1)).await;
+    ///     T::after(Duration::from_millis(500)).await;
+    /// }
+    ///
+    /// block_on(production_code::<MockTimer>());
+    ///
+    /// assert_eq!(
+    ///     MockTimer::recorded_durations(),
+    ///     [Duration::from_secs(1), Duration::from_millis(500)]
+    /// );
+    /// assert!(MockTimer::recorded_durations().is_empty());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn recorded_durations() -> Vec<Duration> {
+        RECORDED_DURATIONS.with(|durations| durations.take())
+    }
+
+    /// Take the [`Instant`] of every [`MockTimer`] created with [`Timer::at()`] on the current
+    /// thread since the last call to this method, in the order they were created.
+    ///
+    /// This is the [`Timer::at()`] counterpart of [`Self::recorded_durations()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTimer, Timer};
+    /// use embassy_time::Instant;
+    ///
+    /// async fn production_code<T: Timer>(deadline: Instant) {
+    ///     T::at(deadline).await;
+    /// }
+    ///
+    /// block_on(production_code::<MockTimer>(Instant::from_secs(10)));
+    ///
+    /// assert_eq!(MockTimer::recorded_deadlines(), [Instant::from_secs(10)]);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn recorded_deadlines() -> Vec<Instant> {
+        RECORDED_DEADLINES.with(|deadlines| deadlines.take())
+    }
+
+    /// Take the [`Duration`] of every call to [`Timer::block_for()`] for [`MockTimer`] on the
+    /// current thread since the last call to this method, in the order they were made.
+    ///
+    /// These are kept separate from [`Self::recorded_durations()`] as no [`MockTimer`] is created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{MockTimer, Timer};
+    /// use embassy_time::Duration;
+    ///
+    /// fn production_code<T: Timer>() {
+    ///     T::block_for(Duration::from_micros(50));
+    /// }
+    ///
+    /// production_code::<MockTimer>();
+    ///
+    /// assert_eq!(MockTimer::recorded_blocks(), [Duration::from_micros(50)]);
+    /// assert!(MockTimer::recorded_durations().is_empty());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn recorded_blocks() -> Vec<Duration> {
+        RECORDED_BLOCKS.with(|blocks| blocks.take())
+    }
+
+    /// Take the number of times every [`MockTimer`] that completed on the current thread since
+    /// the last call to this method was polled, in the order they completed.
+    ///
+    /// A timer that is polled many more times than it returns [`Poll::Pending`] for shows that the
+    /// code under test is busy-polling, for example because it doesn't use the waker correctly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTimer, Timer};
+    /// use embassy_time::Duration;
+    ///
+    /// async fn production_code<T: Timer>() {
+    ///     T::after(Duration::from_secs(1)).await;
+    /// }
+    ///
+    /// MockTimer::set_pending_polls(2);
+    /// block_on(production_code::<MockTimer>());
+    ///
+    /// assert_eq!(MockTimer::recorded_poll_counts(), [3]);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn recorded_poll_counts() -> Vec<usize> {
+        RECORDED_POLLS.with(|polls| polls.take())
+    }
+
+    /// The number of [`MockTimer`]s created with [`Timer::after()`] or [`Timer::at()`] on the
+    /// current thread that haven't been polled to completion.
+    ///
+    /// This catches code that creates a timer but forgets to `.await` it. Timers that lose a race,
+    /// for example in `embassy_futures::select::select()`, are also counted as they never complete.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTimer, Timer};
+    /// use embassy_time::Duration;
+    ///
+    /// async fn production_code<T: Timer>() {
+    ///     T::after(Duration::from_secs(1)).await;
+    ///     let _ = T::after(Duration::from_secs(2)); // Forgot to `.await` the timer.
+    /// }
+    ///
+    /// block_on(production_code::<MockTimer>());
+    ///
+    /// assert_eq!(MockTimer::incomplete_timers(), 1);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn incomplete_timers() -> usize {
+        INCOMPLETE_TIMERS.with(Cell::get)
+    }
+
+    /// Stop counting the incomplete timers created on the current thread before this call.
+    #[cfg(feature = "std")]
+    pub(super) fn clear_incomplete_timers() {
+        INCOMPLETE_TIMERS.with(|count| count.set(0));
+        UNAWAITED_TIMERS.with(|count| count.set(0));
+        INCOMPLETE_GENERATION.with(|generation| generation.set(generation.get().wrapping_add(1)));
+    }
+
+    /// Stop counting this timer as incomplete, if it is counted.
+    #[cfg(feature = "std")]
+    fn mark_complete(&mut self) {
+        if let Some(generation) = self.incomplete_generation.take() {
+            if INCOMPLETE_GENERATION.with(Cell::get) == generation {
+                INCOMPLETE_TIMERS.with(|count| count.set(count.get().saturating_sub(1)));
+            }
+        }
+    }
+
+    /// The number of [`MockTimer`]s created with [`Timer::after()`] or [`Timer::at()`] on the
+    /// current thread since the expectations were created that have never been polled, not
+    /// counting the timeouts of [`WithTimeout`](super::WithTimeout).
+    #[cfg(feature = "std")]
+    pub(super) fn unawaited_timers() -> usize {
+        UNAWAITED_TIMERS.with(Cell::get)
+    }
+
+    /// Stop counting this timer as not awaited, if it is counted.
+    #[cfg(feature = "std")]
+    pub(super) fn mark_awaited(&mut self) {
+        if let Some(generation) = self.unawaited_generation.take() {
+            if INCOMPLETE_GENERATION.with(Cell::get) == generation {
+                UNAWAITED_TIMERS.with(|count| count.set(count.get().saturating_sub(1)));
+            }
+        }
+    }
+
+    /// The records of every [`MockTimer`] created with [`Timer::after()`] or [`Timer::at()`] since
+    /// the registry was last cleared, in the order they were created.
+    ///
+    /// This gives a handle on the timers created by the code under test, to check how they were
+    /// created and used after the code has finished with them.
+    ///
+    /// This needs the `std` feature as well as `registry`, as the records are collected into a
+    /// [`Vec`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTimer, MockTimerRecord, Timer};
+    /// use embassy_time::Duration;
+    ///
+    /// async fn production_code<T: Timer>() {
+    ///     T::after(Duration::from_secs(1)).await;
+    ///     let _forgotten = T::after(Duration::from_secs(2));
+    /// }
+    ///
+    /// block_on(production_code::<MockTimer>());
+    ///
+    /// let timers = MockTimer::registered_timers();
+    /// assert_eq!(timers.len(), 2);
+    /// assert!(timers[0].is_complete);
+    /// assert_eq!(timers[1].duration, Some(Duration::from_secs(2)));
+    /// assert_eq!(timers[1].polls, 0);
+    /// ```
+    #[cfg(all(feature = "registry", feature = "std"))]
+    pub fn registered_timers() -> Vec<MockTimerRecord> {
+        registry::with(&REGISTRY, |registry| registry.records())
+    }
+
+    /// The record of the [`MockTimer`] at `index` in the registry, or [`None`] if fewer timers
+    /// have been registered.
+    ///
+    /// Like [`Self::registered_timers()`], this needs the `std` feature as well as `registry`.
+    #[cfg(all(feature = "registry", feature = "std"))]
+    pub fn registered_timer(index: usize) -> Option<MockTimerRecord> {
+        registry::with(&REGISTRY, |registry| registry.get(index))
+    }
+
+    /// The number of [`MockTimer`]s in the registry.
+    ///
+    /// See [`Self::registered_timers()`].
+    #[cfg(feature = "registry")]
+    pub fn registered_timer_count() -> usize {
+        registry::with(&REGISTRY, |registry| registry.len())
+    }
+
+    /// Remove all of the records from the registry.
+    ///
+    /// Timers created before this is called are no longer recorded. Without the `std` feature the
+    /// registry is shared by all tests, so this should be called at the start of each test.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_mock::time::{MockTimer, Timer};
+    /// use embassy_time::Duration;
+    ///
+    /// let _timer = MockTimer::after(Duration::from_secs(1));
+    /// MockTimer::clear_registry();
+    ///
+    /// assert_eq!(MockTimer::registered_timer_count(), 0);
+    /// ```
+    #[cfg(feature = "registry")]
+    pub fn clear_registry() {
+        registry::with(&REGISTRY, |registry| registry.clear());
+    }
+
+    /// Update the record of this timer in the registry, if it has one.
+    #[cfg(feature = "registry")]
+    fn update_record(&self, update: impl FnOnce(&mut MockTimerRecord)) {
+        if let Some(slot) = self.registry_slot {
+            registry::with(&REGISTRY, |registry| registry.update(slot, update));
+        }
+    }
+
+    /// Create a [`MockTimerSequence`], providing the [`Duration`] of each [`MockTimer`] that is
+    /// expected to be created on the current thread, in order.
+    ///
+    /// Any durations recorded before this call are discarded so only the timers created after it
+    /// are checked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTimer, Timer};
+    /// use embassy_time::Duration;
+    ///
+    /// async fn production_code<T: Timer>() {
+    ///     T::after(Duration::from_secs(1)).await;
+    ///     T::after(Duration::from_millis(500)).await;
+    /// }
+    ///
+    /// let timers = MockTimer::expect_sequence([Duration::from_secs(1), Duration::from_millis(500)]);
+    /// block_on(production_code::<MockTimer>());
+    ///
+    /// timers.done().unwrap();
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn expect_sequence(
+        expected: impl IntoIterator<Item = impl Into<DurationMatcher>>,
+    ) -> MockTimerSequence {
+        Self::recorded_durations();
+
+        MockTimerSequence {
+            expected: expected.into_iter().map(Into::into).collect(),
+            is_done: false,
+        }
+    }
+}
+
+/// The errors that are reported by [`MockTimerSequence`] and
+/// [`MockTimerExpectations`](super::MockTimerExpectations).
+#[cfg(feature = "std")]
+#[derive(Debug, Snafu, PartialEq)]
+pub enum MockTimerError {
+    /// The wrong number of [`MockTimer`]s were created.
+    #[snafu(display("expected to create {expected} timer(s), actually created {actual}"))]
+    WrongNumberOfTimers {
+        /// The expected number of [`MockTimer`]s.
+        expected: usize,
+
+        /// The actual number of [`MockTimer`]s created.
+        actual: usize,
+    },
+
+    /// A [`MockTimer`] was created with the wrong [`Duration`].
+    #[snafu(display(
+        "expected timer {index} to be created with {expected}, actually created with {actual}"
+    ))]
+    WrongDuration {
+        /// The position of the [`MockTimer`] in the sequence, starting from zero.
+        index: usize,
+
+        /// The [`DurationMatcher`] the [`Duration`] of the [`MockTimer`] was expected to match.
+        expected: DurationMatcher,
+
+        /// The actual [`Duration`] the [`MockTimer`] was created with.
+        actual: Duration,
+    },
+
+    /// The wrong number of [`MockTimer`]s were created with [`Timer::at()`].
+    #[snafu(display(
+        "expected to create {expected} timer(s) with a deadline, actually created {actual}"
+    ))]
+    WrongNumberOfDeadlines {
+        /// The expected number of [`MockTimer`]s created with [`Timer::at()`].
+        expected: usize,
+
+        /// The actual number of [`MockTimer`]s created with [`Timer::at()`].
+        actual: usize,
+    },
+
+    /// Some of the [`MockTimer`]s that were created were never awaited.
+    #[snafu(display("{count} timer(s) were never awaited"))]
+    NotAwaited {
+        /// The number of [`MockTimer`]s that were never polled.
+        count: usize,
+    },
+
+    /// A [`MockTimer`] was polled more times than allowed before completing.
+    #[snafu(display(
+        "expected timer {index} to complete within {max} poll(s), actually polled {actual} times"
+    ))]
+    TooManyPolls {
+        /// The position of the [`MockTimer`] in the timers that completed, starting from zero.
+        index: usize,
+
+        /// The maximum number of polls allowed.
+        max: usize,
+
+        /// The actual number of times the [`MockTimer`] was polled.
+        actual: usize,
+    },
+
+    /// A [`MockTimer`] was created with the wrong [`Instant`].
+    #[snafu(display(
+        "expected timer {index} to be created with a deadline of {expected}, actually created with {actual}"
+    ))]
+    WrongDeadline {
+        /// The position of the [`MockTimer`] in the timers created with [`Timer::at()`],
+        /// starting from zero.
+        index: usize,
+
+        /// The expected [`Instant`] of the [`MockTimer`].
+        expected: Instant,
+
+        /// The actual [`Instant`] the [`MockTimer`] was created with.
+        actual: Instant,
+    },
+}
+
+/// The expected sequence of [`MockTimer`]s to be created on the current thread, see
+/// [`MockTimer::expect_sequence()`].
+///
+/// The sequence can be checked using [`Self::done()`]. If [`Self::done()`] is not called then it
+/// asserts that the expected [`MockTimer`]s were created when dropped which causes a panic if
+/// incorrect.
+///
+/// # Panics
+///
+/// Panics if the [`MockTimer`]s created don't match the expected sequence and [`Self`] is dropped
+/// before calling [`Self::done()`].
+///
+/// # Examples
+///
+/// ```
+/// use embassy_futures::block_on;
+/// use embassy_mock::time::{MockTimer, MockTimerError, Timer};
+/// use embassy_time::Duration;
+///
+/// let timers = MockTimer::expect_sequence([Duration::from_secs(1), Duration::from_secs(2)]);
+/// block_on(MockTimer::after(Duration::from_secs(1)));
+///
+/// let res = timers.done();
+///
+/// let expected = Err(MockTimerError::WrongNumberOfTimers {
+///     expected: 2,
+///     actual: 1,
+/// });
+/// assert_eq!(res, expected);
+/// ```
+///
+/// ```should_panic
+/// use embassy_futures::block_on;
+/// use embassy_mock::time::{MockTimer, Timer};
+/// use embassy_time::Duration;
+///
+/// let timers = MockTimer::expect_sequence([Duration::from_secs(1)]);
+/// block_on(MockTimer::after(Duration::from_secs(5))); // Created with the wrong duration.
+///
+/// // `timers` is dropped and will panic.
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct MockTimerSequence {
+    /// The expected [`Duration`] of each [`MockTimer`], in order.
+    expected: Vec<DurationMatcher>,
+
+    /// Has this mock been checked with a call to [`Self::done()`].
+    /// If true it is not checked when dropped.
+    is_done: bool,
+}
+
+#[cfg(feature = "std")]
+impl MockTimerSequence {
+    /// Mark the [`MockTimerSequence`] as done and check if the expected [`MockTimer`]s were
+    /// created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTimer, MockTimerError, Timer};
+    /// use embassy_time::Duration;
+    ///
+    /// let timers = MockTimer::expect_sequence([Duration::from_secs(1)]);
+    /// block_on(MockTimer::after(Duration::from_secs(3)));
+    ///
+    /// let res = timers.done();
+    ///
+    /// let expected = Err(MockTimerError::WrongDuration {
+    ///     index: 0,
+    ///     expected: Duration::from_secs(1).into(),
+    ///     actual: Duration::from_secs(3),
+    /// });
+    /// assert_eq!(res, expected);
+    ///
+    /// // This doesn't panic when `timers` is dropped as `timers.done()` was called.
+    /// ```
+    pub fn done(mut self) -> Result<(), MockTimerError> {
+        self.is_done = true;
+        self.check()
+    }
+
+    /// Check the [`MockTimer`]s recorded since this sequence was created against the expected
+    /// sequence.
+    fn check(&self) -> Result<(), MockTimerError> {
+        let actual = MockTimer::recorded_durations();
+        ensure!(
+            actual.len() == self.expected.len(),
+            WrongNumberOfTimersSnafu {
+                expected: self.expected.len(),
+                actual: actual.len(),
+            }
+        );
+
+        let mismatch = self
+            .expected
+            .iter()
+            .zip(&actual)
+            .enumerate()
+            .find(|(_, (expected, &actual))| !expected.matches(actual));
+        match mismatch {
+            Some((index, (&expected, &actual))) => Err(MockTimerError::WrongDuration {
+                index,
+                expected,
+                actual,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for MockTimerSequence {
+    /// If [`Self::done()`] has not been called before being dropped then check that the expected
+    /// [`MockTimer`]s were created.
+    ///
+    /// The check is skipped while panicking, see [checks on drop](crate#checks-on-drop).
+    fn drop(&mut self) {
+        if !self.is_done && !std::thread::panicking() {
+            if let Err(error) = self.check() {
+                panic!("{error}");
+            }
+        }
+    }
+}
 
 impl Future for MockTimer {
     type Output = ();
 
-    /// Immediately return [`Poll::Ready`].
-    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+    /// Return [`Poll::Pending`] and wake the waker until the configured number of pending polls
+    /// have been used up, then return [`Poll::Ready`] once the [`MockClock`](super::MockClock)
+    /// has reached the deadline, if there is one. If the timer never completes then always return
+    /// [`Poll::Pending`] without waking the waker.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        this.polls = this.polls.saturating_add(1);
+        #[cfg(feature = "std")]
+        this.mark_awaited();
+        #[cfg(feature = "registry")]
+        this.update_record(|record| record.polls = record.polls.saturating_add(1));
+
+        match this.pending_polls {
+            None => {
+                #[cfg(feature = "std")]
+                waiting::record("MockTimer");
+                return Poll::Pending;
+            }
+            Some(0) => {}
+            Some(ref mut polls) => {
+                *polls -= 1;
+                #[cfg(feature = "std")]
+                waiting::waking("MockTimer", || cx.waker().wake_by_ref());
+                #[cfg(not(feature = "std"))]
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+        }
+
+        #[cfg(feature = "std")]
+        if let Some(deadline) = this.clock_deadline {
+            if !clock::poll_deadline(deadline, cx.waker()) {
+                waiting::record("MockTimer");
+                return Poll::Pending;
+            }
+        }
+
+        if !this.is_complete {
+            this.is_complete = true;
+            #[cfg(feature = "std")]
+            RECORDED_POLLS.with(|polls| polls.borrow_mut().push(this.polls));
+        }
+        #[cfg(feature = "std")]
+        this.mark_complete();
+        #[cfg(feature = "registry")]
+        this.update_record(|record| record.is_complete = true);
         Poll::Ready(())
     }
 }
 
 impl Timer for MockTimer {
+    /// Create a [`MockTimer`] that records the [`Instant`] it was requested to expire at.
+    ///
+    /// # Examples
+    /// ```
+    /// use embassy_futures::block_on;
+    /// use embassy_mock::time::{MockTimer, Timer};
+    /// use embassy_time::Instant;
+    ///
+    /// let timer = MockTimer::at(Instant::from_millis(1500));
+    ///
+    /// assert_eq!(timer.deadline(), Some(Instant::from_millis(1500)));
+    /// block_on(timer);
+    /// ```
+    fn at(expires_at: Instant) -> Self {
+        #[cfg(feature = "std")]
+        RECORDED_DEADLINES.with(|deadlines| deadlines.borrow_mut().push(expires_at));
+
+        Self::new(Expiry::At(expires_at))
+    }
+
     /// Create a [`MockTimer`] that can be used to unit test code.
     ///
     /// # Examples
@@ -131,21 +1185,141 @@ impl Timer for MockTimer {
     /// # mod closing {
     /// }
     /// ```
-    fn after(_duration: Duration) -> Self {
-        Self {}
+    fn after(duration: Duration) -> Self {
+        #[cfg(feature = "std")]
+        RECORDED_DURATIONS.with(|durations| durations.borrow_mut().push(duration));
+
+        Self::new(Expiry::After(duration))
+    }
+
+    /// Create a [`MockTimer`] with a [`Duration`] of the specified number of ticks, see
+    /// [`Self::after()`].
+    fn after_ticks(ticks: u64) -> Self {
+        Self::after(Duration::from_ticks(ticks))
+    }
+
+    /// Create a [`MockTimer`] with a [`Duration`] of the specified number of nanoseconds, see
+    /// [`Self::after()`].
+    fn after_nanos(nanos: u64) -> Self {
+        Self::after(Duration::from_nanos(nanos))
+    }
+
+    /// Create a [`MockTimer`] with a [`Duration`] of the specified number of microseconds, see
+    /// [`Self::after()`].
+    fn after_micros(micros: u64) -> Self {
+        Self::after(Duration::from_micros(micros))
+    }
+
+    /// Create a [`MockTimer`] with a [`Duration`] of the specified number of milliseconds, see
+    /// [`Self::after()`].
+    fn after_millis(millis: u64) -> Self {
+        Self::after(Duration::from_millis(millis))
+    }
+
+    /// Create a [`MockTimer`] with a [`Duration`] of the specified number of seconds, see
+    /// [`Self::after()`].
+    fn after_secs(secs: u64) -> Self {
+        Self::after(Duration::from_secs(secs))
+    }
+
+    /// Record the [`Duration`] and return immediately instead of busy-waiting.
+    ///
+    /// If a [`MockClock`](super::MockClock) is alive then it is advanced by `duration`, as if the
+    /// time had passed while blocking.
+    fn block_for(duration: Duration) {
+        #[cfg(feature = "std")]
+        {
+            RECORDED_BLOCKS.with(|blocks| blocks.borrow_mut().push(duration));
+
+            if clock::is_attached() {
+                clock::advance(duration);
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = duration;
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::time::MockClock;
+    use core::pin::pin;
     use embassy_futures::block_on;
+    use futures_util::task::noop_waker_ref;
 
     #[test]
     fn can_create_timer_with_after() {
         let timer = MockTimer::after(Duration::from_secs(1));
 
-        assert_eq!(timer, MockTimer);
+        assert_eq!(timer.duration(), Some(Duration::from_secs(1)));
+        assert_eq!(timer.deadline(), None);
+    }
+
+    #[test]
+    fn with_pending_polls_is_pending_for_polls() {
+        let mut timer = pin!(MockTimer::after(Duration::from_secs(1)).with_pending_polls(2));
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        assert_eq!(timer.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(timer.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(timer.as_mut().poll(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    fn set_pending_polls_applies_to_new_timers() {
+        MockTimer::set_pending_polls(1);
+        let mut timer = pin!(MockTimer::after(Duration::from_secs(1)));
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        assert_eq!(timer.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(timer.as_mut().poll(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    fn never_is_always_pending() {
+        let mut timer = pin!(MockTimer::never());
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        for _ in 0..10 {
+            assert_eq!(timer.as_mut().poll(&mut cx), Poll::Pending);
+        }
+        assert_eq!(timer.duration(), None);
+        assert_eq!(timer.deadline(), None);
+    }
+
+    #[test]
+    fn set_pending_forever_applies_to_new_timers() {
+        MockTimer::set_pending_forever();
+        let mut timer = pin!(MockTimer::after(Duration::from_secs(1)));
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        assert_eq!(timer.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(timer.as_mut().poll(&mut cx), Poll::Pending);
+
+        MockTimer::set_pending_polls(0);
+        block_on(MockTimer::after(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn can_create_timer_with_at() {
+        let timer = MockTimer::at(Instant::from_secs(3));
+
+        assert_eq!(timer.deadline(), Some(Instant::from_secs(3)));
+        assert_eq!(timer.duration(), None);
+    }
+
+    #[test]
+    fn recorded_deadlines_are_separate_from_durations() {
+        block_on(MockTimer::at(Instant::from_secs(3)));
+        block_on(MockTimer::after(Duration::from_secs(1)));
+        block_on(MockTimer::at(Instant::from_secs(7)));
+
+        assert_eq!(
+            MockTimer::recorded_deadlines(),
+            [Instant::from_secs(3), Instant::from_secs(7)]
+        );
+        assert_eq!(MockTimer::recorded_durations(), [Duration::from_secs(1)]);
     }
 
     #[test]
@@ -154,4 +1328,221 @@ mod tests {
 
         block_on(timer);
     }
+
+    #[test]
+    fn convenience_constructors_match_after() {
+        assert_eq!(
+            MockTimer::after_ticks(5),
+            MockTimer::after(Duration::from_ticks(5))
+        );
+        assert_eq!(
+            MockTimer::after_nanos(5),
+            MockTimer::after(Duration::from_nanos(5))
+        );
+        assert_eq!(
+            MockTimer::after_micros(5),
+            MockTimer::after(Duration::from_micros(5))
+        );
+        assert_eq!(
+            MockTimer::after_millis(5),
+            MockTimer::after(Duration::from_millis(5))
+        );
+        assert_eq!(
+            MockTimer::after_secs(5),
+            MockTimer::after(Duration::from_secs(5))
+        );
+    }
+
+    #[test]
+    fn convenience_constructors_are_recorded() {
+        block_on(MockTimer::after_millis(100));
+        block_on(MockTimer::after_secs(2));
+
+        assert_eq!(
+            MockTimer::recorded_durations(),
+            [Duration::from_millis(100), Duration::from_secs(2)]
+        );
+    }
+
+    #[test]
+    fn recorded_durations_are_in_creation_order() {
+        block_on(MockTimer::after(Duration::from_secs(1)));
+        block_on(MockTimer::after(Duration::from_millis(500)));
+
+        assert_eq!(
+            MockTimer::recorded_durations(),
+            [Duration::from_secs(1), Duration::from_millis(500)]
+        );
+    }
+
+    #[test]
+    fn registry_records_timers_created_by_trait() {
+        block_on(MockTimer::after(Duration::from_secs(1)).with_pending_polls(2));
+        let _timer = MockTimer::at(Instant::from_secs(5));
+        let _never = MockTimer::never();
+
+        assert_eq!(
+            MockTimer::registered_timers(),
+            [
+                MockTimerRecord {
+                    duration: Some(Duration::from_secs(1)),
+                    deadline: None,
+                    polls: 3,
+                    is_complete: true,
+                },
+                MockTimerRecord {
+                    duration: None,
+                    deadline: Some(Instant::from_secs(5)),
+                    polls: 0,
+                    is_complete: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn cleared_registry_ignores_older_timers() {
+        let old = MockTimer::after(Duration::from_secs(1));
+        MockTimer::clear_registry();
+        let _new = MockTimer::after(Duration::from_secs(2));
+        block_on(old);
+
+        assert_eq!(MockTimer::registered_timer_count(), 1);
+        let record = MockTimer::registered_timer(0).unwrap();
+        assert_eq!(record.duration, Some(Duration::from_secs(2)));
+        assert!(!record.is_complete);
+    }
+
+    #[test]
+    fn completed_timers_are_not_incomplete() {
+        MockTimer::clear_incomplete_timers();
+        let pending = MockTimer::after(Duration::from_secs(1));
+        block_on(MockTimer::after(Duration::from_secs(2)).with_pending_polls(1));
+        let _never = MockTimer::never();
+
+        assert_eq!(MockTimer::incomplete_timers(), 1);
+        block_on(pending);
+        assert_eq!(MockTimer::incomplete_timers(), 0);
+    }
+
+    #[test]
+    fn poll_counts_are_recorded_once_on_completion() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        {
+            let mut timer = pin!(MockTimer::after(Duration::from_secs(1)).with_pending_polls(2));
+            while timer.as_mut().poll(&mut cx).is_pending() {}
+            let _ = timer.as_mut().poll(&mut cx);
+
+            assert_eq!(timer.poll_count(), 4);
+        }
+        let _unpolled = MockTimer::after(Duration::from_secs(2));
+
+        assert_eq!(MockTimer::recorded_poll_counts(), [3]);
+    }
+
+    #[test]
+    fn block_for_is_recorded() {
+        MockTimer::block_for(Duration::from_millis(5));
+        MockTimer::block_for(Duration::from_secs(1));
+
+        assert_eq!(
+            MockTimer::recorded_blocks(),
+            [Duration::from_millis(5), Duration::from_secs(1)]
+        );
+        assert!(MockTimer::recorded_durations().is_empty());
+    }
+
+    #[test]
+    fn block_for_advances_clock() {
+        let clock = MockClock::new();
+        MockTimer::block_for(Duration::from_secs(2));
+
+        assert_eq!(Instant::from(clock.now()), Instant::from_secs(2));
+    }
+
+    #[test]
+    fn recorded_durations_are_cleared_when_taken() {
+        let _timer = MockTimer::after(Duration::from_secs(1));
+        MockTimer::recorded_durations();
+
+        assert!(MockTimer::recorded_durations().is_empty());
+    }
+
+    #[test]
+    fn sequence_done_returns_ok() {
+        let timers = MockTimer::expect_sequence([Duration::from_secs(1), Duration::from_secs(2)]);
+        block_on(MockTimer::after(Duration::from_secs(1)));
+        block_on(MockTimer::after(Duration::from_secs(2)));
+
+        let res = timers.done();
+
+        assert_eq!(res, Ok(()));
+    }
+
+    #[test]
+    fn sequence_ignores_timers_created_before() {
+        block_on(MockTimer::after(Duration::from_secs(5)));
+        let timers = MockTimer::expect_sequence([Duration::from_secs(1)]);
+        block_on(MockTimer::after(Duration::from_secs(1)));
+
+        let res = timers.done();
+
+        assert_eq!(res, Ok(()));
+    }
+
+    #[test]
+    fn sequence_accepts_duration_matchers() {
+        let timers = MockTimer::expect_sequence([
+            DurationMatcher::at_least(Duration::from_secs(1)),
+            DurationMatcher::any(),
+        ]);
+        block_on(MockTimer::after(Duration::from_secs(2)));
+        block_on(MockTimer::after(Duration::from_millis(1)));
+
+        assert_eq!(timers.done(), Ok(()));
+    }
+
+    #[test]
+    fn sequence_done_returns_err_for_wrong_duration() {
+        let timers = MockTimer::expect_sequence([Duration::from_secs(1), Duration::from_secs(2)]);
+        block_on(MockTimer::after(Duration::from_secs(1)));
+        block_on(MockTimer::after(Duration::from_secs(3)));
+
+        let res = timers.done();
+
+        let expected = Err(MockTimerError::WrongDuration {
+            index: 1,
+            expected: Duration::from_secs(2).into(),
+            actual: Duration::from_secs(3),
+        });
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn sequence_done_returns_err_for_wrong_number_of_timers() {
+        let timers = MockTimer::expect_sequence([Duration::from_secs(1)]);
+        block_on(MockTimer::after(Duration::from_secs(1)));
+        block_on(MockTimer::after(Duration::from_secs(1)));
+
+        let res = timers.done();
+
+        let expected = Err(MockTimerError::WrongNumberOfTimers {
+            expected: 1,
+            actual: 2,
+        });
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn sequence_correct_just_drop() {
+        let _timers = MockTimer::expect_sequence([Duration::from_secs(1)]);
+        block_on(MockTimer::after(Duration::from_secs(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected to create 2 timer(s), actually created 1")]
+    fn sequence_too_few_timers_just_drop() {
+        let _timers = MockTimer::expect_sequence([Duration::from_secs(1), Duration::from_secs(1)]);
+        block_on(MockTimer::after(Duration::from_secs(1)));
+    }
 }
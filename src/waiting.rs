@@ -0,0 +1,61 @@
+//! Records the mocks that the code under test is waiting for during a poll, so that a test that
+//! would hang can report what it was stuck on, and the mock that is waking a waker.
+//!
+//! Each mocked future records itself when it returns [`Poll::Pending`](core::task::Poll::Pending)
+//! to wait for the test, but only while [`recording()`] is running on the same thread, so the
+//! record costs nothing otherwise. Each mock wakes its wakers inside [`waking()`], so a waker
+//! that is woken can ask [`waking_mock()`] which mock woke it.
+
+use core::cell::{Cell, RefCell};
+use std::vec::Vec;
+
+std::thread_local! {
+    /// The mocks waited for while recording, in the order they were first waited for.
+    static WAITING: RefCell<Option<Vec<&'static str>>> = const { RefCell::new(None) };
+
+    /// The mock that is waking a waker, if any.
+    static WAKING: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+/// Record that the code under test is waiting for the `mock`, if the waits are being recorded.
+#[cfg_attr(
+    not(any(feature = "futures", feature = "sync", feature = "time")),
+    allow(dead_code)
+)]
+pub(crate) fn record(mock: &'static str) {
+    WAITING.with_borrow_mut(|waiting| {
+        if let Some(waiting) = waiting {
+            if !waiting.contains(&mock) {
+                waiting.push(mock);
+            }
+        }
+    });
+}
+
+/// Run `f`, returning its output and the mocks that were waited for while it ran.
+#[cfg_attr(not(feature = "futures"), allow(dead_code))]
+pub(crate) fn recording<R>(f: impl FnOnce() -> R) -> (R, Vec<&'static str>) {
+    let outer = WAITING.replace(Some(Vec::new()));
+    let output = f();
+    let waiting = WAITING.replace(outer).unwrap_or_default();
+    (output, waiting)
+}
+
+/// Run `f`, which wakes wakers on behalf of the `mock`.
+#[cfg_attr(
+    not(any(feature = "futures", feature = "sync", feature = "time")),
+    allow(dead_code)
+)]
+pub(crate) fn waking<R>(mock: &'static str, f: impl FnOnce() -> R) -> R {
+    let outer = WAKING.replace(Some(mock));
+    let output = f();
+    WAKING.set(outer);
+    output
+}
+
+/// The mock that is waking a waker on the current thread, or [`None`] if the waker is woken by
+/// something else.
+#[cfg_attr(not(feature = "futures"), allow(dead_code))]
+pub(crate) fn waking_mock() -> Option<&'static str> {
+    WAKING.get()
+}